@@ -0,0 +1,195 @@
+#!/usr/bin/env node
+
+/**
+ * Generate TypeScript definitions for the verifier contract from its NEAR ABI
+ * (`cargo near abi`), so the relayer and frontend share one set of types for
+ * `ProofInput`, `VerificationResult` and every method's arguments.
+ *
+ * Usage:
+ *   pnpm abi-to-ts <abi.json> <out.ts>
+ *
+ * Or from the repo root: `just contract-types`.
+ */
+
+import fs from "fs";
+import path from "path";
+import { ensureFileExists } from "./snarkjsUtils";
+
+type Schema = {
+  $ref?: string;
+  type?: string | string[];
+  description?: string;
+  enum?: unknown[];
+  const?: unknown;
+  format?: string;
+  items?: Schema | Schema[];
+  minItems?: number;
+  maxItems?: number;
+  properties?: Record<string, Schema>;
+  required?: string[];
+  additionalProperties?: boolean | Schema;
+  oneOf?: Schema[];
+  anyOf?: Schema[];
+  allOf?: Schema[];
+  definitions?: Record<string, Schema>;
+};
+
+type AbiArg = { name: string; type_schema: Schema };
+
+type AbiFunction = {
+  name: string;
+  doc?: string;
+  kind: "view" | "call";
+  modifiers?: string[];
+  params?: { serialization_type: "json" | "borsh"; args: AbiArg[] };
+  result?: { serialization_type: "json" | "borsh"; type_schema: Schema };
+};
+
+type Abi = {
+  schema_version: string;
+  metadata: { name?: string; version?: string };
+  body: { functions: AbiFunction[]; root_schema: Schema };
+};
+
+const IDENT = /^[A-Za-z_$][A-Za-z0-9_$]*$/;
+
+function propertyName(name: string): string {
+  return IDENT.test(name) ? name : JSON.stringify(name);
+}
+
+function docComment(doc: string | undefined, indent: string): string {
+  if (!doc) return "";
+  const lines = doc.trim().split("\n");
+  return `${indent}/**\n${lines.map((l) => `${indent} * ${l}`.trimEnd()).join("\n")}\n${indent} */\n`;
+}
+
+/** Render a JSON schema (as emitted by schemars) as a TypeScript type. */
+function toTs(schema: Schema | boolean | undefined, indent = ""): string {
+  if (schema === undefined || schema === true) return "unknown";
+  if (schema === false) return "never";
+  if (schema.$ref) return schema.$ref.replace("#/definitions/", "");
+  if (schema.const !== undefined) return JSON.stringify(schema.const);
+  if (schema.enum) return schema.enum.map((v) => JSON.stringify(v)).join(" | ");
+  for (const variants of [schema.oneOf, schema.anyOf]) {
+    if (variants) return variants.map((v) => toTs(v, indent)).join(" | ");
+  }
+  if (schema.allOf) return schema.allOf.map((v) => toTs(v, indent)).join(" & ");
+
+  const types = Array.isArray(schema.type) ? schema.type : schema.type ? [schema.type] : [];
+  if (types.length > 1) {
+    return types.map((type) => toTs({ ...schema, type }, indent)).join(" | ");
+  }
+  switch (types[0]) {
+    case "string":
+      return "string";
+    case "integer":
+    case "number":
+      return "number";
+    case "boolean":
+      return "boolean";
+    case "null":
+      return "null";
+    case "array": {
+      // schemars writes fixed-size arrays as `items` + `minItems == maxItems`.
+      if (Array.isArray(schema.items)) {
+        return `[${schema.items.map((s) => toTs(s, indent)).join(", ")}]`;
+      }
+      const item = toTs(schema.items, indent);
+      if (schema.minItems !== undefined && schema.minItems === schema.maxItems && schema.minItems <= 16) {
+        return `[${Array(schema.minItems).fill(item).join(", ")}]`;
+      }
+      return /^[\w.]+$/.test(item) ? `${item}[]` : `Array<${item}>`;
+    }
+    case "object": {
+      if (!schema.properties) {
+        const value = schema.additionalProperties;
+        return `Record<string, ${value === undefined || value === true ? "unknown" : toTs(value, indent)}>`;
+      }
+      const required = new Set(schema.required ?? []);
+      const inner = indent + "  ";
+      const fields = Object.entries(schema.properties).map(([name, prop]) => {
+        const optional = required.has(name) ? "" : "?";
+        return `${docComment(prop.description, inner)}${inner}${propertyName(name)}${optional}: ${toTs(prop, inner)};`;
+      });
+      return `{\n${fields.join("\n")}\n${indent}}`;
+    }
+    default:
+      return "unknown";
+  }
+}
+
+function argsType(fn: AbiFunction): string {
+  if (!fn.params || fn.params.args.length === 0) return "Record<string, never>";
+  // Borsh arguments go over the wire as raw bytes.
+  if (fn.params.serialization_type === "borsh") return "Uint8Array";
+  return toTs({
+    type: "object",
+    properties: Object.fromEntries(fn.params.args.map((a) => [a.name, a.type_schema])),
+    required: fn.params.args
+      .filter((a) => !(Array.isArray(a.type_schema.type) && a.type_schema.type.includes("null")))
+      .map((a) => a.name),
+  }, "    ");
+}
+
+function resultType(fn: AbiFunction): string {
+  if (!fn.result) return "void";
+  if (fn.result.serialization_type === "borsh") return "Uint8Array";
+  return toTs(fn.result.type_schema, "    ");
+}
+
+export function render(abi: Abi): string {
+  const out: string[] = [
+    `// Generated by circom-zk-email/scripts/abiToTs.ts from the ABI of ` +
+      `${abi.metadata.name ?? "the contract"} ${abi.metadata.version ?? ""}.`.trimEnd(),
+    "// Do not edit by hand; run `just contract-types` instead.",
+    "",
+  ];
+
+  const definitions = abi.body.root_schema.definitions ?? {};
+  for (const name of Object.keys(definitions).sort()) {
+    const schema = definitions[name]!;
+    out.push(`${docComment(schema.description, "")}export type ${name} = ${toTs(schema)};`, "");
+  }
+
+  const functions = abi.body.functions.filter((fn) => !fn.modifiers?.includes("private"));
+  out.push("export interface ContractMethods {");
+  for (const fn of functions) {
+    if (fn.doc) out.push(docComment(fn.doc, "  ").trimEnd());
+    out.push(
+      `  ${propertyName(fn.name)}: {`,
+      `    kind: ${JSON.stringify(fn.kind)};`,
+      `    args: ${argsType(fn)};`,
+      `    result: ${resultType(fn)};`,
+      "  };"
+    );
+  }
+  out.push("}", "");
+
+  const names = (kind: string) =>
+    functions.filter((fn) => fn.kind === kind).map((fn) => JSON.stringify(fn.name));
+  out.push(`export const viewMethods = [${names("view").join(", ")}] as const;`);
+  out.push(`export const changeMethods = [${names("call").join(", ")}] as const;`, "");
+
+  return out.filter((line, i, all) => line !== "" || all[i - 1] !== "").join("\n");
+}
+
+function main(): void {
+  const [abiPath, outPath] = process.argv.slice(2);
+  if (!abiPath || !outPath) {
+    console.error("Usage: pnpm abi-to-ts <abi.json> <out.ts>");
+    process.exit(2);
+  }
+  ensureFileExists(
+    abiPath,
+    `Missing ABI at ${abiPath}. Generate it via:\n` +
+      `  cd zk-email-verifier-contract && cargo near abi --features abi`
+  );
+  const abi = JSON.parse(fs.readFileSync(abiPath, "utf8")) as Abi;
+  fs.mkdirSync(path.dirname(outPath), { recursive: true });
+  fs.writeFileSync(outPath, render(abi));
+  console.log(`Wrote ${abi.body.functions.length} methods to ${outPath}`);
+}
+
+if (require.main === module) {
+  main();
+}
@@ -0,0 +1,134 @@
+//! `make-fixture <email.eml> <out-dir>`: prove an email and write a fixture
+//! directory (see `prover_client::fixture`) for the contract tests.
+//!
+//! Options:
+//!   --prover-url <url>   HTTP prover to use (default: `just docker-run-prover`)
+//!   --skip-proving       only write expected.json, e.g. next to a snarkjs proof
+//!   --zkey <path> --wtns <path>
+//!                        prove in-process instead (`native` feature)
+
+use std::{env, fs, process};
+
+use prover_client::eml::RecoveryEmail;
+use prover_client::fixture::{write_expected, Fixture};
+use prover_client::{ProverClient, DEFAULT_PROVER_URL};
+
+const USAGE: &str = "\
+Usage: make-fixture <email.eml> <out-dir> [--prover-url <url>] [--skip-proving]
+                    [--zkey <circuit.zkey> --wtns <witness.wtns>]";
+
+#[derive(Debug, Default)]
+struct Args {
+    eml: String,
+    out_dir: String,
+    prover_url: Option<String>,
+    skip_proving: bool,
+    zkey: Option<String>,
+    wtns: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut out = Args::default();
+    let mut positional = Vec::new();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().cloned().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--prover-url" => out.prover_url = Some(value()?),
+            "--skip-proving" => out.skip_proving = true,
+            "--zkey" => out.zkey = Some(value()?),
+            "--wtns" => out.wtns = Some(value()?),
+            flag if flag.starts_with("--") => return Err(format!("unknown argument {flag}")),
+            _ => positional.push(arg.clone()),
+        }
+    }
+    let [eml, out_dir] = <[String; 2]>::try_from(positional)
+        .map_err(|_| "expected an .eml file and an output directory".to_string())?;
+    if out.zkey.is_some() != out.wtns.is_some() {
+        return Err("--zkey and --wtns go together".to_string());
+    }
+    Ok(Args { eml, out_dir, ..out })
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("error: {e}\n\n{USAGE}");
+        process::exit(2);
+    });
+    if let Err(e) = run(&args).await {
+        eprintln!("error: {e}");
+        process::exit(1);
+    }
+}
+
+async fn run(args: &Args) -> Result<(), String> {
+    let raw_eml =
+        fs::read_to_string(&args.eml).map_err(|e| format!("failed to read {}: {e}", args.eml))?;
+    let expected = RecoveryEmail::from_eml(&raw_eml).map_err(|e| e.to_string())?;
+    // Fail before proving if the prover would reject the email anyway.
+    expected.expected_public_inputs().map_err(|e| e.to_string())?;
+    let write_err = |e| format!("failed to write {}: {e}", args.out_dir);
+
+    if args.skip_proving {
+        write_expected(&args.out_dir, &expected).map_err(write_err)?;
+        println!("wrote {}/expected.json", args.out_dir);
+        return Ok(());
+    }
+
+    let (proof, public_signals) = match (&args.zkey, &args.wtns) {
+        (Some(zkey), Some(wtns)) => prove_native(zkey, wtns)?,
+        _ => {
+            let client = ProverClient::new(args.prover_url.as_deref().unwrap_or(DEFAULT_PROVER_URL));
+            client.health().await.map_err(|e| e.to_string())?;
+            client.prove_email(&raw_eml).await.map_err(|e| e.to_string())?
+        }
+    };
+    Fixture {
+        proof,
+        public_signals,
+        expected,
+    }
+    .write(&args.out_dir)
+    .map_err(write_err)?;
+    println!("wrote proof.json, public.json and expected.json to {}", args.out_dir);
+    Ok(())
+}
+
+#[cfg(feature = "native")]
+fn prove_native(
+    zkey: &str,
+    wtns: &str,
+) -> Result<(prover_client::ProofInput, Vec<String>), String> {
+    let prover = prover_client::native::NativeProver::from_zkey(zkey).map_err(|e| e.to_string())?;
+    prover.prove_wtns(wtns).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "native"))]
+fn prove_native(
+    _zkey: &str,
+    _wtns: &str,
+) -> Result<(prover_client::ProofInput, Vec<String>), String> {
+    Err("--zkey/--wtns need make-fixture built with --features native".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_flags_and_positionals() {
+        let parsed = parse_args(&args("a.eml --skip-proving out")).unwrap();
+        assert_eq!((parsed.eml.as_str(), parsed.out_dir.as_str()), ("a.eml", "out"));
+        assert!(parsed.skip_proving);
+
+        assert!(parse_args(&args("a.eml")).is_err());
+        assert!(parse_args(&args("a.eml out --zkey c.zkey")).is_err());
+        assert!(parse_args(&args("a.eml out --bogus")).is_err());
+    }
+}
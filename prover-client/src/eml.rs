@@ -0,0 +1,263 @@
+//! Pre-flight check of a recovery email against `RecoverEmailCircuit`.
+//!
+//! Proving takes tens of seconds and fails late on emails the circuit can't
+//! handle. [`RecoveryEmail::from_eml`] pulls the anchored values out of the
+//! headers the way the witness generator (`scripts/generateWitness.ts`)
+//! does, after DKIM "relaxed" header canonicalization, and
+//! [`RecoveryEmail::expected_public_inputs`] packs them into the public signals
+//! a proof of that email must have.
+//!
+//! The DKIM signature and pubkey limbs are not derived here; see `zkev dkim`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zk_email_verifier_core::layout::RECOVER_EMAIL_LAYOUT;
+use zk_email_verifier_core::packing::{pack_string, MAX_PACKED_SUBSTRING_LEN};
+use zk_email_verifier_core::parse_fr;
+use zk_email_verifier_core::subject::RECOVER_EMAIL_SUBJECT;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmlError {
+    MissingHeader(&'static str),
+    /// The subject doesn't match the circuit's subject template.
+    SubjectTemplate(String),
+    /// A value is longer than the circuit can pack (or hash, for the sender).
+    TooLong(&'static str),
+}
+
+impl fmt::Display for EmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmlError::MissingHeader(name) => write!(f, "missing {name} header"),
+            EmlError::SubjectTemplate(subject) => write!(
+                f,
+                "subject {subject:?} does not match {:?}",
+                RECOVER_EMAIL_SUBJECT.template
+            ),
+            EmlError::TooLong(field) => {
+                write!(f, "{field} is longer than {MAX_PACKED_SUBSTRING_LEN} bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EmlError {}
+
+/// Values a proof of a recovery email binds, read from its headers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryEmail {
+    pub request_id: String,
+    pub account_id: String,
+    /// Base58 data after `ed25519:` in the subject, which is what the circuit
+    /// packs.
+    pub new_public_key: String,
+    /// Bare sender address from the `From:` header, as written.
+    pub from_address: String,
+    /// Raw `Date:` header value.
+    pub timestamp: String,
+}
+
+/// Anchored public signals of a proof, as decimal strings in `public.json`
+/// order. The DKIM `pubkey` and `signature` limbs are not included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedPublicInputs {
+    pub request_id: Vec<String>,
+    pub account_id: Vec<String>,
+    pub new_public_key: Vec<String>,
+    pub from_address_hash: Vec<String>,
+    pub timestamp: Vec<String>,
+}
+
+impl RecoveryEmail {
+    pub fn from_eml(raw_eml: &str) -> Result<Self, EmlError> {
+        let headers = canonical_headers(raw_eml);
+        let header = |name: &'static str| {
+            headers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+                .ok_or(EmlError::MissingHeader(name))
+        };
+        let subject = header("subject")?;
+        let from = header("from")?;
+        let timestamp = header("date")?;
+
+        let template_err = || EmlError::SubjectTemplate(subject.to_string());
+        let values = RECOVER_EMAIL_SUBJECT.parse(subject).ok_or_else(template_err)?;
+        let value = |name: &str| {
+            values.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string()).ok_or_else(template_err)
+        };
+
+        Ok(Self {
+            request_id: value("request_id")?,
+            account_id: value("account_id")?,
+            new_public_key: value("new_public_key")?,
+            from_address: sender_address(from).ok_or(EmlError::MissingHeader("from"))?,
+            timestamp: timestamp.to_string(),
+        })
+    }
+
+    /// `sha256("<from_lower>|<account_id_lower>")`, as the circuit computes
+    /// it (ASCII lowercasing only).
+    pub fn from_address_hash(&self) -> [u8; 32] {
+        let preimage = format!(
+            "{}|{}",
+            self.from_address.to_ascii_lowercase(),
+            self.account_id.to_ascii_lowercase()
+        );
+        Sha256::digest(preimage.as_bytes()).into()
+    }
+
+    pub fn expected_public_inputs(&self) -> Result<ExpectedPublicInputs, EmlError> {
+        let pack = |field: &'static str, value: &str| pack_string(value).ok_or(EmlError::TooLong(field));
+        // The circuit hashes at most 255 bytes of each.
+        if self.from_address.len() > MAX_PACKED_SUBSTRING_LEN {
+            return Err(EmlError::TooLong("from_address"));
+        }
+        Ok(ExpectedPublicInputs {
+            request_id: pack("request_id", &self.request_id)?,
+            account_id: pack("account_id", &self.account_id)?,
+            new_public_key: pack("new_public_key", &self.new_public_key)?,
+            from_address_hash: self.from_address_hash().iter().map(|b| b.to_string()).collect(),
+            timestamp: pack("timestamp", &self.timestamp)?,
+        })
+    }
+}
+
+impl ExpectedPublicInputs {
+    /// Names of the fields whose signals in `public_signals` differ, e.g. to
+    /// tell whether a `public.json` was produced from this email.
+    pub fn mismatches(&self, public_signals: &[String]) -> Vec<&'static str> {
+        self.fields()
+            .into_iter()
+            .filter(|(name, expected)| {
+                let Some(actual) = RECOVER_EMAIL_LAYOUT.get(name, public_signals) else {
+                    return true;
+                };
+                // Compare as field elements so hex signals match too.
+                actual
+                    .iter()
+                    .zip(expected.iter())
+                    .any(|(a, e)| parse_fr(a).is_err() || parse_fr(a) != parse_fr(e))
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Each field's signals with its name in the circuit's layout.
+    fn fields(&self) -> [(&'static str, &Vec<String>); 5] {
+        [
+            ("request_id", &self.request_id),
+            ("account_id", &self.account_id),
+            ("new_public_key", &self.new_public_key),
+            ("from_address_hash", &self.from_address_hash),
+            ("timestamp", &self.timestamp),
+        ]
+    }
+}
+
+/// Header fields in DKIM relaxed canonical form: lowercase names, unfolded
+/// values with whitespace runs collapsed and trimmed.
+fn canonical_headers(raw_eml: &str) -> Vec<(String, String)> {
+    let head = raw_eml
+        .split("\r\n\r\n")
+        .next()
+        .and_then(|h| h.split("\n\n").next())
+        .unwrap_or_default();
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line);
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.trim().to_ascii_lowercase(), value.to_string()));
+        }
+    }
+    for (_, value) in &mut fields {
+        *value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    fields
+}
+
+/// The address in `<...>` if there is one, otherwise the first word.
+fn sender_address(from: &str) -> Option<String> {
+    if let Some(start) = from.find('<') {
+        let inner = &from[start + 1..];
+        if let Some(end) = inner.find('>') {
+            let addr = &inner[..end];
+            if !addr.is_empty() && !addr.contains(char::is_whitespace) {
+                return Some(addr.to_string());
+            }
+        }
+    }
+    from.split_whitespace().next().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EML: &str = "From: Pta <N6378056@gmail.com>\r\n\
+                       Date: Tue, 9 Dec 2025 17:13:23 +0900\r\n\
+                       Subject: recover-123abc kerp30.w3a-v1.testnet\r\n  ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm\r\n\
+                       \r\n\
+                       Subject: not a header\r\n";
+
+    #[test]
+    fn extracts_anchored_values_like_the_witness_generator() {
+        let email = RecoveryEmail::from_eml(EML).unwrap();
+        assert_eq!(
+            email,
+            RecoveryEmail {
+                request_id: "123abc".into(),
+                account_id: "kerp30.w3a-v1.testnet".into(),
+                new_public_key: "86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm".into(),
+                from_address: "N6378056@gmail.com".into(),
+                timestamp: "Tue, 9 Dec 2025 17:13:23 +0900".into(),
+            }
+        );
+
+        let bad_subjects = [
+            "Subject: hello",
+            "Subject: Recover-1 a.near ed25519:k",
+            "Subject: recover-1 a.near ed25519:",
+            "Subject: recover-1 a.near k",
+        ];
+        for subject in bad_subjects {
+            let eml = format!("From: a@b\r\nDate: x\r\n{subject}\r\n\r\n");
+            assert!(matches!(RecoveryEmail::from_eml(&eml), Err(EmlError::SubjectTemplate(_))));
+        }
+        assert_eq!(
+            RecoveryEmail::from_eml("Subject: recover-1 a ed25519:k\r\n\r\n"),
+            Err(EmlError::MissingHeader("from"))
+        );
+    }
+
+    #[test]
+    fn expected_inputs_match_their_own_public_signals() {
+        let email = RecoveryEmail::from_eml(EML).unwrap();
+        let expected = email.expected_public_inputs().unwrap();
+
+        let mut signals = vec!["0".to_string(); RECOVER_EMAIL_LAYOUT.len];
+        for (name, values) in expected.fields() {
+            signals[RECOVER_EMAIL_LAYOUT.field(name).unwrap().range()].clone_from_slice(values);
+        }
+        assert!(expected.mismatches(&signals).is_empty());
+
+        // The sender hash is case-insensitive, the account id binding isn't.
+        let other = RecoveryEmail {
+            from_address: "n6378056@GMAIL.com".into(),
+            account_id: "Kerp30.w3a-v1.testnet".into(),
+            ..email
+        };
+        assert_eq!(
+            other.expected_public_inputs().unwrap().mismatches(&signals),
+            vec!["account_id"]
+        );
+        assert_eq!(expected.mismatches(&signals[..10]).len(), 4);
+    }
+}
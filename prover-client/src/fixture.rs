@@ -0,0 +1,63 @@
+//! Test fixtures: a proof, its public signals and the values the email binds,
+//! stored together in one directory so tests don't repeat them as literals.
+//!
+//! ```text
+//! <dir>/proof.json     snarkjs proof
+//! <dir>/public.json    public signals
+//! <dir>/expected.json  RecoveryEmail
+//! ```
+//!
+//! Written by the `make-fixture` binary from an `.eml` file.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::eml::RecoveryEmail;
+use crate::ProofInput;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fixture {
+    pub proof: ProofInput,
+    pub public_signals: Vec<String>,
+    pub expected: RecoveryEmail,
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> io::Result<T> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to read {}: {e}", path.display())))?;
+    serde_json::from_str(&json).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("failed to parse {}: {e}", path.display()))
+    })
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(value).expect("fixture types serialize");
+    fs::write(path, json + "\n")
+}
+
+impl Fixture {
+    pub fn load(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        Ok(Self {
+            proof: read_json(&dir.join("proof.json"))?,
+            public_signals: read_json(&dir.join("public.json"))?,
+            expected: read_json(&dir.join("expected.json"))?,
+        })
+    }
+
+    pub fn write(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        write_json(&dir.join("proof.json"), &self.proof)?;
+        write_json(&dir.join("public.json"), &self.public_signals)?;
+        write_expected(dir, &self.expected)
+    }
+}
+
+/// Write only `expected.json`, e.g. next to a proof made by snarkjs.
+pub fn write_expected(dir: impl AsRef<Path>, expected: &RecoveryEmail) -> io::Result<()> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    write_json(&dir.join("expected.json"), expected)
+}
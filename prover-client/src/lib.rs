@@ -0,0 +1,205 @@
+//! Client for the prover server in `circom-zk-email` (`just docker-run`).
+//!
+//! ```no_run
+//! # async fn run(raw_eml: &str) -> Result<(), prover_client::ProverError> {
+//! let client = prover_client::ProverClient::new(prover_client::DEFAULT_PROVER_URL);
+//! client.health().await?;
+//! let (proof, public_signals) = client.prove_email(raw_eml).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Proving takes tens of seconds, so the default timeout is generous; check
+//! the email with [`eml::RecoveryEmail::from_eml`] first.
+//! With the `native` feature, [`native::NativeProver`] proves in-process
+//! instead.
+//! Connection failures, timeouts and 5xx responses are retried; 4xx responses
+//! (e.g. an email the circuit can't handle) are returned immediately.
+
+use std::{fmt, time::Duration};
+
+pub mod eml;
+pub mod fixture;
+#[cfg(feature = "native")]
+pub mod native;
+#[cfg(feature = "native")]
+pub mod wtns;
+
+use serde::Deserialize;
+use serde_json::json;
+
+pub use zk_email_verifier_core::ProofInput;
+
+/// Where `just docker-run` exposes the prover.
+pub const DEFAULT_PROVER_URL: &str = "http://localhost:5588";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(180);
+const DEFAULT_RETRIES: u32 = 2;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub enum ProverError {
+    /// The request could not be sent or the response could not be read.
+    Http(reqwest::Error),
+    /// The prover answered with a non-success status.
+    Status { status: u16, body: String },
+    /// `/healthz` answered, but not with `{"status": "ok"}`.
+    Unhealthy(String),
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProverError::Http(e) => write!(f, "prover request failed: {e}"),
+            ProverError::Status { status, body } => write!(f, "prover returned {status}: {body}"),
+            ProverError::Unhealthy(status) => write!(f, "prover is unhealthy: {status}"),
+        }
+    }
+}
+
+impl std::error::Error for ProverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProverError::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ProverError {
+    fn from(e: reqwest::Error) -> Self {
+        ProverError::Http(e)
+    }
+}
+
+impl ProverError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            ProverError::Http(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+            ProverError::Status { status, .. } => *status >= 500,
+            ProverError::Unhealthy(_) => false,
+        }
+    }
+}
+
+/// Response of `POST /prove-email`.
+#[derive(Deserialize)]
+struct ProveResponse {
+    proof: ProofInput,
+    #[serde(rename = "publicSignals")]
+    public_signals: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct HealthResponse {
+    status: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProverClient {
+    base_url: String,
+    http: reqwest::Client,
+    timeout: Duration,
+    retries: u32,
+    retry_delay: Duration,
+}
+
+impl ProverClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+
+    /// Per-request timeout, covering the whole proving run.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retry a failed request up to `retries` more times, waiting `delay`
+    /// before each attempt.
+    pub fn with_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Check `GET /healthz`. Not retried, so it can be used for polling.
+    pub async fn health(&self) -> Result<(), ProverError> {
+        let response = self
+            .http
+            .get(format!("{}/healthz", self.base_url))
+            .timeout(self.timeout.min(Duration::from_secs(10)))
+            .send()
+            .await?;
+        let response = check_status(response).await?;
+        let health: HealthResponse = response.json().await?;
+        if health.status == "ok" {
+            Ok(())
+        } else {
+            Err(ProverError::Unhealthy(health.status))
+        }
+    }
+
+    /// Poll `health` until the prover is up or `max_wait` has passed, e.g.
+    /// right after starting the container.
+    pub async fn wait_until_healthy(&self, max_wait: Duration) -> Result<(), ProverError> {
+        let deadline = tokio::time::Instant::now() + max_wait;
+        loop {
+            match self.health().await {
+                Ok(()) => return Ok(()),
+                Err(e) if tokio::time::Instant::now() >= deadline => return Err(e),
+                Err(_) => tokio::time::sleep(Duration::from_millis(500)).await,
+            }
+        }
+    }
+
+    /// Generate a proof for a raw `.eml` message. Returns the proof and the
+    /// public signals in `public.json` order, ready for the contract's
+    /// `verify` methods.
+    pub async fn prove_email(&self, raw_eml: &str) -> Result<(ProofInput, Vec<String>), ProverError> {
+        let body = json!({ "rawEmail": raw_eml });
+        let mut attempt = 0;
+        loop {
+            match self.prove_once(&body).await {
+                Ok(r) => return Ok((r.proof, r.public_signals)),
+                Err(e) if attempt < self.retries && e.is_retryable() => {
+                    attempt += 1;
+                    tokio::time::sleep(self.retry_delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn prove_once(&self, body: &serde_json::Value) -> Result<ProveResponse, ProverError> {
+        let response = self
+            .http
+            .post(format!("{}/prove-email", self.base_url))
+            .timeout(self.timeout)
+            .json(body)
+            .send()
+            .await?;
+        Ok(check_status(response).await?.json().await?)
+    }
+}
+
+impl Default for ProverClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROVER_URL)
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ProverError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(ProverError::Status { status: status.as_u16(), body })
+}
@@ -0,0 +1,142 @@
+//! In-process Groth16 proving from a circom `.zkey` and a witness, for
+//! relayers that want to ship a single binary instead of talking to the
+//! Node/rapidsnark prover. Enabled with the `native` feature.
+//!
+//! The witness itself still comes from circom's witness generator (the C++
+//! binary or `generate_witness.js`); this module only replaces the proving
+//! step. The `.zkey` already embeds the constraint matrices, so the `.r1cs`
+//! file is not needed.
+
+use std::{fmt, fs::File, io, path::Path};
+
+use ark_bn254::{Bn254, Fr};
+use ark_circom::{read_zkey, CircomReduction};
+use ark_ff::{PrimeField, UniformRand};
+use ark_groth16::{Groth16, Proof, ProvingKey};
+use ark_relations::r1cs::ConstraintMatrices;
+use zk_email_verifier_core::{ProofInput, SNARKJS_CURVE, SNARKJS_PROTOCOL};
+
+use crate::wtns::read_wtns;
+
+#[derive(Debug)]
+pub enum NativeProverError {
+    Io(io::Error),
+    /// The witness doesn't fit the proving key's constraint system.
+    WitnessLength { expected: usize, got: usize },
+    Proving(String),
+}
+
+impl fmt::Display for NativeProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NativeProverError::Io(e) => write!(f, "{e}"),
+            NativeProverError::WitnessLength { expected, got } => {
+                write!(f, "witness has {got} values, the circuit needs {expected}")
+            }
+            NativeProverError::Proving(e) => write!(f, "proving failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for NativeProverError {}
+
+impl From<io::Error> for NativeProverError {
+    fn from(e: io::Error) -> Self {
+        NativeProverError::Io(e)
+    }
+}
+
+/// A loaded proving key. Loading the `RecoverEmailCircuit` zkey takes a while
+/// and a lot of memory, so keep one around and reuse it across proofs.
+pub struct NativeProver {
+    pk: ProvingKey<Bn254>,
+    matrices: ConstraintMatrices<Fr>,
+}
+
+impl NativeProver {
+    pub fn from_zkey(path: impl AsRef<Path>) -> Result<Self, NativeProverError> {
+        let mut file = File::open(path)?;
+        let (pk, matrices) = read_zkey(&mut file)?;
+        Ok(Self { pk, matrices })
+    }
+
+    /// Number of witness values (including the leading constant 1) a proof needs.
+    pub fn witness_len(&self) -> usize {
+        self.matrices.num_instance_variables + self.matrices.num_witness_variables
+    }
+
+    /// Prove from a `.wtns` file.
+    pub fn prove_wtns(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(ProofInput, Vec<String>), NativeProverError> {
+        self.prove(&read_wtns(File::open(path)?)?)
+    }
+
+    /// Prove from a full witness. Returns the proof and the public signals in
+    /// `public.json` format, the same as the HTTP prover.
+    pub fn prove(&self, witness: &[Fr]) -> Result<(ProofInput, Vec<String>), NativeProverError> {
+        if witness.len() != self.witness_len() {
+            return Err(NativeProverError::WitnessLength {
+                expected: self.witness_len(),
+                got: witness.len(),
+            });
+        }
+        let mut rng = rand::thread_rng();
+        let (r, s) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &self.pk,
+            r,
+            s,
+            &self.matrices,
+            self.matrices.num_instance_variables,
+            self.matrices.num_constraints,
+            witness,
+        )
+        .map_err(|e| NativeProverError::Proving(e.to_string()))?;
+
+        // witness[0] is the constant 1; the public signals follow it.
+        let public = witness[1..self.matrices.num_instance_variables]
+            .iter()
+            .map(|v| v.into_bigint().to_string())
+            .collect();
+        Ok((to_proof_input(&proof), public))
+    }
+}
+
+/// Render a proof the way snarkjs writes `proof.json` (affine coordinates
+/// with `z = 1`), which is what the contract parses.
+fn to_proof_input(proof: &Proof<Bn254>) -> ProofInput {
+    let fq = |v: &ark_bn254::Fq| v.into_bigint().to_string();
+    let (ax, ay) = (proof.a.x, proof.a.y);
+    let (bx, by) = (proof.b.x, proof.b.y);
+    let (cx, cy) = (proof.c.x, proof.c.y);
+    ProofInput {
+        pi_a: [fq(&ax), fq(&ay), "1".to_string()],
+        pi_b: [
+            [fq(&bx.c0), fq(&bx.c1)],
+            [fq(&by.c0), fq(&by.c1)],
+            ["1".to_string(), "0".to_string()],
+        ],
+        pi_c: [fq(&cx), fq(&cy), "1".to_string()],
+        protocol: Some(SNARKJS_PROTOCOL.to_string()),
+        curve: Some(SNARKJS_CURVE.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::{AffineRepr, CurveGroup};
+
+    #[test]
+    fn proof_input_roundtrips_through_core_parser() {
+        let proof = Proof::<Bn254> {
+            a: (G1Affine::generator() * Fr::from(3u64)).into_affine(),
+            b: (G2Affine::generator() * Fr::from(5u64)).into_affine(),
+            c: (G1Affine::generator() * Fr::from(7u64)).into_affine(),
+        };
+        assert_eq!(to_proof_input(&proof).parse().unwrap(), proof);
+    }
+}
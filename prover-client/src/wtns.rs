@@ -0,0 +1,116 @@
+//! Reader for the `.wtns` witness files written by circom's witness generators
+//! (`generate_witness.js`, the C++ witness binary) and `snarkjs wtns calculate`.
+//!
+//! Layout (all integers little-endian): `"wtns"`, version `u32`, section count
+//! `u32`, then sections of `id: u32, size: u64, data`. Section 1 holds the
+//! field element size `n8: u32`, the prime `q` (`n8` bytes) and the witness
+//! count `u32`; section 2 holds the witness values, `n8` bytes each, in
+//! standard (non-Montgomery) form.
+
+use std::io::{self, Read};
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid .wtns file: {msg}"))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> io::Result<u32> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| invalid("truncated"))
+}
+
+/// Read a BN254 witness. Fails if the file is for a different field or
+/// contains a value that is not a canonical field element.
+pub fn read_wtns(mut reader: impl Read) -> io::Result<Vec<Fr>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.get(..4) != Some(b"wtns") {
+        return Err(invalid("bad magic"));
+    }
+    let n_sections = read_u32(&bytes, 8)?;
+
+    let mut header = None;
+    let mut values = None;
+    let mut pos = 12;
+    for _ in 0..n_sections {
+        let id = read_u32(&bytes, pos)?;
+        let size = bytes
+            .get(pos + 4..pos + 12)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize)
+            .ok_or_else(|| invalid("truncated"))?;
+        let data = bytes
+            .get(pos + 12..pos + 12 + size)
+            .ok_or_else(|| invalid("truncated section"))?;
+        match id {
+            1 => header = Some(data),
+            2 => values = Some(data),
+            _ => {}
+        }
+        pos += 12 + size;
+    }
+    let header = header.ok_or_else(|| invalid("missing header section"))?;
+    let values = values.ok_or_else(|| invalid("missing witness section"))?;
+
+    let n8 = read_u32(header, 0)? as usize;
+    let modulus = Fr::MODULUS.to_bytes_le();
+    if n8 != modulus.len() || header.get(4..4 + n8) != Some(&modulus[..]) {
+        return Err(invalid("witness is not over the BN254 scalar field"));
+    }
+    let count = read_u32(header, 4 + n8)? as usize;
+    if values.len() != count * n8 {
+        return Err(invalid("witness section size does not match the header"));
+    }
+
+    values
+        .chunks(n8)
+        .map(|chunk| {
+            let value = Fr::from_le_bytes_mod_order(chunk);
+            if value.into_bigint().to_bytes_le() == chunk {
+                Ok(value)
+            } else {
+                Err(invalid("non-canonical witness value"))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wtns(values: &[Vec<u8>]) -> Vec<u8> {
+        let modulus = Fr::MODULUS.to_bytes_le();
+        let mut header = 32u32.to_le_bytes().to_vec();
+        header.extend_from_slice(&modulus);
+        header.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        let data: Vec<u8> = values.concat();
+
+        let mut out = b"wtns".to_vec();
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&2u32.to_le_bytes());
+        for (id, section) in [(1u32, header), (2u32, data)] {
+            out.extend_from_slice(&id.to_le_bytes());
+            out.extend_from_slice(&(section.len() as u64).to_le_bytes());
+            out.extend_from_slice(&section);
+        }
+        out
+    }
+
+    #[test]
+    fn reads_witness_values() {
+        let one = Fr::from(1u64).into_bigint().to_bytes_le();
+        let big = Fr::from(123_456_789u64).into_bigint().to_bytes_le();
+        let witness = read_wtns(&wtns(&[one, big])[..]).unwrap();
+        assert_eq!(witness, vec![Fr::from(1u64), Fr::from(123_456_789u64)]);
+
+        // The modulus itself is not a canonical element.
+        assert!(read_wtns(&wtns(&[Fr::MODULUS.to_bytes_le()])[..]).is_err());
+        let file = wtns(&[Fr::from(1u64).into_bigint().to_bytes_le()]);
+        assert!(read_wtns(&file[..file.len() - 1]).is_err());
+        assert!(read_wtns(&b"r1cs"[..]).is_err());
+    }
+}
@@ -0,0 +1,1399 @@
+//! Typed client for the verifier contract on top of `near-workspaces`.
+//!
+//! Builds the JSON (or Borsh) arguments and decodes the results into the
+//! contract's own types, so relayers and integration tests don't hand-write
+//! `json!({ "proof": ..., "public_inputs": ... })` for every call.
+//!
+//! ```no_run
+//! # async fn run(
+//! #     wasm: &[u8],
+//! #     proof: zk_email_verifier_client::ProofInput,
+//! #     public_inputs: Vec<String>,
+//! # ) -> Result<(), zk_email_verifier_client::ClientError> {
+//! use zk_email_verifier_client::VerifierClient;
+//!
+//! let worker = near_workspaces::sandbox().await?;
+//! let client = VerifierClient::deploy(&worker, wasm).await?;
+//! let result = client.verify(&proof, &public_inputs).await?;
+//! assert!(result.verified);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use near_workspaces::result::{ExecutionFailure, ExecutionSuccess};
+use near_workspaces::{
+    types::{Gas, NearToken},
+    Account, AccountId, Contract, Worker,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+pub use zk_email_verifier_contract::attestation::{Attestation, ExportedAttestation};
+pub use zk_email_verifier_contract::audit::VerificationRecord;
+pub use zk_email_verifier_contract::binding::BindingOptions;
+pub use zk_email_verifier_contract::breaker::CircuitBreakerConfig;
+pub use zk_email_verifier_contract::challenges::Challenge;
+pub use zk_email_verifier_contract::bundle::RecoveryBundleResult;
+pub use zk_email_verifier_contract::circuits::{CircuitStatus, SupportedCircuit};
+pub use zk_email_verifier_contract::cooldown::RecoveryStatus;
+pub use zk_email_verifier_contract::diagnostics::DryRunReport;
+pub use zk_email_verifier_contract::email_change::PendingEmailChange;
+pub use zk_email_verifier_contract::email_template::EmailTemplate;
+pub use zk_email_verifier_contract::gas_estimate::GasBenchmark;
+pub use zk_email_verifier_contract::guardian_epochs::GuardianSet;
+pub use zk_email_verifier_contract::guardian_recovery::{GuardianRecovery, GuardianWeight, GuardianWeights};
+pub use zk_email_verifier_contract::guardians::PendingGuardianRemoval;
+pub use zk_email_verifier_contract::health::HealthCheck;
+pub use zk_email_verifier_contract::info::VerifierInfo;
+pub use zk_email_verifier_contract::key_permission::KeyPermission;
+pub use zk_email_verifier_contract::layout::{PublicInputLayout, SubjectTemplate};
+pub use zk_email_verifier_contract::migration::PendingMigration;
+pub use zk_email_verifier_contract::network::NetworkProfile;
+pub use zk_email_verifier_contract::nullifiers::NullifierRoot;
+pub use zk_email_verifier_contract::rate_limit::RecoveryRateLimit;
+pub use zk_email_verifier_contract::recovery_policy::RecoveryPolicy;
+pub use zk_email_verifier_contract::result_accumulator::ResultRoot;
+pub use zk_email_verifier_contract::spending_limit::PendingSpendingLimit;
+pub use zk_email_verifier_contract::state_export::{StateChunk, StateSection};
+pub use zk_email_verifier_contract::stats::Stats;
+pub use zk_email_verifier_contract::treasury::RelayerReward;
+pub use zk_email_verifier_contract::merkle::{self, Hash};
+pub use zk_email_verifier_contract::{
+    BatchProofInput, InitialVerifyingKey, ProofInput, VerificationResult, VerifyingKeyInput,
+    VerifyingKeyJson,
+};
+
+/// Gas attached to change calls. `verify_staged` forwards whatever is left to
+/// its callback, so this is simply the transaction maximum.
+const CALL_GAS: Gas = Gas::from_tgas(300);
+
+/// Attached to calls that store an entry for their caller. The contract keeps
+/// what the entry's storage costs and refunds the rest.
+const STORAGE_DEPOSIT: NearToken = NearToken::from_millinear(50);
+
+/// Page size when reading every leaf of a Merkle tree; the contract's
+/// maximum.
+const LEAF_PAGE: u32 = 1_000;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The RPC request failed or the result didn't decode.
+    Workspaces(near_workspaces::error::Error),
+    /// The transaction executed but failed (e.g. a contract panic).
+    Execution(Box<ExecutionFailure>),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Workspaces(e) => write!(f, "{e}"),
+            ClientError::Execution(e) => write!(f, "transaction failed: {e:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<near_workspaces::error::Error> for ClientError {
+    fn from(e: near_workspaces::error::Error) -> Self {
+        ClientError::Workspaces(e)
+    }
+}
+
+impl From<ExecutionFailure> for ClientError {
+    fn from(e: ExecutionFailure) -> Self {
+        ClientError::Execution(Box::new(e))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// The values `verify_with_binding` and `dry_run_verify` compare against the
+/// public inputs.
+#[derive(Clone, Debug, Default)]
+pub struct Binding {
+    /// Checked by `verify_with_binding` only; `dry_run_verify` takes it
+    /// separately.
+    pub request_id: String,
+    pub account_id: String,
+    pub new_public_key: String,
+    /// Raw `Date:` header value.
+    pub timestamp: String,
+    pub options: Option<BindingOptions>,
+    /// Raw `From:` header, needed by `verify_with_binding` once the contract
+    /// has a sender-domain allowlist. `dry_run_verify` takes it separately.
+    pub from_header: Option<String>,
+}
+
+/// Proof that `leaf` is leaf `index` of the nullifier tree or the result
+/// accumulator with `root`, for a light client that only trusts the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub leaf: Hash,
+    pub index: u64,
+    pub root: Hash,
+    /// Siblings from the leaf level up.
+    pub siblings: Vec<Hash>,
+}
+
+impl InclusionProof {
+    pub fn verify(&self) -> bool {
+        merkle::verify_inclusion(&self.root, &self.leaf, self.index, &self.siblings, sha256)
+    }
+}
+
+/// A deployed verifier contract.
+pub struct VerifierClient {
+    contract: Contract,
+}
+
+impl VerifierClient {
+    /// Wrap an already deployed and initialized contract.
+    pub fn new(contract: Contract) -> Self {
+        Self { contract }
+    }
+
+    /// Deploy `wasm` to a fresh dev account and call `new`. The dev account
+    /// becomes the owner (`client.contract().as_account()`).
+    pub async fn deploy<T>(worker: &Worker<T>, wasm: &[u8]) -> Result<Self>
+    where
+        T: near_workspaces::Network + near_workspaces::network::TopLevelAccountCreator + 'static,
+    {
+        let contract = worker.dev_deploy(wasm).await?;
+        contract
+            .call("new")
+            .args_json(json!({}))
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(Self { contract })
+    }
+
+    /// Like [`deploy`](Self::deploy), but initializes the contract with `vk`
+    /// through `new_with_vk` instead of the key compiled into `wasm`.
+    pub async fn deploy_with_vk<T>(
+        worker: &Worker<T>,
+        wasm: &[u8],
+        vk: &InitialVerifyingKey,
+    ) -> Result<Self>
+    where
+        T: near_workspaces::Network + near_workspaces::network::TopLevelAccountCreator + 'static,
+    {
+        let contract = worker.dev_deploy(wasm).await?;
+        contract
+            .call("new_with_vk")
+            .args_json(json!({ "vk": vk }))
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(Self { contract })
+    }
+
+    /// Like [`deploy`](Self::deploy), but through `new_for_import`: the
+    /// contract accepts [`import_state`](Self::import_state) and refuses
+    /// proofs until [`activate`](Self::activate).
+    pub async fn deploy_for_import<T>(worker: &Worker<T>, wasm: &[u8]) -> Result<Self>
+    where
+        T: near_workspaces::Network + near_workspaces::network::TopLevelAccountCreator + 'static,
+    {
+        let contract = worker.dev_deploy(wasm).await?;
+        contract
+            .call("new_for_import")
+            .args_json(json!({}))
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(Self { contract })
+    }
+
+    pub fn id(&self) -> &AccountId {
+        self.contract.id()
+    }
+
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    async fn view<T: DeserializeOwned>(&self, method: &str, args: impl Serialize) -> Result<T> {
+        Ok(self.contract.view(method).args_json(args).await?.json()?)
+    }
+
+    async fn call(
+        &self,
+        caller: &Account,
+        method: &str,
+        args: impl Serialize,
+    ) -> Result<ExecutionSuccess> {
+        self.call_with_deposit(caller, method, args, NearToken::from_yoctonear(0))
+            .await
+    }
+
+    async fn call_with_deposit(
+        &self,
+        caller: &Account,
+        method: &str,
+        args: impl Serialize,
+        deposit: NearToken,
+    ) -> Result<ExecutionSuccess> {
+        Ok(caller
+            .call(self.contract.id(), method)
+            .args_json(args)
+            .deposit(deposit)
+            .gas(CALL_GAS)
+            .transact()
+            .await?
+            .into_result()?)
+    }
+
+    pub async fn get_owner_id(&self) -> Result<AccountId> {
+        self.view("get_owner_id", json!({})).await
+    }
+
+    pub async fn get_verifier_info(&self) -> Result<VerifierInfo> {
+        self.view("get_verifier_info", json!({})).await
+    }
+
+    pub async fn supports_method(&self, method_name: &str) -> Result<bool> {
+        self.view("supports_method", json!({ "method_name": method_name }))
+            .await
+    }
+
+    pub async fn get_verifying_key_json(&self) -> Result<VerifyingKeyJson> {
+        self.view("get_verifying_key_json", json!({})).await
+    }
+
+    pub async fn verify(&self, proof: &ProofInput, public_inputs: &[String]) -> Result<VerificationResult> {
+        self.view("verify", json!({ "proof": proof, "public_inputs": public_inputs }))
+            .await
+    }
+
+    /// `verify` with the public inputs laid out by `layout_id`, for proofs of a
+    /// circuit registered with its own layout.
+    pub async fn verify_with_layout(
+        &self,
+        proof: &ProofInput,
+        public_inputs: &[String],
+        layout_id: &str,
+    ) -> Result<VerificationResult> {
+        self.view(
+            "verify",
+            json!({ "proof": proof, "public_inputs": public_inputs, "layout_id": layout_id }),
+        )
+        .await
+    }
+
+    /// `blob` is the base64 envelope from
+    /// `zk_email_verifier_core::blob::encode_blob`.
+    pub async fn verify_blob(&self, blob: &str) -> Result<VerificationResult> {
+        self.view("verify_blob", json!({ "blob": blob })).await
+    }
+
+    pub async fn verify_batch(&self, proofs: &[BatchProofInput]) -> Result<Vec<VerificationResult>> {
+        self.view("verify_batch", json!({ "proofs": proofs })).await
+    }
+
+    /// Verify a recovery proof together with an optional cancellation of the
+    /// same request; a matching cancellation rejects the recovery. Either way
+    /// a verified recovery consumes its request, so `caller` pays for the
+    /// transaction.
+    pub async fn verify_recovery_bundle(
+        &self,
+        caller: &Account,
+        recovery: &BatchProofInput,
+        cancellation: Option<&BatchProofInput>,
+    ) -> Result<RecoveryBundleResult> {
+        Ok(self
+            .call(
+                caller,
+                "verify_recovery_bundle",
+                json!({ "recovery": recovery, "cancellation": cancellation }),
+            )
+            .await?
+            .json()?)
+    }
+
+    pub async fn verify_with_binding(
+        &self,
+        proof: &ProofInput,
+        public_inputs: &[String],
+        binding: &Binding,
+    ) -> Result<VerificationResult> {
+        self.view(
+            "verify_with_binding",
+            json!({
+                "proof": proof,
+                "public_inputs": public_inputs,
+                "request_id": binding.request_id,
+                "account_id": binding.account_id,
+                "new_public_key": binding.new_public_key,
+                "timestamp": binding.timestamp,
+                "options": binding.options,
+                "from_header": binding.from_header,
+            }),
+        )
+        .await
+    }
+
+    /// `verify_with_binding` with SHA-256 digests of the bound values instead
+    /// of the plaintext; see the contract method for what each digest covers.
+    pub async fn verify_with_hashed_binding(
+        &self,
+        proof: &ProofInput,
+        public_inputs: &[String],
+        account_id_hash: &[u8],
+        new_public_key_hash: &[u8],
+        timestamp_hash: &[u8],
+    ) -> Result<VerificationResult> {
+        self.view(
+            "verify_with_hashed_binding",
+            json!({
+                "proof": proof,
+                "public_inputs": public_inputs,
+                "account_id_hash": account_id_hash,
+                "new_public_key_hash": new_public_key_hash,
+                "timestamp_hash": timestamp_hash,
+            }),
+        )
+        .await
+    }
+
+    /// See the contract's `dry_run_verify`; `from_header` and `request_id`
+    /// add optional extra bindings to the report.
+    pub async fn dry_run_verify(
+        &self,
+        proof: &ProofInput,
+        public_inputs: &[String],
+        binding: &Binding,
+        from_header: Option<&str>,
+        request_id: Option<&str>,
+    ) -> Result<DryRunReport> {
+        self.view(
+            "dry_run_verify",
+            json!({
+                "proof": proof,
+                "public_inputs": public_inputs,
+                "account_id": binding.account_id,
+                "new_public_key": binding.new_public_key,
+                "timestamp": binding.timestamp,
+                "from_header": from_header,
+                "request_id": request_id,
+                "options": binding.options,
+            }),
+        )
+        .await
+    }
+
+    /// Verify across two receipts; needs a transaction, so `caller` pays for it.
+    pub async fn verify_staged(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<VerificationResult> {
+        let outcome = self
+            .call(
+                caller,
+                "verify_staged",
+                json!({ "proof": proof, "public_inputs": public_inputs }),
+            )
+            .await?;
+        Ok(outcome.json()?)
+    }
+
+    /// [`verify_staged`](Self::verify_staged) with the verification fee
+    /// attached; see [`get_verification_fee`](Self::get_verification_fee).
+    pub async fn verify_staged_with_fee(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<VerificationResult> {
+        let fee = self.get_verification_fee().await?;
+        let outcome = self
+            .call_with_deposit(
+                caller,
+                "verify_staged",
+                json!({ "proof": proof, "public_inputs": public_inputs }),
+                fee,
+            )
+            .await?;
+        Ok(outcome.json()?)
+    }
+
+    pub async fn get_verification_fee(&self) -> Result<NearToken> {
+        self.view("get_verification_fee", json!({})).await
+    }
+
+    /// Charge `fee` per `verify_staged` call. `owner` must be the contract
+    /// owner.
+    pub async fn set_verification_fee(&self, owner: &Account, fee: NearToken) -> Result<()> {
+        self.call(owner, "set_verification_fee", json!({ "fee": fee }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_relayer_reward(&self) -> Result<Option<RelayerReward>> {
+        self.view("get_relayer_reward", json!({})).await
+    }
+
+    /// Pay `reward` to the submitter of every finalized recovery, or stop
+    /// with `None`. `owner` must be the contract owner.
+    pub async fn set_relayer_reward(&self, owner: &Account, reward: Option<RelayerReward>) -> Result<()> {
+        self.call(owner, "set_relayer_reward", json!({ "reward": reward }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_treasury_balance(&self) -> Result<NearToken> {
+        self.view("get_treasury_balance", json!({})).await
+    }
+
+    /// Send `amount` of the collected fees to `to`. `owner` must be the
+    /// contract owner.
+    pub async fn withdraw_treasury(&self, owner: &Account, amount: NearToken, to: &AccountId) -> Result<()> {
+        self.call(owner, "withdraw_treasury", json!({ "amount": amount, "to": to }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_sponsor_balance(&self, dapp_id: &AccountId) -> Result<NearToken> {
+        self.view("get_sponsor_balance", json!({ "dapp_id": dapp_id })).await
+    }
+
+    /// Add `amount` from `sponsor` to the fee pool of `dapp_id`. Returns the
+    /// new pool balance.
+    pub async fn sponsor_deposit(&self, sponsor: &Account, dapp_id: &AccountId, amount: NearToken) -> Result<NearToken> {
+        Ok(self
+            .call_with_deposit(sponsor, "sponsor_deposit", json!({ "dapp_id": dapp_id }), amount)
+            .await?
+            .json()?)
+    }
+
+    /// Send `amount` from the pool of `dapp` back to it.
+    pub async fn sponsor_withdraw(&self, dapp: &Account, amount: NearToken) -> Result<()> {
+        self.call(dapp, "sponsor_withdraw", json!({ "amount": amount }))
+            .await?;
+        Ok(())
+    }
+
+    /// Close the pool of `dapp`, sending it the balance and freed storage.
+    pub async fn sponsor_close(&self, dapp: &Account) -> Result<()> {
+        self.call(dapp, "sponsor_close", json!({})).await?;
+        Ok(())
+    }
+
+    /// `verify_staged` with `attest: true`. Also returns the attestation id if
+    /// the proof verified and an attestation was stored.
+    pub async fn verify_staged_attested(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<(VerificationResult, Option<u64>)> {
+        let outcome = self
+            .call(
+                caller,
+                "verify_staged",
+                json!({ "proof": proof, "public_inputs": public_inputs, "attest": true }),
+            )
+            .await?;
+        let id = outcome
+            .logs()
+            .iter()
+            .find_map(|log| log.strip_prefix("attestation ")?.parse().ok());
+        Ok((outcome.json()?, id))
+    }
+
+    pub async fn get_attestation(&self, id: u64) -> Result<Option<Attestation>> {
+        self.view("get_attestation", json!({ "id": id })).await
+    }
+
+    pub async fn health_check(&self) -> Result<HealthCheck> {
+        self.view("health_check", json!({})).await
+    }
+
+    pub async fn get_stats(&self) -> Result<Stats> {
+        self.view("get_stats", json!({})).await
+    }
+
+    /// Most recent stateful verifications, newest first.
+    pub async fn get_recent_verifications(&self, limit: Option<u32>) -> Result<Vec<VerificationRecord>> {
+        self.view("get_recent_verifications", json!({ "limit": limit }))
+            .await
+    }
+
+    /// Verified records of `account_id`, oldest first.
+    pub async fn get_verifications_for_account(
+        &self,
+        account_id: &str,
+        from: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<Vec<VerificationRecord>> {
+        self.view(
+            "get_verifications_for_account",
+            json!({ "account_id": account_id, "from": from, "limit": limit }),
+        )
+        .await
+    }
+
+    /// The attestation `id` in the cross-chain export format.
+    pub async fn export_attestation(&self, id: u64) -> Result<Option<ExportedAttestation>> {
+        self.view("export_attestation", json!({ "id": id })).await
+    }
+
+    pub async fn get_supported_circuits(&self) -> Result<Vec<SupportedCircuit>> {
+        self.view("get_supported_circuits", json!({})).await
+    }
+
+    pub async fn get_domain_circuit(&self, domain: &str) -> Result<Option<String>> {
+        self.view("get_domain_circuit", json!({ "domain": domain })).await
+    }
+
+    /// Register or remove (`None`) an additional circuit's key, laid out by
+    /// `layout_id` (default `RecoverEmailCircuit`). `owner` must be the
+    /// contract owner.
+    pub async fn set_circuit_verifying_key(
+        &self,
+        owner: &Account,
+        circuit_id: &str,
+        vk: Option<&VerifyingKeyInput>,
+        layout_id: Option<&str>,
+    ) -> Result<()> {
+        self.call(
+            owner,
+            "set_circuit_verifying_key",
+            json!({ "circuit_id": circuit_id, "vk": vk, "layout_id": layout_id }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_domain_circuit(&self, owner: &Account, domain: &str, circuit_id: Option<&str>) -> Result<()> {
+        self.call(
+            owner,
+            "set_domain_circuit",
+            json!({ "domain": domain, "circuit_id": circuit_id }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Gas a `verify` of `input_len` public inputs against `circuit_id` is
+    /// expected to burn; `None` for an unknown circuit.
+    pub async fn estimate_verify_gas(&self, circuit_id: &str, input_len: u32) -> Result<Option<Gas>> {
+        self.view(
+            "estimate_verify_gas",
+            json!({ "circuit_id": circuit_id, "input_len": input_len }),
+        )
+        .await
+    }
+
+    /// Store the measured verification cost of `circuit_id`, or restore the
+    /// defaults with `None`. `owner` must be the contract owner.
+    pub async fn set_gas_benchmark(
+        &self,
+        owner: &Account,
+        circuit_id: &str,
+        benchmark: Option<&GasBenchmark>,
+    ) -> Result<()> {
+        self.call(
+            owner,
+            "set_gas_benchmark",
+            json!({ "circuit_id": circuit_id, "benchmark": benchmark }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_predecessor_binding(&self) -> Result<bool> {
+        self.view("get_predecessor_binding", json!({})).await
+    }
+
+    /// Only accept `verify_staged` from the recovering account or its
+    /// relayer. `owner` must be the contract owner.
+    pub async fn set_predecessor_binding(&self, owner: &Account, enabled: bool) -> Result<()> {
+        self.call(owner, "set_predecessor_binding", json!({ "enabled": enabled }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_account_relayer(&self, account_id: &str) -> Result<Option<AccountId>> {
+        self.view("get_account_relayer", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// Register the relayer allowed to submit recoveries of `account_id`, or
+    /// remove it with `None`. `owner` must be the contract owner.
+    pub async fn set_account_relayer(
+        &self,
+        owner: &Account,
+        account_id: &str,
+        relayer_id: Option<&AccountId>,
+    ) -> Result<()> {
+        self.call(
+            owner,
+            "set_account_relayer",
+            json!({ "account_id": account_id, "relayer_id": relayer_id }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Let `relayer_id` submit recoveries of `account`, and nobody else but
+    /// `account` itself.
+    pub async fn authorize_relayer(&self, account: &Account, relayer_id: &AccountId) -> Result<()> {
+        let args = json!({ "relayer_id": relayer_id });
+        self.call_with_deposit(account, "authorize_relayer", args, STORAGE_DEPOSIT)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn revoke_relayer(&self, account: &Account) -> Result<()> {
+        self.call(account, "revoke_relayer", json!({})).await?;
+        Ok(())
+    }
+
+    /// When `account_id` was last recovered and until when it is cooling
+    /// down.
+    pub async fn get_recovery_status(&self, account_id: &str) -> Result<RecoveryStatus> {
+        self.view("get_recovery_status", json!({ "account_id": account_id }))
+            .await
+    }
+
+    pub async fn get_recovery_cooldown_ms(&self) -> Result<Option<u64>> {
+        self.view("get_recovery_cooldown_ms", json!({})).await
+    }
+
+    /// Require `cooldown_ms` between two recoveries of an account, or drop
+    /// it with `None`. `owner` must be the contract owner.
+    pub async fn set_recovery_cooldown_ms(&self, owner: &Account, cooldown_ms: Option<u64>) -> Result<()> {
+        self.call(owner, "set_recovery_cooldown_ms", json!({ "cooldown_ms": cooldown_ms }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_recovery_rate_limit(&self) -> Result<Option<RecoveryRateLimit>> {
+        self.view("get_recovery_rate_limit", json!({})).await
+    }
+
+    /// Cap recoveries per window across all accounts, or lift the cap with
+    /// `None`. `owner` must be the contract owner.
+    pub async fn set_recovery_rate_limit(
+        &self,
+        owner: &Account,
+        limit: Option<&RecoveryRateLimit>,
+    ) -> Result<()> {
+        self.call(owner, "set_recovery_rate_limit", json!({ "limit": limit }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_rate_limit_bypass_accounts(&self) -> Result<Vec<AccountId>> {
+        self.view("get_rate_limit_bypass_accounts", json!({})).await
+    }
+
+    /// Grant or revoke the recovery cap bypass of `account_id`. `owner` must
+    /// be the contract owner.
+    pub async fn set_rate_limit_bypass(
+        &self,
+        owner: &Account,
+        account_id: &AccountId,
+        bypass: bool,
+    ) -> Result<()> {
+        self.call(
+            owner,
+            "set_rate_limit_bypass",
+            json!({ "account_id": account_id, "bypass": bypass }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_vk_registry(&self) -> Result<Option<AccountId>> {
+        self.view("get_vk_registry", json!({})).await
+    }
+
+    /// Point `verify_staged` at a verifying key registry, or stop reading
+    /// from one with `None`. `owner` must be the contract owner.
+    pub async fn set_vk_registry(&self, owner: &Account, registry: Option<&AccountId>) -> Result<()> {
+        self.call(owner, "set_vk_registry", json!({ "registry": registry }))
+            .await?;
+        Ok(())
+    }
+
+    /// The cached outcome of a verified `request_id`, for a relayer that
+    /// lost its transaction receipt.
+    pub async fn get_cached_result(&self, request_id: &str) -> Result<Option<VerificationResult>> {
+        self.view("get_cached_result", json!({ "request_id": request_id }))
+            .await
+    }
+
+    pub async fn get_result_cache_ttl_ms(&self) -> Result<Option<u64>> {
+        self.view("get_result_cache_ttl_ms", json!({})).await
+    }
+
+    /// Cache `verify_staged` results by proof for `ttl_ms`, or stop with
+    /// `None`. `owner` must be the contract owner.
+    pub async fn set_result_cache_ttl_ms(&self, owner: &Account, ttl_ms: Option<u64>) -> Result<()> {
+        self.call(owner, "set_result_cache_ttl_ms", json!({ "ttl_ms": ttl_ms }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_public_input_layout(&self, circuit_id: &str) -> Result<Option<PublicInputLayout>> {
+        self.view("get_public_input_layout", json!({ "circuit_id": circuit_id }))
+            .await
+    }
+
+    pub async fn get_subject_template(&self, circuit_id: &str) -> Result<Option<SubjectTemplate>> {
+        self.view("get_subject_template", json!({ "circuit_id": circuit_id }))
+            .await
+    }
+
+    /// The recovery email moving `account_id` to `new_public_key`, with a
+    /// fresh request id.
+    pub async fn get_email_template(&self, account_id: &str, new_public_key: &str) -> Result<Option<EmailTemplate>> {
+        self.view(
+            "get_email_template",
+            json!({ "account_id": account_id, "new_public_key": new_public_key }),
+        )
+        .await
+    }
+
+    /// Issue a request id for `account_id` and record it as an open
+    /// challenge. `caller` must be the account or its relayer, and pays for
+    /// the challenge's storage.
+    pub async fn generate_request_id(&self, caller: &Account, account_id: &str) -> Result<String> {
+        let args = json!({ "account_id": account_id });
+        Ok(self
+            .call_with_deposit(caller, "generate_request_id", args, STORAGE_DEPOSIT)
+            .await?
+            .json()?)
+    }
+
+    pub async fn get_challenge(&self, request_id: &str) -> Result<Option<Challenge>> {
+        self.view("get_challenge", json!({ "request_id": request_id })).await
+    }
+
+    pub async fn get_registered_email(&self, account_id: &str) -> Result<Option<Vec<u8>>> {
+        self.view("get_registered_email", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// Register `from_address_hash` as the email of `account`.
+    pub async fn register_email(&self, account: &Account, from_address_hash: &[u8]) -> Result<()> {
+        let args = json!({ "from_address_hash": from_address_hash });
+        self.call_with_deposit(account, "register_email", args, STORAGE_DEPOSIT)
+            .await?;
+        Ok(())
+    }
+
+    /// Verify a `register-` email and register its sender as the account's
+    /// email. `caller` must be the account or its relayer.
+    pub async fn register_email_with_proof(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<Vec<u8>> {
+        Ok(self
+            .call(
+                caller,
+                "register_email_with_proof",
+                json!({ "proof": proof, "public_inputs": public_inputs }),
+            )
+            .await?
+            .json()?)
+    }
+
+    /// Verify a `change-email-` email from the registered address.
+    pub async fn initiate_email_change(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<PendingEmailChange> {
+        Ok(self
+            .call(
+                caller,
+                "initiate_email_change",
+                json!({ "proof": proof, "public_inputs": public_inputs }),
+            )
+            .await?
+            .json()?)
+    }
+
+    /// Verify the `confirm-email-` email from the new address.
+    pub async fn confirm_email_change(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<()> {
+        self.call(
+            caller,
+            "confirm_email_change",
+            json!({ "proof": proof, "public_inputs": public_inputs }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn cancel_email_change(&self, account: &Account) -> Result<()> {
+        self.call(account, "cancel_email_change", json!({})).await?;
+        Ok(())
+    }
+
+    pub async fn get_pending_email_change(&self, account_id: &str) -> Result<Option<PendingEmailChange>> {
+        self.view("get_pending_email_change", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// Verify a `migrate-` email and start its timelock; `caller` pays for
+    /// the transaction.
+    pub async fn initiate_migration(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<PendingMigration> {
+        Ok(self
+            .call(
+                caller,
+                "initiate_migration",
+                json!({ "proof": proof, "public_inputs": public_inputs }),
+            )
+            .await?
+            .json()?)
+    }
+
+    pub async fn cancel_migration(&self, account: &Account) -> Result<()> {
+        self.call(account, "cancel_migration", json!({})).await?;
+        Ok(())
+    }
+
+    pub async fn finalize_migration(&self, caller: &Account, account_id: &str) -> Result<PendingMigration> {
+        Ok(self
+            .call(caller, "finalize_migration", json!({ "account_id": account_id }))
+            .await?
+            .json()?)
+    }
+
+    pub async fn get_pending_migration(&self, account_id: &str) -> Result<Option<PendingMigration>> {
+        self.view("get_pending_migration", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// `owner` must be the contract owner.
+    pub async fn set_migration_timelock_ms(&self, owner: &Account, timelock_ms: u64) -> Result<()> {
+        self.call(owner, "set_migration_timelock_ms", json!({ "timelock_ms": timelock_ms }))
+            .await?;
+        Ok(())
+    }
+
+    /// Verify a `lock-` email; returns when the account's lock ends.
+    pub async fn lock_account(&self, caller: &Account, proof: &ProofInput, public_inputs: &[String]) -> Result<u64> {
+        Ok(self
+            .call(caller, "lock_account", json!({ "proof": proof, "public_inputs": public_inputs }))
+            .await?
+            .json()?)
+    }
+
+    pub async fn get_account_lock(&self, account_id: &str) -> Result<Option<u64>> {
+        self.view("get_account_lock", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// `owner` must be the contract owner.
+    pub async fn set_lock_duration_ms(&self, owner: &Account, duration_ms: u64) -> Result<()> {
+        self.call(owner, "set_lock_duration_ms", json!({ "duration_ms": duration_ms }))
+            .await?;
+        Ok(())
+    }
+
+    /// Verify a `limit-` email and start its timelock; `caller` pays for the
+    /// transaction.
+    pub async fn initiate_spending_limit(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<PendingSpendingLimit> {
+        Ok(self
+            .call(
+                caller,
+                "initiate_spending_limit",
+                json!({ "proof": proof, "public_inputs": public_inputs }),
+            )
+            .await?
+            .json()?)
+    }
+
+    pub async fn cancel_spending_limit(&self, account: &Account) -> Result<()> {
+        self.call(account, "cancel_spending_limit", json!({})).await?;
+        Ok(())
+    }
+
+    /// Forward the pending limit of `account_id` to the controller contract.
+    pub async fn finalize_spending_limit(&self, caller: &Account, account_id: &str) -> Result<()> {
+        self.call(caller, "finalize_spending_limit", json!({ "account_id": account_id }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_pending_spending_limit(&self, account_id: &str) -> Result<Option<PendingSpendingLimit>> {
+        self.view("get_pending_spending_limit", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// `owner` must be the contract owner.
+    pub async fn set_controller_contract(&self, owner: &Account, controller: Option<&AccountId>) -> Result<()> {
+        self.call(owner, "set_controller_contract", json!({ "controller": controller }))
+            .await?;
+        Ok(())
+    }
+
+    /// Verify an `add-guardian-` email; returns the account's guardian set.
+    pub async fn add_guardian(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<Vec<Vec<u8>>> {
+        Ok(self
+            .call(caller, "add_guardian", json!({ "proof": proof, "public_inputs": public_inputs }))
+            .await?
+            .json()?)
+    }
+
+    /// Verify a `remove-guardian-` email and start the removal delay.
+    pub async fn remove_guardian(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<PendingGuardianRemoval> {
+        Ok(self
+            .call(caller, "remove_guardian", json!({ "proof": proof, "public_inputs": public_inputs }))
+            .await?
+            .json()?)
+    }
+
+    /// Verify a `veto-` email from another guardian of the account.
+    pub async fn veto_guardian_removal(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+    ) -> Result<()> {
+        self.call(
+            caller,
+            "veto_guardian_removal",
+            json!({ "proof": proof, "public_inputs": public_inputs }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn finalize_guardian_removal(
+        &self,
+        caller: &Account,
+        account_id: &str,
+        guardian_hash: &[u8],
+    ) -> Result<()> {
+        self.call(
+            caller,
+            "finalize_guardian_removal",
+            json!({ "account_id": account_id, "guardian_hash": guardian_hash }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_pending_guardian_removals(&self, account_id: &str) -> Result<Vec<PendingGuardianRemoval>> {
+        self.view("get_pending_guardian_removals", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// `owner` must be the contract owner.
+    pub async fn set_guardian_removal_delay_ms(&self, owner: &Account, delay_ms: u64) -> Result<()> {
+        self.call(owner, "set_guardian_removal_delay_ms", json!({ "delay_ms": delay_ms }))
+            .await?;
+        Ok(())
+    }
+
+    /// Verify a guardian's `recover-` email and count its approval.
+    /// `from_header` is the email's `From:` header, for the contract's
+    /// sender policy.
+    pub async fn submit_guardian_proof(
+        &self,
+        caller: &Account,
+        proof: &ProofInput,
+        public_inputs: &[String],
+        from_header: Option<&str>,
+    ) -> Result<GuardianRecovery> {
+        Ok(self
+            .call(
+                caller,
+                "submit_guardian_proof",
+                json!({ "proof": proof, "public_inputs": public_inputs, "from_header": from_header }),
+            )
+            .await?
+            .json()?)
+    }
+
+    pub async fn get_guardian_recovery(&self, account_id: &str, request_id: &str) -> Result<Option<GuardianRecovery>> {
+        self.view(
+            "get_guardian_recovery",
+            json!({ "account_id": account_id, "request_id": request_id }),
+        )
+        .await
+    }
+
+    pub async fn get_guardian_weights(&self, account_id: &str) -> Result<Option<GuardianWeights>> {
+        self.view("get_guardian_weights", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// Set how `account`'s guardian approvals are weighed, or turn guardian
+    /// recovery off with `None`. Attaches the storage deposit.
+    pub async fn set_guardian_weights(&self, account: &Account, weights: Option<&GuardianWeights>) -> Result<()> {
+        let args = json!({ "weights": weights });
+        self.call_with_deposit(account, "set_guardian_weights", args, STORAGE_DEPOSIT)
+            .await?;
+        Ok(())
+    }
+
+    /// The guardian set of `account_id` active at `at_ms`, or now.
+    pub async fn get_guardian_set(&self, account_id: &str, at_ms: Option<u64>) -> Result<GuardianSet> {
+        self.view("get_guardian_set", json!({ "account_id": account_id, "at_ms": at_ms }))
+            .await
+    }
+
+    /// `owner` must be the contract owner.
+    pub async fn set_guardian_epoch_ms(&self, owner: &Account, epoch_ms: u64) -> Result<()> {
+        self.call(owner, "set_guardian_epoch_ms", json!({ "epoch_ms": epoch_ms }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_guardians(&self, account_id: &str) -> Result<Vec<Vec<u8>>> {
+        self.view("get_guardians", json!({ "account_id": account_id }))
+            .await
+    }
+
+    pub async fn get_recovery_policy(&self, account_id: &str) -> Result<Option<RecoveryPolicy>> {
+        self.view("get_recovery_policy", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// Register `account`'s recovery policy, or go back to the global
+    /// settings with `None`.
+    pub async fn set_recovery_policy(&self, account: &Account, policy: Option<&RecoveryPolicy>) -> Result<()> {
+        self.call_with_deposit(account, "set_recovery_policy", json!({ "policy": policy }), STORAGE_DEPOSIT)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_account_namespace(&self) -> Result<Option<String>> {
+        self.view("get_account_namespace", json!({})).await
+    }
+
+    /// Only accept accounts ending in `suffix`. `owner` must be the contract
+    /// owner.
+    pub async fn set_account_namespace(&self, owner: &Account, suffix: Option<&str>) -> Result<()> {
+        self.call(owner, "set_account_namespace", json!({ "suffix": suffix }))
+            .await?;
+        Ok(())
+    }
+
+    /// Outcome of `submitter`'s finished `verify_staged` call with
+    /// `idempotency_key`, while it hasn't expired.
+    pub async fn get_idempotent_result(
+        &self,
+        submitter: &str,
+        idempotency_key: &str,
+    ) -> Result<Option<VerificationResult>> {
+        self.view(
+            "get_idempotent_result",
+            json!({ "submitter": submitter, "idempotency_key": idempotency_key }),
+        )
+        .await
+    }
+
+    pub async fn get_key_permission(&self, account_id: &str) -> Result<KeyPermission> {
+        self.view("get_key_permission", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// Choose how keys recovered for `account` are added, or go back to the
+    /// default with `None`.
+    pub async fn set_key_permission(&self, account: &Account, permission: Option<&KeyPermission>) -> Result<()> {
+        let args = json!({ "permission": permission });
+        self.call_with_deposit(account, "set_key_permission", args, STORAGE_DEPOSIT)
+            .await?;
+        Ok(())
+    }
+
+    /// `owner` must be the contract owner.
+    pub async fn set_default_key_permission(&self, owner: &Account, permission: &KeyPermission) -> Result<()> {
+        self.call(owner, "set_default_key_permission", json!({ "permission": permission }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_open_challenges(&self, account_id: &str) -> Result<Vec<Challenge>> {
+        self.view("get_open_challenges", json!({ "account_id": account_id }))
+            .await
+    }
+
+    pub async fn get_consumed_challenges(&self, account_id: &str) -> Result<Vec<Challenge>> {
+        self.view("get_consumed_challenges", json!({ "account_id": account_id }))
+            .await
+    }
+
+    /// The nullifier a recovery of `account_id` with `request_id` consumes.
+    pub async fn nullifier_hash(&self, account_id: &str, request_id: &str) -> Result<String> {
+        self.view(
+            "nullifier_hash",
+            json!({ "account_id": account_id, "request_id": request_id }),
+        )
+        .await
+    }
+
+    pub async fn has_nullifier(&self, nullifier_hex: &str) -> Result<bool> {
+        self.view("has_nullifier", json!({ "nullifier_hex": nullifier_hex }))
+            .await
+    }
+
+    pub async fn list_nullifiers(&self, from: Option<u32>, limit: Option<u32>) -> Result<Vec<String>> {
+        self.view("list_nullifiers", json!({ "from": from, "limit": limit }))
+            .await
+    }
+
+    pub async fn get_nullifier_root(&self) -> Result<NullifierRoot> {
+        self.view("get_nullifier_root", json!({})).await
+    }
+
+    /// Prove that `nullifier_hex` was consumed, against the root current
+    /// when the call starts. `None` if it wasn't consumed by then. Reads
+    /// every nullifier, so build proofs in an indexer rather than per request.
+    pub async fn nullifier_inclusion_proof(
+        &self,
+        nullifier_hex: &str,
+    ) -> Result<Option<InclusionProof>> {
+        let root = self.get_nullifier_root().await?;
+        let mut nullifiers = Vec::new();
+        while (nullifiers.len() as u64) < root.len {
+            let from = nullifiers.len() as u32;
+            let page = self.list_nullifiers(Some(from), Some(LEAF_PAGE)).await?;
+            if page.is_empty() {
+                break;
+            }
+            nullifiers.extend(page);
+        }
+        Ok(prove_inclusion(nullifiers, &root.root, root.len, nullifier_hex))
+    }
+
+    pub async fn get_result_root(&self) -> Result<ResultRoot> {
+        self.view("get_result_root", json!({})).await
+    }
+
+    pub async fn list_result_hashes(&self, from: Option<u64>, limit: Option<u32>) -> Result<Vec<String>> {
+        self.view("list_result_hashes", json!({ "from": from, "limit": limit }))
+            .await
+    }
+
+    /// Prove that the contract produced the result hashing to
+    /// `result_hash_hex` (see `attestation_hash`), like
+    /// [`nullifier_inclusion_proof`](Self::nullifier_inclusion_proof).
+    pub async fn result_inclusion_proof(&self, result_hash_hex: &str) -> Result<Option<InclusionProof>> {
+        let root = self.get_result_root().await?;
+        let mut hashes = Vec::new();
+        while (hashes.len() as u64) < root.len {
+            let from = hashes.len() as u64;
+            let page = self.list_result_hashes(Some(from), Some(LEAF_PAGE)).await?;
+            if page.is_empty() {
+                break;
+            }
+            hashes.extend(page);
+        }
+        Ok(prove_inclusion(hashes, &root.root, root.len, result_hash_hex))
+    }
+
+    /// Up to `limit` entries of `section` from index `from`; see the
+    /// contract's `state_export` module. `owner` must be the contract owner.
+    pub async fn export_state(
+        &self,
+        owner: &Account,
+        section: StateSection,
+        from: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<StateChunk> {
+        let outcome = self
+            .call(owner, "export_state", json!({ "section": section, "from": from, "limit": limit }))
+            .await?;
+        Ok(outcome.json()?)
+    }
+
+    /// Import a chunk returned by another deployment's
+    /// [`export_state`](Self::export_state). Returns the number of entries
+    /// imported. `owner` must be the contract owner, before
+    /// [`activate`](Self::activate).
+    pub async fn import_state(&self, owner: &Account, chunk: &StateChunk) -> Result<u32> {
+        let outcome = self
+            .call(owner, "import_state", json!({ "section": chunk.section, "blob": chunk.blob }))
+            .await?;
+        Ok(outcome.json()?)
+    }
+
+    /// Close `import_state` for good and start accepting proofs. `owner` must
+    /// be the contract owner.
+    pub async fn activate(&self, owner: &Account) -> Result<()> {
+        self.call(owner, "activate", json!({})).await?;
+        Ok(())
+    }
+
+    /// Set the mailbox recovery emails go to. `owner` must be the contract
+    /// owner.
+    pub async fn set_recovery_mailbox(&self, owner: &Account, mailbox: Option<&str>) -> Result<()> {
+        self.call(owner, "set_recovery_mailbox", json!({ "mailbox": mailbox }))
+            .await?;
+        Ok(())
+    }
+
+    /// `max_len` defaults to the 255 bytes of `RecoverEmailCircuit` fields.
+    pub async fn pack_string(&self, s: &str, max_len: Option<u32>) -> Result<Vec<String>> {
+        self.view("pack_string", json!({ "s": s, "max_len": max_len })).await
+    }
+
+    pub async fn unpack_fields(&self, chunks: &[String]) -> Result<String> {
+        self.view("unpack_fields", json!({ "chunks": chunks })).await
+    }
+
+    pub async fn get_allowed_sender_domains(&self) -> Result<Vec<String>> {
+        self.view("get_allowed_sender_domains", json!({})).await
+    }
+
+    /// Replace the sender-domain allowlist. `owner` must be the contract
+    /// owner.
+    pub async fn set_allowed_sender_domains(&self, owner: &Account, domains: &[&str]) -> Result<()> {
+        self.call(owner, "set_allowed_sender_domains", json!({ "domains": domains }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn sender_block_hash(&self, address_or_domain: &str) -> Result<Vec<u8>> {
+        self.view("sender_block_hash", json!({ "address_or_domain": address_or_domain }))
+            .await
+    }
+
+    pub async fn is_sender_blocked(&self, hash: &[u8]) -> Result<bool> {
+        self.view("is_sender_blocked", json!({ "hash": hash })).await
+    }
+
+    /// Add `sender_block_hash` digests to the blocklist. `owner` must be the
+    /// contract owner.
+    pub async fn block_senders(&self, owner: &Account, hashes: &[Vec<u8>]) -> Result<()> {
+        self.call(owner, "block_senders", json!({ "hashes": hashes })).await?;
+        Ok(())
+    }
+
+    pub async fn unblock_senders(&self, owner: &Account, hashes: &[Vec<u8>]) -> Result<()> {
+        self.call(owner, "unblock_senders", json!({ "hashes": hashes })).await?;
+        Ok(())
+    }
+
+    /// Prune up to `limit` audit records older than `before_block`. `owner`
+    /// must be the contract owner. Returns the number removed.
+    pub async fn prune_records(&self, owner: &Account, before_block: u64, limit: u32) -> Result<u32> {
+        let outcome = self
+            .call(
+                owner,
+                "prune_records",
+                json!({ "before_block": before_block, "limit": limit }),
+            )
+            .await?;
+        Ok(outcome.json()?)
+    }
+
+    /// Pause or resume `verify_staged`. `owner` must be the contract owner.
+    pub async fn set_paused(&self, owner: &Account, paused: bool) -> Result<()> {
+        self.call(owner, "set_paused", json!({ "paused": paused })).await?;
+        Ok(())
+    }
+
+    pub async fn set_freshness_window_ms(&self, owner: &Account, window_ms: Option<u64>) -> Result<()> {
+        self.call(owner, "set_freshness_window_ms", json!({ "window_ms": window_ms }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_circuit_breaker(&self) -> Result<Option<CircuitBreakerConfig>> {
+        self.view("get_circuit_breaker", json!({})).await
+    }
+
+    /// Configure or disable the circuit breaker. `owner` must be the contract
+    /// owner.
+    pub async fn set_circuit_breaker(
+        &self,
+        owner: &Account,
+        config: Option<&CircuitBreakerConfig>,
+    ) -> Result<()> {
+        self.call(owner, "set_circuit_breaker", json!({ "config": config }))
+            .await?;
+        Ok(())
+    }
+
+    /// Rotate the verifying key. `owner` must be the contract owner.
+    pub async fn set_verifying_key(&self, owner: &Account, vk: &VerifyingKeyInput) -> Result<()> {
+        self.call(owner, "set_verifying_key", json!({ "vk": vk })).await?;
+        Ok(())
+    }
+
+    /// Rotate the verifying key from its compressed ark-serialize encoding.
+    /// `owner` must be the contract owner.
+    pub async fn set_verifying_key_bytes(&self, owner: &Account, vk: &[u8]) -> Result<()> {
+        owner
+            .call(self.contract.id(), "set_verifying_key_bytes")
+            .args_borsh(vk.to_vec())
+            .gas(CALL_GAS)
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(())
+    }
+}
+
+/// Prove that `wanted` is among the first `len` of `leaves`, in hex, under
+/// `root`.
+fn prove_inclusion(mut leaves: Vec<String>, root: &str, len: u64, wanted: &str) -> Option<InclusionProof> {
+    leaves.truncate(len as usize);
+    let wanted = wanted.to_ascii_lowercase();
+    let index = leaves.iter().position(|leaf| *leaf == wanted)?;
+    let leaves: Vec<Hash> = leaves.iter().map(|leaf| decode_hash(leaf)).collect();
+    let siblings = merkle::inclusion_proof(&leaves, index, sha256).expect("index is in range");
+    Some(InclusionProof {
+        leaf: leaves[index],
+        index: index as u64,
+        root: decode_hash(root),
+        siblings,
+    })
+}
+
+fn sha256(bytes: &[u8]) -> Hash {
+    Sha256::digest(bytes).into()
+}
+
+/// Decode a hash the contract returned as 64 hex characters.
+fn decode_hash(hex: &str) -> Hash {
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("the contract returns hex"))
+        .collect();
+    bytes.try_into().expect("the contract returns 32-byte hashes")
+}
@@ -0,0 +1,27 @@
+//! Generates `vk.rs` (the compiled-in Groth16 verifying key) from snarkjs's
+//! `verification_key.json`.
+//!
+//! The key is read from `$ZK_EMAIL_VERIFICATION_KEY` when set, otherwise from
+//! the `verification_key.json` snapshot next to this file, which
+//! `pnpm generate-vk-contract` refreshes from the circom build output.
+
+use std::{env, fs, path::PathBuf};
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ZK_EMAIL_VERIFICATION_KEY");
+
+    let vkey_path = match env::var_os("ZK_EMAIL_VERIFICATION_KEY") {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("verification_key.json"),
+    };
+    println!("cargo:rerun-if-changed={}", vkey_path.display());
+
+    let vkey_json = fs::read_to_string(&vkey_path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", vkey_path.display(), e));
+    let vkey = vk_generator::parse_verification_key(&vkey_json)
+        .unwrap_or_else(|e| panic!("{}: {}", vkey_path.display(), e));
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("vk.rs");
+    fs::write(&out_path, vk_generator::render_vk_rs(&vkey))
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", out_path.display(), e));
+}
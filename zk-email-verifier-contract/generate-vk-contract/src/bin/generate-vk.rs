@@ -0,0 +1,71 @@
+//! Convert snarkjs's `verification_key.json` into something the verifier
+//! contract can use:
+//!
+//! - `rust`: the `vk.rs` source compiled into the contract (what `build.rs`
+//!   generates);
+//! - `json`: arguments for `set_verifying_key`;
+//! - `borsh`: arguments for `set_verifying_key_bytes`, e.g.
+//!   `near contract call-function as-transaction <contract> set_verifying_key_bytes file-args vk.borsh ...`.
+//!
+//! The key must match the public-input count of `RecoverEmailCircuit`
+//! unless `--public-inputs` says otherwise.
+
+use std::{env, fs, io::Write, process};
+
+use zk_email_verifier_core::layout::EXPECTED_PUBLIC_LEN;
+
+const USAGE: &str = "\
+Usage: generate-vk <verification_key.json> [--format rust|json|borsh] [--out <path>] [--public-inputs <n>]
+
+Writes to stdout unless --out is given. --public-inputs defaults to the
+RecoverEmailCircuit layout.";
+
+fn main() {
+    if let Err(e) = run(env::args().skip(1).collect()) {
+        eprintln!("error: {e}\n\n{USAGE}");
+        process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut input = None;
+    let mut format = "rust".to_string();
+    let mut out = None;
+    let mut public_inputs = EXPECTED_PUBLIC_LEN;
+
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--format" => format = value()?,
+            "--out" => out = Some(value()?),
+            "--public-inputs" => {
+                public_inputs = value()?
+                    .parse()
+                    .map_err(|_| "--public-inputs must be a number".to_string())?
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown argument {flag}")),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+    let input = input.ok_or("missing verification_key.json path")?;
+
+    let json = fs::read_to_string(&input).map_err(|e| format!("failed to read {input}: {e}"))?;
+    let vkey = vk_generator::parse_verification_key(&json)?;
+    vk_generator::check_public_input_count(&vkey, public_inputs)?;
+
+    let bytes = match format.as_str() {
+        "rust" => vk_generator::render_vk_rs(&vkey).into_bytes(),
+        "json" => vk_generator::render_set_vk_args(&vkey).into_bytes(),
+        "borsh" => vk_generator::render_set_vk_bytes_args(&vkey)?,
+        other => return Err(format!("unknown format {other}")),
+    };
+
+    match out {
+        Some(path) => fs::write(&path, bytes).map_err(|e| format!("failed to write {path}: {e}")),
+        None => std::io::stdout()
+            .write_all(&bytes)
+            .map_err(|e| format!("failed to write to stdout: {e}")),
+    }
+}
@@ -0,0 +1,182 @@
+//! Render `src/vk.rs` for the verifier contract from a snarkjs
+//! `verification_key.json`.
+//!
+//! Used by the contract's `build.rs` and by the `vk_to_rust` and `generate-vk`
+//! binaries.
+
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine};
+use ark_groth16::{prepare_verifying_key, VerifyingKey};
+use ark_serialize::CanonicalSerialize;
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+/// Minimal shape of snarkjs verification_key.json that we care about.
+/// Serializes back to the same shape, which is what `set_verifying_key` takes.
+#[derive(Deserialize, Serialize)]
+pub struct VerificationKey {
+    pub vk_alpha_1: [String; 3],
+    pub vk_beta_2: [[String; 2]; 3],
+    pub vk_gamma_2: [[String; 2]; 3],
+    pub vk_delta_2: [[String; 2]; 3],
+    #[serde(rename = "IC")]
+    pub ic: Vec<[String; 3]>,
+}
+
+/// Parse `verification_key.json` and check that it yields a usable arkworks key.
+pub fn parse_verification_key(json: &str) -> Result<VerificationKey, String> {
+    let vkey: VerificationKey = serde_json::from_str(json)
+        .map_err(|e| format!("failed to parse verification_key.json: {e}"))?;
+
+    // Validate the vkey by constructing an Arkworks VerifyingKey and preparing it.
+    let vk_ark = build_verifying_key(&vkey)?;
+    let _pvk = prepare_verifying_key(&vk_ark);
+    Ok(vkey)
+}
+
+/// Check that `vkey` has one `IC` point per public input plus the constant term.
+pub fn check_public_input_count(vkey: &VerificationKey, public_inputs: usize) -> Result<(), String> {
+    if vkey.ic.len() != public_inputs + 1 {
+        return Err(format!(
+            "verification key has {} IC points, but the layout has {public_inputs} public inputs (expected {})",
+            vkey.ic.len(),
+            public_inputs + 1
+        ));
+    }
+    Ok(())
+}
+
+/// Render the JSON arguments of the contract's `set_verifying_key`.
+pub fn render_set_vk_args(vkey: &VerificationKey) -> String {
+    #[derive(Serialize)]
+    struct Args<'a> {
+        vk: &'a VerificationKey,
+    }
+    serde_json::to_string_pretty(&Args { vk: vkey })
+        .expect("serializing a verification key cannot fail")
+}
+
+/// Render the Borsh arguments of the contract's `set_verifying_key_bytes`: the
+/// key serialized compressed with ark-serialize, wrapped as a Borsh `Vec<u8>`.
+pub fn render_set_vk_bytes_args(vkey: &VerificationKey) -> Result<Vec<u8>, String> {
+    let vk = build_verifying_key(vkey)?;
+    let mut compressed = Vec::with_capacity(vk.compressed_size());
+    vk.serialize_compressed(&mut compressed)
+        .map_err(|e| format!("failed to serialize verifying key: {e}"))?;
+    borsh::to_vec(&compressed).map_err(|e| format!("failed to encode Borsh args: {e}"))
+}
+
+/// Render the Rust source defining `verifying_key()` for `vkey`.
+pub fn render_vk_rs(vkey: &VerificationKey) -> String {
+    // Build gamma_abc_g1 entries as a single string to embed in the template.
+    let gamma_abc_entries = vkey
+        .ic
+        .iter()
+        .map(|ic| {
+            format!(
+                "        G1Affine::new_unchecked(\n            Fq::from_str(\"{}\").unwrap(),\n            Fq::from_str(\"{}\").unwrap(),\n        ),\n",
+                ic[0], ic[1]
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "// This file is @generated by vk-generator from verification_key.json; do not edit manually.
+use core::str::FromStr;
+use ark_bn254::{{Bn254, Fq, Fq2, G1Affine, G2Affine}};
+use ark_groth16::VerifyingKey;
+
+pub fn verifying_key() -> VerifyingKey<Bn254> {{
+    let alpha_g1 = G1Affine::new_unchecked(
+        Fq::from_str(\"{alpha_x}\").unwrap(),
+        Fq::from_str(\"{alpha_y}\").unwrap(),
+    );
+
+    let beta_g2 = G2Affine::new_unchecked(
+        Fq2::new(
+            Fq::from_str(\"{beta_x0}\").unwrap(),
+            Fq::from_str(\"{beta_x1}\").unwrap(),
+        ),
+        Fq2::new(
+            Fq::from_str(\"{beta_y0}\").unwrap(),
+            Fq::from_str(\"{beta_y1}\").unwrap(),
+        ),
+    );
+
+    let gamma_g2 = G2Affine::new_unchecked(
+        Fq2::new(
+            Fq::from_str(\"{gamma_x0}\").unwrap(),
+            Fq::from_str(\"{gamma_x1}\").unwrap(),
+        ),
+        Fq2::new(
+            Fq::from_str(\"{gamma_y0}\").unwrap(),
+            Fq::from_str(\"{gamma_y1}\").unwrap(),
+        ),
+    );
+
+    let delta_g2 = G2Affine::new_unchecked(
+        Fq2::new(
+            Fq::from_str(\"{delta_x0}\").unwrap(),
+            Fq::from_str(\"{delta_x1}\").unwrap(),
+        ),
+        Fq2::new(
+            Fq::from_str(\"{delta_y0}\").unwrap(),
+            Fq::from_str(\"{delta_y1}\").unwrap(),
+        ),
+    );
+
+    let gamma_abc_g1: Vec<G1Affine> = vec![
+        {gamma_abc_entries}
+    ];
+
+    VerifyingKey::<Bn254> {{
+        alpha_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g2,
+        gamma_abc_g1,
+    }}
+}}
+",
+        alpha_x = vkey.vk_alpha_1[0],
+        alpha_y = vkey.vk_alpha_1[1],
+        beta_x0 = vkey.vk_beta_2[0][0],
+        beta_x1 = vkey.vk_beta_2[0][1],
+        beta_y0 = vkey.vk_beta_2[1][0],
+        beta_y1 = vkey.vk_beta_2[1][1],
+        gamma_x0 = vkey.vk_gamma_2[0][0],
+        gamma_x1 = vkey.vk_gamma_2[0][1],
+        gamma_y0 = vkey.vk_gamma_2[1][0],
+        gamma_y1 = vkey.vk_gamma_2[1][1],
+        delta_x0 = vkey.vk_delta_2[0][0],
+        delta_x1 = vkey.vk_delta_2[0][1],
+        delta_y0 = vkey.vk_delta_2[1][0],
+        delta_y1 = vkey.vk_delta_2[1][1],
+        gamma_abc_entries = gamma_abc_entries,
+    )
+}
+
+fn fq(s: &str) -> Result<Fq, String> {
+    Fq::from_str(s).map_err(|_| format!("invalid base field element: {s}"))
+}
+
+fn g2(p: &[[String; 2]; 3]) -> Result<G2Affine, String> {
+    Ok(G2Affine::new_unchecked(
+        Fq2::new(fq(&p[0][0])?, fq(&p[0][1])?),
+        Fq2::new(fq(&p[1][0])?, fq(&p[1][1])?),
+    ))
+}
+
+/// Build an arkworks key from the snarkjs JSON, failing on malformed field elements.
+pub fn build_verifying_key(v: &VerificationKey) -> Result<VerifyingKey<Bn254>, String> {
+    let alpha_g1 = G1Affine::new_unchecked(fq(&v.vk_alpha_1[0])?, fq(&v.vk_alpha_1[1])?);
+    let beta_g2 = g2(&v.vk_beta_2)?;
+    let gamma_g2 = g2(&v.vk_gamma_2)?;
+    let delta_g2 = g2(&v.vk_delta_2)?;
+    let gamma_abc_g1 = v
+        .ic
+        .iter()
+        .map(|ic| Ok(G1Affine::new_unchecked(fq(&ic[0])?, fq(&ic[1])?)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(VerifyingKey::<Bn254> { alpha_g1, beta_g2, gamma_g2, delta_g2, gamma_abc_g1 })
+}
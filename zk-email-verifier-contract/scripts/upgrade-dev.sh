@@ -2,6 +2,8 @@
 set -euo pipefail
 
 # Run from `zk-email-verifier-contract/` (e.g. `./scripts/upgrade-dev.sh`).
+# Deployments created before the contract kept state need a one-off
+# `migrate` call (from the contract account) after this upgrade.
 source .env
 
 : "${CONTRACT_ID:?Missing CONTRACT_ID}"
@@ -0,0 +1,149 @@
+//! Attestations of successful stateful verifications.
+//!
+//! `verify_staged` called with `attest: true` stores a compact record once
+//! the proof verifies: the SHA-256 of the Borsh-serialized
+//! `VerificationResult` and the block height it was verified at, under the
+//! staged verification's id. Other chains and services can then check "this
+//! recovery was verified on NEAR at height H" by reading the record (e.g.
+//! through a state proof) and hashing the result they were given, without
+//! re-verifying the Groth16 proof.
+//!
+//! The record isn't signed by a key: it is contract state, so it carries the
+//! same guarantees as any other state of this account.
+//!
+//! Attestations are hash-linked: each one commits to the keccak256 of the
+//! previous one's export, so a bridge light client that trusts one of them
+//! can walk back through the others. `export_attestation` returns the
+//! packed, fixed-layout bytes an EVM contract can hash and decode directly:
+//!
+//! ```text
+//! version       u8        1
+//! result_hash   bytes32
+//! prev_hash     bytes32   zero for the first attestation
+//! id            uint64    big-endian
+//! block_height  uint64    big-endian
+//! contract_id   bytes     rest of the payload, UTF-8
+//! ```
+
+use near_sdk::{borsh, env, near};
+
+use crate::{VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+pub const EXPORT_VERSION: u8 = 1;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attestation {
+    /// `sha256(borsh(VerificationResult))`, see `attestation_hash`.
+    pub result_hash: Vec<u8>,
+    pub block_height: u64,
+    /// `hash` of the previous attestation's export.
+    pub prev_hash: Vec<u8>,
+}
+
+/// An attestation in the cross-chain format described in the module docs.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportedAttestation {
+    pub encoded: Vec<u8>,
+    /// `keccak256(encoded)`, which the next attestation links to.
+    pub hash: Vec<u8>,
+}
+
+pub(crate) fn result_hash(result: &VerificationResult) -> Vec<u8> {
+    env::sha256(borsh::to_vec(result).expect("serializing into a Vec cannot fail"))
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_attestation(&self, id: u64) -> Option<Attestation> {
+        self.attestations.get(&id).cloned()
+    }
+
+    /// The `result_hash` an attestation of `result` would carry, for consumers
+    /// that can't Borsh-serialize a `VerificationResult` themselves.
+    pub fn attestation_hash(&self, result: VerificationResult) -> Vec<u8> {
+        result_hash(&result)
+    }
+
+    pub fn export_attestation(&self, id: u64) -> Option<ExportedAttestation> {
+        let attestation = self.attestations.get(&id)?;
+        let encoded = encode_export(id, attestation, env::current_account_id().as_str());
+        Some(ExportedAttestation {
+            hash: env::keccak256(&encoded),
+            encoded,
+        })
+    }
+}
+
+fn encode_export(id: u64, attestation: &Attestation, contract_id: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(1 + 32 + 32 + 8 + 8 + contract_id.len());
+    out.push(EXPORT_VERSION);
+    out.extend_from_slice(&attestation.result_hash);
+    out.extend_from_slice(&attestation.prev_hash);
+    out.extend_from_slice(&id.to_be_bytes());
+    out.extend_from_slice(&attestation.block_height.to_be_bytes());
+    out.extend_from_slice(contract_id.as_bytes());
+    out
+}
+
+impl ZkEmailVerifier {
+    /// Record a verified `result` under `id`.
+    pub(crate) fn attest(&mut self, id: u64, result: &VerificationResult) {
+        let attestation = Attestation {
+            result_hash: result_hash(result),
+            block_height: env::block_height(),
+            prev_hash: self.attestation_chain_head.clone(),
+        };
+        let encoded = encode_export(id, &attestation, env::current_account_id().as_str());
+        self.attestation_chain_head = env::keccak256(&encoded);
+        self.attestations.insert(id, attestation);
+        env::log_str(&format!("attestation {id}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn attestation_records_result_hash_and_height() {
+        testing_env!(VMContextBuilder::new().block_height(42).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let mut result = VerificationResult::verified_for("alice.testnet");
+
+        contract.attest(7, &result);
+        let attestation = contract.get_attestation(7).unwrap();
+        assert_eq!(attestation.block_height, 42);
+        assert_eq!(attestation.result_hash, contract.attestation_hash(result.clone()));
+
+        result.account_id = "bob.testnet".to_string();
+        assert_ne!(attestation.result_hash, contract.attestation_hash(result));
+        assert!(contract.get_attestation(8).is_none());
+    }
+
+    #[test]
+    fn exported_attestations_link_to_their_predecessor() {
+        testing_env!(VMContextBuilder::new().block_height(42).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let result = VerificationResult::verified_for("");
+        contract.attest(3, &result);
+        contract.attest(5, &result);
+
+        let first = contract.export_attestation(3).unwrap();
+        let second = contract.export_attestation(5).unwrap();
+        assert_eq!(first.encoded[33..65], [0; 32]);
+        assert_eq!(second.encoded[33..65], first.hash[..]);
+        assert_eq!(second.hash, contract.attestation_chain_head);
+
+        let encoded = &second.encoded;
+        assert_eq!(encoded[0], EXPORT_VERSION);
+        assert_eq!(encoded[1..33], contract.attestation_hash(result)[..]);
+        assert_eq!(encoded[65..73], 5u64.to_be_bytes());
+        assert_eq!(encoded[73..81], 42u64.to_be_bytes());
+        assert_eq!(&encoded[81..], env::current_account_id().as_bytes());
+        assert!(contract.export_attestation(4).is_none());
+    }
+}
@@ -0,0 +1,243 @@
+//! Bounded log of recent stateful verifications, for support staff looking
+//! into an incident without an indexer.
+//!
+//! Records are numbered in order and kept in a ring of `audit_capacity`
+//! entries: writing a new one evicts the oldest once the ring is full.
+//! Setting the capacity to 0 turns the log off. `prune_records` drops old
+//! records ahead of eviction, in batches small enough for one call.
+//!
+//! Verified records are also kept per account, independently of the ring,
+//! so a wallet can show when and to which key an account was recovered
+//! (`get_verifications_for_account`). Rejected proofs are left out there:
+//! anyone can submit them for any account, and they'd grow state for free.
+
+use near_sdk::{env, near};
+
+use crate::events::Event;
+use crate::{VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+pub(crate) const DEFAULT_AUDIT_CAPACITY: u32 = 100;
+/// Upper bound on the capacity, so resizing evicts a bounded number of
+/// records in one call.
+const MAX_AUDIT_CAPACITY: u32 = 1_000;
+const DEFAULT_HISTORY_LIMIT: u32 = 10;
+const MAX_HISTORY_LIMIT: u32 = 100;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerificationRecord {
+    pub request_id: String,
+    /// Empty if the proof was rejected before its account could be decoded.
+    pub account_id: String,
+    /// Bare base58 key the email asked to add; empty if rejected.
+    pub new_public_key: String,
+    pub circuit_id: String,
+    /// SHA-256 of the prepared verifying key the proof was checked against.
+    pub vk_hash: Vec<u8>,
+    pub verified: bool,
+    pub failure_reason: Option<String>,
+    pub block_height: u64,
+    pub block_timestamp_ms: u64,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Up to `limit` (default and maximum: the capacity) records, newest
+    /// first.
+    pub fn get_recent_verifications(&self, limit: Option<u32>) -> Vec<VerificationRecord> {
+        let limit = limit.unwrap_or(self.audit_capacity).min(self.audit_capacity);
+        (self.audit_start..self.audit_next)
+            .rev()
+            .take(limit as usize)
+            .filter_map(|seq| self.audit_log.get(&seq).cloned())
+            .collect()
+    }
+
+    /// Verified records of `account_id`, oldest first, starting at index
+    /// `from` (default 0). `limit` defaults to 10 and is capped at 100.
+    pub fn get_verifications_for_account(
+        &self,
+        account_id: String,
+        from: Option<u32>,
+        limit: Option<u32>,
+    ) -> Vec<VerificationRecord> {
+        let len = self.account_history_len.get(&account_id).copied().unwrap_or(0);
+        let from = from.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT);
+        (from..len.min(from.saturating_add(limit)))
+            .filter_map(|i| self.account_history.get(&(account_id.clone(), i)).cloned())
+            .collect()
+    }
+
+    pub fn get_audit_capacity(&self) -> u32 {
+        self.audit_capacity
+    }
+
+    /// Resize the log, evicting the oldest records if it shrinks. Only
+    /// callable by the owner.
+    pub fn set_audit_capacity(&mut self, capacity: u32) {
+        self.assert_owner();
+        if capacity > MAX_AUDIT_CAPACITY {
+            env::panic_str(&format!("audit capacity is at most {MAX_AUDIT_CAPACITY}"));
+        }
+        self.audit_capacity = capacity;
+        self.evict_audit_records();
+    }
+
+    /// Remove up to `limit` of the oldest records written before block
+    /// `before_block`, and emit a `records_pruned` event with the storage
+    /// released. Returns the number removed. Only callable by the owner.
+    pub fn prune_records(&mut self, before_block: u64, limit: u32) -> u32 {
+        self.assert_owner();
+        let usage_before = env::storage_usage();
+        let mut pruned = 0;
+        while pruned < limit && self.audit_start < self.audit_next {
+            let old = self
+                .audit_log
+                .get(&self.audit_start)
+                .is_some_and(|record| record.block_height < before_block);
+            if !old {
+                break;
+            }
+            self.audit_log.remove(&self.audit_start);
+            self.audit_start += 1;
+            pruned += 1;
+        }
+        // Write the removals now so `storage_usage` reflects them.
+        self.audit_log.flush();
+        Event::RecordsPruned {
+            count: pruned,
+            storage_bytes_released: usage_before.saturating_sub(env::storage_usage()),
+        }
+        .emit();
+        pruned
+    }
+}
+
+impl ZkEmailVerifier {
+    pub(crate) fn audit(&mut self, result: &VerificationResult, circuit_id: &str) {
+        let record = VerificationRecord {
+            request_id: result.request_id.clone(),
+            account_id: result.account_id.clone(),
+            new_public_key: result.new_public_key.clone(),
+            circuit_id: circuit_id.to_string(),
+            vk_hash: self.prepared_vk_hash(circuit_id),
+            verified: result.verified,
+            failure_reason: result.failure_reason.clone(),
+            block_height: env::block_height(),
+            block_timestamp_ms: env::block_timestamp_ms(),
+        };
+        if result.verified {
+            let len = self.account_history_len.get(&record.account_id).copied().unwrap_or(0);
+            self.account_history
+                .insert((record.account_id.clone(), len), record.clone());
+            self.account_history_len.insert(record.account_id.clone(), len + 1);
+        }
+        if self.audit_capacity == 0 {
+            return;
+        }
+        self.audit_log.insert(self.audit_next, record);
+        self.audit_next += 1;
+        self.evict_audit_records();
+    }
+
+    fn evict_audit_records(&mut self) {
+        while self.audit_next - self.audit_start > u64::from(self.audit_capacity) {
+            self.audit_log.remove(&self.audit_start);
+            self.audit_start += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+    use crate::FailureReason;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn record(contract: &mut ZkEmailVerifier, request_id: &str) {
+        let mut result = VerificationResult::rejected(FailureReason::Pairing);
+        result.request_id = request_id.to_string();
+        contract.audit(&result, RECOVER_EMAIL_CIRCUIT_ID);
+    }
+
+    fn request_ids(records: &[VerificationRecord]) -> Vec<&str> {
+        records.iter().map(|r| r.request_id.as_str()).collect()
+    }
+
+    #[test]
+    fn log_keeps_the_newest_records_up_to_its_capacity() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .block_height(9)
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_audit_capacity(3);
+        for id in ["a", "b", "c", "d"] {
+            record(&mut contract, id);
+        }
+
+        let recent = contract.get_recent_verifications(None);
+        assert_eq!(request_ids(&recent), vec!["d", "c", "b"]);
+        assert_eq!(recent[0].block_height, 9);
+        assert_eq!(recent[0].failure_reason.as_deref(), Some("PAIRING"));
+        assert_eq!(recent[0].vk_hash, contract.prepared_vk_hash(RECOVER_EMAIL_CIRCUIT_ID));
+        assert_eq!(request_ids(&contract.get_recent_verifications(Some(1))), vec!["d"]);
+
+        contract.set_audit_capacity(1);
+        assert_eq!(request_ids(&contract.get_recent_verifications(Some(10))), vec!["d"]);
+        assert!(contract.audit_log.get(&2).is_none());
+
+        contract.set_audit_capacity(0);
+        record(&mut contract, "e");
+        assert!(contract.get_recent_verifications(None).is_empty());
+    }
+
+    #[test]
+    fn pruning_stops_at_the_first_record_not_old_enough() {
+        let at_height = |height| {
+            testing_env!(VMContextBuilder::new()
+                .predecessor_account_id(accounts(0))
+                .block_height(height)
+                .build())
+        };
+        at_height(1);
+        let mut contract = ZkEmailVerifier::new(None);
+        for (height, id) in [(1, "a"), (2, "b"), (3, "c"), (4, "d")] {
+            at_height(height);
+            record(&mut contract, id);
+        }
+
+        assert_eq!(contract.prune_records(4, 1), 1);
+        assert_eq!(contract.prune_records(4, 10), 2);
+        assert_eq!(contract.prune_records(4, 10), 0);
+        assert_eq!(request_ids(&contract.get_recent_verifications(None)), vec!["d"]);
+        assert!(near_sdk::test_utils::get_logs()
+            .iter()
+            .any(|log| log.contains("records_pruned")));
+    }
+
+    #[test]
+    fn account_history_keeps_verified_records_only() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_audit_capacity(1);
+        for (request_id, verified) in [("a", true), ("b", false), ("c", true), ("d", true)] {
+            let mut result = VerificationResult::rejected(FailureReason::Pairing);
+            result.verified = verified;
+            result.request_id = request_id.to_string();
+            result.account_id = "alice.near".to_string();
+            contract.audit(&result, RECOVER_EMAIL_CIRCUIT_ID);
+        }
+
+        let history = |from, limit| contract.get_verifications_for_account("alice.near".into(), from, limit);
+        assert_eq!(request_ids(&history(None, None)), vec!["a", "c", "d"]);
+        assert_eq!(request_ids(&history(Some(1), Some(1))), vec!["c"]);
+        assert!(history(Some(3), None).is_empty());
+        assert!(contract
+            .get_verifications_for_account("bob.near".into(), None, None)
+            .is_empty());
+    }
+}
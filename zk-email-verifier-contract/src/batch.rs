@@ -0,0 +1,143 @@
+//! Batched Groth16 verification.
+//!
+//! Each proof `i` satisfies `e(A_i, B_i) = e(alpha, beta) · e(L_i, gamma) · e(C_i, delta)`,
+//! where `L_i` is its prepared public input. Raising each equation to a random
+//! `r_i` and multiplying them together gives a single check
+//!
+//! ```text
+//! Π e(r_i·A_i, B_i) · e(Σ r_i·L_i, -gamma) · e(Σ r_i·C_i, -delta) = e(alpha, beta)^(Σ r_i)
+//! ```
+//!
+//! that needs one multi-Miller loop with `n + 2` pairs and a single final
+//! exponentiation, instead of `n` full pairing checks. An invalid proof makes
+//! the combined check pass only with probability ~2^-128.
+//!
+//! The `r_i` are derived from a hash of every proof and public input (plus the
+//! block's random seed), so they are fixed only after the proofs are.
+
+use ark_bn254::{Bn254, Fr, G1Projective};
+use ark_ec::pairing::Pairing;
+use ark_ec::CurveGroup;
+use ark_ff::{BigInteger, Field, PrimeField};
+use ark_groth16::{PreparedVerifyingKey, Proof};
+use ark_serialize::CanonicalSerialize;
+use near_sdk::env;
+
+use crate::msm;
+
+/// A parsed proof together with its public inputs.
+pub(crate) type BatchItem = (Proof<Bn254>, Vec<Fr>);
+
+/// Check every `(proof, public_inputs)` pair at once. Returns `false` if any
+/// proof is invalid or has the wrong number of public inputs; the caller has
+/// to re-verify individually to find out which one.
+pub(crate) fn verify_batch(pvk: &PreparedVerifyingKey<Bn254>, items: &[BatchItem]) -> bool {
+    if items.is_empty() {
+        return true;
+    }
+    let challenges = challenges(items);
+
+    let mut g1 = Vec::with_capacity(items.len() + 2);
+    let mut g2 = Vec::with_capacity(items.len() + 2);
+    let mut inputs_acc = G1Projective::default();
+    let mut c_acc = G1Projective::default();
+    let mut r_sum = Fr::from(0u64);
+
+    for ((proof, inputs), r) in items.iter().zip(&challenges) {
+        let Ok(prepared) = msm::prepare_inputs(pvk, inputs) else {
+            return false;
+        };
+        inputs_acc += prepared * r;
+        c_acc += proof.c * r;
+        r_sum += r;
+        g1.push(<Bn254 as Pairing>::G1Prepared::from((proof.a * r).into_affine()));
+        g2.push(<Bn254 as Pairing>::G2Prepared::from(proof.b));
+    }
+
+    g1.push(inputs_acc.into_affine().into());
+    g2.push(pvk.gamma_g2_neg_pc.clone());
+    g1.push(c_acc.into_affine().into());
+    g2.push(pvk.delta_g2_neg_pc.clone());
+
+    let Some(lhs) = Bn254::final_exponentiation(Bn254::multi_miller_loop(g1, g2)) else {
+        return false;
+    };
+    lhs.0 == pvk.alpha_g1_beta_g2.pow(r_sum.into_bigint())
+}
+
+/// 128-bit random scalars bound to the whole batch. The first one is fixed to
+/// 1, which saves a scalar multiplication without weakening the check.
+fn challenges(items: &[BatchItem]) -> Vec<Fr> {
+    let mut transcript = env::random_seed();
+    for (proof, inputs) in items {
+        proof
+            .serialize_compressed(&mut transcript)
+            .expect("serializing into a Vec cannot fail");
+        for input in inputs {
+            transcript.extend_from_slice(&input.into_bigint().to_bytes_le());
+        }
+    }
+    let seed = env::sha256(&transcript);
+
+    (0..items.len() as u32)
+        .map(|i| {
+            if i == 0 {
+                return Fr::from(1u64);
+            }
+            let mut preimage = seed.clone();
+            preimage.extend_from_slice(&i.to_le_bytes());
+            Fr::from_le_bytes_mod_order(&env::sha256(&preimage)[..16])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::AffineRepr;
+    use ark_groth16::{prepare_verifying_key, VerifyingKey};
+
+    /// A verifying key with known trapdoor so valid proofs can be forged for
+    /// tests: `alpha = a·G1`, `beta = b·G2`, `gamma = delta = G2`, `IC = [G1, 2·G1]`.
+    /// With `B = G2` and `C = 0`, a proof for input `x` needs
+    /// `A = a·b·G1 + (1 + 2x)·G1`.
+    pub(crate) fn fixture() -> (PreparedVerifyingKey<Bn254>, impl Fn(u64) -> BatchItem) {
+        let (a, b) = (Fr::from(3u64), Fr::from(5u64));
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let vk = VerifyingKey::<Bn254> {
+            alpha_g1: (g1 * a).into_affine(),
+            beta_g2: (g2 * b).into_affine(),
+            gamma_g2: g2,
+            delta_g2: g2,
+            gamma_abc_g1: vec![g1, (g1 * Fr::from(2u64)).into_affine()],
+        };
+        let prove = move |x: u64| {
+            let x = Fr::from(x);
+            let proof = Proof {
+                a: (g1 * (a * b + Fr::from(1u64) + Fr::from(2u64) * x)).into_affine(),
+                b: g2,
+                c: G1Affine::identity(),
+            };
+            (proof, vec![x])
+        };
+        (prepare_verifying_key(&vk), prove)
+    }
+
+    #[test]
+    fn batch_accepts_valid_and_rejects_any_invalid_proof() {
+        let (pvk, prove) = fixture();
+        let mut items: Vec<_> = (1..=4).map(&prove).collect();
+        assert!(verify_batch(&pvk, &items));
+
+        // Swap in a public input the proof wasn't made for.
+        items[2].1[0] = Fr::from(99u64);
+        assert!(!verify_batch(&pvk, &items));
+
+        // Wrong number of public inputs.
+        let mut items: Vec<_> = (1..=2).map(&prove).collect();
+        items[1].1.push(Fr::from(0u64));
+        assert!(!verify_batch(&pvk, &items));
+    }
+}
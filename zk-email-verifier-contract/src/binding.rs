@@ -0,0 +1,86 @@
+//! JSON-facing binding options for `verify_with_binding` and `dry_run_verify`.
+//! The comparison itself lives in `zk_email_verifier_core::binding`, so
+//! off-chain tooling reports exactly what the contract would.
+//!
+//! `verify_with_hashed_binding` compares digests instead; see
+//! [`check_hashed_binding`].
+
+use std::borrow::Cow;
+
+use ark_bn254::Fr;
+use near_sdk::{env, near};
+use zk_email_verifier_core::binding as core_binding;
+use zk_email_verifier_core::packing::unpack_field_chunks_to_str;
+
+pub(crate) use zk_email_verifier_core::binding::nfc;
+
+use crate::diagnostics::BindingCheck;
+
+/// Per-call relaxations for `verify_with_binding` and `dry_run_verify`.
+///
+/// The sender address needs no option here: `from_address_hash` is always
+/// computed over the lowercased address, both in the circuit and in
+/// `email::from_address_hash`.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BindingOptions {
+    /// Compare `account_id` case-insensitively (e.g. `Alice.testnet` vs
+    /// `alice.testnet`). The lowercased form is what gets validated and
+    /// returned.
+    #[serde(default)]
+    pub case_insensitive_account_id: bool,
+    /// Trim leading and trailing ASCII whitespace (including stray `\r\n`
+    /// left over from header extraction) from every packed binding.
+    #[serde(default)]
+    pub trim_whitespace: bool,
+}
+
+impl From<&BindingOptions> for core_binding::BindingOptions {
+    fn from(options: &BindingOptions) -> Self {
+        Self {
+            case_insensitive_account_id: options.case_insensitive_account_id,
+            trim_whitespace: options.trim_whitespace,
+        }
+    }
+}
+
+impl BindingOptions {
+    /// Normalize a value bound to `field` according to these options.
+    pub(crate) fn normalize<'a>(&self, field: &str, value: &'a str) -> Cow<'a, str> {
+        core_binding::BindingOptions::from(self).normalize(field, value)
+    }
+}
+
+/// Compare the packed chunks `actual` taken from the public inputs against
+/// the caller-supplied `value`, normalizing both sides as configured for
+/// `field`.
+pub(crate) fn check_packed_binding(
+    field: &str,
+    actual: &[Fr],
+    value: &str,
+    options: &BindingOptions,
+) -> BindingCheck {
+    let outcome = core_binding::check_packed_binding(field, actual, value, &options.into());
+    BindingCheck::from_outcome(field, outcome)
+}
+
+/// Whether the string packed in `actual` hashes to `digest` under SHA-256.
+/// No normalization is applied: the caller hashes exactly the bytes the
+/// circuit anchored.
+pub(crate) fn check_hashed_binding(actual: &[Fr], digest: &[u8]) -> bool {
+    unpack_field_chunks_to_str(actual).is_ok_and(|value| env::sha256(value.as_bytes()) == digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zk_email_verifier_core::packing::pack_str_to_field_chunks;
+
+    #[test]
+    fn hashed_binding_compares_digest_of_unpacked_value() {
+        let packed = pack_str_to_field_chunks("alice.testnet").unwrap();
+        assert!(check_hashed_binding(&packed, &env::sha256(b"alice.testnet")));
+        assert!(!check_hashed_binding(&packed, &env::sha256(b"Alice.testnet")));
+        assert!(!check_hashed_binding(&packed, b"alice.testnet"));
+    }
+}
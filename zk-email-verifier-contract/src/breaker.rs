@@ -0,0 +1,180 @@
+//! Circuit breaker for the stateful verification paths.
+//!
+//! Once configured by the owner, every `verify_staged` outcome is counted in
+//! fixed windows of `window_ms`. The contract pauses itself (as `set_paused`
+//! would) and emits a `circuit_breaker_tripped` event when either
+//!
+//! - one account gets more than `max_verifications_per_account` verified
+//!   proofs in a window, or
+//! - more than `max_failure_percent` of the outcomes in a window fail, once
+//!   at least `min_samples` were seen.
+//!
+//! Only the owner can resume.
+
+use near_sdk::{env, near};
+
+use crate::events::Event;
+use crate::{VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    pub window_ms: u64,
+    pub max_verifications_per_account: u32,
+    pub max_failure_percent: u8,
+    pub min_samples: u32,
+}
+
+/// Outcomes counted since `start_ms`.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct WindowCounts {
+    start_ms: u64,
+    pub(crate) verified: u32,
+    failed: u32,
+}
+
+impl WindowCounts {
+    /// Start a new window if the current one is over.
+    pub(crate) fn roll(&mut self, now_ms: u64, window_ms: u64) {
+        if now_ms >= self.start_ms.saturating_add(window_ms) {
+            *self = Self {
+                start_ms: now_ms,
+                ..Self::default()
+            };
+        }
+    }
+
+    fn failure_rate_exceeded(&self, config: &CircuitBreakerConfig) -> bool {
+        let total = u64::from(self.verified) + u64::from(self.failed);
+        total >= u64::from(config.min_samples)
+            && u64::from(self.failed) * 100 > total * u64::from(config.max_failure_percent)
+    }
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Configure (or disable, with `None`) the circuit breaker. Only callable
+    /// by the owner. Resets the counts.
+    pub fn set_circuit_breaker(&mut self, config: Option<CircuitBreakerConfig>) {
+        self.assert_owner();
+        self.circuit_breaker = config;
+        self.breaker_window = WindowCounts::default();
+    }
+
+    pub fn get_circuit_breaker(&self) -> Option<CircuitBreakerConfig> {
+        self.circuit_breaker.clone()
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Count a stateful verification outcome, tripping the breaker if it
+    /// crosses a threshold.
+    pub(crate) fn observe_for_breaker(&mut self, result: &VerificationResult) {
+        let Some(config) = self.circuit_breaker.clone() else {
+            return;
+        };
+        let now_ms = env::block_timestamp_ms();
+
+        self.breaker_window.roll(now_ms, config.window_ms);
+        if result.verified {
+            self.breaker_window.verified += 1;
+        } else {
+            self.breaker_window.failed += 1;
+        }
+        if self.breaker_window.failure_rate_exceeded(&config) {
+            self.trip("failure_rate", None);
+            return;
+        }
+
+        if result.verified {
+            let mut counts = self
+                .account_windows
+                .get(&result.account_id)
+                .cloned()
+                .unwrap_or_default();
+            counts.roll(now_ms, config.window_ms);
+            counts.verified += 1;
+            let exceeded = counts.verified > config.max_verifications_per_account;
+            self.account_windows.insert(result.account_id.clone(), counts);
+            if exceeded {
+                self.trip("account_rate", Some(result.account_id.clone()));
+            }
+        }
+    }
+
+    fn trip(&mut self, reason: &str, account_id: Option<String>) {
+        if self.paused {
+            return;
+        }
+        self.paused = true;
+        Event::CircuitBreakerTripped {
+            reason: reason.to_string(),
+            account_id,
+        }
+        .emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::at;
+    use crate::FailureReason;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            window_ms: 60_000,
+            max_verifications_per_account: 2,
+            max_failure_percent: 50,
+            min_samples: 4,
+        }
+    }
+
+    fn contract_at(now_ms: u64) -> ZkEmailVerifier {
+        at(now_ms);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_circuit_breaker(Some(config()));
+        contract
+    }
+
+    #[test]
+    fn too_many_verifications_for_one_account_pause_the_contract() {
+        let mut contract = contract_at(1_000);
+        contract.observe_for_breaker(&VerificationResult::verified_for("alice.near"));
+        contract.observe_for_breaker(&VerificationResult::verified_for("alice.near"));
+        contract.observe_for_breaker(&VerificationResult::verified_for("bob.near"));
+        assert!(!contract.health_check().paused);
+
+        contract.observe_for_breaker(&VerificationResult::verified_for("alice.near"));
+        assert!(contract.health_check().paused);
+        assert!(get_logs().iter().any(|log| log.starts_with("EVENT_JSON:")
+            && log.contains("circuit_breaker_tripped")
+            && log.contains("account_rate")));
+    }
+
+    #[test]
+    fn account_counts_reset_with_the_window() {
+        let mut contract = contract_at(1_000);
+        contract.observe_for_breaker(&VerificationResult::verified_for("alice.near"));
+        contract.observe_for_breaker(&VerificationResult::verified_for("alice.near"));
+        testing_env!(VMContextBuilder::new().block_timestamp(61_000 * 1_000_000).build());
+        contract.observe_for_breaker(&VerificationResult::verified_for("alice.near"));
+        assert!(!contract.health_check().paused);
+    }
+
+    #[test]
+    fn failure_spike_pauses_the_contract() {
+        let mut contract = contract_at(1_000);
+        let failed = VerificationResult::rejected(FailureReason::Pairing);
+        contract.observe_for_breaker(&failed);
+        contract.observe_for_breaker(&failed);
+        contract.observe_for_breaker(&failed);
+        // Below `min_samples` so far.
+        assert!(!contract.health_check().paused);
+        contract.observe_for_breaker(&VerificationResult::verified_for("alice.near"));
+        assert!(contract.health_check().paused);
+    }
+}
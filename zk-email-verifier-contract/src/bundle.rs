@@ -0,0 +1,113 @@
+//! A recovery proof verified together with an optional cancellation proof.
+//!
+//! When the account holder answers a recovery email with a `cancel-` email
+//! (see `CANCEL_RECOVERY_SUBJECT`), relaying the two separately lets them
+//! land in different blocks, and the recovery may be acted on before the
+//! cancellation arrives. `verify_recovery_bundle` checks both in one call
+//! and lets the cancellation win: the recovery comes back rejected with
+//! `CANCELLED` whenever the cancellation verifies for the same request.
+//!
+//! The cancellation is proven with its own circuit, so a recovery proof can
+//! never pass for one. It counts only if it anchors the same `request_id`
+//! and `account_id` and was sent from the same address
+//! (`from_address_hash`); otherwise it is reported but ignored.
+//!
+//! A verified recovery consumes its nullifier (see `nullifiers`) whether or
+//! not it was cancelled, so neither the recovery nor its cancellation can be
+//! replayed afterwards, here or through `verify_staged`.
+
+use near_sdk::near;
+use zk_email_verifier_core::layout::{CircuitKind, CANCEL_RECOVERY_CIRCUIT_ID};
+
+use crate::commands::VerifiedCommand;
+use crate::{BatchProofInput, FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct RecoveryBundleResult {
+    /// Outcome of the recovery proof, rejected with `CANCELLED` if the
+    /// cancellation took precedence.
+    pub recovery: VerificationResult,
+    /// Why a given cancellation was ignored, as a `FailureReason` code.
+    /// `None` when it cancelled the recovery, when none was given, or when
+    /// the recovery was rejected on its own.
+    pub cancellation_failure: Option<String>,
+    pub cancelled: bool,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Verify `recovery` like `verify` does and, if given, `cancellation` as
+    /// a `CancelRecoveryCircuit` proof, rejecting the recovery if the
+    /// cancellation verifies for the same request. A recovery whose request
+    /// was already consumed is rejected with `NULLIFIER_CONSUMED`.
+    pub fn verify_recovery_bundle(
+        &mut self,
+        recovery: BatchProofInput,
+        cancellation: Option<BatchProofInput>,
+    ) -> RecoveryBundleResult {
+        self.assert_activated();
+        let result = self.check_nullifier(self.verify(recovery.proof, recovery.public_inputs, None));
+        let cancellation = cancellation.filter(|_| result.verified).map(|cancellation| {
+            self.verify_command_proof(
+                CircuitKind::Command,
+                CANCEL_RECOVERY_CIRCUIT_ID,
+                cancellation.proof,
+                &cancellation.public_inputs,
+            )
+            .and_then(|command| cancels(&command, &result))
+        });
+        let cancelled = matches!(cancellation, Some(Ok(())));
+        self.consume_nullifier(&result);
+        RecoveryBundleResult {
+            recovery: if cancelled {
+                VerificationResult::rejected(FailureReason::Cancelled)
+            } else {
+                result
+            },
+            cancellation_failure: cancellation
+                .and_then(Result::err)
+                .map(|reason| reason.as_str().to_string()),
+            cancelled,
+        }
+    }
+}
+
+/// Whether `cancel` names the same request and sender as the verified
+/// `recovery`.
+fn cancels(cancel: &VerifiedCommand, recovery: &VerificationResult) -> Result<(), FailureReason> {
+    if cancel.request_id != recovery.request_id {
+        return Err(FailureReason::BindingRequestId);
+    }
+    if cancel.account_id != recovery.account_id {
+        return Err(FailureReason::BindingAccount);
+    }
+    if cancel.from_address_hash != recovery.from_address_hash {
+        return Err(FailureReason::BindingFromAddress);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verified(request_id: &str, account_id: &str, from_hash: u8) -> VerificationResult {
+        let mut result = VerificationResult::verified_for(account_id);
+        result.request_id = request_id.to_string();
+        result.from_address_hash = vec![from_hash; 32];
+        result
+    }
+
+    #[test]
+    fn cancellation_must_match_request_account_and_sender() {
+        let recovery = verified("r1", "alice.near", 1);
+        let cancel = |request_id, account_id, from_hash| {
+            cancels(&VerifiedCommand::stub(account_id, request_id, vec![from_hash; 32]), &recovery)
+        };
+        assert_eq!(cancel("r1", "alice.near", 1), Ok(()));
+        assert_eq!(cancel("r2", "alice.near", 1), Err(FailureReason::BindingRequestId));
+        assert_eq!(cancel("r1", "bob.near", 1), Err(FailureReason::BindingAccount));
+        assert_eq!(cancel("r1", "alice.near", 2), Err(FailureReason::BindingFromAddress));
+    }
+}
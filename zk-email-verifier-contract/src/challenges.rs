@@ -0,0 +1,233 @@
+//! Request ids issued by the contract as challenges.
+//!
+//! `generate_request_id` derives an id for an account (see
+//! `email_template::fresh_request_id`) and records it as an open challenge,
+//! so the `request_id` a proof carries in `request_id_packed` can be traced
+//! back to an id the contract handed out rather than one the relayer picked.
+//!
+//! A challenge is single use: the first `verify_staged` recovery carrying its
+//! id that passes every check consumes it, and any later proof with the same
+//! id is rejected with `CHALLENGE_CONSUMED`. A proof carrying an id issued
+//! for another account is rejected with `CHALLENGE_ACCOUNT_MISMATCH`. Request ids the contract didn't
+//! issue are not tracked.
+//!
+//! Only the account itself or its relayer (see `relayers`) can have ids
+//! issued for it, paying for their storage, and at most
+//! [`MAX_OPEN_CHALLENGES`] of them can be open at once.
+
+use near_sdk::{env, near};
+
+use crate::email_template::fresh_request_id;
+use crate::treasury::charge_storage;
+use crate::{FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Open challenges an account can hold before it must consume some.
+pub const MAX_OPEN_CHALLENGES: usize = 10;
+
+/// An issued request id.
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Challenge {
+    pub request_id: String,
+    /// The account the id was issued for.
+    pub account_id: String,
+    pub issued_at_ms: u64,
+    /// When a recovery consumed the challenge; `None` while it is open.
+    pub consumed_at_ms: Option<u64>,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Issue a new request id for `account_id` and store it as an open
+    /// challenge. Put it in the subject of the recovery email (see
+    /// `get_email_template`). Only callable by the account or its relayer,
+    /// attaching the challenge's storage cost; the rest is refunded.
+    #[payable]
+    pub fn generate_request_id(&mut self, account_id: String) -> String {
+        let caller = env::predecessor_account_id();
+        if !self.is_account_or_relayer(&account_id, &caller) {
+            env::panic_str("only the account or its relayer can request a challenge");
+        }
+        if self.challenges_of(&account_id, false).len() >= MAX_OPEN_CHALLENGES {
+            env::panic_str("the account has too many open challenges");
+        }
+        let usage_before = env::storage_usage();
+        let request_id = fresh_request_id(&account_id, self.challenge_nonce);
+        self.challenge_nonce += 1;
+        let challenge = Challenge {
+            request_id: request_id.clone(),
+            account_id: account_id.clone(),
+            issued_at_ms: env::block_timestamp_ms(),
+            consumed_at_ms: None,
+        };
+        self.challenges.insert(request_id.clone(), challenge);
+        let mut issued = self.account_challenges.get(&account_id).cloned().unwrap_or_default();
+        issued.push(request_id.clone());
+        self.account_challenges.insert(account_id, issued);
+        self.challenges.flush();
+        self.account_challenges.flush();
+        charge_storage(usage_before);
+        request_id
+    }
+
+    pub fn get_challenge(&self, request_id: String) -> Option<Challenge> {
+        self.challenges.get(&request_id).cloned()
+    }
+
+    /// Challenges issued for `account_id` that no recovery has consumed yet,
+    /// oldest first.
+    pub fn get_open_challenges(&self, account_id: String) -> Vec<Challenge> {
+        self.challenges_of(&account_id, false)
+    }
+
+    /// Challenges of `account_id` already consumed by a recovery, oldest
+    /// first.
+    pub fn get_consumed_challenges(&self, account_id: String) -> Vec<Challenge> {
+        self.challenges_of(&account_id, true)
+    }
+}
+
+impl ZkEmailVerifier {
+    fn challenges_of(&self, account_id: &str, consumed: bool) -> Vec<Challenge> {
+        let Some(issued) = self.account_challenges.get(account_id) else {
+            return Vec::new();
+        };
+        issued
+            .iter()
+            .filter_map(|id| self.challenges.get(id))
+            .filter(|c| c.consumed_at_ms.is_some() == consumed)
+            .cloned()
+            .collect()
+    }
+
+    /// Reject a verified `result` whose request id names a consumed
+    /// challenge, or one issued for another account.
+    pub(crate) fn check_challenge(&self, result: VerificationResult) -> VerificationResult {
+        if !result.verified {
+            return result;
+        }
+        match self.challenges.get(&result.request_id) {
+            Some(c) if c.account_id != result.account_id => {
+                result.with_failure(FailureReason::ChallengeAccountMismatch)
+            }
+            Some(c) if c.consumed_at_ms.is_some() => result.with_failure(FailureReason::ChallengeConsumed),
+            _ => result,
+        }
+    }
+
+    /// Mark the challenge of a verified `result`, if it names one, consumed.
+    pub(crate) fn consume_challenge(&mut self, result: &VerificationResult) {
+        if !result.verified {
+            return;
+        }
+        if let Some(challenge) = self.challenges.get_mut(&result.request_id) {
+            challenge.consumed_at_ms.get_or_insert(env::block_timestamp_ms());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    /// Called by `alice.near` with enough attached for a challenge.
+    fn as_alice() -> VMContextBuilder {
+        let mut context = VMContextBuilder::new();
+        context
+            .predecessor_account_id("alice.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(10));
+        context
+    }
+
+    #[test]
+    fn issued_ids_are_unique_and_recorded() {
+        testing_env!(as_alice().block_timestamp(5_000_000_000).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let template = contract
+            .get_email_template("alice.near".to_string(), "Abc123".to_string())
+            .unwrap();
+
+        let first = contract.generate_request_id("alice.near".to_string());
+        let second = contract.generate_request_id("alice.near".to_string());
+        assert_eq!(first, template.request_id);
+        assert_ne!(first, second);
+        assert_eq!(
+            contract.get_challenge(first.clone()),
+            Some(Challenge {
+                request_id: first,
+                account_id: "alice.near".to_string(),
+                issued_at_ms: 5_000,
+                consumed_at_ms: None,
+            })
+        );
+        assert_eq!(contract.get_challenge("unknown".to_string()), None);
+    }
+
+    #[test]
+    fn a_challenge_is_consumed_once() {
+        testing_env!(as_alice().build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let request_id = contract.generate_request_id("alice.near".to_string());
+        let mut result = VerificationResult::verified_for("");
+        result.request_id = request_id.clone();
+        result.account_id = "bob.near".to_string();
+        let open = |contract: &ZkEmailVerifier| contract.get_open_challenges("alice.near".to_string()).len();
+
+        let stolen = contract.check_challenge(result.clone());
+        assert_eq!(stolen.failure_reason.as_deref(), Some("CHALLENGE_ACCOUNT_MISMATCH"));
+        result.account_id = "alice.near".to_string();
+
+        assert_eq!(open(&contract), 1);
+        let first = contract.check_challenge(result.clone());
+        assert!(first.verified);
+        contract.consume_challenge(&first);
+        assert_eq!(open(&contract), 0);
+        assert_eq!(contract.get_consumed_challenges("alice.near".to_string())[0].request_id, request_id);
+
+        let replay = contract.check_challenge(result.clone());
+        assert_eq!(replay.failure_reason.as_deref(), Some("CHALLENGE_CONSUMED"));
+
+        result.request_id = "not-issued".to_string();
+        assert!(contract.check_challenge(result).verified);
+    }
+
+    #[test]
+    fn relayers_can_request_up_to_the_open_limit() {
+        testing_env!(as_alice().build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.authorize_relayer(accounts(1));
+        testing_env!(as_alice().predecessor_account_id(accounts(1)).build());
+        for _ in 0..MAX_OPEN_CHALLENGES {
+            contract.generate_request_id("alice.near".to_string());
+        }
+        assert_eq!(contract.get_open_challenges("alice.near".to_string()).len(), MAX_OPEN_CHALLENGES);
+    }
+
+    #[test]
+    #[should_panic(expected = "the account has too many open challenges")]
+    fn open_challenges_are_capped() {
+        testing_env!(as_alice().build());
+        let mut contract = ZkEmailVerifier::new(None);
+        for _ in 0..=MAX_OPEN_CHALLENGES {
+            contract.generate_request_id("alice.near".to_string());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "only the account or its relayer can request a challenge")]
+    fn others_cannot_request_challenges() {
+        testing_env!(as_alice().predecessor_account_id(accounts(2)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.generate_request_id("alice.near".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "to cover storage")]
+    fn challenges_pay_for_their_storage() {
+        testing_env!(as_alice().attached_deposit(NearToken::from_yoctonear(1)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.generate_request_id("alice.near".to_string());
+    }
+}
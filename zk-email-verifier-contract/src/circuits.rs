@@ -0,0 +1,473 @@
+//! The circuits this deployment verifies, with what a prover needs to pick
+//! matching artifacts.
+//!
+//! `RecoverEmailCircuit` is verified with the key set by `set_verifying_key`.
+//! The owner can register more builds of it under their own circuit ids (e.g.
+//! one per provider, for different key sizes or header quirks) and map sender
+//! domains to them. Methods that are given the `From:` header
+//! (`verify_with_binding`, `verify_staged`) then verify with the circuit
+//! mapped to its domain, and fall back to `RecoverEmailCircuit` otherwise.
+//!
+//! Each registered circuit has a public input layout, `RecoverEmailCircuit`'s
+//! unless registered with another one. `verify` picks the circuit by the
+//! layout the caller names, or by the number of public inputs.
+//!
+//! A layout's kind says what its emails may do: only `recovery` circuits are
+//! considered by `verify` and can be mapped to sender domains, and each
+//! command method only accepts the `command` circuits of its own layout. A
+//! proof of, say, `MigrateAccountCircuit` can't be passed off as a recovery
+//! and skip the timelock and command nullifier of `migrate_account`.
+//!
+//! A registered circuit can be deprecated as of some time: it stays in
+//! `Grace` until then and is skipped when picking a circuit afterwards.
+
+use ark_bn254::Bn254;
+use ark_groth16::{PreparedVerifyingKey, VerifyingKey};
+use ark_serialize::CanonicalSerialize;
+use near_sdk::{env, near};
+
+use crate::layout::{CircuitKind, RECOVER_EMAIL_CIRCUIT_ID};
+use crate::senders::sender_domain;
+use crate::{
+    check_ic_len, prepared_vk, CircuitLayout, FailureReason, VerifyingKeyInput, ZkEmailVerifier,
+    ZkEmailVerifierExt,
+};
+use zk_email_verifier_core::layout::{self as core_layout, public_input_layout, RECOVER_EMAIL_LAYOUT};
+
+#[near(serializers = [json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitStatus {
+    /// Accepted and recommended for new proofs.
+    Active,
+    /// Still accepted during a migration, but provers should switch.
+    Grace,
+    /// No longer accepted.
+    Deprecated,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SupportedCircuit {
+    pub circuit_id: String,
+    /// SHA-256 of the verifying key compressed with ark-serialize, i.e. the
+    /// `generate-vk --format borsh` output without its 4-byte length prefix.
+    pub vk_hash: Vec<u8>,
+    /// Key for `get_public_input_layout` and `get_subject_template`.
+    pub layout_id: String,
+    pub public_input_count: u32,
+    /// Whether the circuit's emails are recoveries or commands.
+    pub kind: CircuitKind,
+    pub status: CircuitStatus,
+    /// When the circuit stops (or stopped) being accepted, if deprecated.
+    pub deprecated_at_ms: Option<u64>,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_supported_circuits(&self) -> Vec<SupportedCircuit> {
+        self.circuit_ids()
+            .into_iter()
+            .map(|circuit_id| {
+                let mut vk = Vec::new();
+                self.prepared_verifying_key_for(&circuit_id)
+                    .vk
+                    .serialize_compressed(&mut vk)
+                    .expect("serializing into a Vec cannot fail");
+                let layout = self.layout_of(&circuit_id);
+                SupportedCircuit {
+                    vk_hash: env::sha256(&vk),
+                    layout_id: layout.circuit_id.to_string(),
+                    public_input_count: layout.len as u32,
+                    kind: layout.kind.into(),
+                    status: self.circuit_status(&circuit_id),
+                    deprecated_at_ms: self.circuit_deprecations.get(&circuit_id).copied(),
+                    circuit_id,
+                }
+            })
+            .collect()
+    }
+
+    /// Register (or, with `None`, remove) the verifying key of an additional
+    /// circuit. Only callable by the owner; `RecoverEmailCircuit` itself is
+    /// keyed with `set_verifying_key`.
+    ///
+    /// `layout_id` names the circuit's public input layout (see
+    /// `get_public_input_layout`) and defaults to `RecoverEmailCircuit`'s.
+    pub fn set_circuit_verifying_key(
+        &mut self,
+        circuit_id: String,
+        vk: Option<VerifyingKeyInput>,
+        layout_id: Option<String>,
+    ) {
+        self.assert_owner();
+        if circuit_id == RECOVER_EMAIL_CIRCUIT_ID {
+            env::panic_str("use set_verifying_key for the default circuit");
+        }
+        match vk {
+            Some(vk) => {
+                let layout = match layout_id.as_deref() {
+                    Some(layout_id) => public_input_layout(layout_id)
+                        .unwrap_or_else(|| env::panic_str(&format!("unknown layout {layout_id}"))),
+                    None => &RECOVER_EMAIL_LAYOUT,
+                };
+                check_ic_len(vk.ic.len(), layout);
+                let vk = prepared_vk::parse_verifying_key(&vk)
+                    .unwrap_or_else(|_| env::panic_str("invalid verifying key"));
+                self.put_verifying_key(&circuit_id, &vk);
+                self.circuit_layouts
+                    .insert(circuit_id.clone(), layout.circuit_id.to_string());
+                env::log_str(&format!("verifying key updated for {circuit_id}"));
+            }
+            None => {
+                self.circuit_vks.remove(&circuit_id);
+                self.circuit_layouts.remove(&circuit_id);
+                self.circuit_deprecations.remove(&circuit_id);
+                self.vk_hashes.remove(&circuit_id);
+                self.pvk_cache.get_mut().remove(&circuit_id);
+            }
+        }
+    }
+
+    /// Stop accepting proofs for `circuit_id` from `deprecated_at_ms` on, or
+    /// reinstate it with `None`. Only callable by the owner; until that time
+    /// the circuit is reported as `Grace`. `RecoverEmailCircuit` is the
+    /// fallback for every sender and can't be deprecated.
+    pub fn set_circuit_deprecation(&mut self, circuit_id: String, deprecated_at_ms: Option<u64>) {
+        self.assert_owner();
+        if circuit_id == RECOVER_EMAIL_CIRCUIT_ID {
+            env::panic_str("the default circuit can't be deprecated");
+        }
+        if self.prepared_vk_bytes(&circuit_id).is_none() {
+            env::panic_str(&format!("unknown circuit {circuit_id}"));
+        }
+        match deprecated_at_ms {
+            Some(at_ms) => self.circuit_deprecations.insert(circuit_id, at_ms),
+            None => self.circuit_deprecations.remove(&circuit_id),
+        };
+    }
+
+    pub fn get_domain_circuit(&self, domain: String) -> Option<String> {
+        self.domain_circuits.get(&domain.to_ascii_lowercase()).cloned()
+    }
+
+    /// Verify proofs for emails from `domain` with `circuit_id`, or clear the
+    /// mapping with `None`. Only callable by the owner; `circuit_id` must be
+    /// a recovery circuit.
+    pub fn set_domain_circuit(&mut self, domain: String, circuit_id: Option<String>) {
+        self.assert_owner();
+        let domain = domain.trim().to_ascii_lowercase();
+        match circuit_id {
+            Some(circuit_id) => {
+                if self.prepared_vk_bytes(&circuit_id).is_none() {
+                    env::panic_str(&format!("unknown circuit {circuit_id}"));
+                }
+                if self.layout_of(&circuit_id).kind != core_layout::CircuitKind::Recovery {
+                    env::panic_str(&format!("{circuit_id} is not a recovery circuit"));
+                }
+                self.domain_circuits.insert(domain, circuit_id);
+            }
+            None => {
+                self.domain_circuits.remove(&domain);
+            }
+        }
+    }
+}
+
+impl ZkEmailVerifier {
+    /// `RecoverEmailCircuit` followed by the registered circuits.
+    pub(crate) fn circuit_ids(&self) -> Vec<String> {
+        let extra = self.circuit_vks.keys().cloned();
+        std::iter::once(RECOVER_EMAIL_CIRCUIT_ID.to_string()).chain(extra).collect()
+    }
+
+    pub(crate) fn circuit_status(&self, circuit_id: &str) -> CircuitStatus {
+        match self.circuit_deprecations.get(circuit_id) {
+            None => CircuitStatus::Active,
+            Some(&at_ms) if env::block_timestamp_ms() < at_ms => CircuitStatus::Grace,
+            Some(_) => CircuitStatus::Deprecated,
+        }
+    }
+
+    fn accepts(&self, circuit_id: &str) -> bool {
+        self.circuit_status(circuit_id) != CircuitStatus::Deprecated
+    }
+
+    /// The circuit to verify an email claimed to be from `from_header` with.
+    /// A mapped circuit whose key was removed, or that is deprecated, falls
+    /// back to the default too.
+    pub(crate) fn circuit_for_sender(&self, from_header: Option<&str>) -> String {
+        from_header
+            .and_then(sender_domain)
+            .and_then(|domain| self.domain_circuits.get(&domain))
+            .filter(|circuit_id| {
+                self.prepared_vk_bytes(circuit_id).is_some()
+                    && self.accepts(circuit_id)
+                    && self.layout_of(circuit_id).kind == core_layout::CircuitKind::Recovery
+            })
+            .cloned()
+            .unwrap_or_else(|| RECOVER_EMAIL_CIRCUIT_ID.to_string())
+    }
+
+    /// The public input layout of `circuit_id`.
+    pub(crate) fn layout_of(&self, circuit_id: &str) -> &'static CircuitLayout {
+        self.circuit_layouts
+            .get(circuit_id)
+            .and_then(|layout_id| public_input_layout(layout_id))
+            .unwrap_or(&RECOVER_EMAIL_LAYOUT)
+    }
+
+    /// The circuit of `kind` to verify proofs laid out by `layout_id` with,
+    /// or, without one, with `input_len` public inputs. Inputs that fit no
+    /// circuit go to `RecoverEmailCircuit`, to be rejected for their length.
+    /// Deprecated circuits and circuits of the other kind are not considered.
+    pub(crate) fn circuit_for_layout(
+        &self,
+        kind: core_layout::CircuitKind,
+        layout_id: Option<&str>,
+        input_len: usize,
+    ) -> Result<String, FailureReason> {
+        let ids: Vec<String> = self
+            .circuit_ids()
+            .into_iter()
+            .filter(|c| self.accepts(c) && self.layout_of(c).kind == kind)
+            .collect();
+        let matching: Vec<&String> = match layout_id {
+            Some(layout_id) => ids.iter().filter(|c| self.layout_of(c).circuit_id == layout_id).collect(),
+            None => ids.iter().filter(|c| self.layout_of(c).len == input_len).collect(),
+        };
+        let Some(first) = matching.first() else {
+            return match layout_id {
+                Some(_) => Err(FailureReason::UnknownLayout),
+                None => Ok(RECOVER_EMAIL_CIRCUIT_ID.to_string()),
+            };
+        };
+        let layout = self.layout_of(first).circuit_id;
+        if matching.iter().any(|c| self.layout_of(c).circuit_id != layout) {
+            return Err(FailureReason::UnknownLayout);
+        }
+        Ok(first.to_string())
+    }
+
+    /// The serialized prepared key of `circuit_id`, if it has one.
+    pub(crate) fn prepared_vk_bytes(&self, circuit_id: &str) -> Option<&[u8]> {
+        if circuit_id == RECOVER_EMAIL_CIRCUIT_ID {
+            self.prepared_vk.get().as_deref()
+        } else {
+            self.circuit_vks.get(circuit_id).map(Vec::as_slice)
+        }
+    }
+
+    /// SHA-256 of the serialized prepared key of `circuit_id`.
+    pub(crate) fn prepared_vk_hash(&self, circuit_id: &str) -> Vec<u8> {
+        env::sha256(self.prepared_vk_bytes(circuit_id).unwrap_or_default())
+    }
+
+    /// Prepare `vk`, store it as the key of `circuit_id` and pin its hash.
+    pub(crate) fn put_verifying_key(&mut self, circuit_id: &str, vk: &VerifyingKey<Bn254>) {
+        let bytes = prepared_vk::prepare_and_serialize(vk);
+        self.vk_hashes.insert(circuit_id.to_string(), env::sha256(&bytes));
+        if circuit_id == RECOVER_EMAIL_CIRCUIT_ID {
+            self.prepared_vk.set(Some(bytes));
+        } else {
+            self.circuit_vks.insert(circuit_id.to_string(), bytes);
+        }
+        self.pvk_cache.get_mut().remove(circuit_id);
+    }
+
+    /// The prepared key of `circuit_id`. It is read from state and checked
+    /// against its pinned hash on first use, then reused for the rest of the
+    /// call.
+    pub(crate) fn prepared_verifying_key_for(&self, circuit_id: &str) -> PreparedVerifyingKey<Bn254> {
+        if let Some(pvk) = self.pvk_cache.borrow().get(circuit_id) {
+            return pvk.clone();
+        }
+        let bytes = self
+            .prepared_vk_bytes(circuit_id)
+            .unwrap_or_else(|| env::panic_str("verifying key is not set"));
+        if self.vk_hashes.get(circuit_id) != Some(&env::sha256(bytes)) {
+            env::panic_str(&format!("verifying key of {circuit_id} does not match its pinned hash"));
+        }
+        let pvk = prepared_vk::deserialize_prepared(bytes)
+            .unwrap_or_else(|| env::panic_str("verifying key is not set"));
+        self.pvk_cache
+            .borrow_mut()
+            .insert(circuit_id.to_string(), pvk.clone());
+        pvk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::trapdoor_circuit;
+    use crate::vk;
+    use core_layout::CircuitKind::{Command, Recovery};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use zk_email_verifier_core::layout::{MIGRATE_ACCOUNT_CIRCUIT_ID, MIGRATE_ACCOUNT_LAYOUT};
+
+    #[test]
+    fn supported_circuit_hashes_the_compiled_in_key() {
+        testing_env!(VMContextBuilder::new().build());
+        let contract = ZkEmailVerifier::new(None);
+        let circuits = contract.get_supported_circuits();
+        assert_eq!(circuits.len(), 1);
+
+        let mut compressed = Vec::new();
+        vk::verifying_key().serialize_compressed(&mut compressed).unwrap();
+        assert_eq!(circuits[0].vk_hash, env::sha256(&compressed));
+        assert!(crate::layout::public_input_layout(&circuits[0].layout_id)
+            .is_some_and(|layout| layout.len == circuits[0].public_input_count));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match its pinned hash")]
+    fn stored_keys_are_checked_against_their_pinned_hash() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let pvk = contract.prepared_verifying_key_for(RECOVER_EMAIL_CIRCUIT_ID);
+
+        // Overwrite the stored key behind the pin's back: the copy checked
+        // earlier in this call is still used ...
+        let mut bytes = contract.prepared_vk_bytes(RECOVER_EMAIL_CIRCUIT_ID).unwrap().to_vec();
+        bytes[0] ^= 1;
+        contract.prepared_vk.set(Some(bytes));
+        assert_eq!(contract.prepared_verifying_key_for(RECOVER_EMAIL_CIRCUIT_ID), pvk);
+
+        // ... but a fresh call refuses it.
+        contract.pvk_cache.get_mut().clear();
+        contract.prepared_verifying_key_for(RECOVER_EMAIL_CIRCUIT_ID);
+    }
+
+    #[test]
+    fn sender_domains_select_their_mapped_circuit() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let vk = contract.get_verifying_key_json().key;
+        contract.set_circuit_verifying_key("recover_email_outlook".into(), Some(vk), None);
+        contract.set_domain_circuit("Outlook.com".into(), Some("recover_email_outlook".into()));
+
+        assert_eq!(
+            contract.get_domain_circuit("outlook.com".into()).as_deref(),
+            Some("recover_email_outlook")
+        );
+        assert_eq!(
+            contract.circuit_for_sender(Some("Bob <bob@OUTLOOK.com>")),
+            "recover_email_outlook"
+        );
+        assert_eq!(contract.circuit_for_sender(Some("bob@gmail.com")), RECOVER_EMAIL_CIRCUIT_ID);
+        assert_eq!(contract.circuit_for_sender(None), RECOVER_EMAIL_CIRCUIT_ID);
+
+        let circuits = contract.get_supported_circuits();
+        assert_eq!(circuits.len(), 2);
+        assert_eq!(circuits[0].vk_hash, circuits[1].vk_hash);
+
+        contract.set_circuit_verifying_key("recover_email_outlook".into(), None, None);
+        assert_eq!(contract.circuit_for_sender(Some("bob@outlook.com")), RECOVER_EMAIL_CIRCUIT_ID);
+    }
+
+    #[test]
+    fn layouts_resolve_to_the_circuits_using_them() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let vk = contract.get_verifying_key_json().key;
+        contract.set_circuit_verifying_key("recover_email_outlook".into(), Some(vk), None);
+
+        let len = RECOVER_EMAIL_LAYOUT.len;
+        assert_eq!(
+            contract.circuit_for_layout(Recovery, Some(RECOVER_EMAIL_CIRCUIT_ID), 0).as_deref(),
+            Ok(RECOVER_EMAIL_CIRCUIT_ID)
+        );
+        assert_eq!(
+            contract.circuit_for_layout(Recovery, None, len).as_deref(),
+            Ok(RECOVER_EMAIL_CIRCUIT_ID)
+        );
+        assert_eq!(
+            contract.circuit_for_layout(Recovery, None, 3).as_deref(),
+            Ok(RECOVER_EMAIL_CIRCUIT_ID)
+        );
+        assert_eq!(
+            contract.circuit_for_layout(Recovery, Some("SlimCircuit"), len),
+            Err(FailureReason::UnknownLayout)
+        );
+        assert_eq!(contract.layout_of("recover_email_outlook").len, len);
+    }
+
+    #[test]
+    fn deprecated_circuits_report_grace_then_stop_being_picked() {
+        let at = |ms: u64| {
+            VMContextBuilder::new()
+                .predecessor_account_id(accounts(0))
+                .block_timestamp(ms * 1_000_000)
+                .build()
+        };
+        testing_env!(at(1_000));
+        let mut contract = ZkEmailVerifier::new(None);
+        let vk = contract.get_verifying_key_json().key;
+        contract.set_circuit_verifying_key("slim".into(), Some(vk), None);
+        contract.set_domain_circuit("outlook.com".into(), Some("slim".into()));
+        assert_eq!(contract.get_supported_circuits()[1].status, CircuitStatus::Active);
+
+        contract.set_circuit_deprecation("slim".into(), Some(2_000));
+        let slim = &contract.get_supported_circuits()[1];
+        assert_eq!((slim.status, slim.deprecated_at_ms), (CircuitStatus::Grace, Some(2_000)));
+        assert_eq!(contract.circuit_for_sender(Some("bob@outlook.com")), "slim");
+
+        testing_env!(at(2_000));
+        assert_eq!(contract.get_supported_circuits()[1].status, CircuitStatus::Deprecated);
+        assert_eq!(contract.circuit_for_sender(Some("bob@outlook.com")), RECOVER_EMAIL_CIRCUIT_ID);
+
+        contract.set_circuit_deprecation("slim".into(), None);
+        assert_eq!(contract.get_supported_circuits()[1].status, CircuitStatus::Active);
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown circuit")]
+    fn domains_can_only_map_to_registered_circuits() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_domain_circuit("outlook.com".into(), Some("nope".into()));
+    }
+
+    #[test]
+    fn command_circuits_are_only_picked_for_their_kind() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let (vk, _) = trapdoor_circuit(MIGRATE_ACCOUNT_LAYOUT.len);
+        let layout_id = Some(MIGRATE_ACCOUNT_CIRCUIT_ID.to_string());
+        contract.set_circuit_verifying_key("migrate".into(), Some(vk), layout_id);
+        let len = MIGRATE_ACCOUNT_LAYOUT.len;
+
+        assert_eq!(
+            contract.circuit_for_layout(Command, Some(MIGRATE_ACCOUNT_CIRCUIT_ID), len).as_deref(),
+            Ok("migrate")
+        );
+        assert_eq!(
+            contract.circuit_for_layout(Recovery, Some(MIGRATE_ACCOUNT_CIRCUIT_ID), len),
+            Err(FailureReason::UnknownLayout)
+        );
+        assert_eq!(
+            contract.circuit_for_layout(Recovery, None, len).as_deref(),
+            Ok(RECOVER_EMAIL_CIRCUIT_ID)
+        );
+        assert_eq!(
+            contract.circuit_for_layout(Command, Some(RECOVER_EMAIL_CIRCUIT_ID), 0),
+            Err(FailureReason::UnknownLayout)
+        );
+        let migrate = &contract.get_supported_circuits()[1];
+        assert_eq!(migrate.kind, CircuitKind::Command);
+        assert_eq!(migrate.layout_id, MIGRATE_ACCOUNT_CIRCUIT_ID);
+    }
+
+    #[test]
+    #[should_panic(expected = "migrate is not a recovery circuit")]
+    fn domains_can_only_map_to_recovery_circuits() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let (vk, _) = trapdoor_circuit(MIGRATE_ACCOUNT_LAYOUT.len);
+        let layout_id = Some(MIGRATE_ACCOUNT_CIRCUIT_ID.to_string());
+        contract.set_circuit_verifying_key("migrate".into(), Some(vk), layout_id);
+        contract.set_domain_circuit("outlook.com".into(), Some("migrate".into()));
+    }
+}
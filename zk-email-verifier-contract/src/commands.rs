@@ -0,0 +1,164 @@
+//! Shared verification of command emails.
+//!
+//! Besides recoveries, an account's registered mailbox can send commands
+//! (`migrate-`, ...), each proven with its own circuit. A command is only
+//! accepted from the email the account registered (see `registration`), so
+//! a valid proof from any other address is not enough. Guardian vetoes are
+//! the exception: they come from a guardian's mailbox instead (see
+//! `guardians`).
+//!
+//! Like recoveries, commands are held to the freshness window and the
+//! sender policy (checked against the optional `from_header` each command
+//! method takes), and each command email consumes a nullifier (see
+//! `nullifiers`), so an old proof can't be replayed.
+
+use ark_bn254::Fr;
+use near_sdk::env;
+use zk_email_verifier_core::layout::CircuitKind;
+use zk_email_verifier_core::{decode_with_layout, parse_email_timestamp};
+
+use crate::packing::unpack_field_chunks_to_str;
+use crate::{
+    decode_verified_inputs, validate_account_id, verify_groth16, CircuitLayout, FailureReason, ProofInput,
+    VerificationResult, ZkEmailVerifier,
+};
+
+/// A command email whose proof verified.
+pub(crate) struct VerifiedCommand {
+    pub account_id: String,
+    pub request_id: String,
+    pub from_address_hash: Vec<u8>,
+    /// Time of the `Date:` header, if it parsed.
+    pub email_timestamp_ms: Option<u64>,
+    layout: &'static CircuitLayout,
+    inputs: Vec<Fr>,
+}
+
+impl VerifiedCommand {
+    /// Id of the layout the command was verified with.
+    pub(crate) fn layout_id(&self) -> &'static str {
+        self.layout.circuit_id
+    }
+
+    /// The packed string field `name`, if the layout has it and it decodes.
+    pub(crate) fn text(&self, name: &str) -> Option<String> {
+        unpack_field_chunks_to_str(self.layout.get(name, &self.inputs)?).ok()
+    }
+
+    /// A `recover-` email decoded the way `verify_staged` decodes it.
+    pub(crate) fn recovery(&self) -> VerificationResult {
+        decode_verified_inputs(self.layout, &self.inputs)
+    }
+
+    /// A command with no fields beyond the ones it is matched on.
+    #[cfg(test)]
+    pub(crate) fn stub(account_id: &str, request_id: &str, from_address_hash: Vec<u8>) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            request_id: request_id.to_string(),
+            from_address_hash,
+            email_timestamp_ms: None,
+            layout: &zk_email_verifier_core::layout::LOCK_ACCOUNT_LAYOUT,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Verify a proof of the circuit laid out by `layout_id`, check that it
+    /// was sent from the registered email of its account and admit it.
+    pub(crate) fn verify_command(
+        &mut self,
+        layout_id: &str,
+        proof: ProofInput,
+        public_inputs: &[String],
+        from_header: Option<&str>,
+    ) -> Result<VerifiedCommand, FailureReason> {
+        let command = self.verify_command_proof(CircuitKind::Command, layout_id, proof, public_inputs)?;
+        self.check_registered_email(&command.account_id, &command.from_address_hash)?;
+        self.admit_command(&command, from_header)?;
+        Ok(command)
+    }
+
+    /// Hold a verified `command` to the freshness window and the sender
+    /// policy, then consume its nullifier. Commands abort on any later
+    /// failure, which undoes the consumption.
+    pub(crate) fn admit_command(
+        &mut self,
+        command: &VerifiedCommand,
+        from_header: Option<&str>,
+    ) -> Result<(), FailureReason> {
+        self.check_freshness_of(command.email_timestamp_ms)?;
+        self.check_sender_of(&command.account_id, &command.from_address_hash, from_header)?;
+        self.consume_command_nullifier(command)
+    }
+
+    /// Verify a proof of the circuit of `kind` laid out by `layout_id`,
+    /// whoever sent the email. The caller admits it with `admit_command`.
+    pub(crate) fn verify_command_proof(
+        &self,
+        kind: CircuitKind,
+        layout_id: &str,
+        proof: ProofInput,
+        public_inputs: &[String],
+    ) -> Result<VerifiedCommand, FailureReason> {
+        self.assert_activated();
+        let circuit_id = self.circuit_for_layout(kind, Some(layout_id), public_inputs.len())?;
+        let layout = self.layout_of(&circuit_id);
+        let (proof, inputs) = self.parse_logged(proof, public_inputs, layout)?;
+        if !verify_groth16(&self.prepared_verifying_key_for(&circuit_id), &proof, &inputs) {
+            return Err(FailureReason::Pairing);
+        }
+        let fields = decode_with_layout(layout, &inputs).unwrap_or_default();
+        let account_id = fields.account_id.unwrap_or_default();
+        validate_account_id(&account_id)?;
+        self.check_namespace_of(&account_id)?;
+        Ok(VerifiedCommand {
+            account_id,
+            request_id: fields.request_id.unwrap_or_default(),
+            from_address_hash: fields.from_address_hash.unwrap_or_default(),
+            email_timestamp_ms: fields
+                .timestamp
+                .as_deref()
+                .and_then(parse_email_timestamp)
+                .map(|t| t.unix_ms),
+            layout,
+            inputs,
+        })
+    }
+}
+
+/// Abort a command with the code of `reason`.
+pub(crate) fn reject(reason: FailureReason) -> ! {
+    env::panic_str(&format!("command rejected: {}", reason.as_str()))
+}
+
+/// Decode a SHA-256 digest anchored as 64 hex characters.
+pub(crate) fn decode_hex_hash(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() != 64 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_hashes_must_be_32_bytes() {
+        let hash = env::sha256(b"alice@example.com|alice.near");
+        let hex = encode_hex(&hash);
+        assert_eq!(decode_hex_hash(&hex), Some(hash));
+        assert_eq!(decode_hex_hash(&hex.to_uppercase()).map(|h| h.len()), Some(32));
+        assert_eq!(decode_hex_hash(&hex[2..]), None);
+        assert_eq!(decode_hex_hash(&format!("zz{}", &hex[2..])), None);
+    }
+}
@@ -0,0 +1,94 @@
+//! Minimum time between two recoveries of the same account.
+//!
+//! Whoever controls an account's recovery mailbox can recover it again and
+//! again. With a cooldown set, a `verify_staged` recovery of an account that
+//! was recovered less than the cooldown ago is rejected with `COOLDOWN`,
+//! which leaves the owner time to notice and react after the first one. The
+//! time of the last recovery comes from the account's verification history
+//! (see `audit`).
+
+use near_sdk::{env, near};
+
+use crate::{FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryStatus {
+    /// Block time of the account's latest verified recovery.
+    pub last_recovery_ms: Option<u64>,
+    /// When the next recovery will be accepted; `None` if it already is.
+    pub cooldown_until_ms: Option<u64>,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_recovery_status(&self, account_id: String) -> RecoveryStatus {
+        let last_recovery_ms = self.last_recovery_ms(&account_id);
+        let cooldown_until_ms = last_recovery_ms
+            .zip(self.recovery_cooldown_ms)
+            .map(|(last, cooldown)| last.saturating_add(cooldown))
+            .filter(|&until| until > env::block_timestamp_ms());
+        RecoveryStatus { last_recovery_ms, cooldown_until_ms }
+    }
+
+    pub fn get_recovery_cooldown_ms(&self) -> Option<u64> {
+        self.recovery_cooldown_ms
+    }
+
+    /// Require `cooldown_ms` between two recoveries of an account, or drop
+    /// the requirement with `None`. Only callable by the owner.
+    pub fn set_recovery_cooldown_ms(&mut self, cooldown_ms: Option<u64>) {
+        self.assert_owner();
+        self.recovery_cooldown_ms = cooldown_ms;
+    }
+}
+
+impl ZkEmailVerifier {
+    fn last_recovery_ms(&self, account_id: &str) -> Option<u64> {
+        let len = *self.account_history_len.get(account_id)?;
+        let last = self.account_history.get(&(account_id.to_string(), len.checked_sub(1)?))?;
+        Some(last.block_timestamp_ms)
+    }
+
+    /// Reject a verified `result` while its account is cooling down.
+    pub(crate) fn check_cooldown(&self, result: VerificationResult) -> VerificationResult {
+        if !result.verified {
+            return result;
+        }
+        match self.get_recovery_status(result.account_id.clone()).cooldown_until_ms {
+            Some(_) => result.with_failure(FailureReason::Cooldown),
+            None => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+    use crate::test_utils::at;
+
+    #[test]
+    fn recoveries_are_rejected_until_the_cooldown_ends() {
+        at(1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_recovery_cooldown_ms(Some(500));
+        let result = VerificationResult::verified_for("alice.near");
+
+        assert!(contract.check_cooldown(result.clone()).verified);
+        contract.audit(&result, RECOVER_EMAIL_CIRCUIT_ID);
+
+        at(1_499);
+        let status = contract.get_recovery_status("alice.near".to_string());
+        assert_eq!(status.last_recovery_ms, Some(1_000));
+        assert_eq!(status.cooldown_until_ms, Some(1_500));
+        assert_eq!(
+            contract.check_cooldown(result.clone()).failure_reason.as_deref(),
+            Some("COOLDOWN")
+        );
+
+        at(1_500);
+        assert_eq!(contract.get_recovery_status("alice.near".to_string()).cooldown_until_ms, None);
+        assert!(contract.check_cooldown(result).verified);
+    }
+}
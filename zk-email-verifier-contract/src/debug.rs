@@ -0,0 +1,87 @@
+//! Verbose diagnostics as `ZKEV_DEBUG:<json>` log lines.
+//!
+//! These carry high-cardinality details (chunk-level binding mismatches,
+//! parse errors) that don't belong in the NEP-297 events of `events`, so they
+//! use their own prefix and are off unless the owner turns them on with
+//! `set_debug_logs`, e.g. on a testnet deployment.
+
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::Proof;
+use near_sdk::{env, near, serde_json};
+
+use crate::diagnostics::BindingCheck;
+use crate::{
+    parse_proof_and_inputs, CircuitLayout, FailureReason, ProofInput, ZkEmailVerifier, ZkEmailVerifierExt,
+};
+
+pub const DEBUG_LOG_PREFIX: &str = "ZKEV_DEBUG:";
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DebugLog {
+    /// The proof or public inputs were rejected before the pairing check.
+    ParseError { reason: String, public_input_len: u32 },
+    BindingMismatch(BindingCheck),
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Turn `ZKEV_DEBUG:` log lines on or off. Only callable by the owner.
+    pub fn set_debug_logs(&mut self, enabled: bool) {
+        self.assert_owner();
+        self.debug_logs = enabled;
+    }
+}
+
+impl ZkEmailVerifier {
+    pub(crate) fn debug_log(&self, log: &DebugLog) {
+        if self.debug_logs {
+            let json = serde_json::to_string(log).expect("debug logs serialize");
+            env::log_str(&format!("{DEBUG_LOG_PREFIX}{json}"));
+        }
+    }
+
+    /// `parse_proof_and_inputs`, logging the reason it failed.
+    pub(crate) fn parse_logged(
+        &self,
+        proof: ProofInput,
+        public_inputs: &[String],
+        layout: &CircuitLayout,
+    ) -> Result<(Proof<Bn254>, Vec<Fr>), FailureReason> {
+        parse_proof_and_inputs(proof, public_inputs, layout).inspect_err(|reason| {
+            self.debug_log(&DebugLog::ParseError {
+                reason: reason.as_str().to_string(),
+                public_input_len: public_inputs.len() as u32,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn debug_logs_are_off_until_enabled() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let mismatch = DebugLog::BindingMismatch(BindingCheck {
+            field: "account_id".into(),
+            matched: false,
+            mismatch_index: Some(2),
+            error: None,
+        });
+        contract.debug_log(&mismatch);
+        assert!(get_logs().is_empty());
+
+        contract.set_debug_logs(true);
+        contract.debug_log(&mismatch);
+        assert_eq!(
+            get_logs(),
+            vec![r#"ZKEV_DEBUG:{"kind":"binding_mismatch","field":"account_id","matched":false,"mismatch_index":2,"error":null}"#]
+        );
+    }
+}
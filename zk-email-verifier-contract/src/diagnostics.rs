@@ -0,0 +1,75 @@
+//! Step-by-step report produced by `dry_run_verify`.
+//!
+//! `verify_with_binding` stops at the first failed check and only returns
+//! `verified: false`. The dry run keeps going and records the outcome of every
+//! check, so integrators can see exactly which step broke a recovery.
+
+use ark_bn254::Fr;
+use near_sdk::near;
+use zk_email_verifier_core::binding::{compare_fields, BindingOutcome};
+
+/// Outcome of comparing one caller-supplied value against the public inputs.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BindingCheck {
+    /// Field name as reported by `get_public_input_layout`.
+    pub field: String,
+    pub matched: bool,
+    /// Index within the field of the first public input that differs.
+    pub mismatch_index: Option<u32>,
+    /// Set when the caller value could not be encoded for comparison at all.
+    pub error: Option<String>,
+}
+
+impl BindingCheck {
+    pub(crate) fn compare(field: &str, actual: &[Fr], expected: &[Fr]) -> Self {
+        Self::from_outcome(field, compare_fields(actual, expected))
+    }
+
+    pub(crate) fn from_outcome(field: &str, outcome: BindingOutcome) -> Self {
+        if let Some(error) = outcome.error() {
+            return Self::failed(field, error);
+        }
+        let mismatch_index = match outcome {
+            BindingOutcome::Mismatch(i) => Some(i as u32),
+            _ => None,
+        };
+        Self {
+            field: field.to_string(),
+            matched: outcome.is_match(),
+            mismatch_index,
+            error: None,
+        }
+    }
+
+    pub(crate) fn failed(field: &str, error: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            matched: false,
+            mismatch_index: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Full diagnostic report for a proof and its expected bindings.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DryRunReport {
+    pub proof_parsed: bool,
+    pub public_inputs_parsed: bool,
+    pub public_input_len: u32,
+    pub expected_public_input_len: u32,
+    pub input_len_ok: bool,
+    /// One entry per checked binding. Empty when the public inputs could not
+    /// be parsed or have the wrong length.
+    pub bindings: Vec<BindingCheck>,
+    /// `None` when the pairing was not attempted because the proof or the
+    /// public inputs failed to parse.
+    pub pairing_ok: Option<bool>,
+    /// Failure code `verify_with_binding` would report when the bindings and
+    /// the pairing hold but the account or sender policy rejects the proof.
+    pub policy_failure: Option<String>,
+    /// Whether `verify_with_binding` would accept the same arguments.
+    pub verified: bool,
+}
@@ -0,0 +1,297 @@
+//! Helpers for working with raw email header payloads anchored by circuits.
+//!
+//! `RecoverEmailCircuit` only exposes a salted hash of the sender address, but
+//! circuits that anchor the whole `From:` header hand us the display name as
+//! well, often as RFC 2047 encoded words (`=?UTF-8?B?...?=`). The functions
+//! here decode those words and pull out the bare `addr-spec` so that binding
+//! and hashing always operate on the same canonical address.
+
+use near_sdk::base64::{engine::general_purpose::STANDARD, Engine};
+use near_sdk::env;
+
+/// Parsed `From:` header value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FromHeader {
+    /// Decoded display name, if the header carried one.
+    pub display_name: Option<String>,
+    /// Bare `local@domain` address, exactly as written in the header.
+    pub addr_spec: String,
+}
+
+/// Decode every RFC 2047 encoded word in `input`.
+///
+/// Supports the `B` (base64) and `Q` (quoted-printable) encodings for the
+/// `UTF-8`, `US-ASCII` and `ISO-8859-1` charsets. Whitespace between two
+/// adjacent encoded words is dropped as required by RFC 2047 §6.2. Words that
+/// fail to decode are left untouched, matching common MUA behaviour.
+pub fn decode_encoded_words(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    let mut prev_was_encoded = false;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find("=?") else {
+            out.push_str(rest);
+            break;
+        };
+
+        let (before, candidate) = rest.split_at(start);
+        match parse_encoded_word(candidate) {
+            Some((decoded, consumed)) => {
+                // Linear whitespace separating two encoded words is not part of the text.
+                if !(prev_was_encoded && before.chars().all(|c| c == ' ' || c == '\t')) {
+                    out.push_str(before);
+                }
+                out.push_str(&decoded);
+                rest = &candidate[consumed..];
+                prev_was_encoded = true;
+            }
+            None => {
+                out.push_str(before);
+                out.push_str("=?");
+                rest = &candidate[2..];
+                prev_was_encoded = false;
+            }
+        }
+    }
+
+    out
+}
+
+/// Parse a `From:` header (with or without the leading `from:` field name)
+/// into its decoded display name and bare address.
+///
+/// Encoded words, quoted strings and comments are skipped when searching for
+/// the `<angle-addr>`, so a display name can never smuggle in a different
+/// address once decoded.
+pub fn parse_from_header(header: &str) -> Option<FromHeader> {
+    let value = strip_field_name(header.trim());
+
+    let (display_raw, addr_raw) = match find_angle_addr(value) {
+        Some((open, close)) => (Some(&value[..open]), &value[open + 1..close]),
+        None => (None, value),
+    };
+
+    let addr_spec = strip_comments(addr_raw).trim().to_string();
+    if !is_valid_addr_spec(&addr_spec) {
+        return None;
+    }
+
+    let display_name = display_raw
+        .map(|raw| unquote(decode_encoded_words(raw.trim()).trim()))
+        .filter(|name| !name.is_empty());
+
+    Some(FromHeader { display_name, addr_spec })
+}
+
+/// Extract only the bare `addr-spec` from a `From:` header value.
+pub fn extract_addr_spec(header: &str) -> Option<String> {
+    parse_from_header(header).map(|h| h.addr_spec)
+}
+
+/// Canonical form of a sender address as hashed by the circuit: trimmed and
+/// ASCII-lowercased.
+pub fn canonicalize_address(addr_spec: &str) -> String {
+    addr_spec.trim().to_ascii_lowercase()
+}
+
+/// Compute the salted sender hash exposed by `RecoverEmailCircuit`:
+/// `sha256("<canonical_from>|<account_id_lower>")`, starting from a raw
+/// `From:` header.
+pub fn from_address_hash(from_header: &str, account_id: &str) -> Option<Vec<u8>> {
+    let addr_spec = extract_addr_spec(from_header)?;
+    let preimage = format!(
+        "{}|{}",
+        canonicalize_address(&addr_spec),
+        account_id.trim().to_ascii_lowercase()
+    );
+    Some(env::sha256(preimage.as_bytes()))
+}
+
+fn strip_field_name(value: &str) -> &str {
+    match value.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("from:") => value[5..].trim_start(),
+        _ => value,
+    }
+}
+
+/// Parse a single `=?charset?enc?text?=` token at the start of `s`, returning
+/// the decoded text and the number of bytes consumed.
+fn parse_encoded_word(s: &str) -> Option<(String, usize)> {
+    let body = s.strip_prefix("=?")?;
+    let (charset, body) = body.split_once('?')?;
+    let (encoding, body) = body.split_once('?')?;
+    let end = body.find("?=")?;
+    let text = &body[..end];
+
+    if charset.is_empty() || text.contains(|c: char| c.is_ascii_whitespace()) {
+        return None;
+    }
+
+    let bytes = match encoding {
+        "B" | "b" => STANDARD.decode(text).ok()?,
+        "Q" | "q" => decode_q(text)?,
+        _ => return None,
+    };
+
+    // RFC 2231 allows a language suffix: `UTF-8*en`.
+    let charset = charset.split('*').next().unwrap_or(charset);
+    let decoded = if charset.eq_ignore_ascii_case("utf-8") {
+        String::from_utf8(bytes).ok()?
+    } else if charset.eq_ignore_ascii_case("us-ascii") {
+        if !bytes.is_ascii() {
+            return None;
+        }
+        String::from_utf8(bytes).ok()?
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") || charset.eq_ignore_ascii_case("latin1") {
+        bytes.into_iter().map(char::from).collect()
+    } else {
+        return None;
+    };
+
+    // `body` is a suffix of `s`; the word ends right after `?=`.
+    let consumed = s.len() - body.len() + end + 2;
+    Some((decoded, consumed))
+}
+
+fn decode_q(text: &str) -> Option<Vec<u8>> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'_' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b'=' => {
+                let hex = text.get(i + 1..i + 3)?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    Some(out)
+}
+
+/// Locate the last `<...>` pair outside quoted strings, comments and encoded
+/// words. Returns the byte indices of `<` and `>`.
+fn find_angle_addr(value: &str) -> Option<(usize, usize)> {
+    let bytes = value.as_bytes();
+    let mut found = None;
+    let mut open = None;
+    let mut in_quote = false;
+    let mut comment_depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if in_quote {
+            match b {
+                b'\\' => i += 1,
+                b'"' => in_quote = false,
+                _ => {}
+            }
+        } else if comment_depth > 0 {
+            match b {
+                b'\\' => i += 1,
+                b'(' => comment_depth += 1,
+                b')' => comment_depth -= 1,
+                _ => {}
+            }
+        } else {
+            match b {
+                b'"' => in_quote = true,
+                b'(' => comment_depth = 1,
+                b'=' if bytes.get(i + 1) == Some(&b'?') => {
+                    if let Some((_, consumed)) = parse_encoded_word(&value[i..]) {
+                        i += consumed;
+                        continue;
+                    }
+                }
+                b'<' => open = Some(i),
+                b'>' => {
+                    if let Some(o) = open.take() {
+                        found = Some((o, i));
+                    }
+                }
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+
+    found
+}
+
+fn strip_comments(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut depth = 0usize;
+    for c in value.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn unquote(s: &str) -> String {
+    match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => s.to_string(),
+    }
+}
+
+fn is_valid_addr_spec(addr: &str) -> bool {
+    let Some((local, domain)) = addr.rsplit_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && domain.contains('.')
+        && !addr
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ',' | ';'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_base64_and_q_words() {
+        assert_eq!(decode_encoded_words("=?UTF-8?B?0JjQstCw0L0=?="), "Иван");
+        assert_eq!(decode_encoded_words("=?iso-8859-1?Q?Andr=E9_Pirard?="), "André Pirard");
+        // Whitespace between adjacent encoded words is dropped.
+        assert_eq!(decode_encoded_words("=?UTF-8?Q?a?= =?UTF-8?Q?b?="), "ab");
+        // Malformed words are passed through untouched.
+        assert_eq!(decode_encoded_words("=?UTF-8?X?abc?="), "=?UTF-8?X?abc?=");
+    }
+
+    #[test]
+    fn extracts_addr_spec_from_encoded_display_name() {
+        let header = "from:=?UTF-8?B?0JjQstCw0L0g0J8u?= <N6378056@gmail.com>";
+        let parsed = parse_from_header(header).unwrap();
+        assert_eq!(parsed.addr_spec, "N6378056@gmail.com");
+        assert_eq!(parsed.display_name.as_deref(), Some("Иван П."));
+
+        assert_eq!(extract_addr_spec("alice@example.com").unwrap(), "alice@example.com");
+        assert_eq!(
+            extract_addr_spec("\"Bob <bob@evil.com>\" <bob@example.com>").unwrap(),
+            "bob@example.com"
+        );
+        assert!(extract_addr_spec("Display Only").is_none());
+    }
+
+    #[test]
+    fn from_address_hash_matches_canonical_preimage() {
+        let hash = from_address_hash("From: Alice <Alice@Example.com>", "Kerp30.testnet").unwrap();
+        assert_eq!(hash, env::sha256(b"alice@example.com|kerp30.testnet"));
+    }
+}
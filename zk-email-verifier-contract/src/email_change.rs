@@ -0,0 +1,177 @@
+//! Changing an account's registered email with proofs from both addresses.
+//!
+//! A `change-email-` email from the registered address (see
+//! `CHANGE_EMAIL_SUBJECT`) names the commitment of the next one and opens a
+//! confirmation window. The change only happens when a `confirm-email-`
+//! email with the same request id arrives from that next address within the
+//! window, which proves the account's owner controls both mailboxes. Until
+//! then the account can `cancel_email_change`.
+//!
+//! A confirmation only completes the change opened by its own
+//! `change-email-` email: it must be dated no earlier than that email, and
+//! the account's registered email must still be the one the change was
+//! sent from. Both emails consume their nullifier (see `nullifiers`), so
+//! neither can be replayed to open or complete another change.
+//!
+//! Unlike a migration (see `migration`), nothing else about the account
+//! changes and there is no timelock to wait out.
+
+use near_sdk::{env, near};
+use zk_email_verifier_core::layout::{CircuitKind, CHANGE_EMAIL_CIRCUIT_ID, CONFIRM_EMAIL_CHANGE_CIRCUIT_ID};
+
+use crate::commands::{decode_hex_hash, reject};
+use crate::events::Event;
+use crate::{FailureReason, ProofInput, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// One day.
+pub(crate) const DEFAULT_EMAIL_CHANGE_WINDOW_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingEmailChange {
+    pub account_id: String,
+    /// Request id the confirmation must carry.
+    pub request_id: String,
+    pub new_email_hash: Vec<u8>,
+    /// Registered email the `change-email-` email was sent from.
+    pub from_email_hash: Vec<u8>,
+    /// Time of the `change-email-` email's `Date:` header.
+    pub sent_at_ms: u64,
+    /// Last moment `confirm_email_change` is accepted.
+    pub expires_at_ms: u64,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Verify a `change-email-` email from the registered address and open
+    /// the window to confirm it, replacing any pending change of the same
+    /// account.
+    pub fn initiate_email_change(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) -> PendingEmailChange {
+        let command = self
+            .verify_command(CHANGE_EMAIL_CIRCUIT_ID, proof, &public_inputs, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        if self.is_locked(&command.account_id) {
+            reject(FailureReason::AccountLocked);
+        }
+        let new_email_hash = command
+            .text("new_email_hash")
+            .and_then(|hex| decode_hex_hash(&hex))
+            .unwrap_or_else(|| reject(FailureReason::InputParse));
+        let sent_at_ms = command
+            .email_timestamp_ms
+            .unwrap_or_else(|| reject(FailureReason::InputParse));
+        let change = PendingEmailChange {
+            account_id: command.account_id,
+            request_id: command.request_id,
+            new_email_hash,
+            from_email_hash: command.from_address_hash,
+            sent_at_ms,
+            expires_at_ms: env::block_timestamp_ms().saturating_add(self.email_change_window_ms),
+        };
+        self.pending_email_changes
+            .insert(change.account_id.clone(), change.clone());
+        Event::EmailChangeInitiated {
+            account_id: change.account_id.clone(),
+            expires_at_ms: change.expires_at_ms,
+        }
+        .emit();
+        change
+    }
+
+    /// Verify a `confirm-email-` email from the address a pending change
+    /// names and make it the account's registered email.
+    pub fn confirm_email_change(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) {
+        let command = self
+            .verify_command_proof(CircuitKind::Command, CONFIRM_EMAIL_CHANGE_CIRCUIT_ID, proof, &public_inputs)
+            .unwrap_or_else(|reason| reject(reason));
+        self.admit_command(&command, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        let change = self
+            .pending_email_changes
+            .get(&command.account_id)
+            .cloned()
+            .unwrap_or_else(|| env::panic_str("no pending email change"));
+        if self.is_locked(&command.account_id) {
+            reject(FailureReason::AccountLocked);
+        }
+        if env::block_timestamp_ms() > change.expires_at_ms {
+            env::panic_str("the email change expired");
+        }
+        if command.request_id != change.request_id {
+            reject(FailureReason::BindingRequestId);
+        }
+        if command.email_timestamp_ms.is_none_or(|t| t < change.sent_at_ms)
+            || self.registered_emails.get(&command.account_id) != Some(&change.from_email_hash)
+        {
+            reject(FailureReason::UnmatchedEmailChange);
+        }
+        if command.from_address_hash != change.new_email_hash {
+            reject(FailureReason::RegisteredEmailMismatch);
+        }
+        self.pending_email_changes.remove(&command.account_id);
+        self.registered_emails
+            .insert(command.account_id.clone(), change.new_email_hash);
+        Event::EmailChanged { account_id: command.account_id }.emit();
+    }
+
+    /// Drop the calling account's pending email change.
+    pub fn cancel_email_change(&mut self) {
+        let account_id = env::predecessor_account_id().to_string();
+        if self.pending_email_changes.remove(&account_id).is_none() {
+            env::panic_str("no pending email change");
+        }
+        Event::EmailChangeCancelled { account_id }.emit();
+    }
+
+    pub fn get_pending_email_change(&self, account_id: String) -> Option<PendingEmailChange> {
+        self.pending_email_changes.get(&account_id).cloned()
+    }
+
+    pub fn get_email_change_window_ms(&self) -> u64 {
+        self.email_change_window_ms
+    }
+
+    /// Only callable by the owner.
+    pub fn set_email_change_window_ms(&mut self, window_ms: u64) {
+        self.assert_owner();
+        self.email_change_window_ms = window_ms;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    #[should_panic(expected = "no pending email change")]
+    fn accounts_cancel_their_own_change() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let change = PendingEmailChange {
+            account_id: accounts(1).to_string(),
+            request_id: "1".to_string(),
+            new_email_hash: env::sha256(b"new"),
+            from_email_hash: env::sha256(b"old"),
+            sent_at_ms: 500,
+            expires_at_ms: 1_000,
+        };
+        contract.pending_email_changes.insert(accounts(1).to_string(), change.clone());
+        assert_eq!(contract.get_pending_email_change(accounts(1).to_string()), Some(change));
+
+        contract.cancel_email_change();
+        assert_eq!(contract.get_pending_email_change(accounts(1).to_string()), None);
+        contract.cancel_email_change();
+    }
+}
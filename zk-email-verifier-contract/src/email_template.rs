@@ -0,0 +1,129 @@
+//! The recovery email a wallet asks its user to send.
+//!
+//! `get_email_template` renders the subject of `RecoverEmailCircuit` for an
+//! account and new key, with a request id derived from the block's random
+//! seed, and addresses it to the relayer mailbox the owner configured. Wallets
+//! can turn the result straight into a `mailto:` link instead of keeping their
+//! own copy of the subject grammar in sync with the circuit.
+
+use near_sdk::{env, near};
+use zk_email_verifier_core::subject::RECOVER_EMAIL_SUBJECT;
+
+use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+use crate::{ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Hex digits in a generated request id.
+const REQUEST_ID_LEN: usize = 16;
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmailTemplate {
+    pub circuit_id: String,
+    /// Mailbox to send the email to; `None` until the owner sets one.
+    pub to: Option<String>,
+    pub subject: String,
+    pub request_id: String,
+    /// `mailto:` link with `to` and `subject` filled in, if `to` is set.
+    pub mailto: Option<String>,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// The email that recovers `account_id` to `new_public_key` (with or
+    /// without its `ed25519:` prefix). The request id changes every block
+    /// and is the one `generate_request_id` would issue next; call that to
+    /// register it as a challenge. `None` if a value can't appear in the
+    /// subject, e.g. it contains whitespace.
+    pub fn get_email_template(&self, account_id: String, new_public_key: String) -> Option<EmailTemplate> {
+        let key = new_public_key.strip_prefix("ed25519:").unwrap_or(&new_public_key);
+        let request_id = fresh_request_id(&account_id, self.challenge_nonce);
+        let subject = RECOVER_EMAIL_SUBJECT.render(&[
+            ("request_id", &request_id),
+            ("account_id", &account_id),
+            ("new_public_key", key),
+        ])?;
+        let to = self.recovery_mailbox.clone();
+        let mailto = to
+            .as_ref()
+            .map(|to| format!("mailto:{to}?subject={}", percent_encode(&subject)));
+        Some(EmailTemplate {
+            circuit_id: RECOVER_EMAIL_CIRCUIT_ID.to_string(),
+            to,
+            subject,
+            request_id,
+            mailto,
+        })
+    }
+
+    pub fn get_recovery_mailbox(&self) -> Option<String> {
+        self.recovery_mailbox.clone()
+    }
+
+    /// Set the address recovery emails are sent to, or clear it with `None`.
+    /// Only callable by the owner.
+    pub fn set_recovery_mailbox(&mut self, mailbox: Option<String>) {
+        self.assert_owner();
+        if mailbox.as_ref().is_some_and(|m| !m.contains('@')) {
+            env::panic_str("mailbox must be an email address");
+        }
+        self.recovery_mailbox = mailbox;
+    }
+}
+
+/// Hex request id from the block's random seed and height, `account_id` and
+/// `nonce`, so it can't be guessed before the block and differs between
+/// accounts and between ids issued in the same block.
+pub(crate) fn fresh_request_id(account_id: &str, nonce: u64) -> String {
+    let mut preimage = env::random_seed();
+    preimage.extend_from_slice(&env::block_height().to_le_bytes());
+    preimage.extend_from_slice(&nonce.to_le_bytes());
+    preimage.extend_from_slice(account_id.as_bytes());
+    env::sha256(&preimage)
+        .iter()
+        .take(REQUEST_ID_LEN / 2)
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn templates_render_the_circuit_subject() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let template = |contract: &ZkEmailVerifier| {
+            contract
+                .get_email_template("alice.near".to_string(), "ed25519:Abc123".to_string())
+                .unwrap()
+        };
+
+        let first = template(&contract);
+        assert_eq!(first.request_id.len(), REQUEST_ID_LEN);
+        assert_eq!(first.subject, format!("recover-{} alice.near ed25519:Abc123", first.request_id));
+        assert_eq!(first.mailto, None);
+
+        contract.set_recovery_mailbox(Some("recover@relayer.example".to_string()));
+        let second = template(&contract);
+        assert_eq!(
+            second.mailto.unwrap(),
+            format!("mailto:recover@relayer.example?subject=recover-{}%20alice.near%20ed25519%3AAbc123", second.request_id)
+        );
+        assert!(contract
+            .get_email_template("alice near".to_string(), "Abc123".to_string())
+            .is_none());
+    }
+}
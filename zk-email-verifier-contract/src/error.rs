@@ -0,0 +1,160 @@
+use core::fmt;
+
+/// Reasons a proof that passed the pairing check is still rejected.
+///
+/// Verification methods keep returning `VerificationResult { verified: false, .. }`
+/// on failure; the error is logged so callers can tell why.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The decoded or bound `account_id` is not a syntactically valid NEAR account.
+    InvalidAccountId,
+    /// The decoded or bound `new_public_key` is not a base58 ed25519 NEAR public key.
+    InvalidPublicKey,
+}
+
+impl VerificationError {
+    /// Stable identifier used in logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationError::InvalidAccountId => "InvalidAccountId",
+            VerificationError::InvalidPublicKey => "InvalidPublicKey",
+        }
+    }
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Machine-readable reason a verification was rejected, returned as
+/// `VerificationResult::failure_reason`. The codes are part of the API and
+/// don't change once released.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureReason {
+    /// A proof coordinate is not a valid field element.
+    ProofParse,
+    /// The proof declares a `protocol` or `curve` other than Groth16 over
+    /// BN254.
+    ProofProtocol,
+    /// A public input is not a valid field element.
+    InputParse,
+    /// Wrong number of public inputs for the circuit.
+    InputLen,
+    /// No circuit uses the requested layout, or the number of public inputs
+    /// fits more than one.
+    UnknownLayout,
+    /// The `blob` argument of `verify_blob` didn't decode.
+    BlobDecode,
+    BindingRequestId,
+    BindingAccount,
+    BindingNewPublicKey,
+    BindingTimestamp,
+    /// The `From:` header doesn't match the committed sender hash.
+    BindingFromAddress,
+    /// The sender's domain is not on the allowlist, or wasn't given.
+    SenderDomain,
+    /// The sender's address or domain is on the blocklist.
+    SenderBlocked,
+    /// The Groth16 pairing check failed.
+    Pairing,
+    /// The verifying key was rotated while a staged verification was pending.
+    VkChanged,
+    /// The email is older than the configured freshness window.
+    StaleEmail,
+    InvalidAccountId,
+    InvalidPublicKey,
+    /// A cancellation for the same request verified alongside the recovery;
+    /// see `verify_recovery_bundle`.
+    Cancelled,
+    /// Predecessor binding is on and the caller is neither the recovering
+    /// account nor its relayer.
+    UnauthorizedSubmitter,
+    /// The account was recovered less than the recovery cooldown ago.
+    Cooldown,
+    /// The cap on recoveries per window was reached.
+    RateLimited,
+    /// The request id names a challenge an earlier recovery consumed.
+    ChallengeConsumed,
+    /// The account has no registered email to accept commands from.
+    EmailNotRegistered,
+    /// The email was not sent from the account's registered address.
+    RegisteredEmailMismatch,
+    /// The account was locked from its registered email; see `lock`.
+    AccountLocked,
+    /// The email was not sent from one of the account's guardians.
+    NotAGuardian,
+    /// The account is outside the configured namespace; see `namespace`.
+    OutsideNamespace,
+    /// A recovery, or a command email of the same circuit, for the same
+    /// account and request id already verified; see `nullifiers`.
+    NullifierConsumed,
+    /// A `confirm-email-` email predates the `change-email-` email it
+    /// confirms, or the registered email changed since that one was sent.
+    UnmatchedEmailChange,
+    /// The request id names a challenge issued for another account.
+    ChallengeAccountMismatch,
+    /// The second receipt of `verify_staged` failed, e.g. out of gas, so the
+    /// proof was neither accepted nor rejected; see `idempotency`.
+    CallbackFailed,
+    /// The proof verified in an earlier `verify_staged` by the same caller,
+    /// whose result is returned from the result cache with this code rather
+    /// than as a second verification; see `result_cache`.
+    CachedReplay,
+}
+
+impl FailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureReason::ProofParse => "PROOF_PARSE",
+            FailureReason::ProofProtocol => "PROOF_PROTOCOL",
+            FailureReason::InputParse => "INPUT_PARSE",
+            FailureReason::InputLen => "INPUT_LEN",
+            FailureReason::UnknownLayout => "UNKNOWN_LAYOUT",
+            FailureReason::BlobDecode => "BLOB_DECODE",
+            FailureReason::BindingRequestId => "BINDING_REQUEST_ID",
+            FailureReason::BindingAccount => "BINDING_ACCOUNT",
+            FailureReason::BindingNewPublicKey => "BINDING_NEW_PUBLIC_KEY",
+            FailureReason::BindingTimestamp => "BINDING_TIMESTAMP",
+            FailureReason::BindingFromAddress => "BINDING_FROM_ADDRESS",
+            FailureReason::SenderDomain => "SENDER_DOMAIN",
+            FailureReason::SenderBlocked => "SENDER_BLOCKED",
+            FailureReason::Pairing => "PAIRING",
+            FailureReason::VkChanged => "VK_CHANGED",
+            FailureReason::StaleEmail => "STALE_EMAIL",
+            FailureReason::InvalidAccountId => "INVALID_ACCOUNT_ID",
+            FailureReason::InvalidPublicKey => "INVALID_PUBLIC_KEY",
+            FailureReason::Cancelled => "CANCELLED",
+            FailureReason::UnauthorizedSubmitter => "UNAUTHORIZED_SUBMITTER",
+            FailureReason::Cooldown => "COOLDOWN",
+            FailureReason::RateLimited => "RATE_LIMITED",
+            FailureReason::ChallengeConsumed => "CHALLENGE_CONSUMED",
+            FailureReason::EmailNotRegistered => "EMAIL_NOT_REGISTERED",
+            FailureReason::RegisteredEmailMismatch => "REGISTERED_EMAIL_MISMATCH",
+            FailureReason::AccountLocked => "ACCOUNT_LOCKED",
+            FailureReason::NotAGuardian => "NOT_A_GUARDIAN",
+            FailureReason::OutsideNamespace => "OUTSIDE_NAMESPACE",
+            FailureReason::NullifierConsumed => "NULLIFIER_CONSUMED",
+            FailureReason::UnmatchedEmailChange => "UNMATCHED_EMAIL_CHANGE",
+            FailureReason::ChallengeAccountMismatch => "CHALLENGE_ACCOUNT_MISMATCH",
+            FailureReason::CallbackFailed => "CALLBACK_FAILED",
+            FailureReason::CachedReplay => "CACHED_REPLAY",
+        }
+    }
+}
+
+impl From<VerificationError> for FailureReason {
+    fn from(err: VerificationError) -> Self {
+        match err {
+            VerificationError::InvalidAccountId => FailureReason::InvalidAccountId,
+            VerificationError::InvalidPublicKey => FailureReason::InvalidPublicKey,
+        }
+    }
+}
+
+impl fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
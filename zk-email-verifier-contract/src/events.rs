@@ -0,0 +1,143 @@
+//! NEP-297 events (`EVENT_JSON:` log lines) for indexers and monitoring.
+
+use near_sdk::{near, AccountId, NearToken};
+
+#[near(event_json(standard = "zk_email_verifier"))]
+pub enum Event {
+    /// The circuit breaker paused `verify_staged`. `reason` is
+    /// `account_rate` or `failure_rate`.
+    #[event_version("1.0.0")]
+    CircuitBreakerTripped {
+        reason: String,
+        account_id: Option<String>,
+    },
+    /// A stateful verification was rejected. `reason` is the
+    /// `failure_reason` code; `account_id` is only set when the proof got far
+    /// enough for its account to be decoded from verified inputs.
+    #[event_version("1.0.0")]
+    ZkEmailVerifyFailed {
+        reason: String,
+        account_id: Option<String>,
+        circuit_id: String,
+        /// SHA-256 of the prepared verifying key the proof was checked against.
+        vk_hash: Vec<u8>,
+    },
+    /// `prune_records` removed `count` audit records.
+    #[event_version("1.0.0")]
+    RecordsPruned { count: u32, storage_bytes_released: u64 },
+    /// `payer` paid the verification fee into the treasury.
+    #[event_version("1.0.0")]
+    FeeAccrued {
+        amount: NearToken,
+        payer: AccountId,
+        treasury_balance: NearToken,
+    },
+    /// `relayer` was paid for submitting the recovery of `account_id`.
+    #[event_version("1.0.0")]
+    RelayerRewarded {
+        relayer: AccountId,
+        account_id: String,
+        amount: NearToken,
+    },
+    #[event_version("1.0.0")]
+    EmailRegistered { account_id: String },
+    /// A migration of `account_id` to a new email was verified and can be
+    /// finalized from `ready_at_ms`.
+    #[event_version("1.0.0")]
+    MigrationInitiated { account_id: String, ready_at_ms: u64 },
+    #[event_version("1.0.0")]
+    MigrationCancelled { account_id: String },
+    #[event_version("1.0.0")]
+    MigrationFinalized { account_id: String, new_public_key: String },
+    /// Recoveries of `account_id` are rejected until `locked_until_ms`.
+    #[event_version("1.0.0")]
+    AccountLocked { account_id: String, locked_until_ms: u64 },
+    /// A new daily limit for `account_id` was verified and can be forwarded
+    /// to the controller from `ready_at_ms`.
+    #[event_version("1.0.0")]
+    SpendingLimitInitiated {
+        account_id: String,
+        daily_limit: NearToken,
+        ready_at_ms: u64,
+    },
+    #[event_version("1.0.0")]
+    SpendingLimitCancelled { account_id: String },
+    #[event_version("1.0.0")]
+    SpendingLimitFinalized { account_id: String, daily_limit: NearToken },
+    /// `guardian_hash` is the hex email commitment of the new guardian.
+    #[event_version("1.0.0")]
+    GuardianAdded { account_id: String, guardian_hash: String },
+    /// The guardian can be removed from `ready_at_ms` unless another guardian
+    /// vetoes it first.
+    #[event_version("1.0.0")]
+    GuardianRemovalInitiated {
+        account_id: String,
+        guardian_hash: String,
+        ready_at_ms: u64,
+    },
+    /// `vetoed_by` is the hex email commitment of the vetoing guardian.
+    #[event_version("1.0.0")]
+    GuardianRemovalVetoed {
+        account_id: String,
+        guardian_hash: String,
+        vetoed_by: String,
+    },
+    #[event_version("1.0.0")]
+    GuardianRemoved { account_id: String, guardian_hash: String },
+    /// `weight` is the total weight of the request's approvals so far.
+    #[event_version("1.0.0")]
+    GuardianApproved {
+        account_id: String,
+        request_id: String,
+        guardian_hash: String,
+        weight: u32,
+    },
+    #[event_version("1.0.0")]
+    GuardianRecoveryApproved {
+        account_id: String,
+        request_id: String,
+        new_public_key: String,
+    },
+    /// The account's guardian changes become active at `active_from_ms`.
+    #[event_version("1.0.0")]
+    GuardianSetScheduled { account_id: String, active_from_ms: u64 },
+    /// The new address must confirm the change of `account_id`'s email by
+    /// `expires_at_ms`.
+    #[event_version("1.0.0")]
+    EmailChangeInitiated { account_id: String, expires_at_ms: u64 },
+    #[event_version("1.0.0")]
+    EmailChangeCancelled { account_id: String },
+    #[event_version("1.0.0")]
+    EmailChanged { account_id: String },
+    /// `nullifier` became leaf `index` of the nullifier tree, whose root is
+    /// now `root`.
+    #[event_version("1.0.0")]
+    NullifierConsumed { nullifier: String, index: u64, root: String },
+    /// A verified result became leaf `index` of the result accumulator,
+    /// whose root is now `root`.
+    #[event_version("1.0.0")]
+    ResultAccumulated { result_hash: String, index: u64, root: String },
+    /// The owner activated the contract, closing `import_state`.
+    #[event_version("1.0.0")]
+    ContractActivated {},
+    /// The transfer of a relayer reward failed and `amount` went back into
+    /// the treasury.
+    #[event_version("1.0.0")]
+    RelayerRewardFailed {
+        relayer: AccountId,
+        amount: NearToken,
+        treasury_balance: NearToken,
+    },
+    #[event_version("1.0.0")]
+    TreasuryWithdrawalFailed {
+        amount: NearToken,
+        to: AccountId,
+        treasury_balance: NearToken,
+    },
+    #[event_version("1.0.0")]
+    TreasuryWithdrawn {
+        amount: NearToken,
+        to: AccountId,
+        treasury_balance: NearToken,
+    },
+}
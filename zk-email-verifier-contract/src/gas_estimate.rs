@@ -0,0 +1,82 @@
+//! Gas estimates for relayers deciding how much gas to attach.
+//!
+//! A verification costs a roughly fixed amount (loading the key, parsing the
+//! proof, the pairing check) plus a share per public input (parsing it and
+//! its MSM term). The owner stores both per circuit from `profile_verify` or
+//! `gas_bench` runs; circuits without a benchmark use the defaults below,
+//! measured on `RecoverEmailCircuit`.
+
+use near_sdk::{near, Gas};
+
+use crate::{ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Measured cost of verifying a proof of one circuit.
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasBenchmark {
+    pub base_gas: Gas,
+    pub per_input_gas: Gas,
+}
+
+pub(crate) const DEFAULT_GAS_BENCHMARK: GasBenchmark = GasBenchmark {
+    base_gas: Gas::from_tgas(150),
+    per_input_gas: Gas::from_ggas(1_000),
+};
+
+#[near]
+impl ZkEmailVerifier {
+    /// Gas a `verify` of `input_len` public inputs against `circuit_id` is
+    /// expected to burn, or `None` for an unknown circuit. `verify_staged`
+    /// spends about the same across its two receipts.
+    pub fn estimate_verify_gas(&self, circuit_id: String, input_len: u32) -> Option<Gas> {
+        self.prepared_vk_bytes(&circuit_id)?;
+        let benchmark = self.gas_benchmark(&circuit_id);
+        let per_inputs = benchmark.per_input_gas.saturating_mul(u64::from(input_len));
+        Some(benchmark.base_gas.saturating_add(per_inputs))
+    }
+
+    /// Store the measured cost of `circuit_id`, or go back to the defaults
+    /// with `None`. Only callable by the owner.
+    pub fn set_gas_benchmark(&mut self, circuit_id: String, benchmark: Option<GasBenchmark>) {
+        self.assert_owner();
+        match benchmark {
+            Some(benchmark) => self.gas_benchmarks.insert(circuit_id, benchmark),
+            None => self.gas_benchmarks.remove(&circuit_id),
+        };
+    }
+}
+
+impl ZkEmailVerifier {
+    fn gas_benchmark(&self, circuit_id: &str) -> GasBenchmark {
+        self.gas_benchmarks
+            .get(circuit_id)
+            .copied()
+            .unwrap_or(DEFAULT_GAS_BENCHMARK)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+    use crate::EXPECTED_PUBLIC_LEN;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn estimates_scale_with_the_number_of_inputs() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let estimate = |contract: &ZkEmailVerifier, len| {
+            contract.estimate_verify_gas(RECOVER_EMAIL_CIRCUIT_ID.to_string(), len)
+        };
+        assert_eq!(estimate(&contract, EXPECTED_PUBLIC_LEN as u32), Some(Gas::from_tgas(252)));
+        assert_eq!(contract.estimate_verify_gas("unknown".to_string(), 1), None);
+
+        let benchmark = GasBenchmark { base_gas: Gas::from_tgas(10), per_input_gas: Gas::from_tgas(2) };
+        contract.set_gas_benchmark(RECOVER_EMAIL_CIRCUIT_ID.to_string(), Some(benchmark));
+        assert_eq!(estimate(&contract, 3), Some(Gas::from_tgas(16)));
+        contract.set_gas_benchmark(RECOVER_EMAIL_CIRCUIT_ID.to_string(), None);
+        assert_eq!(estimate(&contract, 0), Some(DEFAULT_GAS_BENCHMARK.base_gas));
+    }
+}
@@ -0,0 +1,153 @@
+//! Guardian sets that change only at epoch boundaries.
+//!
+//! Adding or removing a guardian, or reweighing them, updates the account's
+//! configured set right away (see `get_guardians`) but only schedules it:
+//! guardian recoveries and vetoes use the set that was active when they
+//! started, and a new set becomes active at the start of the next epoch.
+//! Someone who gains control of one mailbox therefore can't add guardians
+//! and use them to push through a recovery that is already collecting
+//! approvals.
+//!
+//! Snapshots are stored by the time they become active, so changing the
+//! epoch length only affects sets scheduled afterwards.
+
+use near_sdk::{env, near};
+
+use crate::events::Event;
+use crate::guardian_recovery::GuardianWeights;
+use crate::{ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// One day.
+pub(crate) const DEFAULT_GUARDIAN_EPOCH_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GuardianSet {
+    /// Block time from which this set is the active one.
+    pub active_from_ms: u64,
+    pub guardians: Vec<Vec<u8>>,
+    /// `None` while the account has guardian recovery turned off.
+    pub weights: Option<GuardianWeights>,
+    /// Total weight of approvals a recovery needs: the threshold of the
+    /// account's recovery policy if it sets one, else that of `weights`.
+    pub threshold: Option<u32>,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// The guardian set of `account_id` active at `at_ms`, or now. Empty if
+    /// the account had none yet.
+    pub fn get_guardian_set(&self, account_id: String, at_ms: Option<u64>) -> GuardianSet {
+        self.guardian_set_at(&account_id, at_ms.unwrap_or_else(env::block_timestamp_ms))
+    }
+
+    /// When the current guardian epoch ends and scheduled sets take over.
+    pub fn get_next_guardian_epoch_ms(&self) -> u64 {
+        self.next_guardian_epoch_ms()
+    }
+
+    pub fn get_guardian_epoch_ms(&self) -> u64 {
+        self.guardian_epoch_ms
+    }
+
+    /// Only callable by the owner.
+    pub fn set_guardian_epoch_ms(&mut self, epoch_ms: u64) {
+        self.assert_owner();
+        if epoch_ms == 0 {
+            env::panic_str("the guardian epoch must be positive");
+        }
+        self.guardian_epoch_ms = epoch_ms;
+    }
+}
+
+impl ZkEmailVerifier {
+    fn next_guardian_epoch_ms(&self) -> u64 {
+        let now = env::block_timestamp_ms();
+        (now / self.guardian_epoch_ms + 1).saturating_mul(self.guardian_epoch_ms)
+    }
+
+    pub(crate) fn guardian_set_at(&self, account_id: &str, at_ms: u64) -> GuardianSet {
+        self.guardian_sets
+            .get(account_id)
+            .and_then(|sets| sets.iter().rev().find(|set| set.active_from_ms <= at_ms))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Schedule the account's configured guardians, weights and threshold to
+    /// become active at the next epoch, replacing a set already scheduled for
+    /// it.
+    pub(crate) fn schedule_guardian_set(&mut self, account_id: &str) {
+        let weights = self.guardian_weights.get(account_id).cloned();
+        let threshold = weights.as_ref().map(|weights| {
+            self.recovery_policy(account_id)
+                .guardian_threshold
+                .unwrap_or(weights.threshold)
+        });
+        let set = GuardianSet {
+            active_from_ms: self.next_guardian_epoch_ms(),
+            guardians: self.get_guardians(account_id.to_string()),
+            weights,
+            threshold,
+        };
+        let mut sets = self.guardian_sets.get(account_id).cloned().unwrap_or_default();
+        sets.retain(|scheduled| scheduled.active_from_ms < set.active_from_ms);
+        Event::GuardianSetScheduled {
+            account_id: account_id.to_string(),
+            active_from_ms: set.active_from_ms,
+        }
+        .emit();
+        sets.push(set);
+        self.guardian_sets.insert(account_id.to_string(), sets);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{as_account_at, at};
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    fn guardian_changes_apply_from_the_next_epoch() {
+        at(1_500);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_guardian_epoch_ms(1_000);
+        let account = accounts(1).to_string();
+        contract.guardians.insert(account.clone(), vec![env::sha256(b"bob")]);
+        contract.schedule_guardian_set(&account);
+
+        assert!(contract.get_guardian_set(account.clone(), None).guardians.is_empty());
+        at(2_000);
+        assert_eq!(contract.get_guardian_set(account.clone(), None).guardians, vec![env::sha256(b"bob")]);
+
+        contract.guardians.insert(account.clone(), Vec::new());
+        contract.schedule_guardian_set(&account);
+        assert_eq!(contract.get_guardian_set(account.clone(), Some(2_999)).guardians.len(), 1);
+        assert!(contract.get_guardian_set(account.clone(), Some(3_000)).guardians.is_empty());
+        assert!(contract.get_guardian_set(account, Some(1_999)).guardians.is_empty());
+    }
+
+    #[test]
+    fn threshold_changes_apply_from_the_next_epoch() {
+        at(1_500);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_guardian_epoch_ms(1_000);
+        let account = accounts(1).to_string();
+        contract.guardians.insert(account.clone(), vec![env::sha256(b"bob")]);
+        as_account_at(1, 1_500);
+        contract.set_guardian_weights(Some(GuardianWeights {
+            primary_weight: 1,
+            weights: Vec::new(),
+            threshold: 2,
+        }));
+        as_account_at(1, 2_500);
+        contract.set_recovery_policy(Some(crate::recovery_policy::RecoveryPolicy {
+            guardian_threshold: Some(1),
+            ..Default::default()
+        }));
+
+        assert_eq!(contract.get_guardian_set(account.clone(), Some(2_999)).threshold, Some(2));
+        assert_eq!(contract.get_guardian_set(account, Some(3_000)).threshold, Some(1));
+    }
+}
@@ -0,0 +1,319 @@
+//! Recovering an account through its guardians.
+//!
+//! Each guardian (see `guardians`) approves a recovery by sending the same
+//! `recover-` email a user would send themselves, from their own mailbox, and
+//! `submit_guardian_proof` adds the guardian's weight to that request. The
+//! account's registered email can approve as well, with its own weight, so a
+//! primary mailbox can count for more than any single guardian. The recovery
+//! is approved once the approvals reach the account's threshold, which its
+//! recovery policy can override (see `recovery_policy`). Like the guardians
+//! and their weights, the threshold a request needs is the one of the
+//! guardian set it is judged against.
+//!
+//! Accounts opt in with `set_guardian_weights`; without it guardian proofs
+//! are rejected. A request is judged against the guardian set that was
+//! active when its first approval arrived (see `guardian_epochs`).
+//!
+//! Each approval is held to the checks `verify_staged` applies to a
+//! recovery, except that it comes from a guardian's mailbox rather than the
+//! registered one: the sender policy, the freshness window, the cooldown,
+//! the request's challenge and nullifier, and the account lock. The approval
+//! that completes the recovery also counts against the rate limit, and
+//! consumes the challenge and the nullifier, so the request can't be
+//! recovered again here or through `verify_staged`.
+
+use near_sdk::{env, near};
+use zk_email_verifier_core::layout::CircuitKind;
+
+use crate::commands::{encode_hex, reject};
+use crate::events::Event;
+use crate::guardian_epochs::GuardianSet;
+use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+use crate::treasury::charge_storage;
+use crate::{FailureReason, ProofInput, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GuardianWeight {
+    pub guardian_hash: Vec<u8>,
+    pub weight: u32,
+}
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GuardianWeights {
+    /// Weight of the account's registered email.
+    pub primary_weight: u32,
+    /// Guardians that don't weigh 1.
+    pub weights: Vec<GuardianWeight>,
+    /// Total weight of approvals a recovery needs.
+    pub threshold: u32,
+}
+
+impl GuardianWeights {
+    fn weight_of(&self, guardian_hash: &[u8]) -> u32 {
+        self.weights
+            .iter()
+            .find(|w| w.guardian_hash == guardian_hash)
+            .map_or(1, |w| w.weight)
+    }
+}
+
+/// Approvals collected for one recovery request.
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GuardianRecovery {
+    pub account_id: String,
+    pub request_id: String,
+    pub new_public_key: String,
+    /// Email commitments of the guardians that approved, in order.
+    pub approvers: Vec<Vec<u8>>,
+    pub weight: u32,
+    pub threshold: u32,
+    pub approved: bool,
+    /// Time of the first approval, which picks the guardian set.
+    pub initiated_at_ms: u64,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Verify a guardian's `recover-` email and count it towards its
+    /// request. Returns the approvals so far.
+    pub fn submit_guardian_proof(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) -> GuardianRecovery {
+        let command = self
+            .verify_command_proof(CircuitKind::Recovery, RECOVER_EMAIL_CIRCUIT_ID, proof, &public_inputs)
+            .unwrap_or_else(|reason| reject(reason));
+        let result = self.admit_approval(command.recovery(), from_header.as_deref());
+        let new_public_key = result.new_public_key.clone();
+        let key = (command.account_id.clone(), command.request_id.clone());
+        let existing = self.guardian_recoveries.get(&key).cloned();
+        let initiated_at_ms = existing
+            .as_ref()
+            .map_or_else(env::block_timestamp_ms, |recovery| recovery.initiated_at_ms);
+        let set = self.guardian_set_at(&command.account_id, initiated_at_ms);
+        let threshold = set
+            .threshold
+            .unwrap_or_else(|| env::panic_str("the account has no guardian threshold"));
+        let weight = self
+            .approver_weight(&command.account_id, &command.from_address_hash, &set)
+            .unwrap_or_else(|| reject(FailureReason::NotAGuardian));
+
+        let mut recovery = existing.unwrap_or(GuardianRecovery {
+            account_id: command.account_id,
+            request_id: command.request_id,
+            new_public_key: new_public_key.clone(),
+            approvers: Vec::new(),
+            weight: 0,
+            threshold,
+            approved: false,
+            initiated_at_ms,
+        });
+        if recovery.new_public_key != new_public_key {
+            reject(FailureReason::BindingNewPublicKey);
+        }
+        if recovery.approved {
+            env::panic_str("the recovery is already approved");
+        }
+        if recovery.approvers.contains(&command.from_address_hash) {
+            env::panic_str("the guardian already approved");
+        }
+        recovery.approvers.push(command.from_address_hash.clone());
+        recovery.weight = recovery.weight.saturating_add(weight);
+        recovery.approved = recovery.weight >= recovery.threshold;
+        if recovery.approved {
+            let result = self.check_rate_limit(result, &env::predecessor_account_id());
+            reject_failed(&result);
+            self.consume_challenge(&result);
+            self.consume_nullifier(&result);
+        }
+        self.guardian_recoveries.insert(key, recovery.clone());
+
+        Event::GuardianApproved {
+            account_id: recovery.account_id.clone(),
+            request_id: recovery.request_id.clone(),
+            guardian_hash: encode_hex(&command.from_address_hash),
+            weight: recovery.weight,
+        }
+        .emit();
+        if recovery.approved {
+            Event::GuardianRecoveryApproved {
+                account_id: recovery.account_id.clone(),
+                request_id: recovery.request_id.clone(),
+                new_public_key: recovery.new_public_key.clone(),
+            }
+            .emit();
+        }
+        recovery
+    }
+
+    pub fn get_guardian_recovery(&self, account_id: String, request_id: String) -> Option<GuardianRecovery> {
+        self.guardian_recoveries.get(&(account_id, request_id)).cloned()
+    }
+
+    pub fn get_guardian_weights(&self, account_id: String) -> Option<GuardianWeights> {
+        self.guardian_weights.get(&account_id).cloned()
+    }
+
+    /// Set how the calling account's guardian approvals are weighed, or turn
+    /// guardian recovery off with `None`, from the next guardian epoch.
+    /// `weights` may only name the account's guardians, each once. Attach
+    /// the storage the weights and the scheduled set take; the rest is
+    /// refunded.
+    #[payable]
+    pub fn set_guardian_weights(&mut self, weights: Option<GuardianWeights>) {
+        let usage_before = env::storage_usage();
+        let account_id = env::predecessor_account_id().to_string();
+        match weights {
+            Some(weights) => {
+                if weights.threshold == 0 {
+                    env::panic_str("the threshold must be positive");
+                }
+                let guardians = self.get_guardians(account_id.clone());
+                if weights.weights.len() > guardians.len() {
+                    env::panic_str("more weights than guardians");
+                }
+                for (i, weight) in weights.weights.iter().enumerate() {
+                    if !guardians.contains(&weight.guardian_hash) {
+                        env::panic_str("weights may only name the account's guardians");
+                    }
+                    if weights.weights[..i].iter().any(|w| w.guardian_hash == weight.guardian_hash) {
+                        env::panic_str("a guardian is weighed twice");
+                    }
+                }
+                self.guardian_weights.insert(account_id.clone(), weights);
+            }
+            None => {
+                self.guardian_weights.remove(&account_id);
+            }
+        }
+        self.schedule_guardian_set(&account_id);
+        self.guardian_weights.flush();
+        self.guardian_sets.flush();
+        charge_storage(usage_before);
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Hold a guardian's recovery `result` to the checks of `verify_staged`
+    /// that don't depend on who sent it, aborting if one fails.
+    fn admit_approval(&self, result: VerificationResult, from_header: Option<&str>) -> VerificationResult {
+        let result = self.check_sender(result, from_header);
+        let result = self.check_freshness(result);
+        let result = self.check_cooldown(result);
+        let result = self.check_challenge(result);
+        let result = self.check_nullifier(result);
+        let result = self.check_lock(result);
+        reject_failed(&result);
+        result
+    }
+
+    /// Weight of an approval sent from `from_address_hash`, if that is the
+    /// account's registered email or one of the guardians of `set`.
+    fn approver_weight(&self, account_id: &str, from_address_hash: &[u8], set: &GuardianSet) -> Option<u32> {
+        let weights = set.weights.as_ref()?;
+        if self.check_registered_email(account_id, from_address_hash).is_ok() {
+            return Some(weights.primary_weight);
+        }
+        set.guardians
+            .iter()
+            .any(|guardian| guardian == from_address_hash)
+            .then(|| weights.weight_of(from_address_hash))
+    }
+}
+
+/// Abort with the failure of a rejected `result`, like `reject` does.
+fn reject_failed(result: &VerificationResult) {
+    if let Some(code) = &result.failure_reason {
+        env::panic_str(&format!("command rejected: {code}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    #[test]
+    fn approvals_are_weighed_per_guardian() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_millinear(10))
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.register_email(env::sha256(b"own"));
+        let set = GuardianSet {
+            active_from_ms: 0,
+            guardians: vec![env::sha256(b"bob"), env::sha256(b"carol")],
+            weights: Some(GuardianWeights {
+                primary_weight: 3,
+                weights: vec![GuardianWeight { guardian_hash: env::sha256(b"bob"), weight: 2 }],
+                threshold: 4,
+            }),
+            threshold: Some(4),
+        };
+        let weight = |from: &[u8]| contract.approver_weight(accounts(1).as_str(), &env::sha256(from), &set);
+
+        assert_eq!(weight(b"own"), Some(3));
+        assert_eq!(weight(b"bob"), Some(2));
+        assert_eq!(weight(b"carol"), Some(1));
+        assert_eq!(weight(b"mallory"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "command rejected: NULLIFIER_CONSUMED")]
+    fn approvals_of_a_consumed_request_are_rejected() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let mut result = VerificationResult::verified_for(accounts(1).as_str());
+        result.request_id = "r1".to_string();
+        contract.admit_approval(result.clone(), None);
+
+        contract.consume_nullifier(&result);
+        contract.admit_approval(result, None);
+    }
+
+    fn weigh(guardians: &[&[u8]], named: &[&[u8]]) -> ZkEmailVerifier {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_millinear(10))
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let guardians = guardians.iter().map(env::sha256).collect();
+        contract.guardians.insert(accounts(1).to_string(), guardians);
+        contract.set_guardian_weights(Some(GuardianWeights {
+            primary_weight: 1,
+            weights: named
+                .iter()
+                .map(|name| GuardianWeight { guardian_hash: env::sha256(name), weight: 2 })
+                .collect(),
+            threshold: 2,
+        }));
+        contract
+    }
+
+    #[test]
+    fn guardians_can_be_weighed() {
+        let contract = weigh(&[b"bob", b"carol"], &[b"carol"]);
+        let weights = contract.get_guardian_weights(accounts(1).to_string()).unwrap();
+        assert_eq!(weights.weight_of(&env::sha256(b"carol")), 2);
+        assert_eq!(weights.weight_of(&env::sha256(b"bob")), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "weights may only name the account's guardians")]
+    fn weights_may_only_name_guardians() {
+        weigh(&[b"bob"], &[b"mallory"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "more weights than guardians")]
+    fn weights_are_bounded_by_the_guardians() {
+        weigh(&[b"bob"], &[b"bob", b"bob"]);
+    }
+}
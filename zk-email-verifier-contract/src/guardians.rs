@@ -0,0 +1,275 @@
+//! Guardian emails of an account.
+//!
+//! Besides its own registered email (see `registration`), an account can
+//! name other people's mailboxes as guardians. Each is stored only as the
+//! same salted commitment as `from_address_hash`, so the guardian set never
+//! reveals who the guardians are. Guardians are added with an
+//! `add-guardian-` email from the account's registered address (see
+//! `ADD_GUARDIAN_SUBJECT`).
+//!
+//! Removing one takes a `remove-guardian-` email from the same address and
+//! only takes effect after the removal delay. Until then any other guardian
+//! can stop it with a `veto-` email from their own mailbox, so whoever
+//! controls the account's mailbox can't dismantle the guardian set at once.
+//!
+//! Every change is scheduled for the next guardian epoch (see
+//! `guardian_epochs`); vetoes come from guardians of the active set.
+
+use near_sdk::{env, near};
+use zk_email_verifier_core::layout::{
+    CircuitKind, ADD_GUARDIAN_CIRCUIT_ID, REMOVE_GUARDIAN_CIRCUIT_ID, VETO_GUARDIAN_REMOVAL_CIRCUIT_ID,
+};
+
+use crate::commands::{decode_hex_hash, encode_hex, reject, VerifiedCommand};
+use crate::events::Event;
+use crate::{FailureReason, ProofInput, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Three days.
+pub(crate) const DEFAULT_GUARDIAN_REMOVAL_DELAY_MS: u64 = 3 * 24 * 60 * 60 * 1000;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingGuardianRemoval {
+    pub request_id: String,
+    /// Email commitment of the guardian being removed.
+    pub guardian_hash: Vec<u8>,
+    /// When `finalize_guardian_removal` is accepted.
+    pub ready_at_ms: u64,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Verify an `add-guardian-` email and append the guardian it names to
+    /// its account's guardian set. Returns the new set.
+    pub fn add_guardian(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) -> Vec<Vec<u8>> {
+        let command = self
+            .verify_command(ADD_GUARDIAN_CIRCUIT_ID, proof, &public_inputs, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        if self.is_locked(&command.account_id) {
+            reject(FailureReason::AccountLocked);
+        }
+        let guardian = named_guardian(&command);
+        self.insert_guardian(&command.account_id, guardian.clone());
+        Event::GuardianAdded {
+            account_id: command.account_id.clone(),
+            guardian_hash: encode_hex(&guardian),
+        }
+        .emit();
+        self.get_guardians(command.account_id)
+    }
+
+    /// Verify a `remove-guardian-` email and start the removal delay of the
+    /// guardian it names.
+    pub fn remove_guardian(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) -> PendingGuardianRemoval {
+        let command = self
+            .verify_command(REMOVE_GUARDIAN_CIRCUIT_ID, proof, &public_inputs, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        if self.is_locked(&command.account_id) {
+            reject(FailureReason::AccountLocked);
+        }
+        let guardian_hash = named_guardian(&command);
+        if !self.get_guardians(command.account_id.clone()).contains(&guardian_hash) {
+            env::panic_str("not a guardian");
+        }
+        let mut pending = self.get_pending_guardian_removals(command.account_id.clone());
+        if pending.iter().any(|removal| removal.guardian_hash == guardian_hash) {
+            env::panic_str("the guardian's removal is already pending");
+        }
+        let removal = PendingGuardianRemoval {
+            request_id: command.request_id,
+            guardian_hash,
+            ready_at_ms: env::block_timestamp_ms().saturating_add(self.guardian_removal_delay_ms),
+        };
+        pending.push(removal.clone());
+        self.pending_guardian_removals
+            .insert(command.account_id.clone(), pending);
+        Event::GuardianRemovalInitiated {
+            account_id: command.account_id,
+            guardian_hash: encode_hex(&removal.guardian_hash),
+            ready_at_ms: removal.ready_at_ms,
+        }
+        .emit();
+        removal
+    }
+
+    /// Verify a `veto-` email from another guardian of the account and drop
+    /// the pending removal of the guardian it names.
+    pub fn veto_guardian_removal(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) {
+        let command = self
+            .verify_command_proof(CircuitKind::Command, VETO_GUARDIAN_REMOVAL_CIRCUIT_ID, proof, &public_inputs)
+            .unwrap_or_else(|reason| reject(reason));
+        self.admit_command(&command, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        let guardian_hash = named_guardian(&command);
+        if command.from_address_hash == guardian_hash
+            || !self
+                .guardian_set_at(&command.account_id, env::block_timestamp_ms())
+                .guardians
+                .contains(&command.from_address_hash)
+        {
+            reject(FailureReason::NotAGuardian);
+        }
+        if self.take_removal(&command.account_id, &guardian_hash).is_none() {
+            env::panic_str("no pending removal of the guardian");
+        }
+        Event::GuardianRemovalVetoed {
+            account_id: command.account_id,
+            guardian_hash: encode_hex(&guardian_hash),
+            vetoed_by: encode_hex(&command.from_address_hash),
+        }
+        .emit();
+    }
+
+    /// Remove the guardian of `account_id` whose removal delay has passed.
+    pub fn finalize_guardian_removal(&mut self, account_id: String, guardian_hash: Vec<u8>) {
+        self.assert_activated();
+        let ready_at_ms = self
+            .get_pending_guardian_removals(account_id.clone())
+            .into_iter()
+            .find(|removal| removal.guardian_hash == guardian_hash)
+            .map(|removal| removal.ready_at_ms)
+            .unwrap_or_else(|| env::panic_str("no pending removal of the guardian"));
+        if env::block_timestamp_ms() < ready_at_ms {
+            env::panic_str("the guardian removal delay has not passed");
+        }
+        self.take_removal(&account_id, &guardian_hash);
+        let mut guardians = self.get_guardians(account_id.clone());
+        guardians.retain(|guardian| *guardian != guardian_hash);
+        self.guardians.insert(account_id.clone(), guardians);
+        self.schedule_guardian_set(&account_id);
+        Event::GuardianRemoved {
+            account_id,
+            guardian_hash: encode_hex(&guardian_hash),
+        }
+        .emit();
+    }
+
+    pub fn get_pending_guardian_removals(&self, account_id: String) -> Vec<PendingGuardianRemoval> {
+        self.pending_guardian_removals
+            .get(&account_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get_guardian_removal_delay_ms(&self) -> u64 {
+        self.guardian_removal_delay_ms
+    }
+
+    /// Only callable by the owner. Removals already pending keep their delay.
+    pub fn set_guardian_removal_delay_ms(&mut self, delay_ms: u64) {
+        self.assert_owner();
+        self.guardian_removal_delay_ms = delay_ms;
+    }
+
+    /// Email commitments of the guardians of `account_id`, in the order they
+    /// were added, including changes that are not active yet.
+    pub fn get_guardians(&self, account_id: String) -> Vec<Vec<u8>> {
+        self.guardians.get(&account_id).cloned().unwrap_or_default()
+    }
+}
+
+impl ZkEmailVerifier {
+    fn insert_guardian(&mut self, account_id: &str, guardian: Vec<u8>) {
+        if self.registered_emails.get(account_id) == Some(&guardian) {
+            env::panic_str("an account's own email can't be its guardian");
+        }
+        let mut guardians = self.get_guardians(account_id.to_string());
+        if guardians.contains(&guardian) {
+            env::panic_str("already a guardian");
+        }
+        guardians.push(guardian);
+        self.guardians.insert(account_id.to_string(), guardians);
+        self.schedule_guardian_set(account_id);
+    }
+
+    fn take_removal(&mut self, account_id: &str, guardian_hash: &[u8]) -> Option<PendingGuardianRemoval> {
+        let mut pending = self.get_pending_guardian_removals(account_id.to_string());
+        let index = pending
+            .iter()
+            .position(|removal| removal.guardian_hash == guardian_hash)?;
+        let removal = pending.remove(index);
+        if pending.is_empty() {
+            self.pending_guardian_removals.remove(account_id);
+        } else {
+            self.pending_guardian_removals
+                .insert(account_id.to_string(), pending);
+        }
+        Some(removal)
+    }
+}
+
+/// The guardian a command names, or reject it.
+fn named_guardian(command: &VerifiedCommand) -> Vec<u8> {
+    command
+        .text("guardian_email_hash")
+        .and_then(|hex| decode_hex_hash(&hex))
+        .unwrap_or_else(|| reject(FailureReason::InputParse))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    #[test]
+    #[should_panic(expected = "already a guardian")]
+    fn guardians_are_added_once() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_millinear(10))
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.register_email(env::sha256(b"own"));
+        contract.insert_guardian(accounts(1).as_str(), env::sha256(b"bob"));
+        contract.insert_guardian(accounts(1).as_str(), env::sha256(b"carol"));
+        assert_eq!(
+            contract.get_guardians(accounts(1).to_string()),
+            vec![env::sha256(b"bob"), env::sha256(b"carol")]
+        );
+        contract.insert_guardian(accounts(1).as_str(), env::sha256(b"bob"));
+    }
+
+    #[test]
+    fn guardian_removals_wait_for_the_delay() {
+        let at = |timestamp_ms: u64| {
+            testing_env!(VMContextBuilder::new()
+                .predecessor_account_id(accounts(1))
+                .block_timestamp(timestamp_ms * 1_000_000)
+                .build())
+        };
+        at(1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.insert_guardian(accounts(1).as_str(), env::sha256(b"bob"));
+        contract.pending_guardian_removals.insert(
+            accounts(1).to_string(),
+            vec![PendingGuardianRemoval {
+                request_id: "1".to_string(),
+                guardian_hash: env::sha256(b"bob"),
+                ready_at_ms: 2_000,
+            }],
+        );
+
+        at(2_000);
+        contract.finalize_guardian_removal(accounts(1).to_string(), env::sha256(b"bob"));
+        assert!(contract.get_guardians(accounts(1).to_string()).is_empty());
+        assert!(contract
+            .get_pending_guardian_removals(accounts(1).to_string())
+            .is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! Operational settings and the `health_check` view relayer daemons poll
+//! before submitting work.
+//!
+//! The owner can pause the stateful verification paths (`verify_staged`),
+//! which the circuit breaker in `breaker` also does on its own, and set a
+//! freshness window: once set, a staged verification of an email whose
+//! `Date:` is older than the window (or missing) is rejected as
+//! `STALE_EMAIL`. View methods are unaffected by both.
+
+use near_sdk::{env, near};
+
+use crate::{FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthCheck {
+    pub vk_loaded: bool,
+    pub paused: bool,
+    pub active_circuit_ids: Vec<String>,
+    /// Maximum email age accepted by stateful verifications, if any.
+    pub freshness_window_ms: Option<u64>,
+    /// False while a deployment initialized with `new_for_import` waits
+    /// for `activate`.
+    pub activated: bool,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn health_check(&self) -> HealthCheck {
+        HealthCheck {
+            vk_loaded: self.prepared_vk.get().is_some(),
+            paused: self.paused,
+            active_circuit_ids: self.circuit_ids(),
+            freshness_window_ms: self.freshness_window_ms,
+            activated: self.activated,
+        }
+    }
+
+    /// Pause or resume `verify_staged`. Only callable by the owner.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.assert_owner();
+        self.paused = paused;
+        env::log_str(if paused { "paused" } else { "resumed" });
+    }
+
+    /// Set (or clear, with `None`) the freshness window. Only callable by the
+    /// owner.
+    pub fn set_freshness_window_ms(&mut self, window_ms: Option<u64>) {
+        self.assert_owner();
+        self.freshness_window_ms = window_ms;
+    }
+}
+
+impl ZkEmailVerifier {
+    pub(crate) fn assert_not_paused(&self) {
+        if self.paused {
+            env::panic_str("contract is paused");
+        }
+    }
+
+    /// Refuse to act on proofs while state is still being imported.
+    pub(crate) fn assert_activated(&self) {
+        if !self.activated {
+            env::panic_str("contract is not activated yet");
+        }
+    }
+
+    /// Reject a verified `result` whose email is older than the freshness
+    /// window.
+    pub(crate) fn check_freshness(&self, result: VerificationResult) -> VerificationResult {
+        if !result.verified {
+            return result;
+        }
+        match self.check_freshness_of(result.email_timestamp_ms) {
+            Ok(()) => result,
+            Err(reason) => result.with_failure(reason),
+        }
+    }
+
+    /// Reject an email sent at `email_timestamp_ms`, or without a parseable
+    /// date, once it is older than the freshness window.
+    pub(crate) fn check_freshness_of(&self, email_timestamp_ms: Option<u64>) -> Result<(), FailureReason> {
+        let Some(window_ms) = self.freshness_window_ms else {
+            return Ok(());
+        };
+        let oldest = env::block_timestamp_ms().saturating_sub(window_ms);
+        match email_timestamp_ms {
+            Some(ts) if ts >= oldest => Ok(()),
+            _ => Err(FailureReason::StaleEmail),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn health_check_reflects_owner_settings() {
+        let now_ms = 1_765_000_000_000;
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(now_ms * 1_000_000)
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let health = contract.health_check();
+        assert!(health.vk_loaded && !health.paused);
+        assert_eq!(health.active_circuit_ids, vec![RECOVER_EMAIL_CIRCUIT_ID]);
+        assert_eq!(health.freshness_window_ms, None);
+
+        contract.set_paused(true);
+        contract.set_freshness_window_ms(Some(60_000));
+        let health = contract.health_check();
+        assert!(health.paused);
+        assert_eq!(health.freshness_window_ms, Some(60_000));
+
+        let mut result = VerificationResult::verified_for("");
+        result.email_timestamp_ms = Some(now_ms - 60_000);
+        assert!(contract.check_freshness(result.clone()).verified);
+        result.email_timestamp_ms = Some(now_ms - 60_001);
+        let stale = contract.check_freshness(result);
+        assert_eq!(stale.failure_reason.as_deref(), Some("STALE_EMAIL"));
+    }
+
+    #[test]
+    #[should_panic(expected = "only the owner can call this method")]
+    fn set_paused_is_owner_only() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        contract.set_paused(true);
+    }
+}
@@ -0,0 +1,231 @@
+//! Caller-supplied idempotency keys for `verify_staged`.
+//!
+//! A relayer that doesn't know whether its transaction landed can resubmit
+//! with the same `idempotency_key`: if the first submission finished, its
+//! outcome is returned without verifying, charging or recording anything
+//! again, and while it is still between its two receipts the retry is
+//! refused. Unlike the result cache (see `result_cache`), this works for
+//! rejected submissions too and doesn't depend on the proof bytes being
+//! identical.
+//!
+//! Keys are scoped to the submitting account, so one caller can't block
+//! another's. A new key holds `IDEMPOTENT_ENTRY_BYTES` of storage out of the
+//! attached deposit; it expires `IDEMPOTENCY_KEY_TTL_MS` after it was first
+//! used, after which it can be reused and anyone can drop it with
+//! `prune_idempotent_results`, refunding the submitter. If the second
+//! receipt fails (say, out of gas), the submission comes back rejected with
+//! `CALLBACK_FAILED` and its key is dropped, so it can be retried with the
+//! same key (see `on_staged_verification_completed`).
+
+use near_sdk::{env, near, AccountId, NearToken, Promise};
+
+use crate::{VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Longest accepted idempotency key, in bytes.
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 64;
+
+/// How long the outcome of a key is kept. One day.
+pub const IDEMPOTENCY_KEY_TTL_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Storage held per key: the submitter and key, a finished result with a
+/// few public keys, and the map's overhead.
+pub const IDEMPOTENT_ENTRY_BYTES: u64 = 1_500;
+
+#[near(serializers = [borsh])]
+pub(crate) struct IdempotentEntry {
+    /// `None` while the submission is in progress.
+    result: Option<VerificationResult>,
+    expires_at_ms: u64,
+    /// Held out of the submitter's deposit, refunded when the entry is
+    /// dropped.
+    storage_deposit: NearToken,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// The outcome of `submitter`'s finished submission with
+    /// `idempotency_key`, while it hasn't expired.
+    pub fn get_idempotent_result(
+        &self,
+        submitter: AccountId,
+        idempotency_key: String,
+    ) -> Option<VerificationResult> {
+        self.idempotent_results
+            .get(&(submitter, idempotency_key))
+            .filter(|entry| entry.expires_at_ms > env::block_timestamp_ms())
+            .and_then(|entry| entry.result.clone())
+    }
+
+    /// Look at up to `limit` idempotency keys and drop the expired ones,
+    /// refunding their storage to their submitters, and return how many were
+    /// dropped. Like `prune_result_cache`, each call continues where the last
+    /// one stopped. Callable by anyone: only expired entries are touched.
+    pub fn prune_idempotent_results(&mut self, limit: u32) -> u32 {
+        let now_ms = env::block_timestamp_ms();
+        let len = self.idempotent_results.len();
+        let start = if self.idempotent_prune_cursor < len { self.idempotent_prune_cursor } else { 0 };
+        let scanned = limit.min(len - start);
+        let expired: Vec<(AccountId, String)> = self
+            .idempotent_results
+            .iter()
+            .skip(start as usize)
+            .take(scanned as usize)
+            .filter(|(_, entry)| entry.expires_at_ms <= now_ms)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.drop_idempotent(key);
+        }
+        self.idempotent_prune_cursor = start + scanned - expired.len() as u32;
+        expired.len() as u32
+    }
+}
+
+impl ZkEmailVerifier {
+    /// The outcome recorded for `key`, or `None` after marking a new
+    /// submission with it as in progress. Panics while an earlier one is.
+    /// A new entry holds `idempotent_entry_deposit()`, which the caller must
+    /// keep out of the attached deposit.
+    pub(crate) fn begin_idempotent(
+        &mut self,
+        submitter: &AccountId,
+        key: &str,
+    ) -> Option<VerificationResult> {
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+            env::panic_str("idempotency keys must be 1 to 64 bytes");
+        }
+        let now_ms = env::block_timestamp_ms();
+        let entry_key = (submitter.clone(), key.to_string());
+        match self.idempotent_results.get(&entry_key) {
+            Some(entry) if entry.expires_at_ms > now_ms => match &entry.result {
+                Some(result) => return Some(result.clone()),
+                None => env::panic_str("a submission with this idempotency key is in progress"),
+            },
+            Some(_) => self.drop_idempotent(&entry_key),
+            None => {}
+        }
+        self.idempotent_results.insert(
+            entry_key,
+            IdempotentEntry {
+                result: None,
+                expires_at_ms: now_ms.saturating_add(IDEMPOTENCY_KEY_TTL_MS),
+                storage_deposit: idempotent_entry_deposit(),
+            },
+        );
+        None
+    }
+
+    pub(crate) fn finish_idempotent(
+        &mut self,
+        submitter: &AccountId,
+        key: String,
+        result: &VerificationResult,
+    ) {
+        if let Some(entry) = self.idempotent_results.get_mut(&(submitter.clone(), key)) {
+            entry.result = Some(result.clone());
+        }
+    }
+
+    /// Remove the entry under `key`, refunding its storage to the submitter.
+    pub(crate) fn drop_idempotent(&mut self, key: &(AccountId, String)) {
+        if let Some(entry) = self.idempotent_results.remove(key) {
+            if !entry.storage_deposit.is_zero() {
+                Promise::new(key.0.clone()).transfer(entry.storage_deposit).detach();
+            }
+        }
+    }
+}
+
+/// What a new idempotency key holds of the attached deposit.
+pub(crate) fn idempotent_entry_deposit() -> NearToken {
+    env::storage_byte_cost().saturating_mul(u128::from(IDEMPOTENT_ENTRY_BYTES))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::at;
+    use crate::FailureReason;
+    use near_sdk::test_utils::accounts;
+    use near_sdk::PromiseError;
+
+    #[test]
+    fn finished_submissions_are_replayed_per_submitter() {
+        at(0);
+        let mut contract = ZkEmailVerifier::new(None);
+        let result = VerificationResult::rejected(FailureReason::Pairing);
+
+        assert!(contract.begin_idempotent(&accounts(1), "retry-1").is_none());
+        contract.finish_idempotent(&accounts(1), "retry-1".to_string(), &result);
+        let replayed = contract.begin_idempotent(&accounts(1), "retry-1").unwrap();
+        assert_eq!(replayed.failure_reason.as_deref(), Some("PAIRING"));
+
+        assert!(contract.begin_idempotent(&accounts(2), "retry-1").is_none());
+        assert!(contract
+            .get_idempotent_result(accounts(2), "retry-1".to_string())
+            .is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "a submission with this idempotency key is in progress")]
+    fn submissions_in_progress_are_refused() {
+        at(0);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.begin_idempotent(&accounts(1), "retry-1");
+        contract.begin_idempotent(&accounts(1), "retry-1");
+    }
+
+    #[test]
+    fn keys_expire_and_are_pruned() {
+        at(0);
+        let mut contract = ZkEmailVerifier::new(None);
+        let result = VerificationResult::rejected(FailureReason::Pairing);
+        contract.begin_idempotent(&accounts(1), "retry-1");
+        contract.finish_idempotent(&accounts(1), "retry-1".to_string(), &result);
+        contract.begin_idempotent(&accounts(1), "retry-2");
+
+        at(IDEMPOTENCY_KEY_TTL_MS - 1);
+        assert_eq!(contract.prune_idempotent_results(10), 0);
+        assert!(contract.get_idempotent_result(accounts(1), "retry-1".to_string()).is_some());
+
+        at(IDEMPOTENCY_KEY_TTL_MS);
+        assert!(contract.get_idempotent_result(accounts(1), "retry-1".to_string()).is_none());
+        assert!(contract.begin_idempotent(&accounts(1), "retry-2").is_none());
+        assert_eq!(contract.prune_idempotent_results(10), 1);
+        assert_eq!(contract.idempotent_results.len(), 1);
+    }
+
+    #[test]
+    fn pruning_scans_at_most_limit_keys_per_call() {
+        at(0);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.begin_idempotent(&accounts(1), "live");
+        at(IDEMPOTENCY_KEY_TTL_MS / 2);
+        contract.begin_idempotent(&accounts(1), "retry-1");
+        contract.begin_idempotent(&accounts(1), "retry-2");
+
+        at(IDEMPOTENCY_KEY_TTL_MS + 1);
+        contract.begin_idempotent(&accounts(2), "live");
+        assert_eq!(contract.prune_idempotent_results(1), 1);
+        at(IDEMPOTENCY_KEY_TTL_MS * 3 / 2);
+        assert_eq!(contract.prune_idempotent_results(1), 0, "only the live key was looked at");
+        assert_eq!(contract.prune_idempotent_results(2), 2);
+        assert_eq!(contract.idempotent_results.len(), 1);
+    }
+
+    #[test]
+    fn failed_callbacks_release_their_key() {
+        at(0);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.begin_idempotent(&accounts(1), "retry-1");
+
+        let result = contract.on_staged_verification_completed(
+            0,
+            accounts(1),
+            Some("retry-1".to_string()),
+            Err(PromiseError::Failed),
+        );
+        assert_eq!(result.failure_reason.as_deref(), Some("CALLBACK_FAILED"));
+        assert!(contract.begin_idempotent(&accounts(1), "retry-1").is_none());
+    }
+}
@@ -0,0 +1,247 @@
+//! Capability discovery, so SDKs and integrating contracts can tell what a
+//! given deployment supports instead of assuming the latest version.
+
+use near_sdk::near;
+
+use crate::network::NetworkProfile;
+use crate::{ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Bumped whenever the JSON shape of `VerificationResult` changes.
+pub const RESULT_SCHEMA_VERSION: u32 = 7;
+
+/// Public methods callable by anyone other than the contract itself. Init
+/// methods and private callbacks are left out.
+const METHODS: &[&str] = &[
+    "activate",
+    "add_guardian",
+    "attestation_hash",
+    "authorize_relayer",
+    "block_senders",
+    "cancel_email_change",
+    "cancel_migration",
+    "cancel_spending_limit",
+    "confirm_email_change",
+    "dry_run_verify",
+    "estimate_verify_gas",
+    "export_attestation",
+    "export_state",
+    "finalize_guardian_removal",
+    "finalize_migration",
+    "finalize_spending_limit",
+    "generate_request_id",
+    "get_account_lock",
+    "get_account_namespace",
+    "get_account_relayer",
+    "get_allowed_sender_domains",
+    "get_attestation",
+    "get_audit_capacity",
+    "get_cached_result",
+    "get_challenge",
+    "get_circuit_breaker",
+    "get_consumed_challenges",
+    "get_controller_contract",
+    "get_domain_circuit",
+    "get_email_change_window_ms",
+    "get_email_template",
+    "get_guardian_epoch_ms",
+    "get_guardian_recovery",
+    "get_guardian_removal_delay_ms",
+    "get_guardian_set",
+    "get_guardian_weights",
+    "get_guardians",
+    "get_idempotent_result",
+    "get_key_permission",
+    "get_lock_duration_ms",
+    "get_migration_timelock_ms",
+    "get_next_guardian_epoch_ms",
+    "get_nullifier_root",
+    "get_open_challenges",
+    "get_owner_id",
+    "get_pending_email_change",
+    "get_pending_guardian_removals",
+    "get_pending_migration",
+    "get_pending_spending_limit",
+    "get_predecessor_binding",
+    "get_public_input_layout",
+    "get_rate_limit_bypass_accounts",
+    "get_recent_verifications",
+    "get_recovery_cooldown_ms",
+    "get_recovery_mailbox",
+    "get_recovery_policy",
+    "get_recovery_rate_limit",
+    "get_recovery_status",
+    "get_registered_email",
+    "get_relayer_reward",
+    "get_result_cache_len",
+    "get_result_cache_ttl_ms",
+    "get_result_root",
+    "get_sponsor_balance",
+    "get_stats",
+    "get_subject_template",
+    "get_supported_circuits",
+    "get_treasury_balance",
+    "get_verification_fee",
+    "get_verifications_for_account",
+    "get_verifier_info",
+    "get_verifying_key_json",
+    "get_vk_registry",
+    "has_nullifier",
+    "health_check",
+    "import_state",
+    "initiate_email_change",
+    "initiate_migration",
+    "initiate_spending_limit",
+    "is_sender_blocked",
+    "list_nullifiers",
+    "list_result_hashes",
+    "lock_account",
+    "nullifier_hash",
+    "pack_string",
+    "prune_idempotent_results",
+    "prune_records",
+    "prune_result_cache",
+    "register_email",
+    "register_email_with_proof",
+    "remove_guardian",
+    "revoke_relayer",
+    "sender_block_hash",
+    "set_account_namespace",
+    "set_account_relayer",
+    "set_allowed_sender_domains",
+    "set_audit_capacity",
+    "set_circuit_breaker",
+    "set_circuit_deprecation",
+    "set_circuit_verifying_key",
+    "set_controller_contract",
+    "set_debug_logs",
+    "set_default_key_permission",
+    "set_domain_circuit",
+    "set_email_change_window_ms",
+    "set_freshness_window_ms",
+    "set_gas_benchmark",
+    "set_guardian_epoch_ms",
+    "set_guardian_removal_delay_ms",
+    "set_guardian_weights",
+    "set_key_permission",
+    "set_lock_duration_ms",
+    "set_migration_timelock_ms",
+    "set_paused",
+    "set_predecessor_binding",
+    "set_rate_limit_bypass",
+    "set_recovery_cooldown_ms",
+    "set_recovery_mailbox",
+    "set_recovery_policy",
+    "set_recovery_rate_limit",
+    "set_relayer_reward",
+    "set_result_cache_ttl_ms",
+    "set_verification_fee",
+    "set_verifying_key",
+    "set_verifying_key_bytes",
+    "set_vk_registry",
+    "sponsor_close",
+    "sponsor_deposit",
+    "sponsor_withdraw",
+    "submit_guardian_proof",
+    "supports_method",
+    "unblock_senders",
+    "unpack_fields",
+    "verify",
+    "verify_batch",
+    "verify_blob",
+    "verify_recovery_bundle",
+    "verify_staged",
+    "verify_with_binding",
+    "verify_with_hashed_binding",
+    "veto_guardian_removal",
+    "withdraw_treasury",
+];
+
+/// Encodings accepted for proofs and public inputs.
+const PROOF_ENCODINGS: &[&str] = &["decimal", "hex", "blob_v1"];
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifierInfo {
+    /// Crate version the contract was built from.
+    pub version: String,
+    pub result_schema_version: u32,
+    pub methods: Vec<String>,
+    pub circuit_ids: Vec<String>,
+    /// Profile the contract was initialized with.
+    pub network: NetworkProfile,
+    /// `decimal` strings as snarkjs writes them, `0x`-prefixed big-endian
+    /// `hex`, or the binary envelope of `verify_blob` (`blob_v<version>`).
+    pub proof_encodings: Vec<String>,
+}
+
+fn methods() -> impl Iterator<Item = &'static str> {
+    let profiling = cfg!(feature = "profiling").then_some("profile_verify");
+    METHODS.iter().copied().chain(profiling)
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_verifier_info(&self) -> VerifierInfo {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        VerifierInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            methods: methods().map(str::to_string).collect(),
+            circuit_ids: self.circuit_ids(),
+            network: self.network,
+            proof_encodings: strings(PROOF_ENCODINGS),
+        }
+    }
+
+    pub fn supports_method(&self, method_name: String) -> bool {
+        methods().any(|m| m == method_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Names of `pub fn`s taking `&self` or `&mut self` in `source`.
+    fn self_methods(source: &str) -> Vec<&str> {
+        source
+            .split("pub fn ")
+            .skip(1)
+            .filter_map(|rest| {
+                let (name, args) = rest.split_once('(')?;
+                let args = args.trim_start();
+                (args.starts_with("&self") || args.starts_with("&mut self")).then_some(name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn method_list_covers_every_contract_method() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+        let private = [
+            "complete_staged_verification",
+            "on_controller_check",
+            "on_registry_verifying_key",
+            "on_relayer_rewarded",
+            "on_staged_verification_completed",
+            "on_treasury_withdrawn",
+            "profile_verify",
+        ];
+        for entry in fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            if path.file_name().unwrap() == "error.rs" {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            for name in self_methods(&source) {
+                assert!(
+                    METHODS.contains(&name) || private.contains(&name),
+                    "{name} in {} is missing from METHODS",
+                    path.display()
+                );
+            }
+        }
+        assert!(METHODS.windows(2).all(|w| w[0] < w[1]), "METHODS must be sorted");
+    }
+}
@@ -0,0 +1,109 @@
+//! Which kind of access key a recovery adds.
+//!
+//! By default the recovered key is a full-access key. The owner can change
+//! the default, and each account can choose for itself with
+//! `set_key_permission`, e.g. a function-call key that can only call its
+//! controller contract, so that a recovery alone never hands out full
+//! control. Verified results carry the permission in effect for their
+//! account as `key_permission`, for the caller to build its `AddKey` action
+//! from. An account's recovery policy can cap it (see `recovery_policy`).
+
+use near_sdk::{env, near, AccountId, NearToken};
+
+use crate::recovery_policy::cap_key_permission;
+use crate::treasury::charge_storage;
+use crate::{VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyPermission {
+    #[default]
+    FullAccess,
+    /// A function-call key for `receiver_id`, limited to `method_names`
+    /// (any method if empty) and to spending `allowance` on gas.
+    FunctionCall {
+        receiver_id: AccountId,
+        method_names: Vec<String>,
+        allowance: Option<NearToken>,
+    },
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// The permission recoveries of `account_id` add keys with.
+    pub fn get_key_permission(&self, account_id: String) -> KeyPermission {
+        let permission = self
+            .account_key_permissions
+            .get(&account_id)
+            .unwrap_or(&self.default_key_permission)
+            .clone();
+        cap_key_permission(permission, self.recovery_policy(&account_id).max_key_permission)
+    }
+
+    /// Add keys recovered for the calling account with `permission`, or go
+    /// back to the default with `None`. Attach the storage the permission
+    /// takes; the rest is refunded.
+    #[payable]
+    pub fn set_key_permission(&mut self, permission: Option<KeyPermission>) {
+        let usage_before = env::storage_usage();
+        let account_id = env::predecessor_account_id().to_string();
+        match permission {
+            Some(permission) => self.account_key_permissions.insert(account_id, permission),
+            None => self.account_key_permissions.remove(&account_id),
+        };
+        self.account_key_permissions.flush();
+        charge_storage(usage_before);
+    }
+
+    /// Permission for accounts that haven't chosen one. Only callable by the
+    /// owner.
+    pub fn set_default_key_permission(&mut self, permission: KeyPermission) {
+        self.assert_owner();
+        self.default_key_permission = permission;
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Attach the key permission of a verified `result`'s account.
+    pub(crate) fn with_key_permission(&self, mut result: VerificationResult) -> VerificationResult {
+        if result.verified {
+            result.key_permission = Some(self.get_key_permission(result.account_id.clone()));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn accounts_override_the_default_permission() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let mut result = VerificationResult::rejected(crate::FailureReason::Pairing);
+        result.account_id = accounts(1).to_string();
+        assert_eq!(contract.with_key_permission(result.clone()).key_permission, None);
+
+        result.verified = true;
+        let permission = |c: &ZkEmailVerifier| c.with_key_permission(result.clone()).key_permission;
+        assert_eq!(permission(&contract), Some(KeyPermission::FullAccess));
+
+        let controller = KeyPermission::FunctionCall {
+            receiver_id: accounts(2),
+            method_names: vec!["execute".to_string()],
+            allowance: None,
+        };
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_millinear(10))
+            .build());
+        contract.set_key_permission(Some(controller.clone()));
+        assert_eq!(permission(&contract), Some(controller));
+        contract.set_key_permission(None);
+        assert_eq!(permission(&contract), Some(KeyPermission::FullAccess));
+    }
+}
@@ -0,0 +1,151 @@
+//! Public signal layout and subject template of the supported circuits, in the
+//! JSON shape returned by `get_public_input_layout` and `get_subject_template`.
+//! The layouts and template strings themselves live in `zk_email_verifier_core`.
+
+use near_sdk::near;
+
+pub use zk_email_verifier_core::layout::RECOVER_EMAIL_CIRCUIT_ID;
+use zk_email_verifier_core::layout::{self as core_layout, public_input_layout as core_public_input_layout};
+use zk_email_verifier_core::subject::subject_template as core_subject_template;
+
+/// How a public signal field is encoded into field elements.
+#[near(serializers = [json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldEncoding {
+    /// UTF-8 bytes packed 31 per field element (`PackByteSubArray`).
+    PackedBytes,
+    /// One byte per field element.
+    Bytes,
+    /// Big integer split into 121-bit limbs, least significant first.
+    Limbs,
+}
+
+impl From<core_layout::FieldEncoding> for FieldEncoding {
+    fn from(encoding: core_layout::FieldEncoding) -> Self {
+        match encoding {
+            core_layout::FieldEncoding::PackedBytes { .. } => Self::PackedBytes,
+            core_layout::FieldEncoding::Bytes => Self::Bytes,
+            core_layout::FieldEncoding::Limbs => Self::Limbs,
+        }
+    }
+}
+
+/// What a verified email of a circuit is allowed to do.
+#[near(serializers = [json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitKind {
+    /// Accepted by `verify`, `verify_staged` and the other recovery methods.
+    Recovery,
+    /// Only accepted by the method handling its command.
+    Command,
+}
+
+impl From<core_layout::CircuitKind> for CircuitKind {
+    fn from(kind: core_layout::CircuitKind) -> Self {
+        match kind {
+            core_layout::CircuitKind::Recovery => Self::Recovery,
+            core_layout::CircuitKind::Command => Self::Command,
+        }
+    }
+}
+
+/// One named field of the public signal array.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputField {
+    pub name: String,
+    pub offset: u32,
+    pub len: u32,
+    pub encoding: FieldEncoding,
+    /// Maximum byte length of a `packed_bytes` field.
+    pub max_len: Option<u32>,
+}
+
+/// Machine-readable description of a circuit's `public.json` ordering.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputLayout {
+    pub circuit_id: String,
+    pub kind: CircuitKind,
+    /// Total number of public signals.
+    pub len: u32,
+    pub fields: Vec<PublicInputField>,
+}
+
+/// Look up the layout for `circuit_id`.
+pub fn public_input_layout(circuit_id: &str) -> Option<PublicInputLayout> {
+    let layout = core_public_input_layout(circuit_id)?;
+    Some(PublicInputLayout {
+        circuit_id: layout.circuit_id.to_string(),
+        kind: layout.kind.into(),
+        len: layout.len as u32,
+        fields: layout
+            .fields
+            .iter()
+            .map(|f| PublicInputField {
+                name: f.name.to_string(),
+                offset: f.offset as u32,
+                len: f.len as u32,
+                encoding: f.encoding.into(),
+                max_len: match f.encoding {
+                    core_layout::FieldEncoding::PackedBytes { max_len } => Some(max_len as u32),
+                    _ => None,
+                },
+            })
+            .collect(),
+    })
+}
+
+/// The email header a circuit anchors its values in, with `{field}`
+/// placeholders for the values the user fills in.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubjectTemplate {
+    pub circuit_id: String,
+    /// Header name, always `Subject` for now.
+    pub header: String,
+    /// e.g. `recover-{request_id} {account_id} ed25519:{new_public_key}`.
+    pub template: String,
+    /// Placeholder names in the order they appear.
+    pub fields: Vec<String>,
+}
+
+/// Look up the subject template for `circuit_id`.
+pub fn subject_template(circuit_id: &str) -> Option<SubjectTemplate> {
+    let template = core_subject_template(circuit_id)?;
+    Some(SubjectTemplate {
+        circuit_id: template.circuit_id.to_string(),
+        header: "Subject".to_string(),
+        template: template.template.to_string(),
+        fields: template.fields().into_iter().map(str::to_string).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recover_email_layout_is_contiguous() {
+        let layout = public_input_layout(RECOVER_EMAIL_CIRCUIT_ID).unwrap();
+        let mut next = 0;
+        for f in &layout.fields {
+            assert_eq!(f.offset, next, "{} is not contiguous", f.name);
+            next += f.len;
+        }
+        assert_eq!(next, layout.len);
+        assert!(public_input_layout("UnknownCircuit").is_none());
+    }
+
+    #[test]
+    fn subject_template_fields_are_public_inputs() {
+        let template = subject_template(RECOVER_EMAIL_CIRCUIT_ID).unwrap();
+        let layout = public_input_layout(RECOVER_EMAIL_CIRCUIT_ID).unwrap();
+        for name in &template.fields {
+            assert!(layout.fields.iter().any(|f| &f.name == name), "{name}");
+        }
+        assert!(subject_template("UnknownCircuit").is_none());
+    }
+}
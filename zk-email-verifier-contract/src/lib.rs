@@ -1,134 +1,532 @@
-use core::str::FromStr;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 
+use near_sdk::base64::{engine::general_purpose::STANDARD, Engine};
 use near_sdk::{
-    near,
-    serde::{Deserialize, Serialize},
+    env, near,
+    store::{IterableMap, IterableSet, LazyOption, LookupMap},
+    AccountId, BorshStorageKey, CurveType, NearToken, PanicOnDefault, PublicKey,
 };
-use schemars::JsonSchema;
 
-use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
-use ark_ff::{BigInteger, PrimeField};
-use ark_groth16::{prepare_verifying_key, Groth16, Proof};
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{PreparedVerifyingKey, Proof, VerifyingKey};
+use zk_email_verifier_core::layout::{
+    CircuitKind, PublicInputLayout as CircuitLayout, EXPECTED_PUBLIC_LEN, RECOVER_EMAIL_LAYOUT,
+};
+use zk_email_verifier_core::{
+    decode_with_layout, parse_email_timestamp, parse_public_inputs, verify_with_prepared_inputs,
+};
 
+pub mod attestation;
+pub mod audit;
+mod batch;
+pub mod bundle;
+pub mod binding;
+pub mod breaker;
+pub mod challenges;
+pub mod circuits;
+mod commands;
+pub mod cooldown;
+pub mod debug;
+pub mod diagnostics;
+pub mod email;
+pub mod email_change;
+pub mod email_template;
+mod error;
+pub mod events;
+pub mod gas_estimate;
+pub mod guardian_epochs;
+pub mod guardian_recovery;
+pub mod guardians;
+pub mod health;
+pub mod idempotency;
+pub mod info;
+pub mod key_permission;
+pub mod layout;
+pub mod lock;
+pub mod migration;
+pub mod namespace;
+mod msm;
+pub mod network;
+pub mod nullifiers;
+mod prepared_vk;
+pub mod rate_limit;
+pub mod recovery_policy;
+pub mod registry;
+pub mod registration;
+pub mod relayers;
+pub mod result_accumulator;
+mod result_cache;
+pub mod senders;
+pub mod spending_limit;
+pub mod sponsor;
+#[cfg(feature = "profiling")]
+pub mod profile;
+mod staged;
+pub mod state_export;
+pub mod state_import;
+pub mod stats;
+#[cfg(test)]
+mod test_utils;
+pub mod treasury;
 mod vk;
 
+pub use error::{FailureReason, VerificationError};
+pub use prepared_vk::{InitialVerifyingKey, VerifyingKeyInput, VerifyingKeyJson};
+pub use zk_email_verifier_core::{merkle, packing, ProofInput};
+
+use binding::{check_hashed_binding, check_packed_binding, nfc, BindingOptions};
+use diagnostics::{BindingCheck, DryRunReport};
+use layout::{PublicInputLayout, SubjectTemplate};
+use network::NetworkProfile;
+use packing::{unpack_field_chunks_to_str, unpack_field_elems_to_bytes};
+
 /// ZK‑Email verifier contract (WASM) for `RecoverEmailCircuit`.
 ///
 /// This contract exposes view methods that verify Groth16 proofs and
 /// return a structured `VerificationResult` containing the verification
 /// outcome and the human‑readable fields anchored in the circuit.
 #[near(contract_state)]
-#[derive(Default)]
-pub struct ZkEmailVerifier;
+#[derive(PanicOnDefault)]
+pub struct ZkEmailVerifier {
+    /// Account allowed to rotate the verifying key.
+    owner_id: AccountId,
+    /// `PreparedVerifyingKey<Bn254>` serialized uncompressed with ark-serialize.
+    /// Kept behind a `LazyOption` so methods that don't verify never load it.
+    prepared_vk: LazyOption<Vec<u8>>,
+    /// Verifications started by `verify_staged` awaiting their pairing check.
+    staged_verifications: LookupMap<u64, staged::PendingVerification>,
+    next_staged_id: u64,
+    /// Attestations of staged verifications, keyed by their staged id.
+    attestations: IterableMap<u64, attestation::Attestation>,
+    /// Export hash of the latest attestation; zero before the first one.
+    attestation_chain_head: Vec<u8>,
+    /// Totals returned by `get_stats`.
+    stats: stats::Stats,
+    /// Set by the owner to stop `verify_staged`; see `health`.
+    paused: bool,
+    freshness_window_ms: Option<u64>,
+    circuit_breaker: Option<breaker::CircuitBreakerConfig>,
+    breaker_window: breaker::WindowCounts,
+    /// Verified proofs per account in the current circuit breaker window.
+    account_windows: LookupMap<String, breaker::WindowCounts>,
+    /// Lowercased, sorted; empty allows every sender domain.
+    allowed_sender_domains: Vec<String>,
+    /// `sender_block_hash` digests of blocked addresses and domains.
+    blocked_senders: IterableSet<Vec<u8>>,
+    /// Prepared keys of circuits registered besides `RecoverEmailCircuit`.
+    circuit_vks: IterableMap<String, Vec<u8>>,
+    /// Layout id of each circuit in `circuit_vks`.
+    circuit_layouts: IterableMap<String, String>,
+    /// Lowercased sender domain to the circuit its emails are verified with.
+    domain_circuits: IterableMap<String, String>,
+    /// Ring of recent verification records, keyed by sequence number; holds
+    /// `audit_start..audit_next`.
+    audit_log: LookupMap<u64, audit::VerificationRecord>,
+    audit_start: u64,
+    audit_next: u64,
+    audit_capacity: u32,
+    /// Verified records per account, keyed by `(account_id, index)`.
+    account_history: LookupMap<(String, u32), audit::VerificationRecord>,
+    account_history_len: IterableMap<String, u32>,
+    /// Emit `ZKEV_DEBUG:` log lines; see `debug`.
+    debug_logs: bool,
+    /// Registry contract `verify_staged` reads verifying keys from; see
+    /// `registry`.
+    vk_registry: Option<AccountId>,
+    /// Registry version of each cached circuit key.
+    registry_vk_versions: LookupMap<String, u64>,
+    /// Network the defaults were picked for at `new`.
+    network: NetworkProfile,
+    /// SHA-256 of each circuit's prepared key, pinned when the key is
+    /// stored. A stored key that no longer matches is never used.
+    vk_hashes: LookupMap<String, Vec<u8>>,
+    /// Prepared keys already checked and deserialized in this call.
+    #[borsh(skip)]
+    pvk_cache: RefCell<BTreeMap<String, PreparedVerifyingKey<Bn254>>>,
+    /// How long staged results stay cached; `None` turns caching off. See
+    /// `result_cache`.
+    result_cache_ttl_ms: Option<u64>,
+    /// At most `result_cache::MAX_CACHED_RESULTS` verified results.
+    result_cache: IterableMap<Vec<u8>, result_cache::CachedResult>,
+    /// Cache key of the cached verified result of each `request_id`.
+    cached_request_ids: LookupMap<String, Vec<u8>>,
+    /// Owner-measured verification cost per circuit; see `gas_estimate`.
+    gas_benchmarks: IterableMap<String, gas_estimate::GasBenchmark>,
+    /// Only accept `verify_staged` from the account or its relayer; see
+    /// `relayers`.
+    predecessor_binding: bool,
+    account_relayers: IterableMap<String, AccountId>,
+    /// Minimum time between two recoveries of an account; see `cooldown`.
+    recovery_cooldown_ms: Option<u64>,
+    /// Cap on recoveries across all accounts; see `rate_limit`.
+    recovery_rate_limit: Option<rate_limit::RecoveryRateLimit>,
+    recovery_window: breaker::WindowCounts,
+    /// Submitters the recovery cap doesn't apply to.
+    rate_limit_bypass: IterableSet<AccountId>,
+    /// Charged per `verify_staged` call; see `treasury`.
+    verification_fee: NearToken,
+    /// Fees collected and not yet withdrawn.
+    treasury_balance: NearToken,
+    /// Paid from the treasury to the submitter of each finalized recovery.
+    relayer_reward: Option<treasury::RelayerReward>,
+    /// Balances dApps draw their users' verification fees from.
+    sponsor_pools: LookupMap<AccountId, NearToken>,
+    /// Address recovery emails are sent to, shown by `get_email_template`.
+    recovery_mailbox: Option<String>,
+    /// Request ids issued by `generate_request_id`.
+    challenges: IterableMap<String, challenges::Challenge>,
+    /// Number of request ids issued so far, mixed into the next one.
+    challenge_nonce: u64,
+    /// Request ids issued for each account, oldest first.
+    account_challenges: LookupMap<String, Vec<String>>,
+    /// Permission of recovered keys for accounts that haven't chosen one.
+    default_key_permission: key_permission::KeyPermission,
+    /// Permission each account chose for its recovered keys.
+    account_key_permissions: IterableMap<String, key_permission::KeyPermission>,
+    /// `from_address_hash` commitment each account registered; see
+    /// `registration`.
+    registered_emails: IterableMap<String, Vec<u8>>,
+    pending_migrations: IterableMap<String, migration::PendingMigration>,
+    migration_timelock_ms: u64,
+    /// End of each account's latest lock; see `lock`.
+    account_locks: IterableMap<String, u64>,
+    lock_duration_ms: u64,
+    /// Contract that verified commands are forwarded to.
+    controller_contract: Option<AccountId>,
+    pending_spending_limits: IterableMap<String, spending_limit::PendingSpendingLimit>,
+    /// Guardian email commitments of each account; see `guardians`.
+    guardians: IterableMap<String, Vec<Vec<u8>>>,
+    pending_guardian_removals: IterableMap<String, Vec<guardians::PendingGuardianRemoval>>,
+    guardian_removal_delay_ms: u64,
+    /// How each account weighs guardian approvals; see `guardian_recovery`.
+    guardian_weights: IterableMap<String, guardian_recovery::GuardianWeights>,
+    /// Approvals by `(account_id, request_id)`.
+    guardian_recoveries: IterableMap<(String, String), guardian_recovery::GuardianRecovery>,
+    /// Scheduled and past guardian sets of each account, oldest first; see
+    /// `guardian_epochs`.
+    guardian_sets: IterableMap<String, Vec<guardian_epochs::GuardianSet>>,
+    guardian_epoch_ms: u64,
+    /// Per-account overrides; see `recovery_policy`.
+    recovery_policies: IterableMap<String, recovery_policy::RecoveryPolicy>,
+    pending_email_changes: IterableMap<String, email_change::PendingEmailChange>,
+    email_change_window_ms: u64,
+    /// Suffix every account must end in; see `namespace`.
+    account_namespace: Option<String>,
+    /// Outcomes by `(submitter, idempotency_key)`; see `idempotency`.
+    idempotent_results: IterableMap<(AccountId, String), idempotency::IdempotentEntry>,
+    /// Consumed recovery nullifiers, in order; see `nullifiers`.
+    nullifiers: IterableSet<Vec<u8>>,
+    /// Frontier of the Merkle tree over `nullifiers`; see `merkle`.
+    nullifier_branch: LazyOption<Vec<[u8; 32]>>,
+    /// Leaves of the result accumulator by index; see `result_accumulator`.
+    result_leaves: LookupMap<u64, [u8; 32]>,
+    result_count: u64,
+    result_branch: LazyOption<Vec<[u8; 32]>>,
+    /// False only between `new_for_import` and `activate`: imports are
+    /// accepted and recoveries and commands refused until then.
+    activated: bool,
+    /// When each deprecated circuit in `circuit_vks` stops being accepted.
+    circuit_deprecations: IterableMap<String, u64>,
+    /// Index in `result_cache` the next `prune_result_cache` starts at.
+    result_cache_prune_cursor: u32,
+    /// Index in `idempotent_results` the next `prune_idempotent_results`
+    /// starts at.
+    idempotent_prune_cursor: u32,
+}
+
+#[near]
+#[derive(BorshStorageKey)]
+enum StorageKey {
+    PreparedVk,
+    StagedVerifications,
+    Attestations,
+    AccountWindows,
+    BlockedSenders,
+    CircuitVks,
+    DomainCircuits,
+    AuditLog,
+    AccountHistory,
+    AccountHistoryLen,
+    RegistryVkVersions,
+    CircuitLayouts,
+    VkHashes,
+    ResultCache,
+    CachedRequestIds,
+    GasBenchmarks,
+    AccountRelayers,
+    RateLimitBypass,
+    SponsorPools,
+    Challenges,
+    AccountChallenges,
+    AccountKeyPermissions,
+    RegisteredEmails,
+    PendingMigrations,
+    AccountLocks,
+    PendingSpendingLimits,
+    Guardians,
+    PendingGuardianRemovals,
+    GuardianWeights,
+    GuardianRecoveries,
+    GuardianSets,
+    RecoveryPolicies,
+    PendingEmailChanges,
+    IdempotentResults,
+    Nullifiers,
+    NullifierBranch,
+    ResultLeaves,
+    ResultBranch,
+    CircuitDeprecations,
+}
 
 #[near_sdk::near(serializers = [json, borsh])]
 #[derive(Clone)]
 pub struct VerificationResult {
     pub verified: bool,
+    /// `request_id` from the email subject.
+    pub request_id: String,
     pub account_id: String,
     pub new_public_key: String,
+    /// `new_public_key` parsed as a typed NEAR public key, ready to be used
+    /// in an `AddKey` action. `None` whenever `verified` is false.
+    pub new_public_key_parsed: Option<PublicKey>,
+    /// Further keys anchored by circuits that add more than one (e.g. a
+    /// passkey plus a backup key), to be added in the same batch as
+    /// `new_public_key_parsed`. A proof is rejected if any of them doesn't
+    /// parse, so either all keys are present or none. Empty for
+    /// `RecoverEmailCircuit`; one for `RecoverWithBackupKeyCircuit`.
+    pub additional_public_keys: Vec<PublicKey>,
+    /// Keys the recovery removes from the account, e.g. a stolen device's,
+    /// to be deleted in the same batch as the keys above. Anchored as
+    /// `delete_public_key_<n>` with their curve prefix, or as bare ed25519
+    /// base58. Empty for `RecoverEmailCircuit`; one for
+    /// `RecoverAndRevokeKeyCircuit`.
+    pub deleted_public_keys: Vec<PublicKey>,
+    /// Whether to add the new keys as full-access or function-call keys; see
+    /// `key_permission`. `None` when the proof didn't verify.
+    pub key_permission: Option<key_permission::KeyPermission>,
     /// SHA-256 hash of the canonical sender email, salted by account id:
     /// `sha256("<canonical_from>|<account_id_lower>")`.
     /// Returned as raw bytes so the caller contract can compare directly
     /// against `get_recovery_emails()` output (which is `Vec<Vec<u8>>`).
     pub from_address_hash: Vec<u8>,
     pub email_timestamp_ms: Option<u64>,
+    /// The `Date:` header as an RFC 3339 string in the sender's original
+    /// offset, e.g. `2025-12-09T17:13:23+09:00`.
+    pub email_timestamp_rfc3339: Option<String>,
+    /// Why `verified` is false, as a stable code such as `PROOF_PARSE`,
+    /// `BINDING_ACCOUNT` or `PAIRING` (see `FailureReason`). `None` when
+    /// verified.
+    pub failure_reason: Option<String>,
+    /// Block height and time of the transaction that verified the proof.
+    /// Only set by the methods that record their result (`verify_staged`);
+    /// `None` from views, which don't run in any particular block.
+    pub verified_at_block: Option<u64>,
+    pub verified_at_ms: Option<u64>,
+}
+
+impl VerificationResult {
+    fn rejected(reason: FailureReason) -> Self {
+        Self {
+            verified: false,
+            request_id: String::new(),
+            account_id: String::new(),
+            new_public_key: String::new(),
+            new_public_key_parsed: None,
+            additional_public_keys: Vec::new(),
+            deleted_public_keys: Vec::new(),
+            key_permission: None,
+            from_address_hash: Vec::new(),
+            email_timestamp_ms: None,
+            email_timestamp_rfc3339: None,
+            failure_reason: Some(reason.as_str().to_string()),
+            verified_at_block: None,
+            verified_at_ms: None,
+        }
+    }
+
+    fn rejected_with(err: VerificationError) -> Self {
+        log_verification_error(err);
+        Self::rejected(err.into())
+    }
+
+    /// Record the current block as the one that verified the result.
+    fn stamped(mut self) -> Self {
+        self.verified_at_block = Some(env::block_height());
+        self.verified_at_ms = Some(env::block_timestamp_ms());
+        self
+    }
+
+    fn with_failure(mut self, reason: FailureReason) -> Self {
+        self.verified = false;
+        self.failure_reason = Some(reason.as_str().to_string());
+        self
+    }
 }
 
 #[near]
 impl ZkEmailVerifier {
+    /// Initialize with the verifying key compiled into `vk.rs`. The caller
+    /// becomes the owner. `profile` (default `testnet`) picks the initial
+    /// policies; see `network`.
+    #[init]
+    pub fn new(profile: Option<NetworkProfile>) -> Self {
+        Self::with_verifying_key(
+            env::predecessor_account_id(),
+            &vk::verifying_key(),
+            profile.unwrap_or_default(),
+        )
+    }
+
+    /// Same as `new`, but starts from `vk` instead of the key compiled into
+    /// the WASM, so a freshly built circuit can be deployed without
+    /// rebuilding the contract. Panics if the key is malformed or doesn't
+    /// match the `RecoverEmailCircuit` public inputs.
     #[init]
-    pub fn new() -> Self {
-        // In the future we may precompute and cache a PreparedVerifyingKey here.
-        Self
+    pub fn new_with_vk(vk: InitialVerifyingKey, profile: Option<NetworkProfile>) -> Self {
+        let vk = vk.parse().unwrap_or_else(|| env::panic_str("invalid verifying key"));
+        check_ic_len(vk.gamma_abc_g1.len(), &RECOVER_EMAIL_LAYOUT);
+        Self::with_verifying_key(env::predecessor_account_id(), &vk, profile.unwrap_or_default())
     }
 
-    /// Verify a Groth16 proof for RecoverEmailCircuit.
+    /// Re-initialize a contract deployed before it kept any state. Only needed
+    /// once when upgrading from the stateless version; the contract account
+    /// becomes the owner.
     ///
-    /// This is a scaffold: it parses the proof and public inputs and
-    /// then calls `ark_groth16::verify_proof`. The actual verification
-    /// key is still provided by the stub in `vk::verifying_key()`.
-    pub fn verify(&self, proof: ProofInput, public_inputs: Vec<String>) -> VerificationResult {
-        // NOTE: This will panic until vk::verifying_key() is implemented.
-        let vk = vk::verifying_key();
-        let pvk = prepare_verifying_key(&vk);
-
-        let proof_ark = match parse_proof(proof) {
-            Ok(p) => p,
-            Err(_) => {
-                return VerificationResult {
-                    verified: false,
-                    account_id: String::new(),
-                    new_public_key: String::new(),
-                    from_address_hash: Vec::new(),
-                    email_timestamp_ms: None,
-                };
-            }
-        };
+    /// Panics once the contract has state, so it can't be used to reset an
+    /// existing deployment.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        if env::state_exists() {
+            env::panic_str("contract state already exists");
+        }
+        Self::with_verifying_key(
+            env::current_account_id(),
+            &vk::verifying_key(),
+            NetworkProfile::Testnet,
+        )
+    }
 
-        let inputs_ark = match parse_public_inputs(public_inputs) {
-            Ok(v) => v,
-            Err(_) => {
-                return VerificationResult {
-                    verified: false,
-                    account_id: String::new(),
-                    new_public_key: String::new(),
-                    from_address_hash: Vec::new(),
-                    email_timestamp_ms: None,
-                };
-            }
-        };
+    /// Replace the verifying key with one exported by snarkjs
+    /// (`verification_key.json`). Only callable by the owner.
+    ///
+    /// The key must have one `IC` point per public input of
+    /// `RecoverEmailCircuit`, plus the constant term.
+    pub fn set_verifying_key(&mut self, vk: VerifyingKeyInput) {
+        self.assert_owner();
+        check_ic_len(vk.ic.len(), &RECOVER_EMAIL_LAYOUT);
+        let vk = prepared_vk::parse_verifying_key(&vk)
+            .unwrap_or_else(|_| env::panic_str("invalid verifying key"));
+        self.store_verifying_key(&vk);
+    }
+
+    /// Same as `set_verifying_key`, but takes the key as a compressed
+    /// ark-serialize blob passed as Borsh `Vec<u8>` arguments, as written by
+    /// `generate-vk --format borsh`. Much smaller than the JSON form and
+    /// cheaper to decode.
+    pub fn set_verifying_key_bytes(&mut self, #[serializer(borsh)] vk: Vec<u8>) {
+        self.assert_owner();
+        let vk = prepared_vk::deserialize_verifying_key(&vk)
+            .unwrap_or_else(|| env::panic_str("invalid verifying key"));
+        check_ic_len(vk.gamma_abc_g1.len(), &RECOVER_EMAIL_LAYOUT);
+        self.store_verifying_key(&vk);
+    }
 
-        let verified = Groth16::<Bn254>::verify_proof(&pvk, &proof_ark, &inputs_ark)
-            .unwrap_or(false);
-
-        // If the proof didn't verify, return a simple negative result.
-        if !verified {
-            return VerificationResult {
-                verified: false,
-                account_id: String::new(),
-                new_public_key: String::new(),
-                from_address_hash: Vec::new(),
-                email_timestamp_ms: None,
+    pub fn get_owner_id(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    /// The active verifying key in snarkjs `verification_key.json` format, so
+    /// anyone can diff it against the published ceremony output.
+    pub fn get_verifying_key_json(&self) -> VerifyingKeyJson {
+        prepared_vk::verifying_key_json(&self.prepared_verifying_key().vk)
+    }
+
+    /// Verify a Groth16 proof for RecoverEmailCircuit, or for a circuit
+    /// registered with its own public input layout.
+    ///
+    /// Parses the proof and public inputs and checks them against the
+    /// stored prepared verifying key of the circuit using `layout_id` (see
+    /// `get_supported_circuits`). Without `layout_id` the layout is inferred
+    /// from the number of public inputs. Rejected with `UNKNOWN_LAYOUT` if no
+    /// circuit uses the given layout, or the inputs fit several. Only
+    /// recovery circuits are considered: command proofs (e.g. of
+    /// `MigrateAccountCircuit`) go through their own methods.
+    pub fn verify(
+        &self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        layout_id: Option<String>,
+    ) -> VerificationResult {
+        let circuit_id =
+            match self.circuit_for_layout(CircuitKind::Recovery, layout_id.as_deref(), public_inputs.len()) {
+                Ok(circuit_id) => circuit_id,
+                Err(reason) => return VerificationResult::rejected(reason),
             };
+        let layout = self.layout_of(&circuit_id);
+        let pvk = self.prepared_verifying_key_for(&circuit_id);
+
+        let (proof_ark, inputs_ark) = match self.parse_logged(proof, &public_inputs, layout) {
+            Ok(parsed) => parsed,
+            Err(reason) => return VerificationResult::rejected(reason),
+        };
+
+        if !verify_groth16(&pvk, &proof_ark, &inputs_ark) {
+            return VerificationResult::rejected(FailureReason::Pairing);
         }
 
-        // Attempt to decode the packed substrings from the public inputs.
-        let mut account_id = String::new();
-        let mut new_public_key = String::new();
-        let mut from_address_hash = Vec::new();
-        let mut email_timestamp_ms = None;
-
-        if inputs_ark.len() >= EXPECTED_PUBLIC_LEN {
-            let account_chunks = &inputs_ark[ACCOUNT_OFFSET..ACCOUNT_OFFSET + PACKED_SUBSTRING_FIELD_LEN];
-            let new_pk_chunks =
-                &inputs_ark[NEW_PK_OFFSET..NEW_PK_OFFSET + PACKED_SUBSTRING_FIELD_LEN];
-            let from_hash_fields =
-                &inputs_ark[FROM_ADDRESS_HASH_OFFSET..FROM_ADDRESS_HASH_OFFSET + FROM_ADDRESS_HASH_LEN];
-            let ts_chunks =
-                &inputs_ark[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + PACKED_SUBSTRING_FIELD_LEN];
-
-            if let Ok(s) = unpack_field_chunks_to_str(account_chunks) {
-                account_id = s;
-            }
-            if let Ok(s) = unpack_field_chunks_to_str(new_pk_chunks) {
-                new_public_key = s;
-            }
-            if let Ok(h) = unpack_field_elems_to_bytes(from_hash_fields) {
-                from_address_hash = h;
-            }
-            if let Ok(ts_str) = unpack_field_chunks_to_str(ts_chunks) {
-                email_timestamp_ms = parse_email_timestamp_to_unix_ms(&ts_str);
-            }
+        self.admit_recovery(decode_verified_inputs(layout, &inputs_ark))
+    }
+
+    /// `verify` with the proof and public inputs in one base64 payload, in
+    /// the versioned binary envelope of `zk_email_verifier_core::blob`. About
+    /// half the size of the JSON arguments and cheaper to parse.
+    pub fn verify_blob(&self, blob: String) -> VerificationResult {
+        let decoded = STANDARD
+            .decode(&blob)
+            .ok()
+            .and_then(|bytes| zk_email_verifier_core::blob::decode_blob(&bytes).ok());
+        let Some((proof_ark, inputs_ark)) = decoded else {
+            return VerificationResult::rejected(FailureReason::BlobDecode);
+        };
+        if inputs_ark.len() != EXPECTED_PUBLIC_LEN {
+            return VerificationResult::rejected(FailureReason::InputLen);
         }
 
-        VerificationResult {
-            verified: true,
-            account_id,
-            new_public_key,
-            from_address_hash,
-            email_timestamp_ms,
+        if !verify_groth16(&self.prepared_verifying_key(), &proof_ark, &inputs_ark) {
+            return VerificationResult::rejected(FailureReason::Pairing);
         }
+        self.admit_recovery(decode_verified_inputs(&RECOVER_EMAIL_LAYOUT, &inputs_ark))
+    }
+
+    /// Verify several proofs with one batched pairing check (a single
+    /// multi-Miller loop over random linear combinations of the proofs),
+    /// which costs far less gas per proof than calling `verify` for each.
+    ///
+    /// Results are returned in input order. Entries that fail to parse are
+    /// rejected on their own; if the batch check fails, the remaining proofs
+    /// are re-verified one by one so callers still learn which are valid.
+    pub fn verify_batch(&self, proofs: Vec<BatchProofInput>) -> Vec<VerificationResult> {
+        let pvk = self.prepared_verifying_key();
+
+        let parsed: Vec<Result<batch::BatchItem, FailureReason>> = proofs
+            .into_iter()
+            .map(|item| parse_proof_and_inputs(item.proof, &item.public_inputs, &RECOVER_EMAIL_LAYOUT))
+            .collect();
+        let items: Vec<batch::BatchItem> = parsed.iter().flatten().cloned().collect();
+        let batch_ok = batch::verify_batch(&pvk, &items);
+
+        parsed
+            .iter()
+            .map(|item| match item {
+                Ok((proof, inputs)) if batch_ok || verify_groth16(&pvk, proof, inputs) => {
+                    self.admit_recovery(decode_verified_inputs(&RECOVER_EMAIL_LAYOUT, inputs))
+                }
+                Ok(_) => VerificationResult::rejected(FailureReason::Pairing),
+                Err(reason) => VerificationResult::rejected(*reason),
+            })
+            .collect()
     }
 
     /// Verify a Groth16 proof and additionally bind the public signals corresponding to:
@@ -139,353 +537,567 @@ impl ZkEmailVerifier {
     /// The circuit packs these three substrings from the DKIM‑verified header using
     /// PackByteSubArray (255 bytes / 31 bytes per field = 9 field elements each),
     /// appended after the public `pubkey` and `signature` inputs.
+    ///
+    /// `request_id` is compared against the packed request id at the start of
+    /// the public inputs, so a proof can't be replayed under another request.
+    ///
+    /// `new_public_key` may be bare base58 or carry an `ed25519:` prefix;
+    /// only the base58 data is packed and compared, since the subject
+    /// template already fixes the prefix. Other curves are rejected.
+    ///
+    /// `options` relaxes individual bindings (e.g. case-insensitive
+    /// `account_id`); omitting it keeps byte-exact matching.
+    ///
+    /// `from_header` (a raw `From:` header or bare address) is checked against
+    /// the committed sender hash and the sender-domain allowlist; it is
+    /// required once the allowlist is set (see `senders`). Its domain also
+    /// picks the circuit to verify with (see `circuits`).
+    #[allow(clippy::too_many_arguments)]
     pub fn verify_with_binding(
         &self,
         proof: ProofInput,
         public_inputs: Vec<String>,
+        request_id: String,
         account_id: String,
         new_public_key: String,
         timestamp: String,
+        options: Option<BindingOptions>,
+        from_header: Option<String>,
     ) -> VerificationResult {
+        let options = options.unwrap_or_default();
+        let account_id = options.normalize("account_id", &account_id).into_owned();
+        let email_timestamp = parse_email_timestamp(&timestamp);
         let mut result = VerificationResult {
             verified: false,
+            request_id: String::new(),
             account_id: account_id.clone(),
             new_public_key: new_public_key.clone(),
+            new_public_key_parsed: None,
+            additional_public_keys: Vec::new(),
+            deleted_public_keys: Vec::new(),
+            key_permission: None,
             from_address_hash: Vec::new(),
-            email_timestamp_ms: parse_email_timestamp_to_unix_ms(&timestamp),
+            email_timestamp_ms: email_timestamp.as_ref().map(|t| t.unix_ms),
+            email_timestamp_rfc3339: email_timestamp.map(|t| t.rfc3339),
+            failure_reason: None,
+            verified_at_block: None,
+            verified_at_ms: None,
         };
 
-        let vk = vk::verifying_key();
-        let pvk = prepare_verifying_key(&vk);
+        if let Err(err) = validate_account_id(&account_id) {
+            log_verification_error(err);
+            return result.with_failure(err.into());
+        }
+        let (new_public_key, new_public_key_parsed) =
+            match normalize_bound_public_key(&new_public_key) {
+                Ok((bare, pk)) => (bare.to_string(), pk),
+                Err(err) => {
+                    log_verification_error(err);
+                    return result.with_failure(err.into());
+                }
+            };
+        result.new_public_key = new_public_key.clone();
 
-        let proof_ark = match parse_proof(proof) {
-            Ok(p) => p,
-            Err(_) => return result,
-        };
+        let circuit_id = self.circuit_for_sender(from_header.as_deref());
+        let layout = self.layout_of(&circuit_id);
+        let pvk = self.prepared_verifying_key_for(&circuit_id);
 
-        let inputs_ark = match parse_public_inputs(public_inputs.clone()) {
-            Ok(v) => v,
-            Err(_) => return result,
+        let (proof_ark, inputs_ark) = match self.parse_logged(proof, &public_inputs, layout) {
+            Ok(parsed) => parsed,
+            Err(reason) => return result.with_failure(reason),
         };
 
-        if inputs_ark.len() != EXPECTED_PUBLIC_LEN {
-            return result;
-        }
-
+        result.request_id = layout
+            .get("request_id", &inputs_ark)
+            .and_then(|chunks| unpack_field_chunks_to_str(chunks).ok())
+            .unwrap_or_default();
         // Capture from_address_hash bytes from the public inputs (if present and well-formed).
-        let from_hash_fields =
-            &inputs_ark[FROM_ADDRESS_HASH_OFFSET..FROM_ADDRESS_HASH_OFFSET + FROM_ADDRESS_HASH_LEN];
-        if let Ok(h) = unpack_field_elems_to_bytes(from_hash_fields) {
+        if let Some(Ok(h)) = layout.get("from_address_hash", &inputs_ark).map(unpack_field_elems_to_bytes) {
             result.from_address_hash = h;
         }
 
-        let account_chunks = match pack_str_to_field_chunks(&account_id) {
-            Ok(c) => c,
-            Err(_) => return result,
-        };
-        let new_pk_chunks = match pack_str_to_field_chunks(&new_public_key) {
-            Ok(c) => c,
-            Err(_) => return result,
-        };
-        let timestamp_chunks = match pack_str_to_field_chunks(&timestamp) {
-            Ok(c) => c,
-            Err(_) => return result,
-        };
-
-        // Sanity: all packed substrings must have the expected length.
-        if account_chunks.len() != PACKED_SUBSTRING_FIELD_LEN
-            || new_pk_chunks.len() != PACKED_SUBSTRING_FIELD_LEN
-            || timestamp_chunks.len() != PACKED_SUBSTRING_FIELD_LEN
-        {
-            return result;
-        }
-
-        // Check account_id binding.
-        for i in 0..PACKED_SUBSTRING_FIELD_LEN {
-            if inputs_ark[ACCOUNT_OFFSET + i] != account_chunks[i] {
-                return result;
+        let bindings = [
+            ("request_id", &request_id, FailureReason::BindingRequestId),
+            ("account_id", &account_id, FailureReason::BindingAccount),
+            ("new_public_key", &new_public_key, FailureReason::BindingNewPublicKey),
+            ("timestamp", &timestamp, FailureReason::BindingTimestamp),
+        ];
+        for (field, value, reason) in bindings {
+            let actual = layout.get(field, &inputs_ark).unwrap_or_default();
+            let check = check_packed_binding(field, actual, value, &options);
+            if !check.matched {
+                self.debug_log(&debug::DebugLog::BindingMismatch(check));
+                return result.with_failure(reason);
             }
         }
 
-        // Check new_public_key binding.
-        for i in 0..PACKED_SUBSTRING_FIELD_LEN {
-            if inputs_ark[NEW_PK_OFFSET + i] != new_pk_chunks[i] {
-                return result;
-            }
-        }
-
-        // Check timestamp binding.
-        for i in 0..PACKED_SUBSTRING_FIELD_LEN {
-            if inputs_ark[TIMESTAMP_OFFSET + i] != timestamp_chunks[i] {
-                return result;
-            }
+        if !verify_groth16(&pvk, &proof_ark, &inputs_ark) {
+            return result.with_failure(FailureReason::Pairing);
         }
+        result.verified = true;
+        result.new_public_key_parsed = Some(new_public_key_parsed);
 
-        result.verified = Groth16::<Bn254>::verify_proof(
-            &pvk,
-            &proof_ark,
-            &inputs_ark
-        ).unwrap_or(false);
-
-        result
+        self.check_sender(self.admit_recovery(result), from_header.as_deref())
     }
-}
 
-/// Input format for a Groth16 proof, roughly mirroring snarkjs's `proof.json`.
-#[derive(Deserialize, Serialize)]
-#[serde(crate = "near_sdk::serde")]
-#[derive(JsonSchema)]
-pub struct ProofInput {
-    /// pi_a: [Ax, Ay, Az]; we use Ax, Ay and assume Az = 1.
-    pub pi_a: [String; 3],
-    /// pi_b: [[Bx1, Bx0], [By1, By0], [Bz1, Bz0]]; we use the first two pairs.
-    pub pi_b: [[String; 2]; 3],
-    /// pi_c: [Cx, Cy, Cz]; we use Cx, Cy and assume Cz = 1.
-    pub pi_c: [String; 3],
-}
-
-fn parse_fq(s: &str) -> Result<Fq, ()> {
-    Fq::from_str(s).map_err(|_| ())
-}
-
-fn parse_fr(s: &str) -> Result<Fr, ()> {
-    Fr::from_str(s).map_err(|_| ())
-}
-
-fn parse_fq2(c0: &str, c1: &str) -> Result<Fq2, ()> {
-    let a0 = parse_fq(c0)?;
-    let a1 = parse_fq(c1)?;
-    Ok(Fq2::new(a0, a1))
-}
+    /// Same checks as `verify_with_binding`, but the caller passes SHA-256
+    /// digests of the expected values instead of the plaintext, so they never
+    /// appear in call arguments or RPC logs.
+    ///
+    /// Each digest is compared against the hash of the string packed in the
+    /// corresponding public inputs, byte for byte: the `account_id`, the bare
+    /// base58 `new_public_key` (without `ed25519:`) and the raw `Date:` header
+    /// value.
+    pub fn verify_with_hashed_binding(
+        &self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        account_id_hash: Vec<u8>,
+        new_public_key_hash: Vec<u8>,
+        timestamp_hash: Vec<u8>,
+    ) -> VerificationResult {
+        let pvk = self.prepared_verifying_key();
 
-fn parse_proof(input: ProofInput) -> Result<Proof<Bn254>, ()> {
-    // G1 A
-    let ax = parse_fq(&input.pi_a[0])?;
-    let ay = parse_fq(&input.pi_a[1])?;
-    let a = G1Affine::new_unchecked(ax, ay);
-
-    // G2 B
-    // snarkjs bn128 convention: pi_b[0] and pi_b[1] are Fq2 coords.
-    let bx = parse_fq2(&input.pi_b[0][0], &input.pi_b[0][1])?;
-    let by = parse_fq2(&input.pi_b[1][0], &input.pi_b[1][1])?;
-    let b = G2Affine::new_unchecked(bx, by);
-
-    // G1 C
-    let cx = parse_fq(&input.pi_c[0])?;
-    let cy = parse_fq(&input.pi_c[1])?;
-    let c = G1Affine::new_unchecked(cx, cy);
-
-    Ok(Proof::<Bn254> { a, b, c })
-}
+        let (proof_ark, inputs_ark) = match self.parse_logged(proof, &public_inputs, &RECOVER_EMAIL_LAYOUT) {
+            Ok(parsed) => parsed,
+            Err(reason) => return VerificationResult::rejected(reason),
+        };
 
-fn parse_public_inputs(inputs: Vec<String>) -> Result<Vec<Fr>, ()> {
-    inputs.into_iter().map(|s| parse_fr(&s)).collect()
-}
+        let bindings = [
+            ("account_id", &account_id_hash, FailureReason::BindingAccount),
+            ("new_public_key", &new_public_key_hash, FailureReason::BindingNewPublicKey),
+            ("timestamp", &timestamp_hash, FailureReason::BindingTimestamp),
+        ];
+        for (field, digest, reason) in bindings {
+            let actual = RECOVER_EMAIL_LAYOUT.get(field, &inputs_ark).unwrap_or_default();
+            if !check_hashed_binding(actual, digest) {
+                return VerificationResult::rejected(reason);
+            }
+        }
 
-/// Number of bytes packed into a single field element in PackBytes / PackByteSubArray.
-/// Must match MAX_BYTES_IN_FIELD() for BN254 in @zk-email/circuits.
-const PACKED_BYTES_PER_FIELD: usize = 31;
-
-/// Maximum number of bytes we pack for a subject or from-email substring.
-/// Must match the `max_*_len` constants used in `RecoverEmailCircuit.circom` (255).
-const MAX_PACKED_SUBSTRING_LEN: usize = 255;
-
-/// Number of field elements used per packed substring (account_id, new_public_key, timestamp).
-/// 255 bytes / 31 bytes per field = 9.
-const PACKED_SUBSTRING_FIELD_LEN: usize = 9;
-
-/// Number of field elements used for `from_address_hash` (32 SHA-256 bytes).
-const FROM_ADDRESS_HASH_LEN: usize = 32;
-
-/// Layout constants for `RecoverEmailCircuit` public inputs:
-/// [request_id_packed[9], account_id_packed[9], public_key_packed[9], from_address_hash[32], timestamp_packed[9], pubkey[17], signature[17]]
-const PUBKEY_LEN: usize = 17;
-const REQUEST_ID_OFFSET: usize = 0;
-const ACCOUNT_OFFSET: usize = REQUEST_ID_OFFSET + PACKED_SUBSTRING_FIELD_LEN;
-const NEW_PK_OFFSET: usize = ACCOUNT_OFFSET + PACKED_SUBSTRING_FIELD_LEN;
-const FROM_ADDRESS_HASH_OFFSET: usize = NEW_PK_OFFSET + PACKED_SUBSTRING_FIELD_LEN;
-const TIMESTAMP_OFFSET: usize = FROM_ADDRESS_HASH_OFFSET + FROM_ADDRESS_HASH_LEN;
-const EXPECTED_PUBLIC_LEN: usize =
-    PACKED_SUBSTRING_FIELD_LEN * 4 + FROM_ADDRESS_HASH_LEN + PUBKEY_LEN * 2;
-
-fn unpack_field_elems_to_bytes(fields: &[Fr]) -> Result<Vec<u8>, ()> {
-    let mut out = Vec::with_capacity(fields.len());
-    for fr in fields {
-        let bigint = fr.into_bigint();
-        let bytes = bigint.to_bytes_le();
-        if bytes.iter().skip(1).any(|b| *b != 0) {
-            return Err(());
+        if !verify_groth16(&pvk, &proof_ark, &inputs_ark) {
+            return VerificationResult::rejected(FailureReason::Pairing);
         }
-        out.push(*bytes.first().unwrap_or(&0u8));
+        self.admit_recovery(decode_verified_inputs(&RECOVER_EMAIL_LAYOUT, &inputs_ark))
     }
-    Ok(out)
-}
 
-fn pack_str_to_field_chunks(s: &str) -> Result<Vec<Fr>, ()> {
-    let bytes = s.as_bytes();
-    if bytes.len() > MAX_PACKED_SUBSTRING_LEN {
-        return Err(());
-    }
+    /// Run every check performed by `verify_with_binding` without stopping at
+    /// the first failure and report the outcome of each one.
+    ///
+    /// `from_header` (a raw `From:` header or bare address) and `request_id`
+    /// are optional: when given, the sender hash and the packed request id are
+    /// compared as well. As in `verify_with_binding`, `from_header` also picks
+    /// the circuit mapped to its domain. `verified` mirrors what
+    /// `verify_with_binding` would return given the same arguments, including
+    /// its account and sender policy checks; without a `request_id` the
+    /// request id binding is simply not checked.
+    #[allow(clippy::too_many_arguments)]
+    pub fn dry_run_verify(
+        &self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        account_id: String,
+        new_public_key: String,
+        timestamp: String,
+        from_header: Option<String>,
+        request_id: Option<String>,
+        options: Option<BindingOptions>,
+    ) -> DryRunReport {
+        let options = options.unwrap_or_default();
+        let account_id = options.normalize("account_id", &account_id).into_owned();
+        let circuit_id = self.circuit_for_sender(from_header.as_deref());
+        let layout = self.layout_of(&circuit_id);
+        let proof_ark = parse_proof(proof).ok();
+        let inputs_ark = parse_public_inputs(&public_inputs).ok();
+
+        let mut report = DryRunReport {
+            proof_parsed: proof_ark.is_some(),
+            public_inputs_parsed: inputs_ark.is_some(),
+            public_input_len: public_inputs.len() as u32,
+            expected_public_input_len: layout.len as u32,
+            input_len_ok: public_inputs.len() == layout.len,
+            bindings: Vec::new(),
+            pairing_ok: None,
+            policy_failure: None,
+            verified: false,
+        };
 
-    let mut chunks = Vec::with_capacity(PACKED_SUBSTRING_FIELD_LEN);
-    let base = Fr::from(256u64);
+        let account_valid = validate_account_id(&account_id);
+        let bound_pk = normalize_bound_public_key(&new_public_key);
 
-    for i in 0..PACKED_SUBSTRING_FIELD_LEN {
-        let mut acc = Fr::from(0u64);
-        let mut pow = Fr::from(1u64);
+        if let Some(inputs) = inputs_ark.as_deref().filter(|_| report.input_len_ok) {
+            let packed_check = |field: &str, value: &str| {
+                let actual = layout.get(field, inputs).unwrap_or_default();
+                check_packed_binding(field, actual, value, &options)
+            };
 
-        for j in 0..PACKED_BYTES_PER_FIELD {
-            let idx = i * PACKED_BYTES_PER_FIELD + j;
-            if idx >= bytes.len() {
-                break;
+            report.bindings.push(match account_valid {
+                Ok(_) => packed_check("account_id", &account_id),
+                Err(err) => BindingCheck::failed("account_id", err.as_str()),
+            });
+            report.bindings.push(match bound_pk {
+                Ok((bare, _)) => packed_check("new_public_key", bare),
+                Err(err) => BindingCheck::failed("new_public_key", err.as_str()),
+            });
+            if let Some(from_header) = &from_header {
+                let actual = layout.get("from_address_hash", inputs).unwrap_or_default();
+                report.bindings.push(match email::from_address_hash(&nfc(from_header), &account_id) {
+                    Some(hash) => {
+                        let expected: Vec<Fr> = hash.into_iter().map(Fr::from).collect();
+                        BindingCheck::compare("from_address_hash", actual, &expected)
+                    }
+                    None => BindingCheck::failed("from_address_hash", "InvalidFromHeader"),
+                });
+            }
+            report.bindings.push(packed_check("timestamp", &timestamp));
+            if let Some(request_id) = &request_id {
+                report.bindings.push(packed_check("request_id", request_id));
             }
-            let b = Fr::from(bytes[idx] as u64);
-            acc += b * pow;
-            pow *= base;
         }
 
-        chunks.push(acc);
-    }
+        if let (Some(proof_ark), Some(inputs)) = (&proof_ark, &inputs_ark) {
+            let pvk = self.prepared_verifying_key_for(&circuit_id);
+            report.pairing_ok =
+                Some(verify_groth16(&pvk, proof_ark, inputs));
+        }
 
-    Ok(chunks)
-}
+        let required_bindings_ok = report
+            .bindings
+            .iter()
+            .filter(|b| {
+                matches!(b.field.as_str(), "request_id" | "account_id" | "new_public_key" | "timestamp")
+            })
+            .all(|b| b.matched);
+        let proof_ok = report.input_len_ok
+            && !report.bindings.is_empty()
+            && required_bindings_ok
+            && report.pairing_ok == Some(true);
+
+        if let (true, Some(inputs)) = (proof_ok, &inputs_ark) {
+            // The same policy checks `verify_with_binding` ends with, on the
+            // account it would report.
+            let mut result = decode_verified_inputs(layout, inputs);
+            result.account_id = account_id;
+            let result = self.check_sender(self.admit_recovery(result), from_header.as_deref());
+            report.policy_failure = result.failure_reason;
+            report.verified = result.verified;
+        }
 
-fn unpack_field_chunks_to_str(chunks: &[Fr]) -> Result<String, ()> {
-    let mut bytes = Vec::with_capacity(chunks.len() * PACKED_BYTES_PER_FIELD);
+        report
+    }
 
-    for fr in chunks {
-        let bigint = fr.into_bigint();
-        let mut limb_bytes = bigint.to_bytes_le();
-        if limb_bytes.len() < PACKED_BYTES_PER_FIELD {
-            limb_bytes.resize(PACKED_BYTES_PER_FIELD, 0);
-        }
-        bytes.extend_from_slice(&limb_bytes[..PACKED_BYTES_PER_FIELD]);
+    /// Describe the public signal layout (`public.json` ordering) expected for
+    /// `circuit_id`, or `None` if the circuit is unknown. Provers and frontends
+    /// can use this to sanity-check their inputs before submitting.
+    pub fn get_public_input_layout(&self, circuit_id: String) -> Option<PublicInputLayout> {
+        layout::public_input_layout(&circuit_id)
     }
 
-    // Trim trailing zero padding introduced during packing.
-    while matches!(bytes.last(), Some(0)) {
-        bytes.pop();
+    /// The subject line the user must send for `circuit_id`, with `{field}`
+    /// placeholders, or `None` if the circuit is unknown. Frontends render it
+    /// to show the exact email to send.
+    pub fn get_subject_template(&self, circuit_id: String) -> Option<SubjectTemplate> {
+        layout::subject_template(&circuit_id)
     }
 
-    String::from_utf8(bytes).map_err(|_| ())
-}
+    /// Pack `s` into the decimal field elements the circuit would expose for an
+    /// anchored substring, for debugging binding mismatches off-chain.
+    ///
+    /// `max_len` is the field's maximum byte length from
+    /// `get_public_input_layout`; it defaults to the 255 bytes of
+    /// `RecoverEmailCircuit`.
+    pub fn pack_string(&self, s: String, max_len: Option<u32>) -> Vec<String> {
+        let max_len = max_len.map_or(packing::MAX_PACKED_SUBSTRING_LEN, |len| len as usize);
+        packing::pack_string_with_max_len(&s, max_len).unwrap_or_else(|| {
+            env::panic_str(&format!("string exceeds {max_len} bytes or ends in a NUL byte"))
+        })
+    }
 
-fn parse_email_timestamp_to_unix_ms(s: &str) -> Option<u64> {
-    // Expect formats like: "Sun, 30 Nov 2025 17:37:38 +0900"
-    let trimmed = s.trim();
-    let after_comma = match trimmed.split_once(',') {
-        Some((_, rest)) => rest.trim_start(),
-        None => trimmed,
-    };
+    /// Decode packed decimal field elements (e.g. a slice of `public.json`)
+    /// back into the anchored string.
+    pub fn unpack_fields(&self, chunks: Vec<String>) -> String {
+        packing::unpack_fields(&chunks)
+            .unwrap_or_else(|| env::panic_str("chunks do not decode to a UTF-8 string"))
+    }
+}
 
-    let parts: Vec<&str> = after_comma.split_whitespace().collect();
-    if parts.len() < 5 {
-        return None;
-    }
-
-    let day: u32 = parts[0].parse().ok()?;
-    let month_str = parts[1];
-    let year: i32 = parts[2].parse().ok()?;
-
-    let month = match month_str {
-        "Jan" => 1,
-        "Feb" => 2,
-        "Mar" => 3,
-        "Apr" => 4,
-        "May" => 5,
-        "Jun" => 6,
-        "Jul" => 7,
-        "Aug" => 8,
-        "Sep" => 9,
-        "Oct" => 10,
-        "Nov" => 11,
-        "Dec" => 12,
-        _ => return None,
-    };
+impl ZkEmailVerifier {
+    fn with_verifying_key(owner_id: AccountId, vk: &VerifyingKey<Bn254>, network: NetworkProfile) -> Self {
+        let mut this = Self {
+            owner_id,
+            prepared_vk: LazyOption::new(StorageKey::PreparedVk, None),
+            staged_verifications: LookupMap::new(StorageKey::StagedVerifications),
+            next_staged_id: 0,
+            attestations: IterableMap::new(StorageKey::Attestations),
+            attestation_chain_head: vec![0; 32],
+            stats: stats::Stats::default(),
+            paused: false,
+            freshness_window_ms: network.freshness_window_ms(),
+            circuit_breaker: network.circuit_breaker(),
+            breaker_window: breaker::WindowCounts::default(),
+            account_windows: LookupMap::new(StorageKey::AccountWindows),
+            allowed_sender_domains: Vec::new(),
+            blocked_senders: IterableSet::new(StorageKey::BlockedSenders),
+            circuit_vks: IterableMap::new(StorageKey::CircuitVks),
+            circuit_layouts: IterableMap::new(StorageKey::CircuitLayouts),
+            domain_circuits: IterableMap::new(StorageKey::DomainCircuits),
+            audit_log: LookupMap::new(StorageKey::AuditLog),
+            audit_start: 0,
+            audit_next: 0,
+            audit_capacity: audit::DEFAULT_AUDIT_CAPACITY,
+            account_history: LookupMap::new(StorageKey::AccountHistory),
+            account_history_len: IterableMap::new(StorageKey::AccountHistoryLen),
+            debug_logs: false,
+            vk_registry: None,
+            registry_vk_versions: LookupMap::new(StorageKey::RegistryVkVersions),
+            network,
+            vk_hashes: LookupMap::new(StorageKey::VkHashes),
+            pvk_cache: RefCell::default(),
+            result_cache_ttl_ms: None,
+            result_cache: IterableMap::new(StorageKey::ResultCache),
+            cached_request_ids: LookupMap::new(StorageKey::CachedRequestIds),
+            gas_benchmarks: IterableMap::new(StorageKey::GasBenchmarks),
+            predecessor_binding: false,
+            account_relayers: IterableMap::new(StorageKey::AccountRelayers),
+            recovery_cooldown_ms: network.recovery_cooldown_ms(),
+            recovery_rate_limit: None,
+            recovery_window: breaker::WindowCounts::default(),
+            rate_limit_bypass: IterableSet::new(StorageKey::RateLimitBypass),
+            verification_fee: NearToken::from_yoctonear(0),
+            treasury_balance: NearToken::from_yoctonear(0),
+            relayer_reward: None,
+            sponsor_pools: LookupMap::new(StorageKey::SponsorPools),
+            recovery_mailbox: None,
+            challenges: IterableMap::new(StorageKey::Challenges),
+            challenge_nonce: 0,
+            account_challenges: LookupMap::new(StorageKey::AccountChallenges),
+            default_key_permission: key_permission::KeyPermission::FullAccess,
+            account_key_permissions: IterableMap::new(StorageKey::AccountKeyPermissions),
+            registered_emails: IterableMap::new(StorageKey::RegisteredEmails),
+            pending_migrations: IterableMap::new(StorageKey::PendingMigrations),
+            migration_timelock_ms: migration::DEFAULT_MIGRATION_TIMELOCK_MS,
+            account_locks: IterableMap::new(StorageKey::AccountLocks),
+            lock_duration_ms: lock::DEFAULT_LOCK_DURATION_MS,
+            controller_contract: None,
+            pending_spending_limits: IterableMap::new(StorageKey::PendingSpendingLimits),
+            guardians: IterableMap::new(StorageKey::Guardians),
+            pending_guardian_removals: IterableMap::new(StorageKey::PendingGuardianRemovals),
+            guardian_removal_delay_ms: guardians::DEFAULT_GUARDIAN_REMOVAL_DELAY_MS,
+            guardian_weights: IterableMap::new(StorageKey::GuardianWeights),
+            guardian_recoveries: IterableMap::new(StorageKey::GuardianRecoveries),
+            guardian_sets: IterableMap::new(StorageKey::GuardianSets),
+            guardian_epoch_ms: guardian_epochs::DEFAULT_GUARDIAN_EPOCH_MS,
+            recovery_policies: IterableMap::new(StorageKey::RecoveryPolicies),
+            pending_email_changes: IterableMap::new(StorageKey::PendingEmailChanges),
+            email_change_window_ms: email_change::DEFAULT_EMAIL_CHANGE_WINDOW_MS,
+            account_namespace: None,
+            idempotent_results: IterableMap::new(StorageKey::IdempotentResults),
+            nullifiers: IterableSet::new(StorageKey::Nullifiers),
+            nullifier_branch: LazyOption::new(StorageKey::NullifierBranch, None),
+            result_leaves: LookupMap::new(StorageKey::ResultLeaves),
+            result_count: 0,
+            result_branch: LazyOption::new(StorageKey::ResultBranch, None),
+            activated: true,
+            circuit_deprecations: IterableMap::new(StorageKey::CircuitDeprecations),
+            result_cache_prune_cursor: 0,
+            idempotent_prune_cursor: 0,
+        };
+        this.put_verifying_key(layout::RECOVER_EMAIL_CIRCUIT_ID, vk);
+        this
+    }
 
-    let time_parts: Vec<&str> = parts[3].split(':').collect();
-    if time_parts.len() != 3 {
-        return None;
+    /// Attach the key permission of a decoded recovery and apply the account
+    /// checks shared by every verification method.
+    pub(crate) fn admit_recovery(&self, result: VerificationResult) -> VerificationResult {
+        let result = self.check_namespace(self.with_key_permission(result));
+        self.check_registered_sender(result)
     }
-    let hour: u32 = time_parts[0].parse().ok()?;
-    let minute: u32 = time_parts[1].parse().ok()?;
-    let second: u32 = time_parts[2].parse().ok()?;
 
-    let offset_str = parts[4];
-    if offset_str.len() < 3 {
-        return None;
+    fn assert_owner(&self) {
+        if env::predecessor_account_id() != self.owner_id {
+            env::panic_str("only the owner can call this method");
+        }
     }
-    let (sign_char, rest) = offset_str.split_at(1);
-    let sign = match sign_char {
-        "+" => 1i64,
-        "-" => -1i64,
-        _ => return None,
-    };
-    if rest.len() != 4 {
-        return None;
+
+    fn store_verifying_key(&mut self, vk: &VerifyingKey<Bn254>) {
+        self.put_verifying_key(layout::RECOVER_EMAIL_CIRCUIT_ID, vk);
+        env::log_str("verifying key updated");
     }
-    let offset_hours: i64 = rest[0..2].parse().ok()?;
-    let offset_minutes: i64 = rest[2..4].parse().ok()?;
-    let offset_secs: i64 = sign * (offset_hours * 3600 + offset_minutes * 60);
 
-    // Compute days since Unix epoch (1970-01-01).
-    if year < 1970 {
-        return None;
+    fn prepared_verifying_key(&self) -> PreparedVerifyingKey<Bn254> {
+        self.prepared_verifying_key_for(layout::RECOVER_EMAIL_CIRCUIT_ID)
     }
+}
 
-    fn is_leap_year(y: i32) -> bool {
-        (y % 4 == 0 && y % 100 != 0) || (y % 400 == 0)
+/// One entry of a `verify_batch` call.
+#[near(serializers = [json])]
+pub struct BatchProofInput {
+    pub proof: ProofInput,
+    pub public_inputs: Vec<String>,
+}
+
+/// A verifying key needs one `IC` point per public input plus the constant term.
+fn check_ic_len(ic_len: usize, layout: &CircuitLayout) {
+    if ic_len != layout.len + 1 {
+        env::panic_str(&format!(
+            "verifying key must have {} IC points, got {ic_len}",
+            layout.len + 1,
+        ));
     }
+}
 
-    const DAYS_IN_MONTH: [u32; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+fn log_verification_error(err: VerificationError) {
+    env::log_str(&format!("verification rejected: {err}"));
+}
 
-    fn days_in_month(year: i32, month: u32) -> u32 {
-        if month == 2 && is_leap_year(year) {
-            29
-        } else {
-            DAYS_IN_MONTH[(month - 1) as usize]
-        }
+/// Check that a decoded `account_id` is a syntactically valid NEAR account
+/// (2–64 chars of `a-z0-9`, parts joined by `.`, `-` or `_` with no leading,
+/// trailing or doubled separators) so downstream cross-contract calls don't
+/// fail late on it.
+fn validate_account_id(account_id: &str) -> Result<AccountId, VerificationError> {
+    account_id
+        .parse::<AccountId>()
+        .map_err(|_| VerificationError::InvalidAccountId)
+}
+
+/// Parse the base58 key anchored after the fixed `ed25519:` prefix in the
+/// subject line. The packed value itself must not carry a curve prefix.
+fn parse_new_public_key(new_public_key: &str) -> Result<PublicKey, VerificationError> {
+    if new_public_key.contains(':') {
+        return Err(VerificationError::InvalidPublicKey);
     }
+    format!("ed25519:{new_public_key}")
+        .parse::<PublicKey>()
+        .map_err(|_| VerificationError::InvalidPublicKey)
+}
 
-    if month == 0 || month > 12 {
-        return None;
+/// Parse a key to delete: any key type with its curve prefix, or bare
+/// base58 for ed25519.
+fn parse_deleted_public_key(key: &str) -> Result<PublicKey, VerificationError> {
+    if key.contains(':') {
+        key.parse::<PublicKey>().map_err(|_| VerificationError::InvalidPublicKey)
+    } else {
+        parse_new_public_key(key)
     }
-    if day == 0 || day > days_in_month(year, month) {
-        return None;
+}
+
+/// Normalize a caller-supplied key that may be bare base58 or carry an
+/// `ed25519:` prefix. Returns the bare base58 data (what the circuit packs)
+/// together with the typed key. The circuit anchors ed25519 keys only, so a
+/// `secp256k1:` key could never match and is rejected up front.
+fn normalize_bound_public_key(new_public_key: &str) -> Result<(&str, PublicKey), VerificationError> {
+    let parsed = new_public_key
+        .parse::<PublicKey>()
+        .map_err(|_| VerificationError::InvalidPublicKey)?;
+    if parsed.curve_type() != CurveType::ED25519 {
+        return Err(VerificationError::InvalidPublicKey);
     }
+    let bare = new_public_key
+        .split_once(':')
+        .map_or(new_public_key, |(_, data)| data);
+    Ok((bare, parsed))
+}
 
-    let mut days: i64 = 0;
-    let mut y = 1970;
-    while y < year {
-        days += if is_leap_year(y) { 366 } else { 365 };
-        y += 1;
+/// Build the positive result for public inputs whose proof has already passed
+/// the pairing check, decoding the anchored fields and validating them.
+fn decode_verified_inputs(layout: &CircuitLayout, inputs_ark: &[Fr]) -> VerificationResult {
+    let fields = decode_with_layout(layout, inputs_ark).unwrap_or_default();
+    let account_id = fields.account_id.unwrap_or_default();
+    let new_public_key = fields.new_public_key.unwrap_or_default();
+    let from_address_hash = fields.from_address_hash.unwrap_or_default();
+    let email_timestamp = fields.timestamp.as_deref().and_then(parse_email_timestamp);
+
+    if let Err(err) = validate_account_id(&account_id) {
+        return VerificationResult::rejected_with(err);
     }
+    let new_public_key_parsed = match parse_new_public_key(&new_public_key) {
+        Ok(pk) => pk,
+        Err(err) => return VerificationResult::rejected_with(err),
+    };
+    let additional_public_keys = match fields
+        .additional_public_keys
+        .ok_or(VerificationError::InvalidPublicKey)
+        .and_then(|keys| keys.iter().map(|key| parse_new_public_key(key)).collect())
+    {
+        Ok(keys) => keys,
+        Err(err) => return VerificationResult::rejected_with(err),
+    };
+    let deleted_public_keys = match fields
+        .deleted_public_keys
+        .ok_or(VerificationError::InvalidPublicKey)
+        .and_then(|keys| keys.iter().map(|key| parse_deleted_public_key(key)).collect())
+    {
+        Ok(keys) => keys,
+        Err(err) => return VerificationResult::rejected_with(err),
+    };
 
-    let mut m = 1;
-    while m < month {
-        days += days_in_month(year, m) as i64;
-        m += 1;
+    VerificationResult {
+        verified: true,
+        request_id: fields.request_id.unwrap_or_default(),
+        account_id,
+        new_public_key,
+        new_public_key_parsed: Some(new_public_key_parsed),
+        additional_public_keys,
+        deleted_public_keys,
+        key_permission: None,
+        from_address_hash,
+        email_timestamp_ms: email_timestamp.as_ref().map(|t| t.unix_ms),
+        email_timestamp_rfc3339: email_timestamp.map(|t| t.rfc3339),
+        failure_reason: None,
+        verified_at_block: None,
+        verified_at_ms: None,
     }
+}
 
-    days += (day - 1) as i64;
+/// Groth16 pairing check, with the public-input MSM done by `msm::prepare_inputs`.
+fn verify_groth16(pvk: &PreparedVerifyingKey<Bn254>, proof: &Proof<Bn254>, inputs: &[Fr]) -> bool {
+    msm::prepare_inputs(pvk, inputs)
+        .map(|prepared| verify_with_prepared_inputs(pvk, proof, &prepared))
+        .unwrap_or(false)
+}
 
-    let seconds_local: i64 =
-        days * 86_400 + (hour as i64) * 3_600 + (minute as i64) * 60 + (second as i64);
+fn parse_proof(input: ProofInput) -> Result<Proof<Bn254>, ()> {
+    input.parse()
+}
 
-    // Offset is "local = UTC + offset", so UTC = local - offset.
-    let seconds_utc = seconds_local - offset_secs;
-    if seconds_utc < 0 {
-        return None;
+/// Parse a proof and its public inputs, checking the number of inputs
+/// against `layout`.
+fn parse_proof_and_inputs(
+    proof: ProofInput,
+    public_inputs: &[String],
+    layout: &CircuitLayout,
+) -> Result<(Proof<Bn254>, Vec<Fr>), FailureReason> {
+    if !proof.is_groth16_bn254() {
+        return Err(FailureReason::ProofProtocol);
     }
-
-    Some(seconds_utc as u64 * 1000)
+    let proof = parse_proof(proof).map_err(|_| FailureReason::ProofParse)?;
+    let inputs = parse_public_inputs(public_inputs).map_err(|_| FailureReason::InputParse)?;
+    if inputs.len() != layout.len {
+        return Err(FailureReason::InputLen);
+    }
+    Ok((proof, inputs))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ark_groth16::Groth16;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
     use std::{fs, path::Path};
 
+    #[test]
+    fn stamped_results_carry_the_verifying_block() {
+        testing_env!(VMContextBuilder::new()
+            .block_index(42)
+            .block_timestamp(7_000_000_000)
+            .build());
+        let result = VerificationResult::rejected(FailureReason::Pairing);
+        assert_eq!((result.verified_at_block, result.verified_at_ms), (None, None));
+        let result = result.stamped();
+        assert_eq!((result.verified_at_block, result.verified_at_ms), (Some(42), Some(7_000)));
+    }
+
     #[test]
     fn snarkjs_proof_verifies_with_generated_vk() {
         // Load proof.json and public.json from the contract tests/proofs directory.
@@ -503,14 +1115,220 @@ mod tests {
             serde_json::from_str(&public_json).expect("failed to parse public.json");
 
         let vk = vk::verifying_key();
-        let pvk = prepare_verifying_key(&vk);
+        let pvk = ark_groth16::prepare_verifying_key(&vk);
 
         let proof_ark = parse_proof(proof_input).expect("failed to parse proof into ark type");
         let inputs_ark =
-            parse_public_inputs(public_inputs).expect("failed to parse public inputs into Fr");
+            parse_public_inputs(&public_inputs).expect("failed to parse public inputs into Fr");
 
         let ok =
             Groth16::<Bn254>::verify_proof(&pvk, &proof_ark, &inputs_ark).expect("verify_proof failed");
         assert!(ok, "snarkjs proof did not verify under generated verifying key");
     }
+
+    #[test]
+    fn validate_account_id_accepts_only_near_accounts() {
+        assert!(validate_account_id("kerp30.w3a-v1.testnet").is_ok());
+        for bad in ["", "a", "-alice.near", "alice.near.", "alice..near", "Alice.near", "a b"] {
+            assert_eq!(
+                validate_account_id(bad).unwrap_err(),
+                VerificationError::InvalidAccountId,
+                "{bad:?} should be rejected"
+            );
+        }
+        assert!(validate_account_id(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn normalize_bound_public_key_strips_curve_prefix() {
+        let bare = "86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm";
+        for input in [bare.to_string(), format!("ed25519:{bare}"), format!("ED25519:{bare}")] {
+            let (normalized, pk) = normalize_bound_public_key(&input).unwrap();
+            assert_eq!(normalized, bare);
+            assert_eq!(pk, parse_new_public_key(bare).unwrap());
+        }
+        let secp = PublicKey::from_parts(CurveType::SECP256K1, vec![1; 64]).unwrap();
+        assert!(normalize_bound_public_key(&String::from(&secp)).is_err());
+        assert!(normalize_bound_public_key(&format!("secp256k1:{bare}")).is_err());
+        assert!(normalize_bound_public_key(&format!("rsa:{bare}")).is_err());
+    }
+
+    #[test]
+    fn parse_new_public_key_requires_bare_ed25519_base58() {
+        let pk = parse_new_public_key("86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm").unwrap();
+        assert_eq!(
+            String::from(&pk),
+            "ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm"
+        );
+        for bad in [
+            "",
+            "not-base58!",
+            "86mqiBdv45gM4c5u",
+            "ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm",
+        ] {
+            assert_eq!(
+                parse_new_public_key(bad).unwrap_err(),
+                VerificationError::InvalidPublicKey,
+                "{bad:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_deleted_public_key_accepts_prefixed_keys() {
+        let bare = "86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm";
+        let prefixed = format!("ed25519:{bare}");
+        assert_eq!(parse_deleted_public_key(bare), parse_deleted_public_key(&prefixed));
+        assert!(parse_deleted_public_key(&prefixed).is_ok());
+        assert!(parse_deleted_public_key(&format!("rsa:{bare}")).is_err());
+    }
+
+    #[test]
+    fn new_with_vk_accepts_json_and_compressed_keys() {
+        use ark_serialize::CanonicalSerialize;
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let expected = ZkEmailVerifier::new(None).prepared_vk_hash(layout::RECOVER_EMAIL_CIRCUIT_ID);
+
+        let json = prepared_vk::verifying_key_json(&vk::verifying_key()).key;
+        let contract = ZkEmailVerifier::new_with_vk(InitialVerifyingKey::Json(Box::new(json)), None);
+        assert_eq!(contract.prepared_vk_hash(layout::RECOVER_EMAIL_CIRCUIT_ID), expected);
+        assert_eq!(contract.get_owner_id(), accounts(0));
+
+        let mut bytes = Vec::new();
+        vk::verifying_key().serialize_compressed(&mut bytes).unwrap();
+        let contract = ZkEmailVerifier::new_with_vk(InitialVerifyingKey::Bytes(bytes.into()), None);
+        assert_eq!(contract.prepared_vk_hash(layout::RECOVER_EMAIL_CIRCUIT_ID), expected);
+    }
+
+    #[test]
+    fn verify_rejects_command_proofs() {
+        use zk_email_verifier_core::layout::{MIGRATE_ACCOUNT_CIRCUIT_ID, MIGRATE_ACCOUNT_LAYOUT};
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let (vk, prove) = test_utils::trapdoor_circuit(MIGRATE_ACCOUNT_LAYOUT.len);
+        let layout_id = Some(MIGRATE_ACCOUNT_CIRCUIT_ID.to_string());
+        contract.set_circuit_verifying_key("migrate".into(), Some(vk), layout_id);
+        let inputs = test_utils::public_inputs(
+            &MIGRATE_ACCOUNT_LAYOUT,
+            &[("request_id", "1"), ("account_id", "alice.testnet")],
+        );
+
+        // The proof is valid for `initiate_migration` ...
+        let command = contract.verify_command_proof(
+            CircuitKind::Command,
+            MIGRATE_ACCOUNT_CIRCUIT_ID,
+            prove(&inputs),
+            &inputs,
+        );
+        assert_eq!(command.ok().map(|command| command.account_id).as_deref(), Some("alice.testnet"));
+
+        // ... but isn't a recovery, whether named or picked by its length.
+        let result = contract.verify(prove(&inputs), inputs.clone(), Some(MIGRATE_ACCOUNT_CIRCUIT_ID.into()));
+        assert_eq!(result.failure_reason.as_deref(), Some("UNKNOWN_LAYOUT"));
+        let result = contract.verify(prove(&inputs), inputs, None);
+        assert_eq!(result.failure_reason.as_deref(), Some("INPUT_LEN"));
+    }
+
+    #[test]
+    fn verify_returns_every_key_of_a_multi_key_recovery() {
+        use zk_email_verifier_core::layout::{
+            RECOVER_WITH_BACKUP_KEY_CIRCUIT_ID, RECOVER_WITH_BACKUP_KEY_LAYOUT,
+        };
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let (vk, prove) = test_utils::trapdoor_circuit(RECOVER_WITH_BACKUP_KEY_LAYOUT.len);
+        let layout_id = Some(RECOVER_WITH_BACKUP_KEY_CIRCUIT_ID.to_string());
+        contract.set_circuit_verifying_key("backup".into(), Some(vk), layout_id);
+        let (passkey, backup) = (
+            "86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm",
+            "US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx",
+        );
+        let inputs_with = |backup| {
+            test_utils::public_inputs(
+                &RECOVER_WITH_BACKUP_KEY_LAYOUT,
+                &[
+                    ("request_id", "1"),
+                    ("account_id", "alice.testnet"),
+                    ("new_public_key", passkey),
+                    ("new_public_key_1", backup),
+                ],
+            )
+        };
+
+        let inputs = inputs_with(backup);
+        let result = contract.verify(prove(&inputs), inputs, None);
+        assert!(result.verified, "{:?}", result.failure_reason);
+        assert_eq!(result.new_public_key_parsed, parse_new_public_key(passkey).ok());
+        assert_eq!(result.additional_public_keys, vec![parse_new_public_key(backup).unwrap()]);
+
+        // Both keys are added or neither is.
+        let inputs = inputs_with("not-base58!");
+        let result = contract.verify(prove(&inputs), inputs, None);
+        assert_eq!(result.failure_reason.as_deref(), Some("INVALID_PUBLIC_KEY"));
+    }
+
+    #[test]
+    fn verify_returns_the_keys_a_recovery_revokes() {
+        use zk_email_verifier_core::layout::{
+            RECOVER_AND_REVOKE_KEY_CIRCUIT_ID, RECOVER_AND_REVOKE_KEY_LAYOUT,
+        };
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let (vk, prove) = test_utils::trapdoor_circuit(RECOVER_AND_REVOKE_KEY_LAYOUT.len);
+        let layout_id = Some(RECOVER_AND_REVOKE_KEY_CIRCUIT_ID.to_string());
+        contract.set_circuit_verifying_key("revoke".into(), Some(vk), layout_id);
+        let stolen = "ed25519:US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx";
+        let inputs = test_utils::public_inputs(
+            &RECOVER_AND_REVOKE_KEY_LAYOUT,
+            &[
+                ("request_id", "1"),
+                ("account_id", "alice.testnet"),
+                ("new_public_key", "86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm"),
+                ("delete_public_key_1", stolen),
+            ],
+        );
+
+        let result = contract.verify(prove(&inputs), inputs, None);
+        assert!(result.verified, "{:?}", result.failure_reason);
+        assert!(result.additional_public_keys.is_empty());
+        assert_eq!(result.deleted_public_keys, vec![parse_deleted_public_key(stolen).unwrap()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "contract state already exists")]
+    fn migrate_refuses_to_reset_existing_state() {
+        testing_env!(VMContextBuilder::new().build());
+        let contract = ZkEmailVerifier::migrate();
+        env::state_write(&contract);
+        ZkEmailVerifier::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "only the owner can call this method")]
+    fn set_verifying_key_is_owner_only() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        assert_eq!(contract.get_owner_id(), accounts(0));
+
+        let g1 = || ["1".to_string(), "2".to_string(), "1".to_string()];
+        let g2 = || {
+            [
+                ["0".to_string(), "0".to_string()],
+                ["0".to_string(), "0".to_string()],
+                ["1".to_string(), "0".to_string()],
+            ]
+        };
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        contract.set_verifying_key(VerifyingKeyInput {
+            vk_alpha_1: g1(),
+            vk_beta_2: g2(),
+            vk_gamma_2: g2(),
+            vk_delta_2: g2(),
+            ic: vec![g1(); EXPECTED_PUBLIC_LEN + 1],
+        });
+    }
 }
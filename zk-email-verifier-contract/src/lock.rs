@@ -0,0 +1,107 @@
+//! Freezing recoveries from the registered mailbox.
+//!
+//! An owner who suspects their keys or relayer are compromised sends a
+//! `lock-` email (see `LOCK_ACCOUNT_SUBJECT`) from the account's registered
+//! address. `lock_account` verifies it and, until the lock expires, every
+//! recovery of the account is rejected with `ACCOUNT_LOCKED` and migrations
+//! can neither start nor finish.
+//!
+//! The lock runs from the email's `Date:` header rather than from the block
+//! that accepted it, so submitting the same proof again later can't extend
+//! it.
+
+use near_sdk::{env, near};
+use zk_email_verifier_core::layout::LOCK_ACCOUNT_CIRCUIT_ID;
+
+use crate::commands::reject;
+use crate::events::Event;
+use crate::{FailureReason, ProofInput, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Seven days.
+pub(crate) const DEFAULT_LOCK_DURATION_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+#[near]
+impl ZkEmailVerifier {
+    /// Verify a `lock-` email and lock its account for the configured
+    /// duration from the email's date. Returns when the lock ends, which is
+    /// never earlier than an existing lock.
+    pub fn lock_account(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) -> u64 {
+        let command = self
+            .verify_command(LOCK_ACCOUNT_CIRCUIT_ID, proof, &public_inputs, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        let sent_at_ms = command
+            .email_timestamp_ms
+            .unwrap_or_else(|| reject(FailureReason::InputParse));
+        let existing = self.account_locks.get(&command.account_id).copied().unwrap_or(0);
+        let locked_until_ms = existing.max(sent_at_ms.saturating_add(self.lock_duration_ms));
+        self.account_locks
+            .insert(command.account_id.clone(), locked_until_ms);
+        Event::AccountLocked {
+            account_id: command.account_id,
+            locked_until_ms,
+        }
+        .emit();
+        locked_until_ms
+    }
+
+    /// When the lock of `account_id` ends; `None` if it isn't locked.
+    pub fn get_account_lock(&self, account_id: String) -> Option<u64> {
+        self.account_locks
+            .get(&account_id)
+            .copied()
+            .filter(|&until| until > env::block_timestamp_ms())
+    }
+
+    pub fn get_lock_duration_ms(&self) -> u64 {
+        self.lock_duration_ms
+    }
+
+    /// Only callable by the owner. Locks already in place keep their end.
+    pub fn set_lock_duration_ms(&mut self, duration_ms: u64) {
+        self.assert_owner();
+        self.lock_duration_ms = duration_ms;
+    }
+}
+
+impl ZkEmailVerifier {
+    pub(crate) fn is_locked(&self, account_id: &str) -> bool {
+        self.get_account_lock(account_id.to_string()).is_some()
+    }
+
+    /// Reject a verified `result` while its account is locked.
+    pub(crate) fn check_lock(&self, result: VerificationResult) -> VerificationResult {
+        if result.verified && self.is_locked(&result.account_id) {
+            return result.with_failure(FailureReason::AccountLocked);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::at;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    fn locked_accounts_reject_recoveries_until_the_lock_ends() {
+        at(1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.account_locks.insert(accounts(1).to_string(), 2_000);
+        let recovery = VerificationResult::verified_for(accounts(1).as_str());
+
+        let locked = contract.check_lock(recovery.clone());
+        assert!(!locked.verified);
+        assert_eq!(locked.failure_reason.as_deref(), Some("ACCOUNT_LOCKED"));
+        assert_eq!(contract.get_account_lock(accounts(1).to_string()), Some(2_000));
+
+        at(2_000);
+        assert!(contract.check_lock(recovery).verified);
+        assert_eq!(contract.get_account_lock(accounts(1).to_string()), None);
+    }
+}
@@ -0,0 +1,172 @@
+//! Moving an account to a new owner: another email and a new key.
+//!
+//! A `migrate-` email from the account's registered address (see
+//! `MIGRATE_ACCOUNT_SUBJECT`) names the new key and the commitment of the
+//! email to move to. `initiate_migration` verifies it and starts a timelock;
+//! during it the account can still `cancel_migration`, which is the window
+//! for the current owner to react to a compromised mailbox. Once the timelock
+//! has passed, anyone can `finalize_migration`: the new email replaces the
+//! registered one, and the returned key is for the account's controller to
+//! add. Neither step is accepted while the account is locked (see `lock`).
+
+use near_sdk::{env, near, PublicKey};
+use zk_email_verifier_core::layout::MIGRATE_ACCOUNT_CIRCUIT_ID;
+
+use crate::commands::{decode_hex_hash, reject};
+use crate::events::Event;
+use crate::{parse_new_public_key, FailureReason, ProofInput, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Three days.
+pub(crate) const DEFAULT_MIGRATION_TIMELOCK_MS: u64 = 3 * 24 * 60 * 60 * 1000;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingMigration {
+    pub account_id: String,
+    pub request_id: String,
+    pub new_public_key: PublicKey,
+    /// Commitment of the email the account moves to.
+    pub new_email_hash: Vec<u8>,
+    /// When `finalize_migration` is accepted.
+    pub ready_at_ms: u64,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Verify a `migrate-` email and start the timelock of the migration it
+    /// requests, replacing any pending one of the same account.
+    pub fn initiate_migration(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) -> PendingMigration {
+        let command = self
+            .verify_command(MIGRATE_ACCOUNT_CIRCUIT_ID, proof, &public_inputs, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        if self.is_locked(&command.account_id) {
+            reject(FailureReason::AccountLocked);
+        }
+        let new_public_key = command
+            .text("new_public_key")
+            .and_then(|key| parse_new_public_key(&key).ok())
+            .unwrap_or_else(|| reject(FailureReason::InvalidPublicKey));
+        let new_email_hash = command
+            .text("new_email_hash")
+            .and_then(|hex| decode_hex_hash(&hex))
+            .unwrap_or_else(|| reject(FailureReason::InputParse));
+        let timelock_ms = self.timelock_ms_for(&command.account_id);
+        let migration = PendingMigration {
+            account_id: command.account_id,
+            request_id: command.request_id,
+            new_public_key,
+            new_email_hash,
+            ready_at_ms: env::block_timestamp_ms().saturating_add(timelock_ms),
+        };
+        self.pending_migrations
+            .insert(migration.account_id.clone(), migration.clone());
+        Event::MigrationInitiated {
+            account_id: migration.account_id.clone(),
+            ready_at_ms: migration.ready_at_ms,
+        }
+        .emit();
+        migration
+    }
+
+    /// Drop the calling account's pending migration.
+    pub fn cancel_migration(&mut self) {
+        let account_id = env::predecessor_account_id().to_string();
+        if self.pending_migrations.remove(&account_id).is_none() {
+            env::panic_str("no pending migration");
+        }
+        Event::MigrationCancelled { account_id }.emit();
+    }
+
+    /// Complete the pending migration of `account_id` once its timelock has
+    /// passed, registering the new email. Returns the migration, whose key
+    /// the account's controller should now add.
+    pub fn finalize_migration(&mut self, account_id: String) -> PendingMigration {
+        self.assert_activated();
+        let migration = self
+            .pending_migrations
+            .get(&account_id)
+            .cloned()
+            .unwrap_or_else(|| env::panic_str("no pending migration"));
+        if self.is_locked(&account_id) {
+            reject(FailureReason::AccountLocked);
+        }
+        if env::block_timestamp_ms() < migration.ready_at_ms {
+            env::panic_str("the migration timelock has not passed");
+        }
+        self.pending_migrations.remove(&account_id);
+        self.registered_emails
+            .insert(account_id.clone(), migration.new_email_hash.clone());
+        Event::MigrationFinalized {
+            account_id,
+            new_public_key: String::from(&migration.new_public_key),
+        }
+        .emit();
+        migration
+    }
+
+    pub fn get_pending_migration(&self, account_id: String) -> Option<PendingMigration> {
+        self.pending_migrations.get(&account_id).cloned()
+    }
+
+    pub fn get_migration_timelock_ms(&self) -> u64 {
+        self.migration_timelock_ms
+    }
+
+    /// Only callable by the owner.
+    pub fn set_migration_timelock_ms(&mut self, timelock_ms: u64) {
+        self.assert_owner();
+        self.migration_timelock_ms = timelock_ms;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::as_account_at;
+    use near_sdk::test_utils::accounts;
+
+    fn pending(contract: &mut ZkEmailVerifier, ready_at_ms: u64) {
+        let migration = PendingMigration {
+            account_id: accounts(1).to_string(),
+            request_id: "1".to_string(),
+            new_public_key: parse_new_public_key("86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm").unwrap(),
+            new_email_hash: env::sha256(b"new"),
+            ready_at_ms,
+        };
+        contract.pending_migrations.insert(accounts(1).to_string(), migration);
+    }
+
+    #[test]
+    fn migrations_register_the_new_email_after_the_timelock() {
+        as_account_at(1, 1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.register_email(env::sha256(b"old"));
+        pending(&mut contract, 2_000);
+
+        as_account_at(2, 2_000);
+        let migration = contract.finalize_migration(accounts(1).to_string());
+        assert_eq!(migration.new_email_hash, env::sha256(b"new"));
+        assert_eq!(contract.get_registered_email(accounts(1).to_string()), Some(env::sha256(b"new")));
+        assert_eq!(contract.get_pending_migration(accounts(1).to_string()), None);
+
+        pending(&mut contract, 3_000);
+        as_account_at(1, 2_500);
+        contract.cancel_migration();
+        assert_eq!(contract.get_pending_migration(accounts(1).to_string()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "the migration timelock has not passed")]
+    fn migrations_wait_for_the_timelock() {
+        as_account_at(1, 1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+        pending(&mut contract, 2_000);
+        as_account_at(2, 1_999);
+        contract.finalize_migration(accounts(1).to_string());
+    }
+}
@@ -0,0 +1,100 @@
+//! Groth16 public-input preparation: `gamma_abc_g1[0] + sum(gamma_abc_g1[i + 1] * input[i])`.
+//!
+//! `ark_groth16::Groth16::prepare_inputs` does one scalar multiplication per
+//! input in WASM, which is expensive with ~80 public inputs. On-chain the sum is
+//! computed by NEAR's `alt_bn128_g1_multiexp` host function instead; native
+//! builds (unit tests, tooling) use the Pippenger MSM from
+//! `zk_email_verifier_core`.
+
+use ark_bn254::G1Affine;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use zk_email_verifier_core::prepare_inputs;
+
+/// Combine the verifying key's `gamma_abc_g1` with `public_inputs`. Fails if
+/// the number of inputs doesn't match the key.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn prepare_inputs(
+    pvk: &ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254>,
+    public_inputs: &[ark_bn254::Fr],
+) -> Result<ark_bn254::G1Projective, ()> {
+    use ark_ff::{BigInteger, PrimeField};
+
+    let bases = &pvk.vk.gamma_abc_g1;
+    if public_inputs.len() + 1 != bases.len() {
+        return Err(());
+    }
+    let mut input = Vec::with_capacity(public_inputs.len() * (G1_LEN + 32));
+    for (base, scalar) in bases[1..].iter().zip(public_inputs) {
+        write_g1(&mut input, base);
+        input.extend_from_slice(&scalar.into_bigint().to_bytes_le());
+    }
+    let sum = read_g1(&near_sdk::env::alt_bn128_g1_multiexp(&input))?;
+    Ok(sum + bases[0])
+}
+
+/// Size of a G1 point in the host functions' encoding: `x ‖ y`, 32 bytes each,
+/// little-endian, with the point at infinity as all zeroes.
+#[cfg_attr(not(any(target_arch = "wasm32", test)), allow(dead_code))]
+const G1_LEN: usize = 64;
+
+#[cfg_attr(not(any(target_arch = "wasm32", test)), allow(dead_code))]
+fn write_g1(out: &mut Vec<u8>, p: &G1Affine) {
+    use ark_ec::AffineRepr;
+    use ark_ff::{BigInteger, PrimeField};
+
+    match p.xy() {
+        Some((x, y)) => {
+            out.extend_from_slice(&x.into_bigint().to_bytes_le());
+            out.extend_from_slice(&y.into_bigint().to_bytes_le());
+        }
+        None => out.extend_from_slice(&[0u8; G1_LEN]),
+    }
+}
+
+#[cfg_attr(not(any(target_arch = "wasm32", test)), allow(dead_code))]
+fn read_g1(bytes: &[u8]) -> Result<G1Affine, ()> {
+    use ark_bn254::Fq;
+    use ark_ff::PrimeField;
+
+    if bytes.len() != G1_LEN {
+        return Err(());
+    }
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(G1Affine::identity());
+    }
+    let x = Fq::from_le_bytes_mod_order(&bytes[..32]);
+    let y = Fq::from_le_bytes_mod_order(&bytes[32..]);
+    Ok(G1Affine::new_unchecked(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vk;
+    use ark_bn254::{Bn254, Fr};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_groth16::{prepare_verifying_key, Groth16};
+
+    #[test]
+    fn msm_matches_arkworks_prepare_inputs() {
+        let pvk = prepare_verifying_key(&vk::verifying_key());
+        let n = pvk.vk.gamma_abc_g1.len() - 1;
+        let inputs: Vec<Fr> = (0..n as u64).map(|i| Fr::from(i * 7919 + 1)).collect();
+
+        let expected = Groth16::<Bn254>::prepare_inputs(&pvk, &inputs).unwrap();
+        assert_eq!(prepare_inputs(&pvk, &inputs).unwrap(), expected);
+        assert!(prepare_inputs(&pvk, &inputs[1..]).is_err());
+    }
+
+    #[test]
+    fn g1_host_encoding_roundtrips() {
+        let p = (G1Affine::generator() * Fr::from(42u64)).into_affine();
+        for point in [p, G1Affine::identity()] {
+            let mut buf = Vec::new();
+            write_g1(&mut buf, &point);
+            assert_eq!(buf.len(), G1_LEN);
+            assert_eq!(read_g1(&buf).unwrap(), point);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Restricting a deployment to the accounts of one wallet factory.
+//!
+//! With a namespace set (e.g. `.w3a-v1.testnet`), recoveries and command
+//! emails for accounts outside it are rejected with `OUTSIDE_NAMESPACE`, even
+//! when the proof is valid, so an instance deployed for one factory can't be
+//! used to vouch for unrelated accounts.
+
+use near_sdk::{env, near};
+
+use crate::{FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_account_namespace(&self) -> Option<String> {
+        self.account_namespace.clone()
+    }
+
+    /// Only accept accounts ending in `suffix`, which must start with a dot,
+    /// or any account with `None`. Only callable by the owner.
+    pub fn set_account_namespace(&mut self, suffix: Option<String>) {
+        self.assert_owner();
+        if suffix.as_ref().is_some_and(|s| !s.starts_with('.') || s.len() < 2) {
+            env::panic_str("the namespace must be an account suffix starting with '.'");
+        }
+        self.account_namespace = suffix;
+    }
+}
+
+impl ZkEmailVerifier {
+    pub(crate) fn check_namespace_of(&self, account_id: &str) -> Result<(), FailureReason> {
+        match &self.account_namespace {
+            Some(suffix) if !in_namespace(account_id, suffix) => Err(FailureReason::OutsideNamespace),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject a verified `result` for an account outside the namespace.
+    pub(crate) fn check_namespace(&self, result: VerificationResult) -> VerificationResult {
+        if !result.verified {
+            return result;
+        }
+        match self.check_namespace_of(&result.account_id) {
+            Ok(()) => result,
+            Err(reason) => result.with_failure(reason),
+        }
+    }
+}
+
+/// `account_id` is a sub-account somewhere below `suffix`.
+fn in_namespace(account_id: &str, suffix: &str) -> bool {
+    account_id.len() > suffix.len() && account_id.ends_with(suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaces_match_sub_accounts_only() {
+        let suffix = ".w3a-v1.testnet";
+        assert!(in_namespace("kerp30.w3a-v1.testnet", suffix));
+        assert!(in_namespace("a.b.w3a-v1.testnet", suffix));
+        assert!(!in_namespace(".w3a-v1.testnet", suffix));
+        assert!(!in_namespace("w3a-v1.testnet", suffix));
+        assert!(!in_namespace("kerp30.xw3a-v1.testnet.near", suffix));
+        assert!(!in_namespace("alice.testnet", suffix));
+    }
+}
@@ -0,0 +1,78 @@
+//! Defaults picked at `new` from the network the contract is deployed to, so
+//! the same WASM can go to testnet and mainnet without code edits.
+//!
+//! `testnet` starts with every policy off, for experimenting. `mainnet` starts
+//! with a freshness window, the circuit breaker and a recovery cooldown on.
+//! Either way the owner can change them afterwards with
+//! `set_freshness_window_ms`, `set_circuit_breaker` and
+//! `set_recovery_cooldown_ms`.
+
+use near_sdk::near;
+
+use crate::breaker::CircuitBreakerConfig;
+
+/// Oldest email a mainnet deployment accepts by default.
+const MAINNET_FRESHNESS_WINDOW_MS: u64 = 24 * 60 * 60 * 1000;
+/// Time between two recoveries of an account on mainnet by default.
+const MAINNET_RECOVERY_COOLDOWN_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkProfile {
+    #[default]
+    Testnet,
+    Mainnet,
+}
+
+impl NetworkProfile {
+    pub(crate) fn freshness_window_ms(self) -> Option<u64> {
+        match self {
+            NetworkProfile::Testnet => None,
+            NetworkProfile::Mainnet => Some(MAINNET_FRESHNESS_WINDOW_MS),
+        }
+    }
+
+    pub(crate) fn recovery_cooldown_ms(self) -> Option<u64> {
+        match self {
+            NetworkProfile::Testnet => None,
+            NetworkProfile::Mainnet => Some(MAINNET_RECOVERY_COOLDOWN_MS),
+        }
+    }
+
+    pub(crate) fn circuit_breaker(self) -> Option<CircuitBreakerConfig> {
+        match self {
+            NetworkProfile::Testnet => None,
+            NetworkProfile::Mainnet => Some(CircuitBreakerConfig {
+                window_ms: 60 * 60 * 1000,
+                max_verifications_per_account: 5,
+                max_failure_percent: 50,
+                min_samples: 20,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZkEmailVerifier;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn mainnet_starts_with_policies_on() {
+        testing_env!(VMContextBuilder::new().build());
+        let testnet = ZkEmailVerifier::new(None);
+        assert_eq!(testnet.get_verifier_info().network, NetworkProfile::Testnet);
+        assert_eq!(testnet.health_check().freshness_window_ms, None);
+        assert!(testnet.get_circuit_breaker().is_none());
+        assert_eq!(testnet.get_recovery_cooldown_ms(), None);
+
+        let mainnet = ZkEmailVerifier::new(Some(NetworkProfile::Mainnet));
+        assert_eq!(mainnet.get_verifier_info().network, NetworkProfile::Mainnet);
+        assert_eq!(mainnet.health_check().freshness_window_ms, Some(MAINNET_FRESHNESS_WINDOW_MS));
+        assert!(mainnet.get_circuit_breaker().is_some());
+        assert_eq!(mainnet.get_recovery_cooldown_ms(), Some(MAINNET_RECOVERY_COOLDOWN_MS));
+    }
+}
@@ -0,0 +1,232 @@
+//! Nullifiers of consumed recovery requests.
+//!
+//! Each `verify_staged` recovery that passes every check consumes the
+//! nullifier `sha256("<account_id>|<request_id>")`, and any later proof for
+//! the same account and request id is rejected with `NULLIFIER_CONSUMED`,
+//! whether or not the contract issued the id (compare `challenges`).
+//!
+//! Command emails (see `commands`) consume one too, scoped by their circuit:
+//! `sha256("<circuit_id>|<account_id>|<request_id>")`. A `migrate-`, `lock-`
+//! or any other command email is therefore accepted once, and its request
+//! id doesn't collide with a recovery's or another command's.
+//!
+//! Relayers can compute the nullifier of an email before proving it, with
+//! `nullifier_hash`, and check `has_nullifier` to avoid paying for a proof
+//! that would be rejected as a replay.
+//!
+//! Consumed nullifiers are also the leaves of a SHA-256 Merkle tree (see
+//! [`merkle`](crate::merkle)), in the order of `list_nullifiers`. Its root
+//! is updated with each one and published by `get_nullifier_root`, so a
+//! light client or another chain holding a root can be shown that a request
+//! was consumed with an inclusion proof instead of the whole set.
+
+use near_sdk::{env, near};
+
+use crate::commands::{decode_hex_hash, encode_hex, VerifiedCommand};
+use crate::events::Event;
+use crate::merkle::{Hash, IncrementalTree};
+use crate::{FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+const DEFAULT_LIST_LIMIT: u32 = 100;
+const MAX_LIST_LIMIT: u32 = 1_000;
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NullifierRoot {
+    /// Root of the nullifier tree, as 64 hex characters.
+    pub root: String,
+    /// Number of leaves under `root`: the first `len` of `list_nullifiers`.
+    pub len: u64,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// The nullifier a recovery of `account_id` with `request_id` consumes,
+    /// or with `circuit_id`, the one a command email of that circuit
+    /// consumes, as 64 hex characters.
+    pub fn nullifier_hash(&self, account_id: String, request_id: String, circuit_id: Option<String>) -> String {
+        match circuit_id {
+            Some(circuit_id) => encode_hex(&command_nullifier(&circuit_id, &account_id, &request_id)),
+            None => encode_hex(&nullifier(&account_id, &request_id)),
+        }
+    }
+
+    pub fn has_nullifier(&self, nullifier_hex: String) -> bool {
+        let nullifier = decode_hex_hash(&nullifier_hex)
+            .unwrap_or_else(|| env::panic_str("nullifiers are 64 hex characters"));
+        self.nullifiers.contains(&nullifier)
+    }
+
+    /// Consumed nullifiers in the order they were consumed, starting at
+    /// index `from` (default 0). `limit` defaults to 100 and is capped at
+    /// 1000.
+    pub fn list_nullifiers(&self, from: Option<u32>, limit: Option<u32>) -> Vec<String> {
+        let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
+        self.nullifiers
+            .iter()
+            .skip(from.unwrap_or(0) as usize)
+            .take(limit as usize)
+            .map(|nullifier| encode_hex(nullifier))
+            .collect()
+    }
+
+    pub fn get_nullifier_root(&self) -> NullifierRoot {
+        let tree = self.nullifier_tree();
+        NullifierRoot {
+            root: encode_hex(&tree.root(sha256)),
+            len: tree.len,
+        }
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Reject a verified `result` whose nullifier was already consumed.
+    pub(crate) fn check_nullifier(&self, result: VerificationResult) -> VerificationResult {
+        if result.verified && self.nullifiers.contains(&result_nullifier(&result)) {
+            return result.with_failure(FailureReason::NullifierConsumed);
+        }
+        result
+    }
+
+    /// Consume the nullifier of a verified `result` and add it to the tree.
+    pub(crate) fn consume_nullifier(&mut self, result: &VerificationResult) {
+        if result.verified {
+            self.append_nullifier(result_nullifier(result));
+        }
+    }
+
+    /// Consume the nullifier of a verified `command`, failing if it was
+    /// consumed already.
+    pub(crate) fn consume_command_nullifier(&mut self, command: &VerifiedCommand) -> Result<(), FailureReason> {
+        let nullifier = command_nullifier(command.layout_id(), &command.account_id, &command.request_id);
+        if self.nullifiers.contains(&nullifier) {
+            return Err(FailureReason::NullifierConsumed);
+        }
+        self.append_nullifier(nullifier);
+        Ok(())
+    }
+
+    /// Add `nullifier` to the consumed set and the tree, unless it is in
+    /// both already.
+    pub(crate) fn append_nullifier(&mut self, nullifier: Vec<u8>) {
+        let leaf: Hash = nullifier
+            .as_slice()
+            .try_into()
+            .unwrap_or_else(|_| env::panic_str("nullifiers are 32 bytes"));
+        let mut tree = self.nullifier_tree();
+        if !self.nullifiers.insert(nullifier.clone()) {
+            return;
+        }
+        tree.append(leaf, sha256)
+            .unwrap_or_else(|()| env::panic_str("the nullifier tree is full"));
+        self.stats.nullifiers_consumed += 1;
+        Event::NullifierConsumed {
+            nullifier: encode_hex(&nullifier),
+            index: tree.len - 1,
+            root: encode_hex(&tree.root(sha256)),
+        }
+        .emit();
+        self.nullifier_branch.set(Some(tree.branch));
+    }
+
+    fn nullifier_tree(&self) -> IncrementalTree {
+        IncrementalTree {
+            branch: self.nullifier_branch.get().clone().unwrap_or_default(),
+            len: u64::from(self.nullifiers.len()),
+        }
+    }
+}
+
+/// SHA-256 through the host function, for the Merkle trees.
+pub(crate) fn sha256(bytes: &[u8]) -> Hash {
+    env::sha256_array(bytes)
+}
+
+fn nullifier(account_id: &str, request_id: &str) -> Vec<u8> {
+    env::sha256(format!("{account_id}|{request_id}").as_bytes())
+}
+
+fn command_nullifier(circuit_id: &str, account_id: &str, request_id: &str) -> Vec<u8> {
+    env::sha256(format!("{circuit_id}|{account_id}|{request_id}").as_bytes())
+}
+
+fn result_nullifier(result: &VerificationResult) -> Vec<u8> {
+    nullifier(&result.account_id, &result.request_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn a_nullifier_is_consumed_once() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let mut result = VerificationResult::verified_for("alice.near");
+        result.request_id = "abc123".to_string();
+        let hex = contract.nullifier_hash("alice.near".to_string(), "abc123".to_string(), None);
+
+        assert!(!contract.has_nullifier(hex.clone()));
+        let first = contract.check_nullifier(result.clone());
+        contract.consume_nullifier(&first);
+        assert!(contract.has_nullifier(hex.clone()));
+        assert_eq!(contract.get_stats().nullifiers_consumed, 1);
+
+        let replay = contract.check_nullifier(result.clone());
+        assert_eq!(replay.failure_reason.as_deref(), Some("NULLIFIER_CONSUMED"));
+
+        result.request_id = "def456".to_string();
+        contract.consume_nullifier(&contract.check_nullifier(result));
+        assert_eq!(contract.list_nullifiers(None, None).len(), 2);
+        assert_eq!(contract.list_nullifiers(Some(1), Some(5)).len(), 1);
+        assert_eq!(contract.list_nullifiers(None, Some(1)), vec![hex]);
+    }
+
+    #[test]
+    fn command_nullifiers_are_scoped_by_circuit() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let lock = VerifiedCommand::stub("alice.near", "abc123", vec![1; 32]);
+        let hex = contract.nullifier_hash(
+            "alice.near".to_string(),
+            "abc123".to_string(),
+            Some(lock.layout_id().to_string()),
+        );
+
+        assert_eq!(contract.consume_command_nullifier(&lock), Ok(()));
+        assert!(contract.has_nullifier(hex));
+        assert_eq!(contract.consume_command_nullifier(&lock), Err(FailureReason::NullifierConsumed));
+        // A recovery with the same request id is a different nullifier.
+        assert!(!contract.has_nullifier(contract.nullifier_hash(
+            "alice.near".to_string(),
+            "abc123".to_string(),
+            None
+        )));
+    }
+
+    #[test]
+    fn the_root_covers_every_consumed_nullifier() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let mut result = VerificationResult::verified_for("alice.near");
+        let empty = contract.get_nullifier_root();
+        assert_eq!(empty.len, 0);
+
+        let mut leaves = Vec::new();
+        for request_id in ["a", "b", "c"] {
+            result.request_id = request_id.to_string();
+            contract.consume_nullifier(&result);
+            leaves.push(sha256(format!("alice.near|{request_id}").as_bytes()));
+        }
+        let root = contract.get_nullifier_root();
+        assert_eq!(root.len, 3);
+        assert_ne!(root.root, empty.root);
+
+        let proof = merkle::inclusion_proof(&leaves, 1, sha256).unwrap();
+        let root_bytes: Hash = decode_hex_hash(&root.root).unwrap().try_into().unwrap();
+        assert!(merkle::verify_inclusion(&root_bytes, &leaves[1], 1, &proof, sha256));
+    }
+}
@@ -0,0 +1,189 @@
+//! Groth16 verifying key handling for the contract state.
+//!
+//! Preparing a verifying key (computing `e(alpha, beta)` and the line
+//! coefficients for `-gamma` / `-delta`) costs far more gas than
+//! deserializing the result, so the prepared key is computed once in `new()`
+//! or `set_verifying_key()` and stored as an uncompressed ark-serialize blob,
+//! along with its SHA-256 so a corrupted blob is refused instead of trusted.
+
+use ark_bn254::{Bn254, Fq, G1Affine, G2Affine};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::near;
+
+use zk_email_verifier_core::{parse_fq, parse_fq2, SNARKJS_CURVE, SNARKJS_PROTOCOL};
+
+/// Verifying key in the shape of snarkjs's `verification_key.json`.
+/// Only the fields needed to build an arkworks key are read.
+#[near(serializers = [json])]
+pub struct VerifyingKeyInput {
+    /// [x, y, z]; z is assumed to be 1.
+    pub vk_alpha_1: [String; 3],
+    /// [[x0, x1], [y0, y1], [z0, z1]]; z is assumed to be 1.
+    pub vk_beta_2: [[String; 2]; 3],
+    pub vk_gamma_2: [[String; 2]; 3],
+    pub vk_delta_2: [[String; 2]; 3],
+    /// One G1 point per public input, plus the constant term.
+    #[serde(rename = "IC")]
+    pub ic: Vec<[String; 3]>,
+}
+
+/// A verifying key as snarkjs writes `verification_key.json`, returned by
+/// `get_verifying_key_json`. `vk_alphabeta_12` is omitted; snarkjs recomputes
+/// it and the contract never stores it.
+#[near(serializers = [json])]
+pub struct VerifyingKeyJson {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: u32,
+    #[serde(flatten)]
+    pub key: VerifyingKeyInput,
+}
+
+/// Verifying key passed to `new_with_vk`, in either form `set_verifying_key`
+/// and `set_verifying_key_bytes` accept: `{"json": <verification_key.json>}`
+/// or `{"bytes": "<base64 compressed ark-serialize blob>"}`.
+#[near(serializers = [json])]
+#[serde(rename_all = "snake_case")]
+pub enum InitialVerifyingKey {
+    Json(Box<VerifyingKeyInput>),
+    Bytes(Base64VecU8),
+}
+
+impl InitialVerifyingKey {
+    /// Decode and validate the key; `None` if it is malformed.
+    pub(crate) fn parse(&self) -> Option<VerifyingKey<Bn254>> {
+        match self {
+            Self::Json(input) => parse_verifying_key(input).ok(),
+            Self::Bytes(bytes) => deserialize_verifying_key(&bytes.0),
+        }
+    }
+}
+
+fn parse_g1(p: &[String; 3]) -> Result<G1Affine, ()> {
+    let point = G1Affine::new_unchecked(parse_fq(&p[0])?, parse_fq(&p[1])?);
+    if point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve() {
+        Ok(point)
+    } else {
+        Err(())
+    }
+}
+
+fn parse_g2(p: &[[String; 2]; 3]) -> Result<G2Affine, ()> {
+    let point = G2Affine::new_unchecked(parse_fq2(&p[0][0], &p[0][1])?, parse_fq2(&p[1][0], &p[1][1])?);
+    if point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve() {
+        Ok(point)
+    } else {
+        Err(())
+    }
+}
+
+/// Build an arkworks verifying key, checking that every point is on the
+/// curve and in the prime-order subgroup. Stored keys are trusted afterwards.
+pub(crate) fn parse_verifying_key(input: &VerifyingKeyInput) -> Result<VerifyingKey<Bn254>, ()> {
+    Ok(VerifyingKey {
+        alpha_g1: parse_g1(&input.vk_alpha_1)?,
+        beta_g2: parse_g2(&input.vk_beta_2)?,
+        gamma_g2: parse_g2(&input.vk_gamma_2)?,
+        delta_g2: parse_g2(&input.vk_delta_2)?,
+        gamma_abc_g1: input.ic.iter().map(parse_g1).collect::<Result<_, _>>()?,
+    })
+}
+
+fn fq_to_decimal(f: &Fq) -> String {
+    f.into_bigint().to_string()
+}
+
+pub(crate) fn g1_to_json(p: &G1Affine) -> [String; 3] {
+    [fq_to_decimal(&p.x), fq_to_decimal(&p.y), "1".to_string()]
+}
+
+pub(crate) fn g2_to_json(p: &G2Affine) -> [[String; 2]; 3] {
+    [
+        [fq_to_decimal(&p.x.c0), fq_to_decimal(&p.x.c1)],
+        [fq_to_decimal(&p.y.c0), fq_to_decimal(&p.y.c1)],
+        ["1".to_string(), "0".to_string()],
+    ]
+}
+
+/// Inverse of [`parse_verifying_key`], with the header fields snarkjs adds.
+pub(crate) fn verifying_key_json(vk: &VerifyingKey<Bn254>) -> VerifyingKeyJson {
+    VerifyingKeyJson {
+        protocol: SNARKJS_PROTOCOL.to_string(),
+        curve: SNARKJS_CURVE.to_string(),
+        n_public: (vk.gamma_abc_g1.len() - 1) as u32,
+        key: VerifyingKeyInput {
+            vk_alpha_1: g1_to_json(&vk.alpha_g1),
+            vk_beta_2: g2_to_json(&vk.beta_g2),
+            vk_gamma_2: g2_to_json(&vk.gamma_g2),
+            vk_delta_2: g2_to_json(&vk.delta_g2),
+            ic: vk.gamma_abc_g1.iter().map(g1_to_json).collect(),
+        },
+    }
+}
+
+/// Decode a compressed ark-serialize `VerifyingKey`, checking that every point
+/// is on the curve and in the prime-order subgroup.
+pub(crate) fn deserialize_verifying_key(bytes: &[u8]) -> Option<VerifyingKey<Bn254>> {
+    VerifyingKey::deserialize_compressed(bytes).ok()
+}
+
+/// Prepare `vk` and serialize the result for storage.
+pub(crate) fn prepare_and_serialize(vk: &VerifyingKey<Bn254>) -> Vec<u8> {
+    let pvk = prepare_verifying_key(vk);
+    let mut bytes = Vec::with_capacity(pvk.uncompressed_size());
+    pvk.serialize_uncompressed(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    bytes
+}
+
+/// Deserialize a blob written by `prepare_and_serialize`. Curve and subgroup
+/// checks are skipped: the key was validated before it was stored.
+pub(crate) fn deserialize_prepared(bytes: &[u8]) -> Option<PreparedVerifyingKey<Bn254>> {
+    PreparedVerifyingKey::deserialize_uncompressed_unchecked(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vk;
+
+    #[test]
+    fn prepared_key_roundtrips_through_blob() {
+        let vk = vk::verifying_key();
+        let blob = prepare_and_serialize(&vk);
+        let pvk = deserialize_prepared(&blob).unwrap();
+        assert_eq!(pvk, prepare_verifying_key(&vk));
+        assert!(deserialize_prepared(&blob[..blob.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn json_export_matches_snarkjs_key() {
+        let vk = vk::verifying_key();
+        let exported = verifying_key_json(&vk);
+        assert_eq!(parse_verifying_key(&exported.key).unwrap(), vk);
+
+        // Same document as the snapshot `vk.rs` was generated from.
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/verification_key.json");
+        let mut snarkjs: near_sdk::serde_json::Value =
+            near_sdk::serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        snarkjs.as_object_mut().unwrap().remove("vk_alphabeta_12");
+        assert_eq!(near_sdk::serde_json::to_value(&exported).unwrap(), snarkjs);
+    }
+
+    #[test]
+    fn compressed_key_blob_is_validated() {
+        let vk = vk::verifying_key();
+        let mut bytes = Vec::new();
+        vk.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(deserialize_verifying_key(&bytes).unwrap(), vk);
+
+        // Flip a bit in beta's x coordinate: the result is either off the curve
+        // or, given G2's huge cofactor, outside the prime-order subgroup.
+        bytes[32] ^= 1;
+        assert!(deserialize_verifying_key(&bytes).is_none());
+    }
+}
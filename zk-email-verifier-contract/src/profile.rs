@@ -0,0 +1,71 @@
+//! Per-stage gas accounting for `verify`, compiled in with the `profiling`
+//! feature. Deploy a profiling build to testnet or the sandbox and call
+//! `profile_verify` to see where gas goes before optimizing a stage.
+
+use near_sdk::{env, near, Gas};
+
+use zk_email_verifier_core::{parse_public_inputs, verify_with_prepared_inputs};
+
+use crate::{msm, parse_proof, ProofInput, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Gas used by each stage of a single `verify` call, as measured by
+/// `env::used_gas()` around the stage.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyProfile {
+    /// Loading and deserializing the stored prepared verifying key.
+    pub load_vk_gas: Gas,
+    /// Parsing the proof points and public inputs.
+    pub parse_gas: Gas,
+    /// Public-input MSM (`msm::prepare_inputs`).
+    pub prepare_inputs_gas: Gas,
+    /// Multi-Miller loop and final exponentiation.
+    pub pairing_gas: Gas,
+    pub verified: bool,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Run the same steps as `verify` and report the gas spent in each.
+    /// Stages after a failed one are reported as zero.
+    pub fn profile_verify(&self, proof: ProofInput, public_inputs: Vec<String>) -> VerifyProfile {
+        let mut profile = VerifyProfile {
+            load_vk_gas: Gas::from_gas(0),
+            parse_gas: Gas::from_gas(0),
+            prepare_inputs_gas: Gas::from_gas(0),
+            pairing_gas: Gas::from_gas(0),
+            verified: false,
+        };
+
+        let (pvk, gas) = measure(|| self.prepared_verifying_key());
+        profile.load_vk_gas = gas;
+
+        let (parsed, gas) = measure(|| {
+            let proof = parse_proof(proof).ok()?;
+            let inputs = parse_public_inputs(&public_inputs).ok()?;
+            Some((proof, inputs))
+        });
+        profile.parse_gas = gas;
+        let Some((proof, inputs)) = parsed else {
+            return profile;
+        };
+
+        let (prepared, gas) = measure(|| msm::prepare_inputs(&pvk, &inputs));
+        profile.prepare_inputs_gas = gas;
+        let Ok(prepared) = prepared else {
+            return profile;
+        };
+
+        let (verified, gas) = measure(|| verify_with_prepared_inputs(&pvk, &proof, &prepared));
+        profile.pairing_gas = gas;
+        profile.verified = verified;
+
+        profile
+    }
+}
+
+fn measure<T>(stage: impl FnOnce() -> T) -> (T, Gas) {
+    let before = env::used_gas();
+    let out = stage();
+    (out, env::used_gas().saturating_sub(before))
+}
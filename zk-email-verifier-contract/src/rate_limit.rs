@@ -0,0 +1,102 @@
+//! Cap on recoveries across all accounts.
+//!
+//! The circuit breaker reacts to one account being recovered repeatedly or to
+//! a burst of failures; a forged DKIM key or a broken circuit would instead
+//! produce a stream of valid-looking recoveries of different accounts. With a
+//! limit set, at most `max_recoveries` `verify_staged` recoveries are
+//! accepted per fixed window of `window_ms`, and the rest are rejected with
+//! `RATE_LIMITED` until the next window.
+//!
+//! Accounts the owner gives the bypass role (e.g. a security council's
+//! relayer) are neither limited nor counted, so legitimate recoveries can
+//! still go through while the cap is being hit.
+
+use near_sdk::{env, near, AccountId};
+
+use crate::breaker::WindowCounts;
+use crate::{FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryRateLimit {
+    pub window_ms: u64,
+    pub max_recoveries: u32,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_recovery_rate_limit(&self) -> Option<RecoveryRateLimit> {
+        self.recovery_rate_limit.clone()
+    }
+
+    /// Configure (or disable, with `None`) the recovery cap. Resets the
+    /// count. Only callable by the owner.
+    pub fn set_recovery_rate_limit(&mut self, limit: Option<RecoveryRateLimit>) {
+        self.assert_owner();
+        self.recovery_rate_limit = limit;
+        self.recovery_window = WindowCounts::default();
+    }
+
+    pub fn get_rate_limit_bypass_accounts(&self) -> Vec<AccountId> {
+        self.rate_limit_bypass.iter().cloned().collect()
+    }
+
+    /// Grant or revoke the bypass role of `account_id`. Only callable by the
+    /// owner.
+    pub fn set_rate_limit_bypass(&mut self, account_id: AccountId, bypass: bool) {
+        self.assert_owner();
+        if bypass {
+            self.rate_limit_bypass.insert(account_id);
+        } else {
+            self.rate_limit_bypass.remove(&account_id);
+        }
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Count a verified `result` submitted by `submitter` against the cap,
+    /// or reject it if the window is full.
+    pub(crate) fn check_rate_limit(
+        &mut self,
+        result: VerificationResult,
+        submitter: &AccountId,
+    ) -> VerificationResult {
+        let Some(limit) = self.recovery_rate_limit.clone() else {
+            return result;
+        };
+        if !result.verified || self.rate_limit_bypass.contains(submitter) {
+            return result;
+        }
+        self.recovery_window.roll(env::block_timestamp_ms(), limit.window_ms);
+        if self.recovery_window.verified >= limit.max_recoveries {
+            return result.with_failure(FailureReason::RateLimited);
+        }
+        self.recovery_window.verified += 1;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::at;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    fn recoveries_past_the_cap_wait_for_the_next_window() {
+        at(1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_recovery_rate_limit(Some(RecoveryRateLimit { window_ms: 60_000, max_recoveries: 2 }));
+        contract.set_rate_limit_bypass(accounts(4), true);
+        let result = VerificationResult::verified_for("");
+        let mut check = |submitter| contract.check_rate_limit(result.clone(), &submitter).verified;
+
+        assert!(check(accounts(1)));
+        assert!(check(accounts(2)));
+        assert!(!check(accounts(3)));
+        assert!(check(accounts(4)), "the bypass role is not limited");
+
+        at(61_000);
+        assert!(check(accounts(3)));
+    }
+}
@@ -0,0 +1,125 @@
+//! Per-account overrides of the contract-wide recovery settings.
+//!
+//! An account can register a `RecoveryPolicy` to tighten (or relax) what the
+//! owner configured for everyone. Every field is optional and falls back to
+//! the global setting:
+//!
+//! - `timelock_ms` delays the account's migrations and spending limit
+//!   changes instead of `get_migration_timelock_ms`;
+//! - `guardian_threshold` replaces the threshold of its guardian weights,
+//!   from the next guardian epoch like a change of the weights;
+//! - `allowed_relayers` are the only accounts besides itself that may submit
+//!   its recoveries, replacing the relayer set with `set_account_relayer`;
+//! - `max_key_permission` caps the permission of recovered keys: a
+//!   full-access key is downgraded to it.
+
+use near_sdk::{env, near, AccountId};
+
+use crate::key_permission::KeyPermission;
+use crate::treasury::charge_storage;
+use crate::{ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecoveryPolicy {
+    pub timelock_ms: Option<u64>,
+    pub guardian_threshold: Option<u32>,
+    pub allowed_relayers: Option<Vec<AccountId>>,
+    pub max_key_permission: Option<KeyPermission>,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_recovery_policy(&self, account_id: String) -> Option<RecoveryPolicy> {
+        self.recovery_policies.get(&account_id).cloned()
+    }
+
+    /// Register the calling account's policy, or go back to the global
+    /// settings with `None`. Attach the storage the policy takes; the rest
+    /// is refunded.
+    #[payable]
+    pub fn set_recovery_policy(&mut self, policy: Option<RecoveryPolicy>) {
+        let usage_before = env::storage_usage();
+        let account_id = env::predecessor_account_id().to_string();
+        let previous_threshold = self.recovery_policy(&account_id).guardian_threshold;
+        match policy {
+            Some(policy) => {
+                if policy.guardian_threshold == Some(0) {
+                    env::panic_str("the threshold must be positive");
+                }
+                self.recovery_policies.insert(account_id.clone(), policy);
+            }
+            None => {
+                self.recovery_policies.remove(&account_id);
+            }
+        }
+        if self.recovery_policy(&account_id).guardian_threshold != previous_threshold
+            && self.guardian_weights.contains_key(&account_id)
+        {
+            self.schedule_guardian_set(&account_id);
+            self.guardian_sets.flush();
+        }
+        self.recovery_policies.flush();
+        charge_storage(usage_before);
+    }
+}
+
+impl ZkEmailVerifier {
+    pub(crate) fn recovery_policy(&self, account_id: &str) -> RecoveryPolicy {
+        self.recovery_policies.get(account_id).cloned().unwrap_or_default()
+    }
+
+    /// Timelock of the account's migrations and spending limit changes.
+    pub(crate) fn timelock_ms_for(&self, account_id: &str) -> u64 {
+        self.recovery_policy(account_id)
+            .timelock_ms
+            .unwrap_or(self.migration_timelock_ms)
+    }
+}
+
+/// `permission`, downgraded to `max` if it is full access.
+pub(crate) fn cap_key_permission(permission: KeyPermission, max: Option<KeyPermission>) -> KeyPermission {
+    match (permission, max) {
+        (KeyPermission::FullAccess, Some(max)) => max,
+        (permission, _) => permission,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VerificationResult;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    #[test]
+    fn policies_override_the_global_settings() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_millinear(10))
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let result = VerificationResult::verified_for(accounts(1).as_str());
+        let controller = KeyPermission::FunctionCall {
+            receiver_id: accounts(2),
+            method_names: Vec::new(),
+            allowance: None,
+        };
+        contract.set_recovery_policy(Some(RecoveryPolicy {
+            timelock_ms: Some(5),
+            allowed_relayers: Some(vec![accounts(3)]),
+            max_key_permission: Some(controller.clone()),
+            ..RecoveryPolicy::default()
+        }));
+
+        assert_eq!(contract.timelock_ms_for(accounts(1).as_str()), 5);
+        assert_eq!(contract.get_key_permission(accounts(1).to_string()), controller);
+        assert!(contract.check_submitter(result.clone(), &accounts(3)).verified);
+        assert!(contract.check_submitter(result.clone(), &accounts(1)).verified);
+        assert!(!contract.check_submitter(result, &accounts(4)).verified);
+
+        contract.set_recovery_policy(None);
+        assert_eq!(contract.timelock_ms_for(accounts(1).as_str()), contract.get_migration_timelock_ms());
+        assert_eq!(contract.get_key_permission(accounts(1).to_string()), KeyPermission::FullAccess);
+    }
+}
@@ -0,0 +1,166 @@
+//! The email each account registers as its own.
+//!
+//! An account registers the `from_address_hash` commitment of its mailbox
+//! (`sha256("<canonical_from>|<account_id_lower>")`) once, by calling
+//! `register_email` itself. Command emails (see `commands`) are then only
+//! accepted from that address, and changing it takes a command from it (see
+//! `email_change` and `migration`).
+//! Recoveries of a registered account are held to the same rule: a valid
+//! proof of an email from any other address is rejected with
+//! `REGISTERED_EMAIL_MISMATCH`. Accounts that never registered are
+//! unaffected.
+//!
+//! Instead of asserting a commitment it may not own, an account can prove
+//! its mailbox with `register_email_with_proof`: a `register-` email (see
+//! `REGISTER_EMAIL_SUBJECT`) binds the account to the address that sent it.
+//! The proof must still be submitted by the account or its relayer, so a
+//! stranger can't bind their own mailbox to someone else's account.
+
+use near_sdk::{env, near};
+use zk_email_verifier_core::layout::{CircuitKind, REGISTER_EMAIL_CIRCUIT_ID};
+
+use crate::commands::reject;
+use crate::events::Event;
+use crate::treasury::charge_storage;
+use crate::{FailureReason, ProofInput, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near]
+impl ZkEmailVerifier {
+    /// The email commitment registered for `account_id`.
+    pub fn get_registered_email(&self, account_id: String) -> Option<Vec<u8>> {
+        self.registered_emails.get(&account_id).cloned()
+    }
+
+    /// Register `from_address_hash` as the calling account's email. Panics if
+    /// the account already has one; move it with `initiate_migration`.
+    /// Attach the storage the registration takes; the rest is refunded.
+    #[payable]
+    pub fn register_email(&mut self, from_address_hash: Vec<u8>) {
+        if from_address_hash.len() != 32 {
+            env::panic_str("from_address_hash must be a SHA-256 digest");
+        }
+        let usage_before = env::storage_usage();
+        self.store_registered_email(env::predecessor_account_id().to_string(), from_address_hash);
+        self.registered_emails.flush();
+        charge_storage(usage_before);
+    }
+
+    /// Verify a `register-` email and register the address it was sent from
+    /// as its account's email. Returns the registered commitment. Panics if
+    /// the account already has one.
+    pub fn register_email_with_proof(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) -> Vec<u8> {
+        let command = self
+            .verify_command_proof(CircuitKind::Command, REGISTER_EMAIL_CIRCUIT_ID, proof, &public_inputs)
+            .unwrap_or_else(|reason| reject(reason));
+        let submitter = env::predecessor_account_id();
+        if submitter.as_str() != command.account_id
+            && self.account_relayers.get(&command.account_id) != Some(&submitter)
+        {
+            reject(FailureReason::UnauthorizedSubmitter);
+        }
+        self.admit_command(&command, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        self.store_registered_email(command.account_id, command.from_address_hash.clone());
+        command.from_address_hash
+    }
+}
+
+impl ZkEmailVerifier {
+    fn store_registered_email(&mut self, account_id: String, from_address_hash: Vec<u8>) {
+        if self.registered_emails.contains_key(&account_id) {
+            env::panic_str("an email is already registered for this account");
+        }
+        self.registered_emails.insert(account_id.clone(), from_address_hash);
+        Event::EmailRegistered { account_id }.emit();
+    }
+
+    /// Reject a verified recovery `result` of a registered account that was
+    /// sent from another address.
+    pub(crate) fn check_registered_sender(&self, result: VerificationResult) -> VerificationResult {
+        match self.registered_emails.get(&result.account_id) {
+            Some(registered) if result.verified && *registered != result.from_address_hash => {
+                result.with_failure(FailureReason::RegisteredEmailMismatch)
+            }
+            _ => result,
+        }
+    }
+
+    /// Require `from_address_hash` to be the registered email of
+    /// `account_id`.
+    pub(crate) fn check_registered_email(
+        &self,
+        account_id: &str,
+        from_address_hash: &[u8],
+    ) -> Result<(), FailureReason> {
+        match self.registered_emails.get(account_id) {
+            None => Err(FailureReason::EmailNotRegistered),
+            Some(registered) if registered == from_address_hash => Ok(()),
+            Some(_) => Err(FailureReason::RegisteredEmailMismatch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    #[test]
+    fn commands_need_the_registered_email() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_millinear(10))
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let account = accounts(1).to_string();
+        let hash = env::sha256(b"alice@example.com|bob");
+        assert_eq!(
+            contract.check_registered_email(&account, &hash),
+            Err(FailureReason::EmailNotRegistered)
+        );
+
+        contract.register_email(hash.clone());
+        assert_eq!(contract.get_registered_email(account.clone()), Some(hash.clone()));
+        assert_eq!(contract.check_registered_email(&account, &hash), Ok(()));
+        assert_eq!(
+            contract.check_registered_email(&account, &env::sha256(b"mallory")),
+            Err(FailureReason::RegisteredEmailMismatch)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "to cover storage")]
+    fn registrations_pay_for_their_storage() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.register_email(env::sha256(b"alice"));
+    }
+
+    #[test]
+    fn registered_accounts_only_recover_from_their_email() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_millinear(10))
+            .build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let mut result = VerificationResult::verified_for(accounts(1).as_str());
+        result.from_address_hash = env::sha256(b"mallory");
+        assert!(contract.check_registered_sender(result.clone()).verified);
+
+        contract.register_email(env::sha256(b"alice"));
+        let rejected = contract.check_registered_sender(result.clone());
+        assert_eq!(rejected.failure_reason.as_deref(), Some("REGISTERED_EMAIL_MISMATCH"));
+
+        result.from_address_hash = env::sha256(b"alice");
+        assert!(contract.check_registered_sender(result).verified);
+    }
+}
@@ -0,0 +1,150 @@
+//! Verifying keys read from a shared registry contract.
+//!
+//! When the owner points the verifier at a registry with `set_vk_registry`,
+//! every `verify_staged` call first reads the key of the selected circuit
+//! from the registry's `get_verifying_key(circuit_id)` view and then stages
+//! the proof in the callback. The registry versions its keys; a key is only
+//! parsed and stored when its version is newer than the one cached here, so
+//! many verifier instances pick up a rotation on their next verification
+//! without each owner calling `set_verifying_key`.
+//!
+//! If the registry read fails or has no key for the circuit, the cached key
+//! is used as is.
+
+use near_sdk::{
+    env, near, serde_json, AccountId, Gas, GasWeight, NearToken, Promise, PromiseError,
+    PromiseOrValue,
+};
+
+use crate::staged::StagedRequest;
+use crate::{
+    prepared_vk, VerificationResult, VerifyingKeyInput, ZkEmailVerifier, ZkEmailVerifierExt,
+};
+
+/// Gas for the registry's `get_verifying_key` view.
+const REGISTRY_READ_GAS: Gas = Gas::from_tgas(10);
+/// Gas reserved for `on_registry_verifying_key` on top of its share of the
+/// unused gas; it still has to stage the proof.
+const ON_REGISTRY_KEY_GAS: Gas = Gas::from_tgas(60);
+
+/// What a registry's `get_verifying_key(circuit_id)` returns.
+#[near(serializers = [json])]
+pub struct RegistryVerifyingKey {
+    /// Increases with every key the registry publishes for the circuit.
+    pub version: u64,
+    pub vk: VerifyingKeyInput,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_vk_registry(&self) -> Option<AccountId> {
+        self.vk_registry.clone()
+    }
+
+    /// Read verifying keys from `registry` before each `verify_staged`, or
+    /// stop with `None` and keep the keys cached so far. Only callable by the
+    /// owner.
+    pub fn set_vk_registry(&mut self, registry: Option<AccountId>) {
+        self.assert_owner();
+        self.vk_registry = registry;
+    }
+
+    /// Callback of the registry read scheduled by `verify_staged`: cache the
+    /// key if it is newer, then stage `request`.
+    #[private]
+    pub fn on_registry_verifying_key(
+        &mut self,
+        circuit_id: String,
+        request: StagedRequest,
+        #[callback_result] key: Result<Option<RegistryVerifyingKey>, PromiseError>,
+    ) -> PromiseOrValue<VerificationResult> {
+        match key {
+            Ok(Some(key)) => self.cache_registry_key(&circuit_id, key),
+            Ok(None) => env::log_str(&format!("registry has no verifying key for {circuit_id}")),
+            Err(_) => env::log_str("registry read failed; using the cached verifying key"),
+        }
+        self.stage_verification(circuit_id, request)
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Read the key of `circuit_id` from `registry`, then stage `request`
+    /// with it in `on_registry_verifying_key`.
+    pub(crate) fn read_registry_then_stage(
+        &self,
+        registry: AccountId,
+        circuit_id: String,
+        request: StagedRequest,
+    ) -> Promise {
+        let read_args = serde_json::json!({ "circuit_id": circuit_id }).to_string().into_bytes();
+        let callback_args = serde_json::json!({ "circuit_id": circuit_id, "request": request })
+            .to_string()
+            .into_bytes();
+        Promise::new(registry)
+            .function_call("get_verifying_key", read_args, NearToken::from_yoctonear(0), REGISTRY_READ_GAS)
+            .then(Promise::new(env::current_account_id()).function_call_weight(
+                "on_registry_verifying_key",
+                callback_args,
+                NearToken::from_yoctonear(0),
+                ON_REGISTRY_KEY_GAS,
+                GasWeight(1),
+            ))
+    }
+
+    /// Store `key` for `circuit_id` if it is newer than the cached version.
+    /// A key that doesn't parse or has the wrong number of `IC` points is
+    /// logged and skipped rather than failing the verification.
+    pub(crate) fn cache_registry_key(&mut self, circuit_id: &str, key: RegistryVerifyingKey) {
+        let cached = self.registry_vk_versions.get(circuit_id).copied();
+        if cached.is_some_and(|cached| key.version <= cached) {
+            return;
+        }
+        let vk = match prepared_vk::parse_verifying_key(&key.vk) {
+            Ok(vk) if vk.gamma_abc_g1.len() == self.layout_of(circuit_id).len + 1 => vk,
+            _ => {
+                env::log_str(&format!(
+                    "registry verifying key {circuit_id} v{} is invalid; keeping the cached key",
+                    key.version
+                ));
+                return;
+            }
+        };
+        self.put_verifying_key(circuit_id, &vk);
+        self.registry_vk_versions.insert(circuit_id.to_string(), key.version);
+        env::log_str(&format!("cached registry verifying key {circuit_id} v{}", key.version));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn only_newer_registry_keys_replace_the_cached_one() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let vk = || prepared_vk::verifying_key_json(&crate::vk::verifying_key()).key;
+        let hash = contract.prepared_vk_hash(RECOVER_EMAIL_CIRCUIT_ID);
+
+        contract.cache_registry_key("recover_email_outlook", RegistryVerifyingKey { version: 2, vk: vk() });
+        assert!(contract.circuit_ids().contains(&"recover_email_outlook".to_string()));
+        assert_eq!(contract.prepared_vk_hash("recover_email_outlook"), hash);
+
+        // An older version is ignored, so even a broken key doesn't matter.
+        let broken = || {
+            let mut vk = vk();
+            vk.ic.pop();
+            vk
+        };
+        contract.cache_registry_key("recover_email_outlook", RegistryVerifyingKey { version: 1, vk: broken() });
+        assert_eq!(contract.registry_vk_versions.get("recover_email_outlook"), Some(&2));
+
+        // A newer but invalid one is skipped without bumping the version.
+        contract.cache_registry_key("recover_email_outlook", RegistryVerifyingKey { version: 3, vk: broken() });
+        assert_eq!(contract.registry_vk_versions.get("recover_email_outlook"), Some(&2));
+        assert_eq!(contract.prepared_vk_hash("recover_email_outlook"), hash);
+    }
+}
@@ -0,0 +1,156 @@
+//! Who may submit a recovery for an account through `verify_staged`.
+//!
+//! A valid proof is enough to verify, whoever sends it. With predecessor
+//! binding on, the stateful path additionally requires the transaction to
+//! come from the recovering account itself or from the relayer registered
+//! for it, so a third party holding a copy of the proof can't trigger the
+//! recovery's side effects (audit record, attestation, cached result) on the
+//! account's behalf. Proofs sent by anyone else are rejected with
+//! `UNAUTHORIZED_SUBMITTER` after the pairing check.
+//!
+//! An account can also opt in on its own with `authorize_relayer`: once it
+//! has a relayer, its recoveries are bound to it (and to the account itself)
+//! whether or not binding is on contract-wide.
+
+use near_sdk::{env, near, AccountId};
+
+use crate::treasury::charge_storage;
+use crate::{FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_predecessor_binding(&self) -> bool {
+        self.predecessor_binding
+    }
+
+    /// Require `verify_staged` to be called by the recovering account or its
+    /// relayer. Only callable by the owner.
+    pub fn set_predecessor_binding(&mut self, enabled: bool) {
+        self.assert_owner();
+        self.predecessor_binding = enabled;
+    }
+
+    pub fn get_account_relayer(&self, account_id: String) -> Option<AccountId> {
+        self.account_relayers.get(&account_id).cloned()
+    }
+
+    /// Let `relayer_id` submit recoveries of the calling account, and nobody
+    /// else but the account itself. Replaces any earlier relayer. Attach the
+    /// storage the entry takes; the rest is refunded.
+    #[payable]
+    pub fn authorize_relayer(&mut self, relayer_id: AccountId) {
+        let usage_before = env::storage_usage();
+        self.account_relayers
+            .insert(env::predecessor_account_id().to_string(), relayer_id);
+        self.account_relayers.flush();
+        charge_storage(usage_before);
+    }
+
+    /// Remove the calling account's relayer. Its recoveries are then only
+    /// bound if predecessor binding is on.
+    pub fn revoke_relayer(&mut self) {
+        self.account_relayers
+            .remove(env::predecessor_account_id().as_str());
+    }
+
+    /// Register `relayer_id` as the account allowed to submit recoveries of
+    /// `account_id`, or remove it with `None`. Only callable by the owner.
+    pub fn set_account_relayer(&mut self, account_id: String, relayer_id: Option<AccountId>) {
+        self.assert_owner();
+        match relayer_id {
+            Some(relayer_id) => self.account_relayers.insert(account_id, relayer_id),
+            None => self.account_relayers.remove(&account_id),
+        };
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Whether `caller` is `account_id` itself, its registered relayer or one
+    /// of the relayers its recovery policy allows.
+    pub(crate) fn is_account_or_relayer(&self, account_id: &str, caller: &AccountId) -> bool {
+        caller.as_str() == account_id
+            || self.account_relayers.get(account_id) == Some(caller)
+            || self
+                .recovery_policy(account_id)
+                .allowed_relayers
+                .is_some_and(|relayers| relayers.contains(caller))
+    }
+
+    /// Reject a verified `result` submitted by `submitter` if the account is
+    /// bound (predecessor binding is on, or it has a relayer) and `submitter`
+    /// is neither the account nor its relayer. A recovery policy with
+    /// `allowed_relayers` binds the account to those instead.
+    pub(crate) fn check_submitter(
+        &self,
+        result: VerificationResult,
+        submitter: &AccountId,
+    ) -> VerificationResult {
+        if !result.verified {
+            return result;
+        }
+        if let Some(allowed_relayers) = self.recovery_policy(&result.account_id).allowed_relayers {
+            if submitter.as_str() == result.account_id || allowed_relayers.contains(submitter) {
+                return result;
+            }
+            return result.with_failure(FailureReason::UnauthorizedSubmitter);
+        }
+        let relayer = self.account_relayers.get(&result.account_id);
+        if !self.predecessor_binding && relayer.is_none() {
+            return result;
+        }
+        let allowed = submitter.as_str() == result.account_id || relayer == Some(submitter);
+        if allowed {
+            result
+        } else {
+            result.with_failure(FailureReason::UnauthorizedSubmitter)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    #[test]
+    fn binding_admits_the_account_and_its_relayer() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let result = VerificationResult::verified_for(accounts(1).as_str());
+        let reason = |r: VerificationResult| r.failure_reason;
+
+        assert_eq!(reason(contract.check_submitter(result.clone(), &accounts(2))), None);
+
+        contract.set_predecessor_binding(true);
+        assert_eq!(reason(contract.check_submitter(result.clone(), &accounts(1))), None);
+        assert_eq!(
+            reason(contract.check_submitter(result.clone(), &accounts(2))).as_deref(),
+            Some("UNAUTHORIZED_SUBMITTER")
+        );
+
+        contract.set_account_relayer(accounts(1).to_string(), Some(accounts(2)));
+        assert_eq!(reason(contract.check_submitter(result.clone(), &accounts(2))), None);
+        assert!(contract.check_submitter(result, &accounts(3)).failure_reason.is_some());
+    }
+
+    #[test]
+    fn an_authorized_relayer_binds_the_account_on_its_own() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let result = VerificationResult::verified_for(accounts(1).as_str());
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_millinear(10))
+            .build());
+        contract.authorize_relayer(accounts(2));
+        assert_eq!(contract.get_account_relayer(accounts(1).to_string()), Some(accounts(2)));
+        assert!(contract.check_submitter(result.clone(), &accounts(2)).verified);
+        assert!(contract.check_submitter(result.clone(), &accounts(1)).verified);
+        assert!(!contract.check_submitter(result.clone(), &accounts(3)).verified);
+
+        contract.revoke_relayer();
+        assert!(contract.check_submitter(result, &accounts(3)).verified);
+    }
+}
@@ -0,0 +1,130 @@
+//! Merkle accumulator over the results of stateful verifications.
+//!
+//! Every verified `verify_staged` result is folded into an append-only
+//! SHA-256 Merkle tree as `attestation_hash(result)` (see `attestation`), in
+//! the order the callbacks ran. `get_result_root` publishes the root, so a
+//! system that tracks it can check "this contract produced result R" from
+//! R, its leaf index and a sibling path, instead of a state proof per
+//! record. Rejected results are left out: anyone can produce those for any
+//! account.
+//!
+//! Unlike attestations, results are accumulated without the caller asking
+//! for it. The leaves stay readable through `list_result_hashes`, which is
+//! what off-chain services build inclusion proofs from (see
+//! [`merkle`](crate::merkle)).
+
+use near_sdk::{env, near};
+
+use crate::attestation::result_hash;
+use crate::commands::encode_hex;
+use crate::events::Event;
+use crate::merkle::{Hash, IncrementalTree};
+use crate::nullifiers::sha256;
+use crate::{VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+const DEFAULT_LIST_LIMIT: u32 = 100;
+const MAX_LIST_LIMIT: u32 = 1_000;
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResultRoot {
+    /// Root of the result accumulator, as 64 hex characters.
+    pub root: String,
+    /// Number of results folded into `root`.
+    pub len: u64,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_result_root(&self) -> ResultRoot {
+        let tree = self.result_tree();
+        ResultRoot {
+            root: encode_hex(&tree.root(sha256)),
+            len: tree.len,
+        }
+    }
+
+    /// Accumulated result hashes in hex, oldest first, starting at index
+    /// `from` (default 0). `limit` defaults to 100 and is capped at 1000.
+    pub fn list_result_hashes(&self, from: Option<u64>, limit: Option<u32>) -> Vec<String> {
+        let from = from.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
+        (from..self.result_count.min(from.saturating_add(u64::from(limit))))
+            .filter_map(|i| self.result_leaves.get(&i))
+            .map(|leaf| encode_hex(leaf))
+            .collect()
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Fold a verified `result` into the accumulator.
+    pub(crate) fn accumulate_result(&mut self, result: &VerificationResult) {
+        if !result.verified {
+            return;
+        }
+        let leaf: Hash = result_hash(result).try_into().expect("result hashes are SHA-256 digests");
+        let tree = self.append_result_leaf(leaf);
+        Event::ResultAccumulated {
+            result_hash: encode_hex(&leaf),
+            index: tree.len - 1,
+            root: encode_hex(&tree.root(sha256)),
+        }
+        .emit();
+    }
+
+    /// Append `leaf` to the accumulator and return the tree that now holds it.
+    pub(crate) fn append_result_leaf(&mut self, leaf: Hash) -> IncrementalTree {
+        let mut tree = self.result_tree();
+        tree.append(leaf, sha256)
+            .unwrap_or_else(|()| env::panic_str("the result accumulator is full"));
+        self.result_leaves.insert(self.result_count, leaf);
+        self.result_count = tree.len;
+        self.result_branch.set(Some(tree.branch.clone()));
+        tree
+    }
+
+    pub(crate) fn result_tree(&self) -> IncrementalTree {
+        IncrementalTree {
+            branch: self.result_branch.get().clone().unwrap_or_default(),
+            len: self.result_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::decode_hex_hash;
+    use crate::{merkle, FailureReason};
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn verified_results_are_provable_against_the_root() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let rejected = VerificationResult::rejected(FailureReason::Pairing);
+        contract.accumulate_result(&rejected);
+        assert_eq!(contract.get_result_root().len, 0);
+
+        let mut results = Vec::new();
+        for account_id in ["alice.near", "bob.near"] {
+            let result = VerificationResult::verified_for(account_id);
+            contract.accumulate_result(&result);
+            results.push(result);
+        }
+        let root = contract.get_result_root();
+        assert_eq!(root.len, 2);
+
+        let leaves: Vec<Hash> = contract
+            .list_result_hashes(None, None)
+            .iter()
+            .map(|hex| decode_hex_hash(hex).unwrap().try_into().unwrap())
+            .collect();
+        assert_eq!(leaves[1].to_vec(), contract.attestation_hash(results[1].clone()));
+        let proof = merkle::inclusion_proof(&leaves, 1, sha256).unwrap();
+        let root: Hash = decode_hex_hash(&root.root).unwrap().try_into().unwrap();
+        assert!(merkle::verify_inclusion(&root, &leaves[1], 1, &proof, sha256));
+        assert_eq!(contract.list_result_hashes(Some(1), Some(5)).len(), 1);
+    }
+}
@@ -0,0 +1,288 @@
+//! Results of staged verifications cached by proof hash, for relayers that
+//! retry transactions.
+//!
+//! With a TTL set, `complete_staged_verification` stores its result under
+//! the submitter and `sha256(proof || public_inputs)`, and a later
+//! `verify_staged` by the same submitter with the same proof and inputs
+//! returns that result without a second pairing check, audit record, or
+//! anything else the first verification did. The recovery happened the
+//! first time, so the replay comes back with `verified: false` and
+//! `CACHED_REPLAY`, its other fields as they were; it can't be mistaken for
+//! a recovery that passed the nullifier, challenge, cooldown and rate-limit
+//! checks again. The retry still pays the fee. Entries expire after the TTL
+//! and are dropped when next looked up, or by anyone through
+//! `prune_result_cache`. Off by default.
+//!
+//! Only verified results are cached, so a rejected proof can't take up
+//! storage, and at most `MAX_CACHED_RESULTS` at a time: past that, results
+//! are not cached until expired entries are pruned. While caching is on,
+//! `verify_staged` holds `CACHED_RESULT_BYTES` of storage out of the
+//! attached deposit, refunded to the submitter when the result isn't cached
+//! or its entry is dropped.
+//!
+//! Cached results are also indexed by their `request_id`, so a relayer that
+//! lost the receipt can read the outcome back with `get_cached_result`
+//! instead of resubmitting the proof.
+
+use near_sdk::{env, near, AccountId, NearToken, Promise};
+
+use crate::{FailureReason, ProofInput, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Upper bound on the number of cached results.
+pub const MAX_CACHED_RESULTS: u32 = 10_000;
+
+/// Storage held per cached result: the key, a verified result with a few
+/// public keys, its `request_id` index and the maps' overhead.
+pub const CACHED_RESULT_BYTES: u64 = 1_500;
+
+#[near(serializers = [borsh])]
+pub(crate) struct CachedResult {
+    result: VerificationResult,
+    expires_at_ms: u64,
+    submitter: AccountId,
+    /// Held out of the submitter's deposit, refunded when the entry is
+    /// dropped.
+    storage_deposit: NearToken,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// The cached verified result for `request_id`, while it hasn't expired.
+    pub fn get_cached_result(&self, request_id: String) -> Option<VerificationResult> {
+        let proof_hash = self.cached_request_ids.get(&request_id)?;
+        self.result_cache
+            .get(proof_hash)
+            .filter(|entry| entry.expires_at_ms > env::block_timestamp_ms())
+            .map(|entry| entry.result.clone())
+    }
+
+    pub fn get_result_cache_ttl_ms(&self) -> Option<u64> {
+        self.result_cache_ttl_ms
+    }
+
+    /// Cache staged verification results for `ttl_ms`, or stop caching with
+    /// `None`. Entries already cached keep their expiry but are no longer
+    /// served. Only callable by the owner.
+    pub fn set_result_cache_ttl_ms(&mut self, ttl_ms: Option<u64>) {
+        self.assert_owner();
+        self.result_cache_ttl_ms = ttl_ms;
+    }
+
+    pub fn get_result_cache_len(&self) -> u32 {
+        self.result_cache.len()
+    }
+
+    /// Look at up to `limit` cached results and drop the expired ones,
+    /// returning how many were dropped. Each call continues where the last
+    /// one stopped and wraps around at the end, so repeated calls reach
+    /// every entry. Callable by anyone: only expired entries are touched.
+    pub fn prune_result_cache(&mut self, limit: u32) -> u32 {
+        let now_ms = env::block_timestamp_ms();
+        let len = self.result_cache.len();
+        let start = if self.result_cache_prune_cursor < len { self.result_cache_prune_cursor } else { 0 };
+        let scanned = limit.min(len - start);
+        let expired: Vec<Vec<u8>> = self
+            .result_cache
+            .iter()
+            .skip(start as usize)
+            .take(scanned as usize)
+            .filter(|(_, entry)| entry.expires_at_ms <= now_ms)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.drop_cached(key);
+        }
+        // Removal moves the last entries into the freed slots, so the kept
+        // ones shift down by as many as were dropped.
+        self.result_cache_prune_cursor = start + scanned - expired.len() as u32;
+        expired.len() as u32
+    }
+}
+
+impl ZkEmailVerifier {
+    /// The unexpired cached result for `key`, if caching is on, as the
+    /// `CACHED_REPLAY` rejection a resubmission gets.
+    pub(crate) fn cached_result(&mut self, key: &[u8]) -> Option<VerificationResult> {
+        self.result_cache_ttl_ms?;
+        let entry = self.result_cache.get(key)?;
+        if entry.expires_at_ms <= env::block_timestamp_ms() {
+            self.drop_cached(key);
+            return None;
+        }
+        Some(entry.result.clone().with_failure(FailureReason::CachedReplay))
+    }
+
+    /// Cache a verified `result` of `submitter` under `key`, holding
+    /// `cached_result_deposit()` of theirs. Rejections aren't cached, nor is
+    /// anything while caching is off or the cache is full; the deposit is
+    /// refunded then.
+    pub(crate) fn cache_result(&mut self, key: Vec<u8>, result: &VerificationResult, submitter: &AccountId) {
+        let ttl_ms = match self.result_cache_ttl_ms {
+            Some(ttl_ms) if result.verified => ttl_ms,
+            _ => return refund_cache_deposit(submitter),
+        };
+        self.drop_cached(&key);
+        if self.result_cache.len() >= MAX_CACHED_RESULTS {
+            env::log_str("result cache is full; prune expired entries to cache more");
+            return refund_cache_deposit(submitter);
+        }
+        let entry = CachedResult {
+            result: result.clone(),
+            expires_at_ms: env::block_timestamp_ms().saturating_add(ttl_ms),
+            submitter: submitter.clone(),
+            storage_deposit: cached_result_deposit(),
+        };
+        self.cached_request_ids
+            .insert(result.request_id.clone(), key.clone());
+        self.result_cache.insert(key, entry);
+    }
+
+    /// Remove the entry under `key` and its `request_id` index, refunding
+    /// its storage to the submitter.
+    fn drop_cached(&mut self, key: &[u8]) {
+        let Some(entry) = self.result_cache.remove(key) else {
+            return;
+        };
+        let request_id = entry.result.request_id;
+        if self.cached_request_ids.get(&request_id).is_some_and(|k| k == key) {
+            self.cached_request_ids.remove(&request_id);
+        }
+        if !entry.storage_deposit.is_zero() {
+            Promise::new(entry.submitter).transfer(entry.storage_deposit).detach();
+        }
+    }
+}
+
+/// What a staged verification holds of the attached deposit to cache its
+/// result.
+pub(crate) fn cached_result_deposit() -> NearToken {
+    env::storage_byte_cost().saturating_mul(u128::from(CACHED_RESULT_BYTES))
+}
+
+/// Return the deposit held for a result that won't be cached.
+pub(crate) fn refund_cache_deposit(submitter: &AccountId) {
+    Promise::new(submitter.clone()).transfer(cached_result_deposit()).detach();
+}
+
+/// Key of a cached result: `sha256(submitter || NUL || proof_hash)`.
+pub(crate) fn cache_key(submitter: &AccountId, proof: &ProofInput, public_inputs: &[String]) -> Vec<u8> {
+    env::sha256([submitter.as_bytes(), &[0], &proof_hash(proof, public_inputs)].concat())
+}
+
+/// `sha256` over every proof coordinate and public input, each followed by a
+/// NUL byte. Computed from the strings as submitted, so a retry must resend
+/// them unchanged to hit the cache.
+pub(crate) fn proof_hash(proof: &ProofInput, public_inputs: &[String]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let coordinates = proof.pi_a.iter().chain(proof.pi_b.iter().flatten()).chain(&proof.pi_c);
+    for s in coordinates.chain(public_inputs) {
+        bytes.extend_from_slice(s.as_bytes());
+        bytes.push(0);
+    }
+    env::sha256(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::at;
+    use crate::FailureReason;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn verified(request_id: &str) -> VerificationResult {
+        let mut result = VerificationResult::verified_for("");
+        result.request_id = request_id.to_string();
+        result
+    }
+
+    #[test]
+    fn cached_results_expire_after_the_ttl() {
+        at(1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+
+        contract.cache_result(vec![1], &verified("req-1"), &accounts(1));
+        assert!(contract.cached_result(&[1]).is_none(), "caching is off by default");
+
+        contract.set_result_cache_ttl_ms(Some(500));
+        contract.cache_result(vec![1], &verified("req-1"), &accounts(1));
+        contract.cache_result(vec![2], &VerificationResult::rejected(FailureReason::Pairing), &accounts(1));
+        assert!(contract.result_cache.get(&[2][..]).is_none(), "rejections aren't cached");
+
+        assert_eq!(contract.result_cache.get(&[1][..]).unwrap().storage_deposit, cached_result_deposit());
+
+        at(1_499);
+        let replay = contract.cached_result(&[1]).unwrap();
+        assert!(!replay.verified, "replays aren't verifications");
+        assert_eq!(replay.failure_reason.as_deref(), Some("CACHED_REPLAY"));
+        assert_eq!(replay.request_id, "req-1");
+        assert!(contract.get_cached_result("req-1".to_string()).is_some_and(|r| r.verified));
+
+        at(1_500);
+        assert!(contract.get_cached_result("req-1".to_string()).is_none());
+        assert!(contract.cached_result(&[1]).is_none());
+        assert!(contract.result_cache.get(&[1][..]).is_none());
+        assert!(contract.cached_request_ids.get("req-1").is_none());
+    }
+
+    #[test]
+    fn expired_results_can_be_pruned_by_anyone() {
+        at(1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_result_cache_ttl_ms(Some(500));
+        for i in 0..3u8 {
+            contract.cache_result(vec![i], &verified(&format!("req-{i}")), &accounts(1));
+        }
+        at(1_200);
+        contract.cache_result(vec![9], &verified("req-9"), &accounts(1));
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(1_600 * 1_000_000)
+            .build());
+        assert_eq!(contract.prune_result_cache(2), 2);
+        assert_eq!(contract.prune_result_cache(10), 1);
+        assert_eq!(contract.get_result_cache_len(), 1);
+        assert!(contract.get_cached_result("req-9".to_string()).is_some());
+        assert!(contract.cached_request_ids.get("req-0").is_none());
+    }
+
+    #[test]
+    fn pruning_scans_at_most_limit_entries_per_call() {
+        at(1_000);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_result_cache_ttl_ms(Some(10_000));
+        for i in 7..9u8 {
+            contract.cache_result(vec![i], &verified(&format!("req-{i}")), &accounts(1));
+        }
+        contract.set_result_cache_ttl_ms(Some(500));
+        for i in 0..3u8 {
+            contract.cache_result(vec![i], &verified(&format!("req-{i}")), &accounts(1));
+        }
+
+        at(1_600);
+        assert_eq!(contract.prune_result_cache(2), 0, "only the live entries were looked at");
+        assert_eq!(contract.prune_result_cache(2), 2);
+        assert_eq!(contract.prune_result_cache(2), 1);
+        assert_eq!(contract.prune_result_cache(2), 0);
+        assert_eq!(contract.get_result_cache_len(), 2);
+        assert!(contract.get_cached_result("req-7".to_string()).is_some());
+    }
+
+    #[test]
+    fn proof_hash_separates_fields() {
+        let proof = ProofInput {
+            pi_a: ["1".into(), "2".into(), "1".into()],
+            pi_b: core::array::from_fn(|_| ["0".into(), "0".into()]),
+            pi_c: ["1".into(), "2".into(), "1".into()],
+            protocol: None,
+            curve: None,
+        };
+        let hash = proof_hash(&proof, &["12".into(), "3".into()]);
+        assert_eq!(hash, proof_hash(&proof, &["12".into(), "3".into()]));
+        assert_ne!(hash, proof_hash(&proof, &["1".into(), "23".into()]));
+
+        let inputs = ["12".to_string(), "3".to_string()];
+        assert_ne!(cache_key(&accounts(0), &proof, &inputs), cache_key(&accounts(1), &proof, &inputs));
+    }
+}
@@ -0,0 +1,194 @@
+//! Owner-managed policy on who may send recovery emails.
+//!
+//! `RecoverEmailCircuit` only commits to `sha256("<from>|<account_id>")`, so
+//! the sender's domain can't be read from the public inputs. Callers pass the
+//! `From:` header to `verify_with_binding` or `verify_staged`; it is checked
+//! against the committed hash first, and only then is its domain looked up.
+//!
+//! With an empty allowlist every domain is accepted. Once domains are set, a
+//! verification without a matching `From:` header is rejected as well, with
+//! `SENDER_DOMAIN`. Methods that don't take the header (`verify`,
+//! `verify_batch`, ...) don't apply the policy.
+//!
+//! The blocklist holds SHA-256 digests of lowercased addresses or domains
+//! (see `sender_block_hash`), so the contract state doesn't list them in
+//! clear. A sender whose address or domain is blocked is rejected with
+//! `SENDER_BLOCKED`, and as with the allowlist, a non-empty blocklist makes
+//! the `From:` header mandatory.
+
+use near_sdk::{env, near};
+
+use crate::binding::nfc;
+use crate::email::{canonicalize_address, extract_addr_spec, from_address_hash};
+use crate::{FailureReason, VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_allowed_sender_domains(&self) -> Vec<String> {
+        self.allowed_sender_domains.clone()
+    }
+
+    /// Replace the sender-domain allowlist; an empty list allows every
+    /// domain. Domains are matched case-insensitively. Only callable by the
+    /// owner.
+    pub fn set_allowed_sender_domains(&mut self, domains: Vec<String>) {
+        self.assert_owner();
+        let mut domains: Vec<String> = domains.iter().map(|d| d.trim().to_ascii_lowercase()).collect();
+        domains.sort();
+        domains.dedup();
+        env::log_str(&format!("allowed sender domains: {}", domains.join(", ")));
+        self.allowed_sender_domains = domains;
+    }
+
+    /// The blocklist entry for an address or a domain.
+    pub fn sender_block_hash(&self, address_or_domain: String) -> Vec<u8> {
+        block_hash(&address_or_domain)
+    }
+
+    pub fn is_sender_blocked(&self, hash: Vec<u8>) -> bool {
+        self.blocked_senders.contains(&hash)
+    }
+
+    /// Add `sender_block_hash` digests to the blocklist. Only callable by
+    /// the owner.
+    pub fn block_senders(&mut self, hashes: Vec<Vec<u8>>) {
+        self.assert_owner();
+        for hash in hashes {
+            self.blocked_senders.insert(hash);
+        }
+    }
+
+    pub fn unblock_senders(&mut self, hashes: Vec<Vec<u8>>) {
+        self.assert_owner();
+        for hash in &hashes {
+            self.blocked_senders.remove(hash);
+        }
+    }
+}
+
+/// Lowercased domain of the address in a `From:` header.
+pub(crate) fn sender_domain(from_header: &str) -> Option<String> {
+    let address = canonicalize_address(&extract_addr_spec(&nfc(from_header))?);
+    address.rsplit_once('@').map(|(_, domain)| domain.to_string())
+}
+
+fn block_hash(address_or_domain: &str) -> Vec<u8> {
+    env::sha256(address_or_domain.trim().to_ascii_lowercase().as_bytes())
+}
+
+impl ZkEmailVerifier {
+    /// Apply the sender policy to a verified `result`, given the `From:`
+    /// header the caller claims the email was sent from.
+    pub(crate) fn check_sender(
+        &self,
+        result: VerificationResult,
+        from_header: Option<&str>,
+    ) -> VerificationResult {
+        if !result.verified {
+            return result;
+        }
+        match self.check_sender_of(&result.account_id, &result.from_address_hash, from_header) {
+            Ok(()) => result,
+            Err(reason) => result.with_failure(reason),
+        }
+    }
+
+    /// The sender policy for an email of `account_id` whose sender is
+    /// committed to as `sender_hash`, shared by recoveries and commands.
+    pub(crate) fn check_sender_of(
+        &self,
+        account_id: &str,
+        sender_hash: &[u8],
+        from_header: Option<&str>,
+    ) -> Result<(), FailureReason> {
+        let address = match from_header {
+            Some(header) => {
+                let header = nfc(header);
+                if from_address_hash(&header, account_id).as_deref() != Some(sender_hash) {
+                    return Err(FailureReason::BindingFromAddress);
+                }
+                extract_addr_spec(&header).map(|addr| canonicalize_address(&addr))
+            }
+            None => None,
+        };
+        let domain = address.as_deref().and_then(|addr| addr.rsplit_once('@')).map(|(_, d)| d);
+
+        if !self.blocked_senders.is_empty() {
+            let blocked = match (&address, domain) {
+                (Some(address), Some(domain)) => {
+                    self.blocked_senders.contains(&block_hash(address))
+                        || self.blocked_senders.contains(&block_hash(domain))
+                }
+                _ => true,
+            };
+            if blocked {
+                return Err(FailureReason::SenderBlocked);
+            }
+        }
+
+        if self.allowed_sender_domains.is_empty() {
+            return Ok(());
+        }
+        match domain {
+            Some(domain) if self.allowed_sender_domains.iter().any(|d| d == domain) => Ok(()),
+            _ => Err(FailureReason::SenderDomain),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn verified_from(address: &str) -> VerificationResult {
+        let mut result = VerificationResult::verified_for("alice.testnet");
+        result.from_address_hash = from_address_hash(address, "alice.testnet").unwrap();
+        result
+    }
+
+    #[test]
+    fn sender_domain_must_be_allowed_once_the_list_is_set() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let gmail = verified_from("Alice@Gmail.com");
+
+        assert!(contract.check_sender(gmail.clone(), None).verified);
+        let forged = contract.check_sender(gmail.clone(), Some("mallory@gmail.com"));
+        assert_eq!(forged.failure_reason.as_deref(), Some("BINDING_FROM_ADDRESS"));
+
+        contract.set_allowed_sender_domains(vec!["GMAIL.com ".into(), "outlook.com".into()]);
+        assert_eq!(contract.get_allowed_sender_domains(), vec!["gmail.com", "outlook.com"]);
+        assert!(contract
+            .check_sender(gmail.clone(), Some("Alice <alice@gmail.com>"))
+            .verified);
+
+        let missing = contract.check_sender(gmail, None);
+        assert_eq!(missing.failure_reason.as_deref(), Some("SENDER_DOMAIN"));
+        let other = contract.check_sender(verified_from("alice@example.com"), Some("alice@example.com"));
+        assert_eq!(other.failure_reason.as_deref(), Some("SENDER_DOMAIN"));
+    }
+
+    #[test]
+    fn blocked_addresses_and_domains_are_rejected() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let spam = contract.sender_block_hash("Spam.example".into());
+        let mallory = contract.sender_block_hash("mallory@gmail.com".into());
+        contract.block_senders(vec![spam.clone(), mallory.clone()]);
+        assert!(contract.is_sender_blocked(mallory.clone()));
+
+        let blocked = |contract: &ZkEmailVerifier, address: &str, header: Option<&str>| {
+            let result = contract.check_sender(verified_from(address), header);
+            result.failure_reason.as_deref() == Some("SENDER_BLOCKED")
+        };
+        assert!(blocked(&contract, "bob@spam.example", Some("bob@SPAM.example")));
+        assert!(blocked(&contract, "Mallory@gmail.com", Some("<Mallory@gmail.com>")));
+        assert!(blocked(&contract, "alice@gmail.com", None));
+        assert!(!blocked(&contract, "alice@gmail.com", Some("alice@gmail.com")));
+
+        contract.unblock_senders(vec![spam, mallory]);
+        assert!(!blocked(&contract, "alice@gmail.com", None));
+    }
+}
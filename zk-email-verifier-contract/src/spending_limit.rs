@@ -0,0 +1,209 @@
+//! Changing an account's daily spending limit by email.
+//!
+//! The limit itself is enforced by the account's controller contract, which
+//! the owner configures with `set_controller_contract`. A `limit-` email from
+//! the registered address (see `SET_SPENDING_LIMIT_SUBJECT`) starts a change
+//! that waits out the migration timelock (see `migration`), or the one in
+//! the account's recovery policy; until then the account can
+//! `cancel_spending_limit`. `finalize_spending_limit` forwards
+//! `set_limit(account_id, amount)` to the controller.
+//!
+//! Before forwarding, the controller's `is_controlled(account_id)` view must
+//! confirm that the account exists and is one it controls. Otherwise the
+//! finalization fails with a clear message and the change stays pending,
+//! instead of ending in a `set_limit` receipt that fails on its own.
+
+use near_sdk::{env, near, serde_json, AccountId, Gas, GasWeight, NearToken, Promise, PromiseError};
+use zk_email_verifier_core::layout::SET_SPENDING_LIMIT_CIRCUIT_ID;
+
+use crate::commands::reject;
+use crate::events::Event;
+use crate::{FailureReason, ProofInput, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Gas for the controller's `set_limit`.
+const SET_LIMIT_GAS: Gas = Gas::from_tgas(10);
+/// Gas for the controller's `is_controlled` view.
+const IS_CONTROLLED_GAS: Gas = Gas::from_tgas(5);
+/// Gas reserved for `on_controller_check` on top of its share of the unused
+/// gas; enough to schedule `set_limit`.
+const ON_CONTROLLER_CHECK_GAS: Gas = Gas::from_tgas(15);
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingSpendingLimit {
+    pub account_id: String,
+    pub request_id: String,
+    pub daily_limit: NearToken,
+    /// When `finalize_spending_limit` is accepted.
+    pub ready_at_ms: u64,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Verify a `limit-` email and start the timelock of the limit it names,
+    /// replacing any pending change of the same account.
+    pub fn initiate_spending_limit(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        from_header: Option<String>,
+    ) -> PendingSpendingLimit {
+        let command = self
+            .verify_command(SET_SPENDING_LIMIT_CIRCUIT_ID, proof, &public_inputs, from_header.as_deref())
+            .unwrap_or_else(|reason| reject(reason));
+        if self.is_locked(&command.account_id) {
+            reject(FailureReason::AccountLocked);
+        }
+        let daily_limit = command
+            .text("daily_limit")
+            .and_then(|amount| parse_daily_limit(&amount))
+            .unwrap_or_else(|| reject(FailureReason::InputParse));
+        let timelock_ms = self.timelock_ms_for(&command.account_id);
+        let change = PendingSpendingLimit {
+            account_id: command.account_id,
+            request_id: command.request_id,
+            daily_limit,
+            ready_at_ms: env::block_timestamp_ms().saturating_add(timelock_ms),
+        };
+        self.pending_spending_limits
+            .insert(change.account_id.clone(), change.clone());
+        Event::SpendingLimitInitiated {
+            account_id: change.account_id.clone(),
+            daily_limit,
+            ready_at_ms: change.ready_at_ms,
+        }
+        .emit();
+        change
+    }
+
+    /// Drop the calling account's pending spending limit change.
+    pub fn cancel_spending_limit(&mut self) {
+        let account_id = env::predecessor_account_id().to_string();
+        if self.pending_spending_limits.remove(&account_id).is_none() {
+            env::panic_str("no pending spending limit");
+        }
+        Event::SpendingLimitCancelled { account_id }.emit();
+    }
+
+    /// Forward the pending limit of `account_id` to the controller once its
+    /// timelock has passed and the controller confirmed it controls the
+    /// account.
+    pub fn finalize_spending_limit(&mut self, account_id: String) -> Promise {
+        self.assert_activated();
+        let controller = self.controller();
+        self.ready_spending_limit(&account_id);
+        let args = serde_json::json!({ "account_id": account_id }).to_string().into_bytes();
+        Promise::new(controller)
+            .function_call("is_controlled", args.clone(), NearToken::from_yoctonear(0), IS_CONTROLLED_GAS)
+            .then(Promise::new(env::current_account_id()).function_call_weight(
+                "on_controller_check",
+                args,
+                NearToken::from_yoctonear(0),
+                ON_CONTROLLER_CHECK_GAS,
+                GasWeight(1),
+            ))
+    }
+
+    /// Callback of the `is_controlled` check scheduled by
+    /// `finalize_spending_limit`: forward the limit if the controller
+    /// confirmed the account.
+    #[private]
+    pub fn on_controller_check(
+        &mut self,
+        account_id: String,
+        #[callback_result] controlled: Result<bool, PromiseError>,
+    ) -> Promise {
+        match controlled {
+            Ok(true) => {}
+            Ok(false) => env::panic_str(&format!(
+                "{account_id} does not exist or is not controlled by the controller contract"
+            )),
+            Err(_) => env::panic_str("the controller contract's is_controlled check failed"),
+        }
+        let change = self.ready_spending_limit(&account_id);
+        self.pending_spending_limits.remove(&account_id);
+        Event::SpendingLimitFinalized {
+            account_id: account_id.clone(),
+            daily_limit: change.daily_limit,
+        }
+        .emit();
+        let args = serde_json::json!({ "account_id": account_id, "amount": change.daily_limit })
+            .to_string()
+            .into_bytes();
+        Promise::new(self.controller()).function_call(
+            "set_limit",
+            args,
+            NearToken::from_yoctonear(0),
+            SET_LIMIT_GAS,
+        )
+    }
+
+    pub fn get_pending_spending_limit(&self, account_id: String) -> Option<PendingSpendingLimit> {
+        self.pending_spending_limits.get(&account_id).cloned()
+    }
+
+    pub fn get_controller_contract(&self) -> Option<AccountId> {
+        self.controller_contract.clone()
+    }
+
+    /// Set the contract that verified commands are forwarded to, or clear it
+    /// with `None`. Only callable by the owner.
+    pub fn set_controller_contract(&mut self, controller: Option<AccountId>) {
+        self.assert_owner();
+        self.controller_contract = controller;
+    }
+}
+
+impl ZkEmailVerifier {
+    fn controller(&self) -> AccountId {
+        self.controller_contract
+            .clone()
+            .unwrap_or_else(|| env::panic_str("no controller contract configured"))
+    }
+
+    /// The pending change of `account_id`, if it may be forwarded now.
+    fn ready_spending_limit(&self, account_id: &str) -> PendingSpendingLimit {
+        let change = self
+            .pending_spending_limits
+            .get(account_id)
+            .cloned()
+            .unwrap_or_else(|| env::panic_str("no pending spending limit"));
+        if self.is_locked(account_id) {
+            reject(FailureReason::AccountLocked);
+        }
+        if env::block_timestamp_ms() < change.ready_at_ms {
+            env::panic_str("the spending limit timelock has not passed");
+        }
+        change
+    }
+}
+
+/// A limit in yoctoNEAR, written as a plain decimal integer.
+fn parse_daily_limit(amount: &str) -> Option<NearToken> {
+    if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    amount.parse().ok().map(NearToken::from_yoctonear)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_limits_are_decimal_yoctonear() {
+        assert_eq!(parse_daily_limit("1000"), Some(NearToken::from_yoctonear(1000)));
+        assert_eq!(parse_daily_limit("0"), Some(NearToken::from_yoctonear(0)));
+        assert_eq!(parse_daily_limit(""), None);
+        assert_eq!(parse_daily_limit("+5"), None);
+        assert_eq!(parse_daily_limit("1.5"), None);
+        assert_eq!(parse_daily_limit(&format!("{}0", u128::MAX)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "bob.near does not exist or is not controlled by the controller contract")]
+    fn limits_are_not_forwarded_for_accounts_the_controller_disowns() {
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.on_controller_check("bob.near".to_string(), Ok(false)).detach();
+    }
+}
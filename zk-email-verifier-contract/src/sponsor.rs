@@ -0,0 +1,163 @@
+//! Sponsorship pools that pay verification fees on behalf of dApps.
+//!
+//! Anyone can top up the pool of a dApp with `sponsor_deposit`. When that
+//! dApp (usually the relayer account behind its recovery flow) calls
+//! `verify_staged`, the verification fee is taken from its pool instead of
+//! the attached deposit, so its users can recover without holding NEAR. Once
+//! the pool can no longer cover the fee, the deposit pays as usual. A dApp can
+//! take back what is left of its own pool with `sponsor_withdraw`.
+//!
+//! The first deposit into a pool pays for the pool's storage, and only what
+//! is left of it is credited; it must leave something to credit. A pool stays
+//! open when it runs empty, so topping it up later doesn't pay for storage
+//! again, until the dApp closes it with `sponsor_close`, which sends it the
+//! balance and the freed storage.
+
+use near_sdk::{env, near, AccountId, NearToken, Promise};
+
+use crate::treasury::storage_cost_since;
+use crate::{ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_sponsor_balance(&self, dapp_id: AccountId) -> NearToken {
+        self.sponsor_pools
+            .get(&dapp_id)
+            .copied()
+            .unwrap_or(NearToken::from_yoctonear(0))
+    }
+
+    /// Add the attached deposit to the pool of `dapp_id`, less the storage
+    /// of the pool if this opens it.
+    #[payable]
+    pub fn sponsor_deposit(&mut self, dapp_id: AccountId) -> NearToken {
+        let deposit = env::attached_deposit();
+        if deposit.is_zero() {
+            env::panic_str("attach the amount to sponsor");
+        }
+        let usage_before = env::storage_usage();
+        let balance = self.get_sponsor_balance(dapp_id.clone());
+        self.sponsor_pools.insert(dapp_id.clone(), balance);
+        self.sponsor_pools.flush();
+        let storage = storage_cost_since(usage_before);
+        let Some(credited) = deposit.checked_sub(storage).filter(|credited| !credited.is_zero()) else {
+            env::panic_str(&format!("attach more than {storage} to cover the pool's storage"));
+        };
+        let balance = balance.saturating_add(credited);
+        self.sponsor_pools.insert(dapp_id, balance);
+        balance
+    }
+
+    /// Send `amount` from the calling dApp's pool back to it.
+    pub fn sponsor_withdraw(&mut self, amount: NearToken) -> Promise {
+        let dapp_id = env::predecessor_account_id();
+        let balance = self
+            .get_sponsor_balance(dapp_id.clone())
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("amount exceeds the sponsor balance"));
+        self.sponsor_pools.insert(dapp_id.clone(), balance);
+        Promise::new(dapp_id).transfer(amount)
+    }
+
+    /// Close the calling dApp's pool, sending it what is left of the pool
+    /// along with the storage it frees.
+    pub fn sponsor_close(&mut self) -> Promise {
+        let dapp_id = env::predecessor_account_id();
+        let usage_before = env::storage_usage();
+        let balance = self
+            .sponsor_pools
+            .remove(&dapp_id)
+            .unwrap_or_else(|| env::panic_str("no sponsor pool to close"));
+        self.sponsor_pools.flush();
+        let freed = usage_before.saturating_sub(env::storage_usage());
+        let storage = env::storage_byte_cost().saturating_mul(u128::from(freed));
+        Promise::new(dapp_id).transfer(balance.saturating_add(storage))
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Take `fee` out of the pool of `dapp_id`. Returns false, leaving the
+    /// pool as is, if it doesn't cover the fee.
+    pub(crate) fn draw_sponsored_fee(&mut self, dapp_id: &AccountId, fee: NearToken) -> bool {
+        match self.get_sponsor_balance(dapp_id.clone()).checked_sub(fee) {
+            Some(balance) => {
+                self.sponsor_pools.insert(dapp_id.clone(), balance);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::called_by;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    fn sponsored_dapps_pay_fees_from_their_pool() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_verification_fee(NearToken::from_millinear(10));
+
+        // Opening the pool pays for its storage; topping it up doesn't.
+        called_by(2, NearToken::from_millinear(15));
+        let storage = NearToken::from_millinear(15).saturating_sub(contract.sponsor_deposit(accounts(1)));
+        assert!(!storage.is_zero());
+        called_by(2, storage);
+        assert_eq!(contract.sponsor_deposit(accounts(1)), NearToken::from_millinear(15));
+
+        called_by(1, NearToken::from_yoctonear(0));
+        contract.charge_verification_fee(NearToken::from_yoctonear(0));
+        assert_eq!(contract.get_sponsor_balance(accounts(1)), NearToken::from_millinear(5));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(10));
+
+        called_by(1, NearToken::from_millinear(10));
+        contract.charge_verification_fee(NearToken::from_yoctonear(0));
+        assert_eq!(contract.get_sponsor_balance(accounts(1)), NearToken::from_millinear(5));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(20));
+
+        contract.sponsor_withdraw(NearToken::from_millinear(5)).detach();
+        assert!(contract.get_sponsor_balance(accounts(1)).is_zero());
+    }
+
+    #[test]
+    fn empty_pools_stay_open_until_closed() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        called_by(2, NearToken::from_millinear(15));
+        let credited = contract.sponsor_deposit(accounts(1));
+
+        called_by(1, NearToken::from_yoctonear(0));
+        contract.sponsor_withdraw(credited).detach();
+        assert!(contract.sponsor_pools.contains_key(&accounts(1)));
+
+        // Topping up the empty pool credits the whole deposit.
+        called_by(2, NearToken::from_millinear(5));
+        assert_eq!(contract.sponsor_deposit(accounts(1)), NearToken::from_millinear(5));
+
+        called_by(1, NearToken::from_yoctonear(0));
+        let usage_before = env::storage_usage();
+        contract.sponsor_close().detach();
+        assert!(!contract.sponsor_pools.contains_key(&accounts(1)));
+        assert!(env::storage_usage() < usage_before);
+    }
+
+    #[test]
+    #[should_panic(expected = "no sponsor pool to close")]
+    fn only_open_pools_can_be_closed() {
+        called_by(1, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.sponsor_close().detach();
+    }
+
+    #[test]
+    #[should_panic(expected = "to cover the pool's storage")]
+    fn opening_a_pool_must_cover_its_storage() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        called_by(2, NearToken::from_yoctonear(1));
+        contract.sponsor_deposit(accounts(1));
+    }
+}
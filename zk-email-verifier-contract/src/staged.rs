@@ -0,0 +1,399 @@
+//! Two-receipt verification for circuits whose proof check doesn't fit in a
+//! single function call's gas.
+//!
+//! `verify_staged` parses the proof and public inputs and computes the
+//! public-input MSM, stores the result under a fresh id and schedules
+//! `complete_staged_verification` on this contract with all of its remaining
+//! gas. The callback does the pairing check and returns the same
+//! `VerificationResult` that `verify` would, which becomes the outcome of the
+//! transaction.
+//!
+//! With `attest: true`, a successful verification also stores an
+//! [`Attestation`](crate::attestation::Attestation) under the staged id.
+//!
+//! The pending entry records a hash of the prepared verifying key it was
+//! staged against, so rotating the key between the two receipts rejects the
+//! proof instead of mixing inputs prepared for one key with another. If the
+//! callback fails instead (say, out of gas), `on_staged_verification_completed`
+//! removes the entry, refunds what it held and rejects the submission with
+//! `CALLBACK_FAILED`.
+
+use ark_bn254::{Bn254, Fr, G1Affine};
+use ark_ec::CurveGroup;
+use ark_groth16::{PreparedVerifyingKey, Proof};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use near_sdk::{
+    env, near, serde_json, AccountId, Gas, GasWeight, NearToken, Promise, PromiseError, PromiseOrValue,
+};
+
+use zk_email_verifier_core::verify_with_prepared_inputs;
+
+use crate::idempotency::idempotent_entry_deposit;
+use crate::result_cache::{cache_key, cached_result_deposit, refund_cache_deposit};
+use crate::treasury::refund;
+use crate::{
+    decode_verified_inputs, msm, FailureReason, ProofInput, VerificationResult, ZkEmailVerifier,
+    ZkEmailVerifierExt,
+};
+
+/// Gas reserved for the callback on top of its share of the unused gas.
+const COMPLETE_STAGED_GAS: Gas = Gas::from_tgas(20);
+
+/// Gas for `on_staged_verification_completed`.
+const ON_STAGED_COMPLETED_GAS: Gas = Gas::from_tgas(10);
+
+/// State carried from `verify_staged` to `complete_staged_verification`.
+#[near(serializers = [borsh])]
+pub(crate) struct PendingVerification {
+    /// SHA-256 of the stored prepared verifying key blob.
+    vk_hash: Vec<u8>,
+    /// `(proof, prepared_inputs, public_inputs)`, serialized uncompressed with
+    /// ark-serialize.
+    payload: Vec<u8>,
+    /// Store an attestation if the proof verifies.
+    attest: bool,
+    /// Sender to check against the sender policy, see `senders`.
+    from_header: Option<String>,
+    /// Circuit whose key the proof was staged against.
+    circuit_id: String,
+    /// Key to cache the result under, if result caching is on. The
+    /// submitter attached `cached_result_deposit()` for it.
+    cache_key: Option<Vec<u8>>,
+    /// Caller of `verify_staged`, checked by `check_submitter`.
+    submitter: AccountId,
+    /// Key to record the outcome under for the submitter; see `idempotency`.
+    idempotency_key: Option<String>,
+}
+
+type StagedPayload = (Proof<Bn254>, G1Affine, Vec<Fr>);
+
+/// The arguments of a `verify_staged` call, carried through the registry
+/// read when the verifying key comes from `registry`.
+#[near(serializers = [json])]
+pub struct StagedRequest {
+    pub proof: ProofInput,
+    pub public_inputs: Vec<String>,
+    pub attest: bool,
+    pub from_header: Option<String>,
+    /// Caller of `verify_staged`.
+    pub submitter: AccountId,
+    pub idempotency_key: Option<String>,
+    /// Key to cache the result under, set when the submitter paid for it.
+    pub cache_key: Option<Vec<u8>>,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Verify a proof across two receipts: parsing and the public-input MSM
+    /// run here, the pairing check in a self-callback. Use this instead of
+    /// `verify` when a single call runs out of gas.
+    ///
+    /// Unparseable inputs, or public inputs that don't match the verifying
+    /// key, are rejected immediately without scheduling the callback.
+    ///
+    /// With `attest` set, a verified result is also recorded as an
+    /// attestation under the id logged as `staged verification <id>`; see
+    /// `get_attestation`.
+    ///
+    /// `from_header` is checked like in `verify_with_binding`. With
+    /// predecessor binding on, or once the account has authorized a relayer,
+    /// the caller must be the recovering account or that relayer (see
+    /// `relayers`).
+    ///
+    /// Resubmitting a proof whose result the caller still has cached (see
+    /// `set_result_cache_ttl_ms`) returns that result, rejected as
+    /// `CACHED_REPLAY`, without verifying it again. Resubmitting with an
+    /// `idempotency_key` the caller already used returns the earlier outcome
+    /// (see `idempotency`).
+    ///
+    /// The verification fee, if any, must be attached (see `treasury`) unless
+    /// the caller's sponsorship pool covers it (see `sponsor`), and so must
+    /// the storage of a new idempotency key and, while results are cached,
+    /// of the cache entry; the rest of the deposit is refunded, all of it on
+    /// an idempotent retry.
+    ///
+    /// Panics while the contract is paused (see `health_check`).
+    #[payable]
+    pub fn verify_staged(
+        &mut self,
+        proof: ProofInput,
+        public_inputs: Vec<String>,
+        attest: Option<bool>,
+        from_header: Option<String>,
+        idempotency_key: Option<String>,
+    ) -> PromiseOrValue<VerificationResult> {
+        self.assert_not_paused();
+        self.assert_activated();
+        let submitter = env::predecessor_account_id();
+        let mut storage = NearToken::from_yoctonear(0);
+        if let Some(key) = &idempotency_key {
+            if let Some(result) = self.begin_idempotent(&submitter, key) {
+                env::log_str("returning the outcome of the earlier submission with this key");
+                refund(env::attached_deposit());
+                return PromiseOrValue::Value(result);
+            }
+            storage = idempotent_entry_deposit();
+        }
+        let cache_key = self
+            .result_cache_ttl_ms
+            .map(|_| cache_key(&submitter, &proof, &public_inputs));
+        let cached = cache_key.as_deref().and_then(|key| self.cached_result(key));
+        if cached.is_none() && cache_key.is_some() {
+            storage = storage.saturating_add(cached_result_deposit());
+        }
+        self.charge_verification_fee(storage);
+        if let Some(result) = cached {
+            env::log_str("returning the cached result of an earlier verification");
+            if let Some(key) = idempotency_key {
+                self.finish_idempotent(&submitter, key, &result);
+            }
+            return PromiseOrValue::Value(result);
+        }
+        let circuit_id = self.circuit_for_sender(from_header.as_deref());
+        let request = StagedRequest {
+            proof,
+            public_inputs,
+            attest: attest.unwrap_or(false),
+            from_header,
+            submitter,
+            idempotency_key,
+            cache_key,
+        };
+        match self.vk_registry.clone() {
+            Some(registry) => self.read_registry_then_stage(registry, circuit_id, request).into(),
+            None => self.stage_verification(circuit_id, request),
+        }
+    }
+
+    /// Second half of `verify_staged`: run the pairing check for the staged
+    /// entry `id`, remove it and attest the result if asked to.
+    #[private]
+    pub fn complete_staged_verification(&mut self, id: u64) -> VerificationResult {
+        let pending = self
+            .staged_verifications
+            .remove(&id)
+            .unwrap_or_else(|| env::panic_str("unknown staged verification"));
+        if pending.vk_hash != self.prepared_vk_hash(&pending.circuit_id) {
+            env::log_str("verifying key changed while the verification was staged");
+            let result = VerificationResult::rejected(FailureReason::VkChanged).stamped();
+            self.record_outcome(&result, &pending.circuit_id);
+            if pending.cache_key.is_some() {
+                refund_cache_deposit(&pending.submitter);
+            }
+            if let Some(key) = pending.idempotency_key {
+                self.finish_idempotent(&pending.submitter, key, &result);
+            }
+            return result;
+        }
+        let pvk = self.prepared_verifying_key_for(&pending.circuit_id);
+        let result = match complete(&pvk, &pending.payload) {
+            Some((true, inputs)) => {
+                let layout = self.layout_of(&pending.circuit_id);
+                let result = self.check_sender(
+                    self.admit_recovery(decode_verified_inputs(layout, &inputs)),
+                    pending.from_header.as_deref(),
+                );
+                let result = self.check_freshness(result);
+                let result = self.check_submitter(result, &pending.submitter);
+                let result = self.check_cooldown(result);
+                let result = self.check_challenge(result);
+                let result = self.check_nullifier(result);
+                let result = self.check_lock(result);
+                self.check_rate_limit(result, &pending.submitter)
+            }
+            _ => VerificationResult::rejected(FailureReason::Pairing),
+        }
+        .stamped();
+        self.record_outcome(&result, &pending.circuit_id);
+        self.consume_challenge(&result);
+        self.consume_nullifier(&result);
+        self.accumulate_result(&result);
+        if pending.attest && result.verified {
+            self.attest(id, &result);
+        }
+        self.reward_relayer(&result, &pending.submitter);
+        if let Some(cache_key) = pending.cache_key {
+            self.cache_result(cache_key, &result, &pending.submitter);
+        }
+        if let Some(key) = pending.idempotency_key {
+            self.finish_idempotent(&pending.submitter, key, &result);
+        }
+        result
+    }
+
+    /// Settle a `verify_staged` submission once `complete_staged_verification`
+    /// for the staged entry `id` ran. Its result is passed through; if it
+    /// failed, the staged entry and the idempotency key, if any, are dropped
+    /// and the submission is rejected with `CALLBACK_FAILED`.
+    #[private]
+    pub fn on_staged_verification_completed(
+        &mut self,
+        id: u64,
+        submitter: AccountId,
+        idempotency_key: Option<String>,
+        #[callback_result] outcome: Result<VerificationResult, PromiseError>,
+    ) -> VerificationResult {
+        if let Ok(result) = outcome {
+            return result;
+        }
+        env::log_str("the staged verification failed and was dropped");
+        self.drop_staged(id);
+        if let Some(key) = idempotency_key {
+            self.drop_idempotent(&(submitter, key));
+        }
+        VerificationResult::rejected(FailureReason::CallbackFailed).stamped()
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Remove the staged entry `id` whose second receipt failed, refunding
+    /// what it held for the result cache.
+    fn drop_staged(&mut self, id: u64) {
+        let Some(pending) = self.staged_verifications.remove(&id) else {
+            return;
+        };
+        if pending.cache_key.is_some() {
+            refund_cache_deposit(&pending.submitter);
+        }
+    }
+
+    /// Parse, run the MSM and schedule the pairing check of `request` against
+    /// the key of `circuit_id`.
+    pub(crate) fn stage_verification(
+        &mut self,
+        circuit_id: String,
+        request: StagedRequest,
+    ) -> PromiseOrValue<VerificationResult> {
+        let pvk = self.prepared_verifying_key_for(&circuit_id);
+        let payload = self
+            .parse_logged(request.proof, &request.public_inputs, self.layout_of(&circuit_id))
+            .and_then(|(proof, inputs)| stage(&pvk, proof, inputs).ok_or(FailureReason::InputLen));
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(reason) => {
+                let result = VerificationResult::rejected(reason).stamped();
+                self.record_outcome(&result, &circuit_id);
+                if request.cache_key.is_some() {
+                    refund_cache_deposit(&request.submitter);
+                }
+                if let Some(key) = request.idempotency_key {
+                    self.finish_idempotent(&request.submitter, key, &result);
+                }
+                return PromiseOrValue::Value(result);
+            }
+        };
+
+        let (submitter, idempotency_key) = (request.submitter.clone(), request.idempotency_key.clone());
+        let id = self.next_staged_id;
+        self.next_staged_id += 1;
+        self.staged_verifications.insert(
+            id,
+            PendingVerification {
+                vk_hash: self.prepared_vk_hash(&circuit_id),
+                payload,
+                attest: request.attest,
+                from_header: request.from_header,
+                circuit_id,
+                cache_key: request.cache_key,
+                submitter: request.submitter,
+                idempotency_key: request.idempotency_key,
+            },
+        );
+        env::log_str(&format!("staged verification {id}"));
+
+        let args = serde_json::json!({ "id": id }).to_string().into_bytes();
+        let settle_args = serde_json::json!({
+            "id": id,
+            "submitter": submitter,
+            "idempotency_key": idempotency_key,
+        })
+        .to_string()
+        .into_bytes();
+        // The settling callback gets a fixed allowance and the pairing check
+        // all of the unused gas.
+        Promise::new(env::current_account_id())
+            .function_call_weight(
+                "complete_staged_verification",
+                args,
+                NearToken::from_yoctonear(0),
+                COMPLETE_STAGED_GAS,
+                GasWeight(1),
+            )
+            .then(Promise::new(env::current_account_id()).function_call_weight(
+                "on_staged_verification_completed",
+                settle_args,
+                NearToken::from_yoctonear(0),
+                ON_STAGED_COMPLETED_GAS,
+                GasWeight(0),
+            ))
+            .into()
+    }
+}
+
+/// Compute the prepared inputs for `proof` and serialize everything the
+/// pairing check needs. `None` if the inputs don't match the key.
+fn stage(pvk: &PreparedVerifyingKey<Bn254>, proof: Proof<Bn254>, inputs: Vec<Fr>) -> Option<Vec<u8>> {
+    let prepared = msm::prepare_inputs(pvk, &inputs).ok()?.into_affine();
+    let payload: StagedPayload = (proof, prepared, inputs);
+    let mut bytes = Vec::with_capacity(payload.uncompressed_size());
+    payload
+        .serialize_uncompressed(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    Some(bytes)
+}
+
+/// Run the pairing check on a payload written by `stage`, returning the
+/// outcome together with the public inputs to decode.
+fn complete(pvk: &PreparedVerifyingKey<Bn254>, payload: &[u8]) -> Option<(bool, Vec<Fr>)> {
+    // Written by this contract in the previous receipt from points that
+    // `parse_proof` or `decode_blob` already checked, so skip re-validating.
+    let (proof, prepared, inputs) = StagedPayload::deserialize_uncompressed_unchecked(payload).ok()?;
+    Some((verify_with_prepared_inputs(pvk, &proof, &prepared.into()), inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::tests::fixture;
+    use crate::test_utils::at;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    fn staged_payload_completes_to_the_same_outcome() {
+        let (pvk, prove) = fixture();
+        let (proof, inputs) = prove(7);
+
+        let payload = stage(&pvk, proof.clone(), inputs.clone()).unwrap();
+        assert_eq!(complete(&pvk, &payload), Some((true, inputs.clone())));
+
+        let wrong = stage(&pvk, proof.clone(), vec![Fr::from(8u64)]).unwrap();
+        assert_eq!(complete(&pvk, &wrong).map(|(ok, _)| ok), Some(false));
+
+        assert!(stage(&pvk, proof, vec![]).is_none());
+        assert!(complete(&pvk, &payload[1..]).is_none());
+    }
+
+    #[test]
+    fn failed_callbacks_drop_their_staged_entry() {
+        at(0);
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.staged_verifications.insert(
+            7,
+            PendingVerification {
+                vk_hash: Vec::new(),
+                payload: Vec::new(),
+                attest: false,
+                from_header: None,
+                circuit_id: crate::layout::RECOVER_EMAIL_CIRCUIT_ID.to_string(),
+                cache_key: None,
+                submitter: accounts(1),
+                idempotency_key: None,
+            },
+        );
+
+        let result =
+            contract.on_staged_verification_completed(7, accounts(1), None, Err(PromiseError::Failed));
+        assert_eq!(result.failure_reason.as_deref(), Some("CALLBACK_FAILED"));
+        assert!(contract.staged_verifications.get(&7).is_none());
+    }
+}
@@ -0,0 +1,428 @@
+//! Owner-only export of contract state, for seeding a redeployment (see
+//! `state_import`) or handing an auditor something to decode offline.
+//!
+//! State is exported by section: `config` (the owner's settings), the
+//! per-account `registries`, the consumed `nullifiers` in tree order, the
+//! `pending_recoveries` still waiting on a timelock or approvals, the
+//! per-circuit settings in `circuits` (layouts, deprecations, gas
+//! benchmarks and sender domains), the issued `challenges`, each account's
+//! verified `account_history`, the leaves of the result accumulator in
+//! `results` and the `attestations` with their chain head. Each call
+//! returns up to `limit` entries of a section from index `from`, and the
+//! index to continue from. Entries come in storage order, which only stays
+//! stable while nothing is written, so pause the contract first (see
+//! `health`) for a consistent export.
+//!
+//! A chunk's `blob` is the version byte [`STATE_EXPORT_VERSION`] followed by
+//! the Borsh encoding of [`ExportedEntries`]. Verifying keys, balances and
+//! caches are left out: a new deployment brings its own keys, and funds move
+//! by transfer, not by state.
+//!
+//! `export_state` takes `&mut self` so that it is a change method, which can
+//! check its caller; a view call has no predecessor to check. Everything
+//! it returns is readable from the account's raw storage as well; the check
+//! guards the API, not the data.
+
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{borsh, near, AccountId, NearToken};
+
+use crate::attestation::Attestation;
+use crate::audit::VerificationRecord;
+use crate::breaker::CircuitBreakerConfig;
+use crate::challenges::Challenge;
+use crate::email_change::PendingEmailChange;
+use crate::gas_estimate::GasBenchmark;
+use crate::guardian_epochs::GuardianSet;
+use crate::guardian_recovery::{GuardianRecovery, GuardianWeights};
+use crate::guardians::PendingGuardianRemoval;
+use crate::key_permission::KeyPermission;
+use crate::merkle::Hash;
+use crate::migration::PendingMigration;
+use crate::network::NetworkProfile;
+use crate::rate_limit::RecoveryRateLimit;
+use crate::recovery_policy::RecoveryPolicy;
+use crate::spending_limit::PendingSpendingLimit;
+use crate::treasury::RelayerReward;
+use crate::{ZkEmailVerifier, ZkEmailVerifierExt};
+
+pub const STATE_EXPORT_VERSION: u8 = 1;
+
+const DEFAULT_CHUNK_LIMIT: u32 = 100;
+const MAX_CHUNK_LIMIT: u32 = 500;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StateSection {
+    Config,
+    Registries,
+    Nullifiers,
+    PendingRecoveries,
+    Circuits,
+    Challenges,
+    AccountHistory,
+    Results,
+    Attestations,
+}
+
+/// Settings the owner controls, as of the export.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportedConfig {
+    pub network: NetworkProfile,
+    pub freshness_window_ms: Option<u64>,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    pub allowed_sender_domains: Vec<String>,
+    pub audit_capacity: u32,
+    pub debug_logs: bool,
+    pub vk_registry: Option<AccountId>,
+    pub result_cache_ttl_ms: Option<u64>,
+    pub predecessor_binding: bool,
+    pub recovery_cooldown_ms: Option<u64>,
+    pub recovery_rate_limit: Option<RecoveryRateLimit>,
+    pub verification_fee: NearToken,
+    pub relayer_reward: Option<RelayerReward>,
+    pub recovery_mailbox: Option<String>,
+    pub default_key_permission: KeyPermission,
+    pub migration_timelock_ms: u64,
+    pub lock_duration_ms: u64,
+    pub controller_contract: Option<AccountId>,
+    pub guardian_removal_delay_ms: u64,
+    pub guardian_epoch_ms: u64,
+    pub email_change_window_ms: u64,
+    pub account_namespace: Option<String>,
+}
+
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegistryEntry {
+    RegisteredEmail { account_id: String, email_hash: Vec<u8> },
+    Relayer { account_id: String, relayer: AccountId },
+    KeyPermission { account_id: String, permission: KeyPermission },
+    Lock { account_id: String, locked_until_ms: u64 },
+    Guardians { account_id: String, guardians: Vec<Vec<u8>> },
+    GuardianWeights { account_id: String, weights: GuardianWeights },
+    GuardianSets { account_id: String, sets: Vec<GuardianSet> },
+    RecoveryPolicy { account_id: String, policy: RecoveryPolicy },
+    BlockedSender { hash: Vec<u8> },
+    RateLimitBypass { account_id: AccountId },
+}
+
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PendingEntry {
+    Migration(PendingMigration),
+    SpendingLimit(PendingSpendingLimit),
+    GuardianRemovals { account_id: String, removals: Vec<PendingGuardianRemoval> },
+    EmailChange(PendingEmailChange),
+    GuardianRecovery(GuardianRecovery),
+}
+
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CircuitEntry {
+    Layout { circuit_id: String, layout_id: String },
+    Deprecation { circuit_id: String, deprecated_at_ms: u64 },
+    GasBenchmark { circuit_id: String, benchmark: GasBenchmark },
+    DomainCircuit { domain: String, circuit_id: String },
+}
+
+/// The verified records of one account, oldest first.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountHistory {
+    pub account_id: String,
+    pub records: Vec<VerificationRecord>,
+}
+
+/// The entries of one chunk, tagged with their section.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExportedEntries {
+    Config(Box<ExportedConfig>),
+    Registries(Vec<RegistryEntry>),
+    Nullifiers(Vec<Vec<u8>>),
+    PendingRecoveries(Vec<PendingEntry>),
+    Circuits(Vec<CircuitEntry>),
+    /// `nonce` is the one the next `generate_request_id` would use.
+    Challenges { challenges: Vec<Challenge>, nonce: u64 },
+    AccountHistory(Vec<AccountHistory>),
+    /// `branch` and `len` describe the whole accumulator, so the importer
+    /// can check the leaves it appended reproduce it.
+    Results { leaves: Vec<Hash>, branch: Vec<Hash>, len: u64 },
+    /// Attestations with their id, and the chain head of the whole section.
+    Attestations { attestations: Vec<(u64, Attestation)>, chain_head: Vec<u8> },
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateChunk {
+    pub version: u8,
+    pub section: StateSection,
+    pub from: u32,
+    /// Where the next chunk starts; `None` once the section is exhausted.
+    pub next_from: Option<u32>,
+    /// Entries in the section, for progress reporting.
+    pub total: u32,
+    /// `version || borsh(ExportedEntries)`.
+    pub blob: Base64VecU8,
+}
+
+#[near]
+impl ZkEmailVerifier {
+    /// Up to `limit` (default 100, at most 500) entries of `section` from
+    /// index `from` (default 0). The config is a single entry. Only
+    /// callable by the owner.
+    pub fn export_state(&mut self, section: StateSection, from: Option<u32>, limit: Option<u32>) -> StateChunk {
+        self.assert_owner();
+        let from = from.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_CHUNK_LIMIT).min(MAX_CHUNK_LIMIT);
+        let (entries, total) = match section {
+            StateSection::Config => (ExportedEntries::Config(Box::new(self.exported_config())), 1),
+            StateSection::Registries => {
+                let page = self.registry_page(from, limit);
+                (ExportedEntries::Registries(page.items), page.total)
+            }
+            StateSection::Nullifiers => {
+                let mut page = Page::new(from, limit);
+                page.extend(self.nullifiers.iter(), Vec::clone);
+                (ExportedEntries::Nullifiers(page.items), page.total)
+            }
+            StateSection::PendingRecoveries => {
+                let page = self.pending_page(from, limit);
+                (ExportedEntries::PendingRecoveries(page.items), page.total)
+            }
+            StateSection::Circuits => {
+                let page = self.circuit_page(from, limit);
+                (ExportedEntries::Circuits(page.items), page.total)
+            }
+            StateSection::Challenges => {
+                let mut page = Page::new(from, limit);
+                page.extend(self.challenges.values(), Challenge::clone);
+                let nonce = self.challenge_nonce;
+                (ExportedEntries::Challenges { challenges: page.items, nonce }, page.total)
+            }
+            StateSection::AccountHistory => {
+                let mut page = Page::new(from, limit);
+                page.extend(self.account_history_len.iter(), |(account_id, len)| AccountHistory {
+                    account_id: account_id.clone(),
+                    records: (0..*len)
+                        .filter_map(|i| self.account_history.get(&(account_id.clone(), i)).cloned())
+                        .collect(),
+                });
+                (ExportedEntries::AccountHistory(page.items), page.total)
+            }
+            StateSection::Results => {
+                let mut page = Page::new(from, limit);
+                let count = u32::try_from(self.result_count).unwrap_or(u32::MAX);
+                page.extend(0..count, |i| self.result_leaves.get(&u64::from(i)).copied().unwrap_or_default());
+                let tree = self.result_tree();
+                let (branch, len) = (tree.branch, tree.len);
+                (ExportedEntries::Results { leaves: page.items, branch, len }, page.total)
+            }
+            StateSection::Attestations => {
+                let mut page = Page::new(from, limit);
+                page.extend(self.attestations.iter(), |(id, attestation)| (*id, attestation.clone()));
+                let chain_head = self.attestation_chain_head.clone();
+                (ExportedEntries::Attestations { attestations: page.items, chain_head }, page.total)
+            }
+        };
+        let end = from.saturating_add(limit);
+        let mut blob = vec![STATE_EXPORT_VERSION];
+        blob.extend(borsh::to_vec(&entries).expect("serializing into a Vec cannot fail"));
+        StateChunk {
+            version: STATE_EXPORT_VERSION,
+            section,
+            from,
+            next_from: (end < total).then_some(end),
+            total,
+            blob: blob.into(),
+        }
+    }
+}
+
+impl ZkEmailVerifier {
+    fn exported_config(&self) -> ExportedConfig {
+        ExportedConfig {
+            network: self.network,
+            freshness_window_ms: self.freshness_window_ms,
+            circuit_breaker: self.circuit_breaker.clone(),
+            allowed_sender_domains: self.allowed_sender_domains.clone(),
+            audit_capacity: self.audit_capacity,
+            debug_logs: self.debug_logs,
+            vk_registry: self.vk_registry.clone(),
+            result_cache_ttl_ms: self.result_cache_ttl_ms,
+            predecessor_binding: self.predecessor_binding,
+            recovery_cooldown_ms: self.recovery_cooldown_ms,
+            recovery_rate_limit: self.recovery_rate_limit.clone(),
+            verification_fee: self.verification_fee,
+            relayer_reward: self.relayer_reward,
+            recovery_mailbox: self.recovery_mailbox.clone(),
+            default_key_permission: self.default_key_permission.clone(),
+            migration_timelock_ms: self.migration_timelock_ms,
+            lock_duration_ms: self.lock_duration_ms,
+            controller_contract: self.controller_contract.clone(),
+            guardian_removal_delay_ms: self.guardian_removal_delay_ms,
+            guardian_epoch_ms: self.guardian_epoch_ms,
+            email_change_window_ms: self.email_change_window_ms,
+            account_namespace: self.account_namespace.clone(),
+        }
+    }
+
+    fn registry_page(&self, from: u32, limit: u32) -> Page<RegistryEntry> {
+        let mut page = Page::new(from, limit);
+        page.extend(self.registered_emails.iter(), |(account_id, email_hash)| {
+            RegistryEntry::RegisteredEmail { account_id: account_id.clone(), email_hash: email_hash.clone() }
+        });
+        page.extend(self.account_relayers.iter(), |(account_id, relayer)| RegistryEntry::Relayer {
+            account_id: account_id.clone(),
+            relayer: relayer.clone(),
+        });
+        page.extend(self.account_key_permissions.iter(), |(account_id, permission)| {
+            RegistryEntry::KeyPermission { account_id: account_id.clone(), permission: permission.clone() }
+        });
+        page.extend(self.account_locks.iter(), |(account_id, locked_until_ms)| RegistryEntry::Lock {
+            account_id: account_id.clone(),
+            locked_until_ms: *locked_until_ms,
+        });
+        page.extend(self.guardians.iter(), |(account_id, guardians)| RegistryEntry::Guardians {
+            account_id: account_id.clone(),
+            guardians: guardians.clone(),
+        });
+        page.extend(self.guardian_weights.iter(), |(account_id, weights)| {
+            RegistryEntry::GuardianWeights { account_id: account_id.clone(), weights: weights.clone() }
+        });
+        page.extend(self.guardian_sets.iter(), |(account_id, sets)| RegistryEntry::GuardianSets {
+            account_id: account_id.clone(),
+            sets: sets.clone(),
+        });
+        page.extend(self.recovery_policies.iter(), |(account_id, policy)| {
+            RegistryEntry::RecoveryPolicy { account_id: account_id.clone(), policy: policy.clone() }
+        });
+        page.extend(self.blocked_senders.iter(), |hash| RegistryEntry::BlockedSender { hash: hash.clone() });
+        page.extend(self.rate_limit_bypass.iter(), |account_id| RegistryEntry::RateLimitBypass {
+            account_id: account_id.clone(),
+        });
+        page
+    }
+
+    fn pending_page(&self, from: u32, limit: u32) -> Page<PendingEntry> {
+        let mut page = Page::new(from, limit);
+        page.extend(self.pending_migrations.values(), |m| PendingEntry::Migration(m.clone()));
+        page.extend(self.pending_spending_limits.values(), |l| PendingEntry::SpendingLimit(l.clone()));
+        page.extend(self.pending_guardian_removals.iter(), |(account_id, removals)| {
+            PendingEntry::GuardianRemovals { account_id: account_id.clone(), removals: removals.clone() }
+        });
+        page.extend(self.pending_email_changes.values(), |c| PendingEntry::EmailChange(c.clone()));
+        page.extend(self.guardian_recoveries.values(), |r| PendingEntry::GuardianRecovery(r.clone()));
+        page
+    }
+
+    fn circuit_page(&self, from: u32, limit: u32) -> Page<CircuitEntry> {
+        let mut page = Page::new(from, limit);
+        page.extend(self.circuit_layouts.iter(), |(circuit_id, layout_id)| CircuitEntry::Layout {
+            circuit_id: circuit_id.clone(),
+            layout_id: layout_id.clone(),
+        });
+        page.extend(self.circuit_deprecations.iter(), |(circuit_id, at_ms)| CircuitEntry::Deprecation {
+            circuit_id: circuit_id.clone(),
+            deprecated_at_ms: *at_ms,
+        });
+        page.extend(self.gas_benchmarks.iter(), |(circuit_id, benchmark)| CircuitEntry::GasBenchmark {
+            circuit_id: circuit_id.clone(),
+            benchmark: *benchmark,
+        });
+        page.extend(self.domain_circuits.iter(), |(domain, circuit_id)| CircuitEntry::DomainCircuit {
+            domain: domain.clone(),
+            circuit_id: circuit_id.clone(),
+        });
+        page
+    }
+}
+
+/// A window of `limit` entries from index `from` over several collections
+/// read back to back.
+struct Page<T> {
+    items: Vec<T>,
+    skip: u32,
+    limit: u32,
+    /// Entries in every collection passed to `extend` so far.
+    total: u32,
+}
+
+impl<T> Page<T> {
+    fn new(from: u32, limit: u32) -> Self {
+        Self { items: Vec::new(), skip: from, limit, total: 0 }
+    }
+
+    /// Take the entries of `iter` that fall in the window, without reading
+    /// the ones before it.
+    fn extend<I: ExactSizeIterator>(&mut self, iter: I, entry: impl Fn(I::Item) -> T) {
+        let len = iter.len() as u32;
+        self.total += len;
+        if self.skip >= len {
+            self.skip -= len;
+            return;
+        }
+        let room = self.limit - self.items.len() as u32;
+        self.items.extend(iter.skip(self.skip as usize).take(room as usize).map(entry));
+        self.skip = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::borsh::BorshDeserialize;
+    use near_sdk::env;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn entries(chunk: &StateChunk) -> ExportedEntries {
+        assert_eq!(chunk.blob.0[0], STATE_EXPORT_VERSION);
+        ExportedEntries::try_from_slice(&chunk.blob.0[1..]).unwrap()
+    }
+
+    #[test]
+    fn sections_are_exported_in_chunks() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        for account in 1..4 {
+            testing_env!(VMContextBuilder::new()
+                .predecessor_account_id(accounts(account))
+                .attached_deposit(NearToken::from_millinear(10))
+                .build());
+            contract.register_email(env::sha256([account as u8]));
+        }
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        contract.set_lock_duration_ms(5);
+
+        let first = contract.export_state(StateSection::Registries, None, Some(2));
+        assert_eq!((first.total, first.next_from), (3, Some(2)));
+        let ExportedEntries::Registries(registries) = entries(&first) else { panic!() };
+        assert_eq!(registries.len(), 2);
+        let rest = contract.export_state(StateSection::Registries, first.next_from, Some(2));
+        assert_eq!(rest.next_from, None);
+        let ExportedEntries::Registries(registries) = entries(&rest) else { panic!() };
+        assert_eq!(
+            registries,
+            vec![RegistryEntry::RegisteredEmail {
+                account_id: accounts(3).to_string(),
+                email_hash: env::sha256([3]),
+            }]
+        );
+
+        let config = contract.export_state(StateSection::Config, None, None);
+        let ExportedEntries::Config(config) = entries(&config) else { panic!() };
+        assert_eq!(config.lock_duration_ms, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "only the owner can call this method")]
+    fn only_the_owner_can_export() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = ZkEmailVerifier::new(None);
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        contract.export_state(StateSection::Config, None, None);
+    }
+}
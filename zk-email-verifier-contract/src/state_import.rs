@@ -0,0 +1,348 @@
+//! Seeding a fresh deployment from another one's `export_state` chunks.
+//!
+//! The new deployment is initialized with `new_for_import` instead of `new`.
+//! The owner then passes each chunk's `blob` to `import_state` in the order
+//! it was exported, and calls `activate`. Imported entries overwrite
+//! whatever the new deployment holds under the same key. Nullifiers and
+//! result hashes are appended to their trees in export order, so once every
+//! chunk is in, `get_nullifier_root` and `get_result_root` match the old
+//! deployment's; the last `results` chunk is refused if they don't.
+//! Imported attestations keep their ids, and staged ids resume after them.
+//!
+//! Until activation, `verify_staged`, command emails and the guardian
+//! methods are refused, so nothing is accepted against half-imported
+//! nullifiers or registries. Activation can't be undone, and deployments
+//! initialized any other way start activated: a deployment that has
+//! started serving recoveries keeps its own history.
+
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{borsh, env, near};
+
+use crate::events::Event;
+use crate::challenges::Challenge;
+use crate::network::NetworkProfile;
+use crate::state_export::{
+    AccountHistory, CircuitEntry, ExportedConfig, ExportedEntries, PendingEntry, RegistryEntry, StateSection,
+    STATE_EXPORT_VERSION,
+};
+use crate::{ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near]
+impl ZkEmailVerifier {
+    /// Like `new`, but start closed to proofs and open to `import_state`
+    /// until `activate`.
+    #[init]
+    pub fn new_for_import(profile: Option<NetworkProfile>) -> Self {
+        let mut this = Self::new(profile);
+        this.activated = false;
+        this
+    }
+
+    /// Import the `blob` of a chunk exported from `section`. Returns the
+    /// number of entries imported. Only callable by the owner, before
+    /// `activate`.
+    pub fn import_state(&mut self, section: StateSection, blob: Base64VecU8) -> u32 {
+        self.assert_owner();
+        if self.activated {
+            env::panic_str("imports are closed once the contract is activated");
+        }
+        let entries = decode_chunk(&blob.0)
+            .unwrap_or_else(|| env::panic_str("not a state export of a supported version"));
+        let count = match (section, entries) {
+            (StateSection::Config, ExportedEntries::Config(config)) => {
+                self.import_config(*config);
+                1
+            }
+            (StateSection::Registries, ExportedEntries::Registries(entries)) => {
+                let count = entries.len();
+                entries.into_iter().for_each(|entry| self.import_registry_entry(entry));
+                count
+            }
+            (StateSection::Nullifiers, ExportedEntries::Nullifiers(nullifiers)) => {
+                let count = nullifiers.len();
+                nullifiers.into_iter().for_each(|nullifier| self.append_nullifier(nullifier));
+                count
+            }
+            (StateSection::PendingRecoveries, ExportedEntries::PendingRecoveries(entries)) => {
+                let count = entries.len();
+                entries.into_iter().for_each(|entry| self.import_pending_entry(entry));
+                count
+            }
+            (StateSection::Circuits, ExportedEntries::Circuits(entries)) => {
+                let count = entries.len();
+                entries.into_iter().for_each(|entry| self.import_circuit_entry(entry));
+                count
+            }
+            (StateSection::Challenges, ExportedEntries::Challenges { challenges, nonce }) => {
+                let count = challenges.len();
+                self.challenge_nonce = self.challenge_nonce.max(nonce);
+                challenges.into_iter().for_each(|challenge| self.import_challenge(challenge));
+                count
+            }
+            (StateSection::AccountHistory, ExportedEntries::AccountHistory(histories)) => {
+                let count = histories.len();
+                histories.into_iter().for_each(|history| self.import_account_history(history));
+                count
+            }
+            (StateSection::Results, ExportedEntries::Results { leaves, branch, len }) => {
+                let count = leaves.len();
+                for leaf in leaves {
+                    self.append_result_leaf(leaf);
+                }
+                if self.result_count == len && self.result_tree().branch != branch {
+                    env::panic_str("the imported results don't reproduce the exported accumulator");
+                }
+                count
+            }
+            (StateSection::Attestations, ExportedEntries::Attestations { attestations, chain_head }) => {
+                let count = attestations.len();
+                for (id, attestation) in attestations {
+                    self.next_staged_id = self.next_staged_id.max(id + 1);
+                    self.attestations.insert(id, attestation);
+                }
+                self.attestation_chain_head = chain_head;
+                count
+            }
+            _ => env::panic_str("the chunk was exported from another section"),
+        } as u32;
+        env::log_str(&format!("imported {count} entries"));
+        count
+    }
+
+    /// Close `import_state` for good. Only callable by the owner.
+    pub fn activate(&mut self) {
+        self.assert_owner();
+        if self.activated {
+            env::panic_str("the contract is already activated");
+        }
+        self.activated = true;
+        Event::ContractActivated {}.emit();
+    }
+}
+
+impl ZkEmailVerifier {
+    fn import_config(&mut self, config: ExportedConfig) {
+        self.network = config.network;
+        self.freshness_window_ms = config.freshness_window_ms;
+        self.circuit_breaker = config.circuit_breaker;
+        self.allowed_sender_domains = config.allowed_sender_domains;
+        self.set_audit_capacity(config.audit_capacity);
+        self.debug_logs = config.debug_logs;
+        self.vk_registry = config.vk_registry;
+        self.result_cache_ttl_ms = config.result_cache_ttl_ms;
+        self.predecessor_binding = config.predecessor_binding;
+        self.recovery_cooldown_ms = config.recovery_cooldown_ms;
+        self.recovery_rate_limit = config.recovery_rate_limit;
+        self.verification_fee = config.verification_fee;
+        self.relayer_reward = config.relayer_reward;
+        self.recovery_mailbox = config.recovery_mailbox;
+        self.default_key_permission = config.default_key_permission;
+        self.migration_timelock_ms = config.migration_timelock_ms;
+        self.lock_duration_ms = config.lock_duration_ms;
+        self.controller_contract = config.controller_contract;
+        self.guardian_removal_delay_ms = config.guardian_removal_delay_ms;
+        self.guardian_epoch_ms = config.guardian_epoch_ms;
+        self.email_change_window_ms = config.email_change_window_ms;
+        self.account_namespace = config.account_namespace;
+    }
+
+    fn import_registry_entry(&mut self, entry: RegistryEntry) {
+        match entry {
+            RegistryEntry::RegisteredEmail { account_id, email_hash } => {
+                self.registered_emails.insert(account_id, email_hash);
+            }
+            RegistryEntry::Relayer { account_id, relayer } => {
+                self.account_relayers.insert(account_id, relayer);
+            }
+            RegistryEntry::KeyPermission { account_id, permission } => {
+                self.account_key_permissions.insert(account_id, permission);
+            }
+            RegistryEntry::Lock { account_id, locked_until_ms } => {
+                self.account_locks.insert(account_id, locked_until_ms);
+            }
+            RegistryEntry::Guardians { account_id, guardians } => {
+                self.guardians.insert(account_id, guardians);
+            }
+            RegistryEntry::GuardianWeights { account_id, weights } => {
+                self.guardian_weights.insert(account_id, weights);
+            }
+            RegistryEntry::GuardianSets { account_id, sets } => {
+                self.guardian_sets.insert(account_id, sets);
+            }
+            RegistryEntry::RecoveryPolicy { account_id, policy } => {
+                self.recovery_policies.insert(account_id, policy);
+            }
+            RegistryEntry::BlockedSender { hash } => {
+                self.blocked_senders.insert(hash);
+            }
+            RegistryEntry::RateLimitBypass { account_id } => {
+                self.rate_limit_bypass.insert(account_id);
+            }
+        }
+    }
+
+    fn import_pending_entry(&mut self, entry: PendingEntry) {
+        match entry {
+            PendingEntry::Migration(migration) => {
+                self.pending_migrations.insert(migration.account_id.clone(), migration);
+            }
+            PendingEntry::SpendingLimit(limit) => {
+                self.pending_spending_limits.insert(limit.account_id.clone(), limit);
+            }
+            PendingEntry::GuardianRemovals { account_id, removals } => {
+                self.pending_guardian_removals.insert(account_id, removals);
+            }
+            PendingEntry::EmailChange(change) => {
+                self.pending_email_changes.insert(change.account_id.clone(), change);
+            }
+            PendingEntry::GuardianRecovery(recovery) => {
+                let key = (recovery.account_id.clone(), recovery.request_id.clone());
+                self.guardian_recoveries.insert(key, recovery);
+            }
+        }
+    }
+
+    fn import_circuit_entry(&mut self, entry: CircuitEntry) {
+        match entry {
+            CircuitEntry::Layout { circuit_id, layout_id } => {
+                self.circuit_layouts.insert(circuit_id, layout_id);
+            }
+            CircuitEntry::Deprecation { circuit_id, deprecated_at_ms } => {
+                self.circuit_deprecations.insert(circuit_id, deprecated_at_ms);
+            }
+            CircuitEntry::GasBenchmark { circuit_id, benchmark } => {
+                self.gas_benchmarks.insert(circuit_id, benchmark);
+            }
+            CircuitEntry::DomainCircuit { domain, circuit_id } => {
+                self.domain_circuits.insert(domain, circuit_id);
+            }
+        }
+    }
+
+    fn import_challenge(&mut self, challenge: Challenge) {
+        let mut issued = self.account_challenges.get(&challenge.account_id).cloned().unwrap_or_default();
+        if !issued.contains(&challenge.request_id) {
+            issued.push(challenge.request_id.clone());
+            self.account_challenges.insert(challenge.account_id.clone(), issued);
+        }
+        self.challenges.insert(challenge.request_id.clone(), challenge);
+    }
+
+    fn import_account_history(&mut self, history: AccountHistory) {
+        let len = history.records.len() as u32;
+        for (i, record) in history.records.into_iter().enumerate() {
+            self.account_history.insert((history.account_id.clone(), i as u32), record);
+        }
+        self.account_history_len.insert(history.account_id, len);
+    }
+}
+
+/// The entries of an exported `blob`, if its version is supported.
+fn decode_chunk(blob: &[u8]) -> Option<ExportedEntries> {
+    let (&version, entries) = blob.split_first()?;
+    if version != STATE_EXPORT_VERSION {
+        return None;
+    }
+    borsh::from_slice(entries).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+    use crate::VerificationResult;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn owner_context() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+    }
+
+    #[test]
+    fn an_import_reproduces_the_exported_state() {
+        owner_context();
+        let mut old = ZkEmailVerifier::new(None);
+        let mut result = VerificationResult::verified_for("alice.near");
+        for request_id in ["a", "b", "c"] {
+            result.request_id = request_id.to_string();
+            old.consume_nullifier(&result);
+            old.accumulate_result(&result);
+            old.audit(&result, RECOVER_EMAIL_CIRCUIT_ID);
+        }
+        old.attest(4, &result);
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("alice.near".parse().unwrap())
+            .attached_deposit(near_sdk::NearToken::from_millinear(10))
+            .build());
+        let request_id = old.generate_request_id("alice.near".to_string());
+        owner_context();
+        old.set_lock_duration_ms(5);
+
+        let mut new = ZkEmailVerifier::new_for_import(None);
+        for section in [
+            StateSection::Config,
+            StateSection::Nullifiers,
+            StateSection::Challenges,
+            StateSection::AccountHistory,
+            StateSection::Results,
+            StateSection::Attestations,
+        ] {
+            let mut from = Some(0);
+            while let Some(start) = from {
+                let chunk = old.export_state(section, Some(start), Some(2));
+                new.import_state(section, chunk.blob);
+                from = chunk.next_from;
+            }
+        }
+        assert_eq!(new.get_nullifier_root(), old.get_nullifier_root());
+        assert_eq!(new.get_lock_duration_ms(), 5);
+        assert_eq!(new.get_result_root(), old.get_result_root());
+        assert_eq!(new.export_attestation(4), old.export_attestation(4));
+        assert_eq!(new.attestation_chain_head, old.attestation_chain_head);
+        assert_eq!(new.next_staged_id, 5);
+        let open = |contract: &ZkEmailVerifier| contract.get_open_challenges("alice.near".to_string());
+        assert_eq!(open(&new), open(&old));
+        assert_eq!(new.challenge_nonce, old.challenge_nonce);
+        assert!(new.get_challenge(request_id).is_some());
+        let history = |contract: &ZkEmailVerifier| {
+            contract.get_verifications_for_account("alice.near".to_string(), None, None)
+        };
+        assert_eq!(history(&new).len(), 3);
+        assert_eq!(history(&new), history(&old));
+    }
+
+    #[test]
+    #[should_panic(expected = "the chunk was exported from another section")]
+    fn chunks_are_imported_into_their_own_section() {
+        owner_context();
+        let mut contract = ZkEmailVerifier::new_for_import(None);
+        let chunk = contract.export_state(StateSection::Config, None, None);
+        contract.import_state(StateSection::Registries, chunk.blob);
+    }
+
+    #[test]
+    #[should_panic(expected = "imports are closed once the contract is activated")]
+    fn activation_closes_imports() {
+        owner_context();
+        let mut contract = ZkEmailVerifier::new_for_import(None);
+        let chunk = contract.export_state(StateSection::Config, None, None);
+        assert!(!contract.health_check().activated);
+        contract.activate();
+        assert!(contract.health_check().activated);
+        contract.import_state(StateSection::Config, chunk.blob);
+    }
+
+    #[test]
+    #[should_panic(expected = "contract is not activated yet")]
+    fn proofs_are_refused_until_activation() {
+        owner_context();
+        let mut contract = ZkEmailVerifier::new_for_import(None);
+        contract.finalize_migration("alice.near".to_string());
+    }
+
+    #[test]
+    fn new_contracts_start_activated() {
+        owner_context();
+        assert!(ZkEmailVerifier::new(None).health_check().activated);
+    }
+}
@@ -0,0 +1,108 @@
+//! Running totals kept in contract state, so operators can watch verification
+//! health with a view call instead of an indexer.
+//!
+//! Only calls that write state are counted: `verify`, `verify_batch` and the
+//! other views run on whichever RPC node serves them and leave no trace, so
+//! the totals cover `verify_staged` and the stateful flows built on it.
+
+use std::collections::BTreeMap;
+
+use near_sdk::near;
+
+use crate::events::Event;
+use crate::{VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub proofs_verified: u64,
+    /// Rejected proofs, keyed by their `failure_reason` code.
+    pub proofs_failed: BTreeMap<String, u64>,
+    /// Counted by the recovery and nullifier flows; proof verification alone
+    /// never touches these two.
+    pub recoveries_finalized: u64,
+    pub nullifiers_consumed: u64,
+}
+
+impl Stats {
+    /// Count the outcome of one stateful verification.
+    pub(crate) fn record(&mut self, result: &VerificationResult) {
+        match &result.failure_reason {
+            None if result.verified => self.proofs_verified += 1,
+            reason => {
+                let code = reason.clone().unwrap_or_default();
+                *self.proofs_failed.entry(code).or_default() += 1;
+            }
+        }
+    }
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_stats(&self) -> Stats {
+        self.stats.clone()
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Record the outcome of a stateful verification against `circuit_id`:
+    /// in the totals, the circuit breaker and the audit log, plus an event if
+    /// it failed.
+    pub(crate) fn record_outcome(&mut self, result: &VerificationResult, circuit_id: &str) {
+        if !result.verified {
+            Event::ZkEmailVerifyFailed {
+                reason: result.failure_reason.clone().unwrap_or_default(),
+                account_id: Some(result.account_id.clone()).filter(|a| !a.is_empty()),
+                circuit_id: circuit_id.to_string(),
+                vk_hash: self.prepared_vk_hash(circuit_id),
+            }
+            .emit();
+        }
+        self.stats.record(result);
+        self.observe_for_breaker(result);
+        self.audit(result, circuit_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::RECOVER_EMAIL_CIRCUIT_ID;
+    use crate::FailureReason;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn failures_are_counted_by_reason() {
+        let mut stats = Stats::default();
+        let verified = VerificationResult::verified_for("");
+
+        stats.record(&verified);
+        stats.record(&VerificationResult::rejected(FailureReason::Pairing));
+        stats.record(&VerificationResult::rejected(FailureReason::InputLen));
+        stats.record(&VerificationResult::rejected(FailureReason::Pairing));
+
+        assert_eq!(stats.proofs_verified, 1);
+        assert_eq!(
+            stats.proofs_failed,
+            BTreeMap::from([("INPUT_LEN".to_string(), 1), ("PAIRING".to_string(), 2)])
+        );
+    }
+
+    #[test]
+    fn failed_outcomes_emit_an_event() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut contract = ZkEmailVerifier::new(None);
+        let mut stale = VerificationResult::rejected(FailureReason::StaleEmail);
+        stale.account_id = "alice.near".to_string();
+        contract.record_outcome(&stale, RECOVER_EMAIL_CIRCUIT_ID);
+
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].starts_with("EVENT_JSON:"));
+        for part in ["zk_email_verify_failed", "STALE_EMAIL", "alice.near", RECOVER_EMAIL_CIRCUIT_ID] {
+            assert!(logs[0].contains(part), "{part} missing from {}", logs[0]);
+        }
+        assert_eq!(contract.get_stats().proofs_failed["STALE_EMAIL"], 1);
+    }
+}
@@ -0,0 +1,96 @@
+//! Contexts and fixtures shared by the unit tests.
+
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_groth16::VerifyingKey;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, NearToken};
+use zk_email_verifier_core::layout::{FieldEncoding, PublicInputLayout};
+use zk_email_verifier_core::packing::pack_str_to_chunks;
+
+use crate::prepared_vk::{g1_to_json, g2_to_json, verifying_key_json};
+use crate::{FailureReason, ProofInput, VerificationResult, VerifyingKeyInput};
+
+/// What tests attach to calls that charge for storage.
+pub(crate) const STORAGE_DEPOSIT: NearToken = NearToken::from_millinear(10);
+
+/// Call as the owner, `accounts(0)`, at `timestamp_ms`.
+pub(crate) fn at(timestamp_ms: u64) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(accounts(0))
+        .block_timestamp(timestamp_ms * 1_000_000)
+        .build());
+}
+
+/// Call as `accounts(account)` at `timestamp_ms`, attaching
+/// `STORAGE_DEPOSIT`.
+pub(crate) fn as_account_at(account: usize, timestamp_ms: u64) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(accounts(account))
+        .block_timestamp(timestamp_ms * 1_000_000)
+        .attached_deposit(STORAGE_DEPOSIT)
+        .build());
+}
+
+/// Call as `accounts(account)`, attaching `deposit`.
+pub(crate) fn called_by(account: usize, deposit: NearToken) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(accounts(account))
+        .attached_deposit(deposit)
+        .build());
+}
+
+impl VerificationResult {
+    /// A verified recovery of `account_id` with every other field empty.
+    pub(crate) fn verified_for(account_id: &str) -> Self {
+        let mut result = Self::rejected(FailureReason::Pairing);
+        result.verified = true;
+        result.failure_reason = None;
+        result.account_id = account_id.to_string();
+        result
+    }
+}
+
+/// A verifying key with `n_public` inputs and a prover for it. There is no
+/// circuit behind it: the key is made with a known trapdoor (every `IC` point
+/// is the generator), so a proof can be forged for any public inputs.
+pub(crate) fn trapdoor_circuit(n_public: usize) -> (VerifyingKeyInput, impl Fn(&[String]) -> ProofInput) {
+    let (a, b) = (Fr::from(3u64), Fr::from(5u64));
+    let g1 = G1Affine::generator();
+    let g2 = G2Affine::generator();
+    let vk = VerifyingKey::<Bn254> {
+        alpha_g1: (g1 * a).into_affine(),
+        beta_g2: (g2 * b).into_affine(),
+        gamma_g2: g2,
+        delta_g2: g2,
+        gamma_abc_g1: vec![g1; n_public + 1],
+    };
+    // With B = G2 and C = G1 the pairing check reduces to
+    // A = (ab + (1 + sum(x)) + 1) G1.
+    let prove = move |inputs: &[String]| {
+        let sum: Fr = inputs.iter().map(|x| x.parse::<Fr>().expect("decimal field element")).sum();
+        ProofInput {
+            pi_a: g1_to_json(&(g1 * (a * b + sum + Fr::from(2u64))).into_affine()),
+            pi_b: g2_to_json(&g2),
+            pi_c: g1_to_json(&g1),
+            protocol: None,
+            curve: None,
+        }
+    };
+    (verifying_key_json(&vk).key, prove)
+}
+
+/// Public inputs of `layout` with the packed fields named in `values` set to
+/// their strings and every other signal zero.
+pub(crate) fn public_inputs(layout: &PublicInputLayout, values: &[(&str, &str)]) -> Vec<String> {
+    let mut inputs = vec![Fr::from(0u64); layout.len];
+    for (name, value) in values {
+        let field = layout.field(name).unwrap_or_else(|| panic!("no field {name}"));
+        let FieldEncoding::PackedBytes { max_len } = field.encoding else {
+            panic!("{name} is not a packed field");
+        };
+        let packed = pack_str_to_chunks(value, max_len).expect("value fits its field");
+        inputs[field.range()].copy_from_slice(&packed);
+    }
+    inputs.iter().map(Fr::to_string).collect()
+}
@@ -0,0 +1,334 @@
+//! Verification fees and the treasury they are collected into.
+//!
+//! The owner can charge a fee per `verify_staged` call, to be attached as
+//! deposit; anything attached beyond it is refunded. Collected fees are
+//! tracked in `treasury_balance` rather than left mixed into the account
+//! balance that also backs storage, so they can be audited through the
+//! `fee_accrued` and `treasury_withdrawn` events and only leave the contract
+//! through `withdraw_treasury`. A withdrawal whose transfer fails (say, to
+//! an account that doesn't exist) is put back into the treasury.
+//!
+//! The owner can also pay the account that submitted a recovery a reward out
+//! of the treasury once the recovery is finalized, as a fixed amount or a
+//! share of the fee. Rewards the treasury can't cover are skipped, and like a
+//! withdrawal, a reward whose transfer fails is put back into the treasury.
+
+use near_sdk::{env, is_promise_success, near, serde_json, AccountId, Gas, NearToken, Promise};
+
+use crate::events::Event;
+use crate::{VerificationResult, ZkEmailVerifier, ZkEmailVerifierExt};
+
+/// Gas for `on_treasury_withdrawn`.
+const ON_TREASURY_WITHDRAWN_GAS: Gas = Gas::from_tgas(5);
+
+/// Gas for `on_relayer_rewarded`.
+const ON_RELAYER_REWARDED_GAS: Gas = Gas::from_tgas(5);
+
+/// What the submitter of a finalized recovery is paid.
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayerReward {
+    Fixed { amount: NearToken },
+    /// `percent` of the current verification fee.
+    FeePercent { percent: u8 },
+}
+
+#[near]
+impl ZkEmailVerifier {
+    pub fn get_verification_fee(&self) -> NearToken {
+        self.verification_fee
+    }
+
+    /// Charge `fee` per `verify_staged` call; zero turns fees off. Only
+    /// callable by the owner.
+    pub fn set_verification_fee(&mut self, fee: NearToken) {
+        self.assert_owner();
+        self.verification_fee = fee;
+    }
+
+    pub fn get_relayer_reward(&self) -> Option<RelayerReward> {
+        self.relayer_reward
+    }
+
+    /// Pay `reward` to the submitter of every finalized recovery, or stop
+    /// with `None`. Only callable by the owner.
+    pub fn set_relayer_reward(&mut self, reward: Option<RelayerReward>) {
+        self.assert_owner();
+        if let Some(RelayerReward::FeePercent { percent }) = reward {
+            if percent > 100 {
+                env::panic_str("reward percent must be at most 100");
+            }
+        }
+        self.relayer_reward = reward;
+    }
+
+    pub fn get_treasury_balance(&self) -> NearToken {
+        self.treasury_balance
+    }
+
+    /// Send `amount` of the collected fees to `to`; it returns to the
+    /// treasury if the transfer fails. Only callable by the owner.
+    pub fn withdraw_treasury(&mut self, amount: NearToken, to: AccountId) -> Promise {
+        self.assert_owner();
+        self.treasury_balance = self
+            .treasury_balance
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("amount exceeds the treasury balance"));
+        Event::TreasuryWithdrawn {
+            amount,
+            to: to.clone(),
+            treasury_balance: self.treasury_balance,
+        }
+        .emit();
+        let args = serde_json::json!({ "amount": amount, "to": to }).to_string().into_bytes();
+        Promise::new(to).transfer(amount).then(Promise::new(env::current_account_id()).function_call(
+            "on_treasury_withdrawn",
+            args,
+            NearToken::from_yoctonear(0),
+            ON_TREASURY_WITHDRAWN_GAS,
+        ))
+    }
+
+    /// Callback of the transfer scheduled by `withdraw_treasury`: put
+    /// `amount` back into the treasury if it failed.
+    #[private]
+    pub fn on_treasury_withdrawn(&mut self, amount: NearToken, to: AccountId) {
+        if is_promise_success() {
+            return;
+        }
+        self.treasury_balance = self.treasury_balance.saturating_add(amount);
+        Event::TreasuryWithdrawalFailed {
+            amount,
+            to,
+            treasury_balance: self.treasury_balance,
+        }
+        .emit();
+    }
+
+    /// Callback of the transfer scheduled by `reward_relayer`: put `amount`
+    /// back into the treasury if it failed.
+    #[private]
+    pub fn on_relayer_rewarded(&mut self, amount: NearToken, relayer: AccountId) {
+        if is_promise_success() {
+            return;
+        }
+        self.treasury_balance = self.treasury_balance.saturating_add(amount);
+        Event::RelayerRewardFailed {
+            relayer,
+            amount,
+            treasury_balance: self.treasury_balance,
+        }
+        .emit();
+    }
+}
+
+impl ZkEmailVerifier {
+    /// Take the verification fee into the treasury, from the caller's
+    /// sponsorship pool if it covers it and otherwise out of the attached
+    /// deposit, keep `storage` of the deposit for storage the call reserves,
+    /// and refund the rest. Panics if the deposit doesn't cover what it must.
+    pub(crate) fn charge_verification_fee(&mut self, storage: NearToken) {
+        let fee = self.verification_fee;
+        let payer = env::predecessor_account_id();
+        let due = if !fee.is_zero() && self.draw_sponsored_fee(&payer, fee) {
+            storage
+        } else {
+            fee.saturating_add(storage)
+        };
+        let surplus = env::attached_deposit().checked_sub(due).unwrap_or_else(|| {
+            let what = if storage.is_zero() { "" } else { " and storage" };
+            env::panic_str(&format!("attach {due} to pay the verification fee{what}"))
+        });
+        if !fee.is_zero() {
+            self.treasury_balance = self.treasury_balance.saturating_add(fee);
+            Event::FeeAccrued {
+                amount: fee,
+                payer,
+                treasury_balance: self.treasury_balance,
+            }
+            .emit();
+        }
+        refund(surplus);
+    }
+
+    /// Pay the relayer reward for a verified `result` to `relayer` from the
+    /// treasury, if one is configured and the treasury covers it. The reward
+    /// returns to the treasury if the transfer fails.
+    pub(crate) fn reward_relayer(&mut self, result: &VerificationResult, relayer: &AccountId) {
+        if !result.verified {
+            return;
+        }
+        let amount = match self.relayer_reward {
+            None => return,
+            Some(RelayerReward::Fixed { amount }) => amount,
+            Some(RelayerReward::FeePercent { percent }) => {
+                self.verification_fee.saturating_mul(u128::from(percent)).saturating_div(100)
+            }
+        };
+        if amount.is_zero() {
+            return;
+        }
+        let Some(balance) = self.treasury_balance.checked_sub(amount) else {
+            env::log_str(&format!("treasury can't cover the {amount} relayer reward"));
+            return;
+        };
+        self.treasury_balance = balance;
+        Event::RelayerRewarded {
+            relayer: relayer.clone(),
+            account_id: result.account_id.clone(),
+            amount,
+        }
+        .emit();
+        let args = serde_json::json!({ "amount": amount, "relayer": relayer }).to_string().into_bytes();
+        Promise::new(relayer.clone())
+            .transfer(amount)
+            .then(Promise::new(env::current_account_id()).function_call(
+                "on_relayer_rewarded",
+                args,
+                NearToken::from_yoctonear(0),
+                ON_RELAYER_REWARDED_GAS,
+            ))
+            .detach();
+    }
+}
+
+/// What the storage grown since `usage_before` costs.
+pub(crate) fn storage_cost_since(usage_before: u64) -> NearToken {
+    let bytes = env::storage_usage().saturating_sub(usage_before);
+    env::storage_byte_cost().saturating_mul(u128::from(bytes))
+}
+
+/// Take the cost of the storage grown since `usage_before` out of the
+/// attached deposit and refund the rest. Panics if the deposit doesn't cover
+/// it. Collections must be flushed first for their writes to count.
+pub(crate) fn charge_storage(usage_before: u64) {
+    let cost = storage_cost_since(usage_before);
+    let surplus = env::attached_deposit()
+        .checked_sub(cost)
+        .unwrap_or_else(|| env::panic_str(&format!("attach {cost} to cover storage")));
+    refund(surplus);
+}
+
+/// Send `amount` back to the caller.
+pub(crate) fn refund(amount: NearToken) {
+    if !amount.is_zero() {
+        Promise::new(env::predecessor_account_id()).transfer(amount).detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::called_by;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+
+    #[test]
+    fn fees_accrue_to_the_treasury_until_withdrawn() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.charge_verification_fee(NearToken::from_yoctonear(0));
+        assert!(contract.get_treasury_balance().is_zero());
+
+        contract.set_verification_fee(NearToken::from_millinear(10));
+        called_by(1, NearToken::from_millinear(10));
+        contract.charge_verification_fee(NearToken::from_yoctonear(0));
+        contract.charge_verification_fee(NearToken::from_yoctonear(0));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(20));
+
+        called_by(0, NearToken::from_yoctonear(0));
+        contract.withdraw_treasury(NearToken::from_millinear(15), accounts(2)).detach();
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(5));
+    }
+
+    #[test]
+    fn relayers_are_rewarded_while_the_treasury_covers_it() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_verification_fee(NearToken::from_millinear(10));
+        contract.set_relayer_reward(Some(RelayerReward::FeePercent { percent: 40 }));
+        let mut result = VerificationResult::rejected(crate::FailureReason::Pairing);
+
+        called_by(1, NearToken::from_millinear(10));
+        contract.charge_verification_fee(NearToken::from_yoctonear(0));
+        contract.reward_relayer(&result, &accounts(1));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(10));
+
+        result.verified = true;
+        contract.reward_relayer(&result, &accounts(1));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(6));
+
+        called_by(0, NearToken::from_yoctonear(0));
+        contract.set_relayer_reward(Some(RelayerReward::Fixed { amount: NearToken::from_millinear(7) }));
+        contract.reward_relayer(&result, &accounts(1));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(6));
+    }
+
+    #[test]
+    fn failed_withdrawals_return_to_the_treasury() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.treasury_balance = NearToken::from_millinear(20);
+        contract.withdraw_treasury(NearToken::from_millinear(15), accounts(2)).detach();
+
+        let callback = |result: PromiseResult| {
+            let context = VMContextBuilder::new().predecessor_account_id(accounts(0)).build();
+            testing_env!(
+                context,
+                near_sdk::test_vm_config(),
+                near_sdk::RuntimeFeesConfig::test(),
+                Default::default(),
+                vec![result],
+            );
+        };
+        callback(PromiseResult::Successful(vec![]));
+        contract.on_treasury_withdrawn(NearToken::from_millinear(15), accounts(2));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(5));
+
+        callback(PromiseResult::Failed);
+        contract.on_treasury_withdrawn(NearToken::from_millinear(15), accounts(2));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(20));
+    }
+
+    #[test]
+    fn failed_rewards_return_to_the_treasury() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.treasury_balance = NearToken::from_millinear(20);
+        contract.set_relayer_reward(Some(RelayerReward::Fixed { amount: NearToken::from_millinear(5) }));
+        let mut result = VerificationResult::rejected(crate::FailureReason::Pairing);
+        result.verified = true;
+        contract.reward_relayer(&result, &accounts(1));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(15));
+
+        let context = VMContextBuilder::new().predecessor_account_id(accounts(0)).build();
+        testing_env!(
+            context,
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_relayer_rewarded(NearToken::from_millinear(5), accounts(1));
+        assert_eq!(contract.get_treasury_balance(), NearToken::from_millinear(20));
+    }
+
+    #[test]
+    #[should_panic(expected = "amount exceeds the treasury balance")]
+    fn withdrawals_are_bounded_by_the_balance() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.withdraw_treasury(NearToken::from_yoctonear(1), accounts(2)).detach();
+    }
+
+    #[test]
+    #[should_panic(expected = "to pay the verification fee")]
+    fn an_uncovered_fee_is_refused() {
+        called_by(0, NearToken::from_yoctonear(0));
+        let mut contract = ZkEmailVerifier::new(None);
+        contract.set_verification_fee(NearToken::from_millinear(10));
+        called_by(1, NearToken::from_millinear(9));
+        contract.charge_verification_fee(NearToken::from_yoctonear(0));
+    }
+}
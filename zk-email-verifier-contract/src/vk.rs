@@ -1,468 +1,4 @@
-// This file is @generated by vk_to_rust; do not edit manually.
-use core::str::FromStr;
-use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine, Fr};
-use ark_groth16::VerifyingKey;
+//! Compiled-in verifying key for `RecoverEmailCircuit`, generated by `build.rs`
+//! from `verification_key.json`.
 
-pub fn verifying_key() -> VerifyingKey<Bn254> {
-    let alpha_g1 = G1Affine::new_unchecked(
-        Fq::from_str("20491192805390485299153009773594534940189261866228447918068658471970481763042").unwrap(),
-        Fq::from_str("9383485363053290200918347156157836566562967994039712273449902621266178545958").unwrap(),
-    );
-
-    let beta_g2 = G2Affine::new_unchecked(
-        Fq2::new(
-            Fq::from_str("6375614351688725206403948262868962793625744043794305715222011528459656738731").unwrap(),
-            Fq::from_str("4252822878758300859123897981450591353533073413197771768651442665752259397132").unwrap(),
-        ),
-        Fq2::new(
-            Fq::from_str("10505242626370262277552901082094356697409835680220590971873171140371331206856").unwrap(),
-            Fq::from_str("21847035105528745403288232691147584728191162732299865338377159692350059136679").unwrap(),
-        ),
-    );
-
-    let gamma_g2 = G2Affine::new_unchecked(
-        Fq2::new(
-            Fq::from_str("10857046999023057135944570762232829481370756359578518086990519993285655852781").unwrap(),
-            Fq::from_str("11559732032986387107991004021392285783925812861821192530917403151452391805634").unwrap(),
-        ),
-        Fq2::new(
-            Fq::from_str("8495653923123431417604973247489272438418190587263600148770280649306958101930").unwrap(),
-            Fq::from_str("4082367875863433681332203403145435568316851327593401208105741076214120093531").unwrap(),
-        ),
-    );
-
-    let delta_g2 = G2Affine::new_unchecked(
-        Fq2::new(
-            Fq::from_str("10857046999023057135944570762232829481370756359578518086990519993285655852781").unwrap(),
-            Fq::from_str("11559732032986387107991004021392285783925812861821192530917403151452391805634").unwrap(),
-        ),
-        Fq2::new(
-            Fq::from_str("8495653923123431417604973247489272438418190587263600148770280649306958101930").unwrap(),
-            Fq::from_str("4082367875863433681332203403145435568316851327593401208105741076214120093531").unwrap(),
-        ),
-    );
-
-    let gamma_abc_g1: Vec<G1Affine> = vec![
-                G1Affine::new_unchecked(
-            Fq::from_str("7956436899764839012573170199179226111622259895152251510195913936149612855082").unwrap(),
-            Fq::from_str("6496543431964558455081458508573354896678165401194643421699677910592188889950").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("4148197327467881481661994301335310468251813246083832751571741452091452183862").unwrap(),
-            Fq::from_str("8641798417437867674194984148900103528517498558351370475794051050114377888116").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16816868337908708196319965791012130686583699257108947677477573009634241662636").unwrap(),
-            Fq::from_str("12678098288711281925569204306188000513098914327606241831061815214829108223623").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("8513897747978816927970174788026065663854588160826869612023077443474807311715").unwrap(),
-            Fq::from_str("14796883666682783548812258677886289110635593600634802183538836162675857427533").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("350333687639819193010764609005146313203884662178342539873388907236275715146").unwrap(),
-            Fq::from_str("9317876111063988951046209721047183879973447394874762588751967547946728756164").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("6280405581617546183806891472857024268182129696547222969366193404018631781819").unwrap(),
-            Fq::from_str("9802391879599439522253270148865459139610142442130149877247544665029993416449").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("19439929335629218501794294579120936834838413581590039347054219035443126062050").unwrap(),
-            Fq::from_str("5198592865994707785355398883371573139005040958056600679160588908781969781267").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("953983816583569618961436332036839880541620669633910304294919982900465402363").unwrap(),
-            Fq::from_str("16108818508609490358260110689172438214174186414783767150555493620394179763671").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("7707922659980186150533011462755619175260345711750797045807309047411975583547").unwrap(),
-            Fq::from_str("8487652083367753348783146092386418717129991907525920892650805737944706705733").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("1846385401148317947597229030394901629103845360728909968936247878513504805149").unwrap(),
-            Fq::from_str("10716889096548901204538405537666361669946505230698681274776671709414848378554").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("18864558659602020128651209855329148204722036032057379329475425059348433785333").unwrap(),
-            Fq::from_str("15745310971673500759617626622853567602607611782293622205277225128733115873967").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("7450741651660251494065745093449144420886019853579426419462932219877666762586").unwrap(),
-            Fq::from_str("18405202072335493602153865940629021522857798280900669729744742304424362846081").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("3853744557167347105417751348938020480921654028341502021933038588664891953151").unwrap(),
-            Fq::from_str("6778680175769526968883928444438487351598369825085399869816810078474722939237").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("9300698778229010375434926082488923164140058534968033695909580820089250200813").unwrap(),
-            Fq::from_str("3979373714722269110934485095939939037003413097717490273267363654500747897951").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("19463282344664568278923857573498347266033000779346064082102453499769714063305").unwrap(),
-            Fq::from_str("8796865255888408473072546813536637701753596756889785591886801810422045551659").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("11055816475204836757574978786033202564920378132358824736420009515257013796499").unwrap(),
-            Fq::from_str("12684146855224438959726369004793722694604552912977116811847155004101657417980").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16993775868849169647381830262170351541135344487974966496258257178612919084969").unwrap(),
-            Fq::from_str("11795070032605532792221544405781561867930345690590338568329605097313817156444").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16523855626056388498242927293441636109823911665608069995679021534991848054237").unwrap(),
-            Fq::from_str("15687939048288668521404016774321286610096510606531218970097416586055422427608").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("6353694674895828126075611781266667943812072525194622021327049296735801839240").unwrap(),
-            Fq::from_str("4310774705256217898613597991898639992627847357379402138951589651808877543390").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("17217573593867201088707218132661651120960325208105959521673344083552310549923").unwrap(),
-            Fq::from_str("12391180604404844671405885989294793301287844350351685808433648608605321553405").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("213759093779778877807079764287751303171746530841960563616790655133414176385").unwrap(),
-            Fq::from_str("13806543252807277568490457808704222844606518477900419415857448237288293965484").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("7874305180037354601632721695930661918388752496183664546833542116078944835082").unwrap(),
-            Fq::from_str("3896007200494244072587643268811619861990794076016969857313489985078444076705").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("7013389717457132848350102396524996312359704578005068623083825273868171035042").unwrap(),
-            Fq::from_str("6133691768629073868401320534079244715953284541087020625998951870071688099254").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("8725692469348854444941089732908192197207981975758686382118173376502539160916").unwrap(),
-            Fq::from_str("4203276141181358678542114322965857124808375106466519751550048163990184523873").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16109245853664752882639402536104508920143746155783118521637871178637217383937").unwrap(),
-            Fq::from_str("1844612767776393564582601058119835866729829017098341341570629694652369696688").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("1925746584881245672982570978873797929611525370270321114533319263446107152731").unwrap(),
-            Fq::from_str("2135082809251291412606031554451953447141717642904264297334005707824563893056").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("7045121980686498833938339876710831694627682391293035119320774854175889421979").unwrap(),
-            Fq::from_str("3320268968936259395908835243380860871454448799147037281470044895867477517657").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("6642193529407664801997415572962499124239649139683878787264759095228742402292").unwrap(),
-            Fq::from_str("1739619390453701148481916558194789816639669029378402700952284940823087835233").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("12938656172166683326460040350240877935356296909335259155586065483973007769498").unwrap(),
-            Fq::from_str("2033130749436667094138797666405694877896219784523377012701785984418182989757").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("1268923493249016620291818517336865081953601698332728416604431950966078170229").unwrap(),
-            Fq::from_str("2223662552756532035299698096422814673394017660884752045390333465215345638893").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("20772485199653661634498670527321825663713703747505305206258627324086292453737").unwrap(),
-            Fq::from_str("21755405636662878330415262874983678317707924166510101674603894434779947895638").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("9550602491131513077332639624925013829127598720781020014301711305062729993140").unwrap(),
-            Fq::from_str("4162563444648909467834714491617940291874595700187395284445150058627800552490").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("13901692112224726933203939827545320526054644420527711194918096757403369641217").unwrap(),
-            Fq::from_str("3731334909199042117498958645523966082592791246847096980819824231100104750382").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("14040145744446208015489072860971281277346446102015296972434252979839731932111").unwrap(),
-            Fq::from_str("21310877320720349322612209365377078788552933912998835444542387632941633514226").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("19815363052483073946651199686749899107984462169259091053593684551698201227113").unwrap(),
-            Fq::from_str("12059213312579590782439360776241178016688907612280957074116468631961002717916").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("18642822820734883613296843352672419525532048603945354394818648794801047717505").unwrap(),
-            Fq::from_str("17095195428478067323861272463771536581036632305917579813633331847975062028182").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("19444771882092155446196347155553006461799046646216396038852453610106863392580").unwrap(),
-            Fq::from_str("2067610349835388335136050448386720384690766423857592608138089310497001974347").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("20459245373631103900369432936232335247244549295694566761934019382988604925172").unwrap(),
-            Fq::from_str("7026186710577425461875583632870431742690610064228289244508313245852316520629").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("4287838042193560201271239239624960746022235533313528073543055601612677374555").unwrap(),
-            Fq::from_str("3527689624889279045237395274826637125754519061088947649109441180074855531487").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("20642524366858814285176905170903811773648395033247407310026702399673020796359").unwrap(),
-            Fq::from_str("18711978950961285270871160477495736734899001316273770706677263631084802680248").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("18563639183729547501287128579978874134289188397938116812878049442893925019311").unwrap(),
-            Fq::from_str("5687909231546045019426345490252655880774594510113010272035555241039122846007").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("11004369397916544937360894265068120276217770990700158397003955968776662377281").unwrap(),
-            Fq::from_str("3734495318949502896423253856179936884612877136565070396790049141859735490301").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("19483914041236405148416480773445489356949717568796348973881988168980051425735").unwrap(),
-            Fq::from_str("18682916267111496174314056303906684204177518954730020945575244717262807859186").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("15579024599271301602996736947637973999740281299285137196596476109043287547584").unwrap(),
-            Fq::from_str("9122400119396449882802329305318731985446176982940380194775040339560766892027").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("19112821627351164764676105405952866271853115307797641536346578339524807242598").unwrap(),
-            Fq::from_str("9661592805494453213512741716347944830613369168489637303373476226650650043761").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("20426789627616427643964756554849111917092916504605176904809464626853092470549").unwrap(),
-            Fq::from_str("18843673625529705057552024446774139849396903121701587776962550981283925010274").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("2572081461996235140789389975589063809061486413245601083218334978191717003389").unwrap(),
-            Fq::from_str("17825645751549477909735364847593116421199205654641034993455048366336846247634").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("19126014872452667615398543419297129822825472892352552402858671577317234359704").unwrap(),
-            Fq::from_str("17679797223569942279180981888335298456199981524141178221129323267386895327940").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("17706915857575761597844542908620426331194636611799035455056012406544805978822").unwrap(),
-            Fq::from_str("8785648932095581462694570563037542854087610130484441339902003624812552744023").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("3183732479054916171854418650665984756071345953949927583616407468700022537760").unwrap(),
-            Fq::from_str("12258034162835510316282402390298074244766470099524467372236815547885070191054").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("4959191017009951817273394013940663846570971124801993653101043439217372790555").unwrap(),
-            Fq::from_str("10672067777074663206262206073863441150463294272025872006731519567898318240364").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("1423951732832012631600067498380844172811222936406038331746399956011002439879").unwrap(),
-            Fq::from_str("1804414548082244412954412754701240484696127166723361034277215597267111678600").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("8563375052494460100689085275133171697266377933259088949232945094096813649064").unwrap(),
-            Fq::from_str("21337764954877461815859094563770891407288008148882141993206772598145790206699").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("8539521477234488611454006606864336460729733357579651788555303680314940766139").unwrap(),
-            Fq::from_str("2390032676609921323997640858429547134001695883422528798679435681774403784370").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("12565247989790633591690532092793479683873452146163417539885135597602697936728").unwrap(),
-            Fq::from_str("21827323531179697423884645676287124113802857947136088175280826595144216384696").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("1194123558304053123335748627828893858581702401464414484108818134030589590821").unwrap(),
-            Fq::from_str("21018488275821274066807834279333640660360960993427740899541278454409599386431").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("6352518997535118837895770054195850545807147269738065670932969153224519161952").unwrap(),
-            Fq::from_str("9923135367455976272751401805512676122411938318940528532398573381889454414347").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("5804985158082759330575392574596860636108057807530626138452057595699919609611").unwrap(),
-            Fq::from_str("14298872739661159747641267393166532542686233198511823632843630674429679361092").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("14101684579375720667051706665384299382053239577007361884759473861304416723703").unwrap(),
-            Fq::from_str("8573212545855249740566282384563929652519390617779954044932747366116858296893").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("21281276802294711407523771668349398016684163084584083679153408257518956818694").unwrap(),
-            Fq::from_str("3521447329647840215921925189158957796976042788128963641133370422584120030514").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("9531076000681702546194879107757328835654990892467446460374337968812858579457").unwrap(),
-            Fq::from_str("4311363228899819485573007965346777425568850195838827495501998839223868945131").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("13795772225423146470673875737070048136910298635628365383548820674703470703596").unwrap(),
-            Fq::from_str("325735122343226354366202028631945020093394131036475338061301868723971969237").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("944836314537701423226298042077576591214617379111544226225003935733591485807").unwrap(),
-            Fq::from_str("9273679114503496337365159889936032871626852083701877806208868788776883073090").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("14644017804166771080064906028685496243619036674304000610857812479598623920396").unwrap(),
-            Fq::from_str("15367876605119748913298073789777304930275789995306870277330192940719988265510").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("7601064788963148432750506394083109781320063518047155926821110040089245069550").unwrap(),
-            Fq::from_str("13524175776206723704810403274012368921166124526506848519405929209885825366367").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("563507047051256589949362899641133951390882312063941446457928926314755960765").unwrap(),
-            Fq::from_str("6561841608031304133861416491343397772560909330049372114984994186069565409284").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("14761399909510381065563735615159857008740191331793544315441078524555967926430").unwrap(),
-            Fq::from_str("14402677737437549391828132549685267875492127887066632562667643431755491131397").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16060482820667125629107309332018703681397264577898432066762262997706087244064").unwrap(),
-            Fq::from_str("3491318941407061628635237792259730741446871092635655203709975464430510981250").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("2148705696784050371998823892130297112077466746957821090268692610796538753478").unwrap(),
-            Fq::from_str("8566917276854166365984463962904057991879072405903334992857872864599394190821").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("14994313864467176494940972669310235156591653404819745798910540664674817332850").unwrap(),
-            Fq::from_str("12993597643401265080289510593195264226218952925474818213616614249588451206280").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("2904247164966059876300823698085247524032943020380839905995634978324468067572").unwrap(),
-            Fq::from_str("476394630326463960806620230319379620609357394416009847690826066935301448896").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("4282743406463070216237426586932088398443803676005194937474591905419944942425").unwrap(),
-            Fq::from_str("17183384471196215057497832625677859232109889641306215814833997085702884236690").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16291271106396562369650208180583336481740426314801389395092398372906330350639").unwrap(),
-            Fq::from_str("16905252806127174517582767901496509239734673879594571557064429872460615656780").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("14674126523270618979772474255765247643377113882536062458167639414836361380220").unwrap(),
-            Fq::from_str("9304788378149438140131811349198774812093340761457886231107657925731952142092").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("9842598244180717893993572079757797448085462287057117127417020811540832893228").unwrap(),
-            Fq::from_str("1055934216830829027495484621746188808046368619894516378435602702947837605215").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("17040204700600304991594206647713521956443501545070546007207032068137052573766").unwrap(),
-            Fq::from_str("2740111034009468690723419304639254701322259227933056937588387603594548007540").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("3480993598869914734376860829676413034467171977903786638647667132779723918677").unwrap(),
-            Fq::from_str("7538030649227533293187088308557240809930192010346554331354321023310845667549").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("4365199088140768950752505740150514365445241187603983535269306910605510738320").unwrap(),
-            Fq::from_str("19103636532636788379353873744974088643267657002767173974507542679893820262483").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("8885827661019595374114567925050226023442425531467304432572777213350319570843").unwrap(),
-            Fq::from_str("14477960738813991558858026504214861033531919640399827316037756460073122077442").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("19986683198499713872870455778394747796520699275765522748645227163257920462855").unwrap(),
-            Fq::from_str("10613834296591413745968318001935338485562199207085520197482358483554316999390").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("3560578502331383193887509071908697864690588440827859571324054403301409183681").unwrap(),
-            Fq::from_str("14791344939754023974363356537367157348439902287906257255119475408658403918225").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("21087272725752109613886467014355669988675823660364355372060853320619371589395").unwrap(),
-            Fq::from_str("5711591829539949466576779343139332826484691392615168339459127133578354594504").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("13959087695468611649596430311587754893553781089651653786194836494538563276775").unwrap(),
-            Fq::from_str("20077329001517657443606441409311994857779202243036629044638503237696223364659").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("7474320370637088045815338023553887697644612564918326269881141299466385978968").unwrap(),
-            Fq::from_str("7179523591776841679605094975857749855310194703724468585542879174420740281069").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("11688994784415067309410851347609985110519780957367071109070242376410992963310").unwrap(),
-            Fq::from_str("10765089292229711193702269207934448607602079729253620656380573952328252584478").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("20570172688793416634624730891052771164326486944651618297496876082382732095479").unwrap(),
-            Fq::from_str("9316868929721959393670460011350820860439112065080442954952787019197319099901").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16309005405780585197540926514084385109010212987335517467608288159171188020986").unwrap(),
-            Fq::from_str("16331355785886830080629353760961681545093658999464191955866909995870762360455").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("8712854813204057288076448155283060245017088068020447435556668856495109826804").unwrap(),
-            Fq::from_str("17712113050522211155868314244706086565275894298397380741824693681702649188917").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16240890005909699180242814014116301019547650535122858665542355549579314908418").unwrap(),
-            Fq::from_str("4212062743442199577099669843165184497388046274484844468396390707344624491349").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("21336724941065741271778995446380782800689089718313568723011823500259250009191").unwrap(),
-            Fq::from_str("16320583459851263802401057892980608357917065799102268416203464342339973690522").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("9611261600092487127386885005271446359310420844450485439447010763938262853225").unwrap(),
-            Fq::from_str("21326061108032076795217379375529478048393854631757077430728216512616323694780").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("14165608901448799649944639847191752110037449696196505058921550337899196700022").unwrap(),
-            Fq::from_str("18245029773875364547957268221472494460969855562154736044910075993922258186135").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("1182904389017882228759013367718064403836796028562434791061965182549592503891").unwrap(),
-            Fq::from_str("17616978305247220869514618494054258225882618867914917208455563474076722266095").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("5468238348321344576182513808666722219804485197990776810100700538338773977677").unwrap(),
-            Fq::from_str("971433639640449211826379253790664742190900847733319262301501107388640208029").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("4023171991907474813149689917837989361249082357614267401874217830601864873566").unwrap(),
-            Fq::from_str("5689782448937061893266065636555855579450897930394984585569744932131845394180").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("5559267740694614548854052179370498932419561115641736113649050814840985736466").unwrap(),
-            Fq::from_str("21237182939549762198604729515036232780878501159271978523638731940592229508632").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("917384909345838797078396560714807343236068046245729182477783799162615028996").unwrap(),
-            Fq::from_str("15261998844794680061000000088636372423676688842029241802527711548865904382341").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("21510429174185880481775798804285489786007845315760573446164650524230130909079").unwrap(),
-            Fq::from_str("4454336451218123213936153397574399280542071482617692809272850016360926093640").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("15998873025835647703306195457843642614504454342611498305724223732850539357982").unwrap(),
-            Fq::from_str("14929448043122565815105462964089054273497408449541468386014908876697858710872").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("5274747706495523085262366878403943404124559719513205459964663493584226252462").unwrap(),
-            Fq::from_str("16910085275280486121978862771248862060682796661500007180346314353798755666378").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("3950170215019621069493135456187969769195059052555929597646187094971006833860").unwrap(),
-            Fq::from_str("16454379095342431493732456795748459368088565056700446188229758446375419239033").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("18420869489578872592563588864764648978873451630933458852041229215991930554551").unwrap(),
-            Fq::from_str("19128156541539797045666952762037734014460755880118993280817024548191808702812").unwrap(),
-        ),
-        G1Affine::new_unchecked(
-            Fq::from_str("16311046850834419956825491843953567816064943762681018665175633666072142768020").unwrap(),
-            Fq::from_str("20342484480986120130993736680943126714376787089606326800673137165848358655498").unwrap(),
-        ),
-
-    ];
-
-    VerifyingKey::<Bn254> {
-        alpha_g1,
-        beta_g2,
-        gamma_g2,
-        delta_g2,
-        gamma_abc_g1,
-    }
-}
+include!(concat!(env!("OUT_DIR"), "/vk.rs"));
@@ -0,0 +1,56 @@
+//! Shared helpers for the integration tests.
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use ark_bn254::Fq;
+use ark_ff::{BigInteger, PrimeField};
+use prover_client::fixture::Fixture;
+use zk_email_verifier_contract::ProofInput;
+use sha2::{Digest, Sha256};
+
+pub fn proofs_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("proofs")
+}
+
+/// The proof, public signals and expected bindings in `tests/proofs`.
+pub fn fixture() -> Fixture {
+    Fixture::load(proofs_dir()).unwrap_or_else(|e| {
+        panic!("{e}; generate the fixture with `just cargo-test` or `just make-fixture`")
+    })
+}
+
+pub fn expected_from_address_hash(from_email: &str, account_id: &str) -> Vec<u8> {
+    let canonical_from = from_email.trim().to_ascii_lowercase();
+    let account_id_lower = account_id.trim().to_ascii_lowercase();
+    let preimage = format!("{canonical_from}|{account_id_lower}");
+    Sha256::digest(preimage.as_bytes()).to_vec()
+}
+
+/// `decimal` as the contract's `0x` hex spelling of a field element: big
+/// endian, without leading zeros.
+pub fn to_hex<F: PrimeField>(decimal: &str) -> String {
+    let value = F::from_str(decimal).unwrap_or_else(|_| panic!("invalid field element {decimal}"));
+    let digits: String = value
+        .into_bigint()
+        .to_bytes_be()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    match digits.trim_start_matches('0') {
+        "" => "0x0".to_string(),
+        digits => format!("0x{digits}"),
+    }
+}
+
+/// `proof` with its coordinates in hex.
+pub fn proof_to_hex(proof: &ProofInput) -> ProofInput {
+    ProofInput {
+        pi_a: proof.pi_a.clone().map(|c| to_hex::<Fq>(&c)),
+        pi_b: proof.pi_b.clone().map(|pair| pair.map(|c| to_hex::<Fq>(&c))),
+        pi_c: proof.pi_c.clone().map(|c| to_hex::<Fq>(&c)),
+        protocol: proof.protocol.clone(),
+        curve: proof.curve.clone(),
+    }
+}
@@ -1,15 +1,14 @@
+mod common;
+
 use std::{fs, path::Path};
 
+use ark_bn254::Fr;
+use common::{expected_from_address_hash, fixture, proof_to_hex, to_hex};
+use near_sdk::base64::{engine::general_purpose::STANDARD, Engine};
 use serde_json::json;
 use sha2::{Digest, Sha256};
-use zk_email_verifier_contract::{ProofInput, VerificationResult};
-
-fn expected_from_address_hash(from_email: &str, account_id: &str) -> Vec<u8> {
-    let canonical_from = from_email.trim().to_ascii_lowercase();
-    let account_id_lower = account_id.trim().to_ascii_lowercase();
-    let preimage = format!("{canonical_from}|{account_id_lower}");
-    Sha256::digest(preimage.as_bytes()).to_vec()
-}
+use zk_email_verifier_contract::VerificationResult;
+use zk_email_verifier_core::blob::encode_blob_from_json;
 
 /// End-to-end style test that:
 /// 1. Spins up a local NEAR sandbox node (via near-workspaces),
@@ -48,18 +47,8 @@ async fn deploy_and_verify_proof() -> Result<(), Box<dyn std::error::Error>> {
         .await?
         .into_result()?;
 
-    // Load proof.json and public.json from the contract tests/proofs directory.
-    let proofs_dir = Path::new(manifest_dir).join("tests").join("proofs");
-
-    let proof_json =
-        fs::read_to_string(proofs_dir.join("proof.json")).expect("failed to read proof.json");
-    let public_json =
-        fs::read_to_string(proofs_dir.join("public.json")).expect("failed to read public.json");
-
-    let proof_input: ProofInput =
-        serde_json::from_str(&proof_json).expect("failed to parse proof.json into ProofInput");
-    let public_inputs: Vec<String> =
-        serde_json::from_str(&public_json).expect("failed to parse public.json");
+    let fixture = fixture();
+    let (proof_input, public_inputs) = (fixture.proof, fixture.public_signals);
 
     // Call the on-chain `verify` view method.
     let res = contract
@@ -107,33 +96,20 @@ async fn deploy_and_verify_with_binding_snarkjs_proof_on_sandbox(
         .await?
         .into_result()?;
 
-    let proofs_dir = Path::new(manifest_dir).join("tests").join("proofs");
-
-    let proof_json =
-        fs::read_to_string(proofs_dir.join("proof.json")).expect("failed to read proof.json");
-    let public_json =
-        fs::read_to_string(proofs_dir.join("public.json")).expect("failed to read public.json");
+    let fixture = fixture();
+    let (proof_input, public_inputs) = (fixture.proof, fixture.public_signals);
 
-    let proof_input: ProofInput =
-        serde_json::from_str(&proof_json).expect("failed to parse proof.json into ProofInput");
-    let public_inputs: Vec<String> =
-        serde_json::from_str(&public_json).expect("failed to parse public.json");
-
-    // These values match the anchored substrings used to generate the proof.
-    let account_id = "kerp30.w3a-v1.testnet".to_string();
-    let new_public_key =
-        "86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm".to_string();
-    let from_email = "n6378056@gmail.com";
-    let timestamp = "Tue, 9 Dec 2025 17:13:23 +0900".to_string();
+    let expected = fixture.expected;
 
     let res = contract
         .call("verify_with_binding")
         .args_json(json!({
             "proof": proof_input,
             "public_inputs": public_inputs,
-            "account_id": account_id,
-            "new_public_key": new_public_key,
-            "timestamp": timestamp,
+            "request_id": expected.request_id,
+            "account_id": expected.account_id,
+            "new_public_key": expected.new_public_key,
+            "timestamp": expected.timestamp,
         }))
         .view()
         .await?;
@@ -144,8 +120,144 @@ async fn deploy_and_verify_with_binding_snarkjs_proof_on_sandbox(
         "on-chain verify_with_binding returned false for snarkjs proof"
     );
 
-    let expected_hash = expected_from_address_hash(from_email, &account_id);
+    let expected_hash = expected_from_address_hash(&expected.from_address, &expected.account_id);
     assert_eq!(result.from_address_hash, expected_hash);
 
     Ok(())
 }
+
+/// `verify_with_hashed_binding` accepts SHA-256 digests of the bound values
+/// and rejects a digest of anything else.
+#[tokio::test]
+async fn deploy_and_verify_with_hashed_binding() -> Result<(), Box<dyn std::error::Error>> {
+    let worker = near_workspaces::sandbox().await?;
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let wasm_path = Path::new(manifest_dir)
+        .join("../target/near/zk_email_verifier_contract/zk_email_verifier_contract.wasm");
+    let wasm_bytes = fs::read(&wasm_path).expect(
+        "failed to read compiled contract WASM; did you run `pnpm cargo:build` (cargo near build)?",
+    );
+
+    let contract = worker.dev_deploy(&wasm_bytes).await?;
+
+    contract
+        .call("new")
+        .args_json(json!({}))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let fixture = fixture();
+    let expected = fixture.expected;
+    let digest = |s: &str| Sha256::digest(s.as_bytes()).to_vec();
+
+    let cases = [
+        (expected.account_id.as_str(), None),
+        ("someone-else.testnet", Some("BINDING_ACCOUNT")),
+    ];
+    for (account_id, failure_reason) in cases {
+        let result: VerificationResult = contract
+            .call("verify_with_hashed_binding")
+            .args_json(json!({
+                "proof": fixture.proof,
+                "public_inputs": fixture.public_signals,
+                "account_id_hash": digest(account_id),
+                "new_public_key_hash": digest(&expected.new_public_key),
+                "timestamp_hash": digest(&expected.timestamp),
+            }))
+            .view()
+            .await?
+            .json()?;
+        assert_eq!(result.verified, failure_reason.is_none(), "account_id {account_id}");
+        assert_eq!(result.failure_reason.as_deref(), failure_reason);
+    }
+
+    Ok(())
+}
+
+/// The same proof sent as one base64 blob verifies, and a truncated blob
+/// doesn't.
+#[tokio::test]
+async fn deploy_and_verify_blob() -> Result<(), Box<dyn std::error::Error>> {
+    let worker = near_workspaces::sandbox().await?;
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let wasm_path = Path::new(manifest_dir)
+        .join("../target/near/zk_email_verifier_contract/zk_email_verifier_contract.wasm");
+    let wasm_bytes = fs::read(&wasm_path).expect(
+        "failed to read compiled contract WASM; did you run `pnpm cargo:build` (cargo near build)?",
+    );
+
+    let contract = worker.dev_deploy(&wasm_bytes).await?;
+
+    contract
+        .call("new")
+        .args_json(json!({}))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let fixture = fixture();
+    let bytes = encode_blob_from_json(&fixture.proof, &fixture.public_signals)
+        .expect("fixture proof parses");
+
+    for (blob, verified) in [(&bytes[..], true), (&bytes[..bytes.len() - 32], false)] {
+        let result: VerificationResult = contract
+            .call("verify_blob")
+            .args_json(json!({ "blob": STANDARD.encode(blob) }))
+            .view()
+            .await?
+            .json()?;
+        assert_eq!(result.verified, verified);
+    }
+
+    Ok(())
+}
+
+/// Compares gas burnt by `verify` when the same proof and public inputs are
+/// sent as decimal strings versus `0x` hex, which skips decimal parsing.
+#[tokio::test]
+async fn hex_inputs_burn_less_gas_than_decimal() -> Result<(), Box<dyn std::error::Error>> {
+    let worker = near_workspaces::sandbox().await?;
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let wasm_path = Path::new(manifest_dir)
+        .join("../target/near/zk_email_verifier_contract/zk_email_verifier_contract.wasm");
+    let wasm_bytes = fs::read(&wasm_path).expect(
+        "failed to read compiled contract WASM; did you run `pnpm cargo:build` (cargo near build)?",
+    );
+
+    let contract = worker.dev_deploy(&wasm_bytes).await?;
+
+    contract
+        .call("new")
+        .args_json(json!({}))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let fixture = fixture();
+    let (proof_input, public_inputs) = (fixture.proof, fixture.public_signals);
+
+    let proof_hex = proof_to_hex(&proof_input);
+    let public_inputs_hex: Vec<String> = public_inputs.iter().map(|s| to_hex::<Fr>(s)).collect();
+
+    let mut gas = Vec::new();
+    for (proof, inputs) in [(&proof_input, &public_inputs), (&proof_hex, &public_inputs_hex)] {
+        let outcome = contract
+            .call("verify")
+            .args_json(json!({ "proof": proof, "public_inputs": inputs }))
+            .max_gas()
+            .transact()
+            .await?;
+        gas.push(outcome.total_gas_burnt);
+        let result: VerificationResult = outcome.into_result()?.json()?;
+        assert!(result.verified, "verify returned false");
+    }
+
+    println!("verify gas: decimal = {}, hex = {}", gas[0], gas[1]);
+    assert!(gas[1] < gas[0], "hex inputs should be cheaper to parse");
+
+    Ok(())
+}
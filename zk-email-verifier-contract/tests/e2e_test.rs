@@ -1,24 +1,13 @@
+mod common;
+
 use std::{fs, path::Path};
 
-use serde::{Deserialize, Serialize};
+use common::{expected_from_address_hash, proofs_dir};
+use prover_client::eml::RecoveryEmail;
+use prover_client::fixture::Fixture;
+use prover_client::ProverClient;
 use serde_json::json;
-use sha2::{Digest, Sha256};
-use zk_email_verifier_contract::{ProofInput, VerificationResult};
-
-fn expected_from_address_hash(from_email: &str, account_id: &str) -> Vec<u8> {
-    let canonical_from = from_email.trim().to_ascii_lowercase();
-    let account_id_lower = account_id.trim().to_ascii_lowercase();
-    let preimage = format!("{canonical_from}|{account_id_lower}");
-    Sha256::digest(preimage.as_bytes()).to_vec()
-}
-
-/// Response from the Docker prover's /prove-email endpoint
-#[derive(Deserialize)]
-struct ProveResponse {
-    proof: ProofInput,
-    #[serde(rename = "publicSignals")]
-    public_signals: Vec<String>,
-}
+use zk_email_verifier_contract::VerificationResult;
 
 /// End-to-end test that:
 /// 1. Calls the Docker ZK prover API to generate a proof
@@ -40,34 +29,23 @@ async fn e2e_generate_and_verify_proof() -> Result<(), Box<dyn std::error::Error
     let raw_eml = fs::read_to_string(&eml_path)
         .expect("failed to read gmail_reset_full.eml");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post("http://localhost:5588/prove-email")
-        .json(&json!({ "rawEmail": raw_eml }))
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        panic!("Failed to generate proof: {}", error_text);
-    }
-
-    let prove_response: ProveResponse = response.json().await?;
+    let client = ProverClient::default();
+    client.health().await?;
+    let (proof, public_signals) = client.prove_email(&raw_eml).await?;
     println!("Proof generated successfully\n");
 
-    // Step 2: Save proof to tests/proofs directory
-    println!("Step 2: Saving proof to tests/proofs/...");
-    let proofs_dir = Path::new(manifest_dir).join("tests").join("proofs");
-    fs::create_dir_all(&proofs_dir)?;
-
-    let proof_json = serde_json::to_string_pretty(&prove_response.proof)?;
-    let public_json = serde_json::to_string_pretty(&prove_response.public_signals)?;
-
-    fs::write(proofs_dir.join("proof.json"), proof_json)?;
-    fs::write(proofs_dir.join("public.json"), public_json)?;
-    println!("Proof saved to: {:?}", proofs_dir);
+    // Step 2: Save the fixture to tests/proofs directory
+    println!("Step 2: Saving fixture to tests/proofs/...");
+    let fixture = Fixture {
+        proof,
+        public_signals,
+        expected: RecoveryEmail::from_eml(&raw_eml)?,
+    };
+    fixture.write(proofs_dir())?;
+    println!("Fixture saved to: {:?}", proofs_dir());
     println!("  - proof.json");
-    println!("  - public.json\n");
+    println!("  - public.json");
+    println!("  - expected.json\n");
 
     // Step 3: Deploy contract to NEAR sandbox
     println!("Step 3: Deploying contract to NEAR sandbox...");
@@ -96,8 +74,8 @@ async fn e2e_generate_and_verify_proof() -> Result<(), Box<dyn std::error::Error
     let res = contract
         .call("verify")
         .args_json(json!({
-            "proof": prove_response.proof,
-            "public_inputs": prove_response.public_signals,
+            "proof": fixture.proof,
+            "public_inputs": fixture.public_signals,
         }))
         .view()
         .await?;
@@ -118,7 +96,8 @@ async fn e2e_generate_and_verify_proof() -> Result<(), Box<dyn std::error::Error
         "on-chain verify returned false for generated proof"
     );
 
-    let expected_hash = expected_from_address_hash("n6378056@gmail.com", &result.account_id);
+    let expected_hash =
+        expected_from_address_hash(&fixture.expected.from_address, &result.account_id);
     assert_eq!(result.from_address_hash, expected_hash);
 
     Ok(())
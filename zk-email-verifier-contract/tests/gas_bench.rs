@@ -0,0 +1,189 @@
+//! Gas benchmark for the verification entry points on a NEAR sandbox.
+//!
+//! Every case is sent as a transaction (view calls don't report gas) and the
+//! burnt gas is written as JSON to `$GAS_REPORT` (default
+//! `target/gas-report.json`). If `$GAS_BASELINE` points at an earlier report,
+//! the run fails when any case costs more than `$GAS_TOLERANCE_PERCENT`
+//! (default 5) above it.
+//!
+//! Ignored by default since it deploys the release WASM; run it with
+//!     just gas-bench
+
+mod common;
+
+use std::{collections::BTreeMap, env, fs, path::Path, path::PathBuf};
+
+use ark_bn254::Fr;
+use common::{fixture, proof_to_hex, to_hex};
+use near_workspaces::Contract;
+use serde_json::{json, Value};
+use zk_email_verifier_contract::VerificationResult;
+
+const DEFAULT_TOLERANCE_PERCENT: u64 = 5;
+
+struct Case {
+    name: String,
+    method: &'static str,
+    args: Value,
+    /// Results that should come back verified, as a sanity check.
+    verified: usize,
+}
+
+/// Burnt gas per case, and how many results verified.
+async fn run_case(contract: &Contract, case: &Case) -> Result<(u64, usize), Box<dyn std::error::Error>> {
+    let outcome = contract
+        .call(case.method)
+        .args_json(&case.args)
+        .max_gas()
+        .transact()
+        .await?;
+    let gas = outcome.total_gas_burnt.as_gas();
+    let outcome = outcome.into_result()?;
+    let verified = if case.method == "verify_batch" {
+        let results: Vec<VerificationResult> = outcome.json()?;
+        results.iter().filter(|r| r.verified).count()
+    } else {
+        let result: VerificationResult = outcome.json()?;
+        usize::from(result.verified)
+    };
+    Ok((gas, verified))
+}
+
+fn report_path() -> PathBuf {
+    env::var_os("GAS_REPORT").map(PathBuf::from).unwrap_or_else(|| {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../target/gas-report.json")
+    })
+}
+
+/// Cases whose gas grew by more than the tolerance over `baseline`.
+fn regressions(report: &BTreeMap<String, u64>, baseline: &BTreeMap<String, u64>) -> Vec<String> {
+    let tolerance = env::var("GAS_TOLERANCE_PERCENT")
+        .ok()
+        .and_then(|t| t.parse().ok())
+        .unwrap_or(DEFAULT_TOLERANCE_PERCENT);
+    report
+        .iter()
+        .filter_map(|(name, &gas)| {
+            let &before = baseline.get(name)?;
+            (gas > before + before * tolerance / 100)
+                .then(|| format!("{name}: {before} -> {gas} (> {tolerance}%)"))
+        })
+        .collect()
+}
+
+#[tokio::test]
+#[ignore = "deploys the release WASM to a sandbox; run with `just gas-bench`"]
+async fn gas_bench() -> Result<(), Box<dyn std::error::Error>> {
+    let worker = near_workspaces::sandbox().await?;
+
+    let wasm_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../target/near/zk_email_verifier_contract/zk_email_verifier_contract.wasm");
+    let wasm_bytes = fs::read(&wasm_path).expect(
+        "failed to read compiled contract WASM; did you run `pnpm cargo:build` (cargo near build)?",
+    );
+    let contract = worker.dev_deploy(&wasm_bytes).await?;
+    contract
+        .call("new")
+        .args_json(json!({}))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let fixture = fixture();
+    let expected = &fixture.expected;
+    let proof = &fixture.proof;
+    let valid = json!({ "proof": proof, "public_inputs": fixture.public_signals });
+    // The same proof and signals spelled in hex, to compare against decimal.
+    let hex_inputs: Vec<String> = fixture.public_signals.iter().map(|s| to_hex::<Fr>(s)).collect();
+    let valid_hex = json!({ "proof": proof_to_hex(proof), "public_inputs": hex_inputs });
+    // Same proof over a different request id: parses fine, fails the pairing.
+    let mut tampered_inputs = fixture.public_signals.clone();
+    tampered_inputs[0] = "1".to_string();
+    let tampered = json!({ "proof": proof, "public_inputs": tampered_inputs });
+    let binding = |account_id: &str| {
+        json!({
+            "proof": proof,
+            "public_inputs": fixture.public_signals,
+            "request_id": expected.request_id,
+            "account_id": account_id,
+            "new_public_key": expected.new_public_key,
+            "timestamp": expected.timestamp,
+        })
+    };
+
+    let mut cases = vec![
+        Case {
+            name: "verify/valid".into(),
+            method: "verify",
+            args: valid.clone(),
+            verified: 1,
+        },
+        Case {
+            name: "verify/valid_hex".into(),
+            method: "verify",
+            args: valid_hex,
+            verified: 1,
+        },
+        Case {
+            name: "verify/invalid".into(),
+            method: "verify",
+            args: tampered.clone(),
+            verified: 0,
+        },
+        Case {
+            name: "verify/malformed".into(),
+            method: "verify",
+            args: json!({ "proof": proof, "public_inputs": ["not a field element"] }),
+            verified: 0,
+        },
+        Case {
+            name: "verify_with_binding/valid".into(),
+            method: "verify_with_binding",
+            args: binding(&expected.account_id),
+            verified: 1,
+        },
+        Case {
+            name: "verify_with_binding/mismatch".into(),
+            method: "verify_with_binding",
+            args: binding("someone-else.testnet"),
+            verified: 0,
+        },
+    ];
+    for n in [1, 2, 4, 8] {
+        cases.push(Case {
+            name: format!("verify_batch/valid_x{n}"),
+            method: "verify_batch",
+            args: json!({ "proofs": vec![&valid; n] }),
+            verified: n,
+        });
+    }
+    // One bad proof makes the batch check fail and fall back to one by one.
+    cases.push(Case {
+        name: "verify_batch/one_invalid_of_4".into(),
+        method: "verify_batch",
+        args: json!({ "proofs": [&valid, &valid, &valid, &tampered] }),
+        verified: 3,
+    });
+
+    let mut report = BTreeMap::new();
+    for case in &cases {
+        let (gas, verified) = run_case(&contract, case).await?;
+        println!("{:<32} {:>8.2} Tgas", case.name, gas as f64 / 1e12);
+        assert_eq!(verified, case.verified, "{}: unexpected number of verified results", case.name);
+        report.insert(case.name.clone(), gas);
+    }
+
+    let path = report_path();
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, serde_json::to_string_pretty(&report)? + "\n")?;
+    println!("wrote {}", path.display());
+
+    if let Some(baseline_path) = env::var_os("GAS_BASELINE").filter(|p| !p.is_empty()) {
+        let baseline: BTreeMap<String, u64> =
+            serde_json::from_str(&fs::read_to_string(&baseline_path)?)?;
+        let regressed = regressions(&report, &baseline);
+        assert!(regressed.is_empty(), "gas regressions:\n{}", regressed.join("\n"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,15 @@
+//! The decoded `blob` argument of `verify_blob`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_email_verifier_core::blob::{decode_blob, encode_blob};
+
+fuzz_target!(|data: &[u8]| {
+    // Not every accepted blob is canonical (the infinity flag hides the
+    // coordinates), but re-encoding must decode to the same values.
+    if let Ok((proof, inputs)) = decode_blob(data) {
+        let reencoded = encode_blob(&proof, &inputs);
+        assert_eq!(decode_blob(&reencoded), Ok((proof, inputs)));
+    }
+});
@@ -0,0 +1,13 @@
+//! The `timestamp` argument of `verify_with_binding`, parsed before any proof
+//! check.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_email_verifier_core::parse_email_timestamp;
+
+fuzz_target!(|s: &str| {
+    if let Some(ts) = parse_email_timestamp(s) {
+        assert_eq!(ts.unix_ms % 1000, 0);
+    }
+});
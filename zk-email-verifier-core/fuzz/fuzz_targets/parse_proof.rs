@@ -0,0 +1,13 @@
+//! `proof` argument of `verify`: arbitrary JSON into `ProofInput`, then into
+//! curve points.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_email_verifier_core::ProofInput;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proof) = serde_json::from_slice::<ProofInput>(data) {
+        let _ = proof.parse();
+    }
+});
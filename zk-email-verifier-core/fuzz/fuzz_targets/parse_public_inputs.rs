@@ -0,0 +1,16 @@
+//! `public_inputs` argument of `verify`: arbitrary JSON into field elements,
+//! then decoded the way a verified result is.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_email_verifier_core::{decode_anchored_fields, parse_public_inputs};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(inputs) = serde_json::from_slice::<Vec<String>>(data) else {
+        return;
+    };
+    if let Ok(fields) = parse_public_inputs(&inputs) {
+        let _ = decode_anchored_fields(&fields);
+    }
+});
@@ -0,0 +1,21 @@
+//! Unpacking arbitrary field elements, as `unpack_fields` and the binding
+//! checks do with whatever a proof exposes.
+
+#![no_main]
+
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use libfuzzer_sys::fuzz_target;
+use zk_email_verifier_core::packing::{pack_str_to_field_chunks, unpack_field_chunks_to_str};
+
+fuzz_target!(|data: &[u8]| {
+    let chunks: Vec<Fr> = data.chunks(32).map(Fr::from_le_bytes_mod_order).collect();
+    if let Ok(s) = unpack_field_chunks_to_str(&chunks) {
+        // Whatever decodes and fits must pack back to the same elements.
+        if s.len() <= zk_email_verifier_core::packing::MAX_PACKED_SUBSTRING_LEN {
+            let repacked = pack_str_to_field_chunks(&s).unwrap();
+            let n = chunks.len().min(repacked.len());
+            assert_eq!(repacked[..n], chunks[..n]);
+        }
+    }
+});
@@ -0,0 +1,205 @@
+//! Comparison of caller-supplied binding strings against packed public inputs.
+//!
+//! By default a bound string must match the circuit-anchored bytes exactly.
+//! `BindingOptions` relaxes that per field; any normalization is applied to
+//! both sides so the comparison stays symmetric.
+//!
+//! Both sides are additionally NFC-normalized, so a value typed as `e` +
+//! combining acute accent binds the same as the precomposed `é`, whichever
+//! form the email carried. The anchored bytes are only decoded and
+//! normalized when they don't already match, so ASCII bindings stay a
+//! straight element comparison.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+use ark_bn254::Fr;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+use crate::packing::{pack_str_to_chunks, unpack_field_chunks_to_str, PACKED_BYTES_PER_FIELD};
+
+/// Per-field relaxations of the byte-exact comparison.
+///
+/// The sender address needs no option here: `from_address_hash` is always
+/// computed over the lowercased address.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BindingOptions {
+    /// Compare `account_id` case-insensitively (e.g. `Alice.testnet` vs
+    /// `alice.testnet`).
+    pub case_insensitive_account_id: bool,
+    /// Trim leading and trailing ASCII whitespace (including stray `\r\n`
+    /// left over from header extraction) from every packed binding.
+    pub trim_whitespace: bool,
+}
+
+impl BindingOptions {
+    fn folds_case(&self, field: &str) -> bool {
+        field == "account_id" && self.case_insensitive_account_id
+    }
+
+    fn normalizes(&self, field: &str) -> bool {
+        self.trim_whitespace || self.folds_case(field)
+    }
+
+    /// Normalize a value bound to `field` according to these options.
+    pub fn normalize<'a>(&self, field: &str, value: &'a str) -> Cow<'a, str> {
+        let value = if self.trim_whitespace {
+            value.trim_matches(|c: char| c.is_ascii_whitespace())
+        } else {
+            value
+        };
+        if self.folds_case(field) {
+            Cow::Owned(value.to_lowercase())
+        } else {
+            Cow::Borrowed(value)
+        }
+    }
+}
+
+/// Result of comparing one bound value against its public inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingOutcome {
+    Matched,
+    /// Index within the field of the first public input that differs.
+    Mismatch(usize),
+    /// The caller value is longer than the field's elements can hold.
+    ValueTooLong,
+    /// The anchored chunks don't decode to UTF-8, so they can't be normalized.
+    UndecodableInput,
+}
+
+impl BindingOutcome {
+    pub fn is_match(&self) -> bool {
+        matches!(self, BindingOutcome::Matched)
+    }
+
+    /// Stable identifier for the outcomes that aren't a plain comparison.
+    pub fn error(&self) -> Option<&'static str> {
+        match self {
+            BindingOutcome::ValueTooLong => Some("ValueTooLong"),
+            BindingOutcome::UndecodableInput => Some("UndecodableInput"),
+            _ => None,
+        }
+    }
+}
+
+/// NFC-normalize a caller-supplied string, borrowing it when it is already in
+/// normal form (always the case for ASCII).
+pub fn nfc(value: &str) -> Cow<'_, str> {
+    match is_nfc_quick(value.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(value),
+        _ => Cow::Owned(value.nfc().collect::<String>()),
+    }
+}
+
+/// Compare field elements one by one.
+pub fn compare_fields(actual: &[Fr], expected: &[Fr]) -> BindingOutcome {
+    match first_mismatch(actual, expected) {
+        Some(i) => BindingOutcome::Mismatch(i),
+        None => BindingOutcome::Matched,
+    }
+}
+
+/// Compare the packed chunks `actual` taken from the public inputs against
+/// the caller-supplied `value`, normalizing both sides as configured for
+/// `field`. `value` is packed into as many elements as `actual` has, so this
+/// works for fields of any size.
+pub fn check_packed_binding(
+    field: &str,
+    actual: &[Fr],
+    value: &str,
+    options: &BindingOptions,
+) -> BindingOutcome {
+    let capacity = actual.len() * PACKED_BYTES_PER_FIELD;
+    let normalize = |s: &str| pack_str_to_chunks(&options.normalize(field, &nfc(s)), capacity);
+    let Ok(expected) = normalize(value) else {
+        return BindingOutcome::ValueTooLong;
+    };
+    let exact = compare_fields(actual, &expected);
+    if exact.is_match() {
+        return exact;
+    }
+
+    // Decode the anchored string so the same normalization can be applied to it.
+    let normalized = unpack_field_chunks_to_str(actual)
+        .ok()
+        .and_then(|s| normalize(&s).ok())
+        .map(|actual| compare_fields(&actual, &expected));
+    match normalized {
+        Some(outcome) if outcome.is_match() || options.normalizes(field) => outcome,
+        // Without options, report where the anchored bytes differ.
+        _ if !options.normalizes(field) => exact,
+        _ => BindingOutcome::UndecodableInput,
+    }
+}
+
+/// Position of the first element where `actual` and `expected` differ, also
+/// treating a length difference as a mismatch at the shorter length.
+fn first_mismatch(actual: &[Fr], expected: &[Fr]) -> Option<usize> {
+    actual
+        .iter()
+        .zip(expected)
+        .position(|(a, e)| a != e)
+        .or_else(|| (actual.len() != expected.len()).then_some(actual.len().min(expected.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packing::pack_str_to_field_chunks;
+    use alloc::format;
+
+    #[test]
+    fn case_folding_applies_to_both_sides() {
+        let anchored = pack_str_to_field_chunks("Alice.Testnet").unwrap();
+        let exact = BindingOptions::default();
+        let folded = BindingOptions {
+            case_insensitive_account_id: true,
+            ..Default::default()
+        };
+
+        assert!(!check_packed_binding("account_id", &anchored, "alice.testnet", &exact).is_match());
+        assert!(check_packed_binding("account_id", &anchored, "alice.testnet", &folded).is_match());
+        assert!(check_packed_binding("account_id", &anchored, "ALICE.testnet", &folded).is_match());
+        // Only the configured field is folded.
+        assert!(!check_packed_binding("timestamp", &anchored, "alice.testnet", &folded).is_match());
+    }
+
+    #[test]
+    fn trimming_applies_to_both_sides() {
+        let anchored = pack_str_to_field_chunks("Tue, 9 Dec 2025 17:13:23 +0900\r\n").unwrap();
+        let trimmed = BindingOptions {
+            trim_whitespace: true,
+            ..Default::default()
+        };
+        let value = " Tue, 9 Dec 2025 17:13:23 +0900";
+
+        assert!(!check_packed_binding("timestamp", &anchored, value, &BindingOptions::default()).is_match());
+        assert!(check_packed_binding("timestamp", &anchored, value, &trimmed).is_match());
+    }
+
+    #[test]
+    fn both_sides_are_nfc_normalized() {
+        let options = BindingOptions::default();
+        assert!(matches!(nfc("plain ascii"), Cow::Borrowed(_)));
+
+        for anchored in ["caf\u{e9}", "cafe\u{301}"] {
+            let anchored = pack_str_to_field_chunks(anchored).unwrap();
+            assert!(check_packed_binding("timestamp", &anchored, "cafe\u{301}", &options).is_match());
+            assert!(check_packed_binding("timestamp", &anchored, "caf\u{e9}", &options).is_match());
+            assert!(!check_packed_binding("timestamp", &anchored, "cafe", &options).is_match());
+        }
+    }
+
+    #[test]
+    fn reports_first_mismatching_chunk() {
+        let long = "a".repeat(40);
+        let actual = pack_str_to_field_chunks(&long).unwrap();
+        let expected = pack_str_to_field_chunks(&format!("{}b", &long[..39])).unwrap();
+
+        // Byte 39 lives in the second 31-byte chunk.
+        assert_eq!(compare_fields(&actual, &expected), BindingOutcome::Mismatch(1));
+        assert_eq!(compare_fields(&actual, &actual), BindingOutcome::Matched);
+        assert_eq!(compare_fields(&actual, &actual[1..]), BindingOutcome::Mismatch(0));
+    }
+}
@@ -0,0 +1,124 @@
+//! Decoding of the human-readable values anchored in the public inputs.
+
+use alloc::{format, string::String, vec::Vec};
+
+use ark_bn254::Fr;
+
+use crate::layout::{PublicInputLayout, RECOVER_EMAIL_LAYOUT};
+use crate::packing::{unpack_field_chunks_to_str, unpack_field_elems_to_bytes};
+
+/// Values decoded from `RecoverEmailCircuit` public inputs. A field is `None`
+/// when its chunks don't decode (non-UTF-8 bytes, out-of-range elements).
+/// Nothing is validated beyond the encoding.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnchoredFields {
+    pub request_id: Option<String>,
+    pub account_id: Option<String>,
+    pub new_public_key: Option<String>,
+    pub from_address_hash: Option<Vec<u8>>,
+    /// Raw `Date:` header value; see [`crate::parse_email_timestamp`].
+    pub timestamp: Option<String>,
+    /// Keys to add alongside `new_public_key`, from the fields
+    /// `new_public_key_1`, `new_public_key_2`, ... of circuits that anchor
+    /// more than one. `None` if any of them doesn't decode.
+    pub additional_public_keys: Option<Vec<String>>,
+    /// Keys to delete in the same recovery (e.g. a stolen device's), from
+    /// the fields `delete_public_key_1`, `delete_public_key_2`, ... `None`
+    /// if any of them doesn't decode.
+    pub deleted_public_keys: Option<Vec<String>>,
+}
+
+/// Decode every anchored field, or `None` if `inputs` is shorter than the
+/// circuit's public signal array.
+pub fn decode_anchored_fields(inputs: &[Fr]) -> Option<AnchoredFields> {
+    decode_with_layout(&RECOVER_EMAIL_LAYOUT, inputs)
+}
+
+/// [`decode_anchored_fields`] for any circuit laid out by `layout`. Fields the
+/// layout doesn't have decode as `None`.
+pub fn decode_with_layout(layout: &PublicInputLayout, inputs: &[Fr]) -> Option<AnchoredFields> {
+    if inputs.len() < layout.len {
+        return None;
+    }
+    let packed = |name: &str| unpack_field_chunks_to_str(layout.get(name, inputs)?).ok();
+    Some(AnchoredFields {
+        request_id: packed("request_id"),
+        account_id: packed("account_id"),
+        new_public_key: packed("new_public_key"),
+        from_address_hash: layout
+            .get("from_address_hash", inputs)
+            .and_then(|hash| unpack_field_elems_to_bytes(hash).ok()),
+        timestamp: packed("timestamp"),
+        additional_public_keys: numbered_fields(layout, "new_public_key")
+            .map(|name| packed(&name))
+            .collect(),
+        deleted_public_keys: numbered_fields(layout, "delete_public_key")
+            .map(|name| packed(&name))
+            .collect(),
+    })
+}
+
+/// Names of the `<prefix>_<n>` fields of `layout`, numbered from 1 without
+/// gaps.
+pub fn numbered_fields<'a>(layout: &'a PublicInputLayout, prefix: &'a str) -> impl Iterator<Item = String> + 'a {
+    (1..)
+        .map(move |i| format!("{prefix}_{i}"))
+        .take_while(|name| layout.field(name).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::EXPECTED_PUBLIC_LEN;
+    use crate::packing::{pack_str_to_chunks, pack_str_to_field_chunks};
+    use alloc::vec;
+
+    #[test]
+    fn decodes_fields_at_their_offsets() {
+        let mut inputs = vec![Fr::from(0u64); EXPECTED_PUBLIC_LEN];
+        let account = pack_str_to_field_chunks("kerp30.testnet").unwrap();
+        let field = |name| RECOVER_EMAIL_LAYOUT.field(name).unwrap().range();
+        inputs[field("account_id")].copy_from_slice(&account);
+        let hash_offset = field("from_address_hash").start;
+        inputs[hash_offset] = Fr::from(0xabu64);
+
+        let fields = decode_anchored_fields(&inputs).unwrap();
+        assert_eq!(fields.account_id.as_deref(), Some("kerp30.testnet"));
+        assert_eq!(fields.request_id.as_deref(), Some(""));
+        assert_eq!(fields.from_address_hash.unwrap()[0], 0xab);
+
+        inputs[hash_offset] = Fr::from(256u64);
+        assert!(decode_anchored_fields(&inputs).unwrap().from_address_hash.is_none());
+        assert!(decode_anchored_fields(&inputs[1..]).is_none());
+    }
+
+    #[test]
+    fn decodes_every_numbered_public_key() {
+        use crate::layout::{FieldEncoding, PublicInputField};
+        const KEY: FieldEncoding = FieldEncoding::PackedBytes { max_len: 62 };
+        const FIELDS: [PublicInputField; 4] = [
+            PublicInputField { name: "new_public_key", offset: 0, len: 2, encoding: KEY },
+            PublicInputField { name: "new_public_key_1", offset: 2, len: 2, encoding: KEY },
+            PublicInputField { name: "new_public_key_3", offset: 4, len: 2, encoding: KEY },
+            PublicInputField { name: "delete_public_key_1", offset: 6, len: 2, encoding: KEY },
+        ];
+        let layout = PublicInputLayout {
+            circuit_id: "TwoKeys",
+            kind: crate::layout::CircuitKind::Recovery,
+            len: 8,
+            fields: &FIELDS,
+        };
+        let mut inputs = pack_str_to_chunks("passkey", 62).unwrap();
+        inputs.extend(pack_str_to_chunks("backup", 62).unwrap());
+        inputs.extend([Fr::from(0u64); 2]);
+        inputs.extend(pack_str_to_chunks("stolen", 62).unwrap());
+
+        let fields = decode_with_layout(&layout, &inputs).unwrap();
+        assert_eq!(fields.new_public_key.as_deref(), Some("passkey"));
+        assert_eq!(fields.additional_public_keys, Some(vec!["backup".into()]));
+        assert_eq!(fields.deleted_public_keys, Some(vec!["stolen".into()]));
+        let recover_email = decode_anchored_fields(&[Fr::from(0u64); EXPECTED_PUBLIC_LEN]).unwrap();
+        assert_eq!(recover_email.additional_public_keys, Some(vec![]));
+        assert_eq!(recover_email.deleted_public_keys, Some(vec![]));
+    }
+}
@@ -0,0 +1,73 @@
+//! DKIM RSA public keys in the form the circuit takes them: the modulus split
+//! into `PUBKEY_LEN` 121-bit limbs, least significant first, exactly as they
+//! appear in `pubkey[17]` of the public signals.
+//!
+//! A key is identified by the SHA-256 of [`limbs_preimage`], i.e. of the
+//! public-signal slice itself, so a registry can look up a proof's key
+//! without re-deriving the modulus.
+
+use alloc::vec::Vec;
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+
+use crate::layout::PUBKEY_LEN;
+
+/// Bits per limb (`n` in zk-email's `EmailVerifier(n, k)`).
+pub const LIMB_BITS: usize = 121;
+
+/// Split a big-endian RSA modulus into circuit limbs. `None` if it doesn't
+/// fit in `PUBKEY_LEN * LIMB_BITS` bits.
+pub fn modulus_to_limbs(modulus_be: &[u8]) -> Option<Vec<Fr>> {
+    let le: Vec<u8> = modulus_be.iter().rev().copied().collect();
+    let bit = |i: usize| le.get(i / 8).is_some_and(|b| b >> (i % 8) & 1 == 1);
+    let used_bits = le.len() * 8;
+    if (PUBKEY_LEN * LIMB_BITS..used_bits).any(bit) {
+        return None;
+    }
+    let limbs = (0..PUBKEY_LEN)
+        .map(|limb| {
+            let start = limb * LIMB_BITS;
+            let value = (0..LIMB_BITS)
+                .filter(|j| bit(start + j))
+                .fold(0u128, |acc, j| acc | 1 << j);
+            Fr::from(value)
+        })
+        .collect();
+    Some(limbs)
+}
+
+/// Bytes hashed to identify a key: each limb as 32 little-endian bytes.
+pub fn limbs_preimage(limbs: &[Fr]) -> Vec<u8> {
+    limbs
+        .iter()
+        .flat_map(|limb| limb.into_bigint().to_bytes_le())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn splits_modulus_into_121_bit_limbs() {
+        // 2^121 + 5: limb 0 is 5, limb 1 is 1.
+        let mut modulus = vec![0u8; 16];
+        modulus[0] = 0x02;
+        modulus[15] = 5;
+        let limbs = modulus_to_limbs(&modulus).unwrap();
+        assert_eq!(limbs.len(), PUBKEY_LEN);
+        assert_eq!(limbs[0], Fr::from(5u64));
+        assert_eq!(limbs[1], Fr::from(1u64));
+        assert!(limbs[2..].iter().all(|l| *l == Fr::from(0u64)));
+
+        // 2048-bit keys fit, anything past 17 * 121 = 2057 bits doesn't.
+        assert!(modulus_to_limbs(&[0xff; 256]).is_some());
+        let mut too_big = vec![0u8; 258];
+        too_big[0] = 0x02;
+        assert!(modulus_to_limbs(&too_big).is_none());
+
+        assert_eq!(limbs_preimage(&limbs).len(), PUBKEY_LEN * 32);
+    }
+}
@@ -0,0 +1,189 @@
+//! Parsing of snarkjs-style field elements and proof points.
+//!
+//! Field elements may be decimal strings (as snarkjs emits them) or
+//! `0x`-prefixed big-endian hex. Proof points are checked to be on the curve
+//! and in the prime-order subgroup: the pairing alone doesn't reject points
+//! outside it.
+
+use alloc::vec::Vec;
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_ff::{BigInt, PrimeField};
+use ark_groth16::Proof;
+
+pub fn parse_fq(s: &str) -> Result<Fq, ()> {
+    parse_field(s)
+}
+
+pub fn parse_fr(s: &str) -> Result<Fr, ()> {
+    parse_field(s)
+}
+
+pub fn parse_fq2(c0: &str, c1: &str) -> Result<Fq2, ()> {
+    let a0 = parse_fq(c0)?;
+    let a1 = parse_fq(c1)?;
+    Ok(Fq2::new(a0, a1))
+}
+
+/// Parse a field element given either as a decimal string or as
+/// `0x`-prefixed big-endian hex.
+///
+/// Both forms are read straight into limbs and must be canonical: values at
+/// or above the modulus are rejected rather than reduced, and so are leading
+/// zeros in either form, so each element has one decimal spelling and one
+/// hex spelling (up to the case of the hex digits).
+fn parse_field<F: PrimeField<BigInt = BigInt<4>>>(s: &str) -> Result<F, ()> {
+    let bigint = match s.strip_prefix("0x") {
+        Some(hex) => parse_hex_bigint(hex)?,
+        None => parse_decimal_bigint(s)?,
+    };
+    F::from_bigint(bigint).ok_or(())
+}
+
+fn parse_decimal_bigint(decimal: &str) -> Result<BigInt<4>, ()> {
+    let digits = decimal.as_bytes();
+    if digits.is_empty() || (digits.len() > 1 && digits[0] == b'0') {
+        return Err(());
+    }
+    let mut limbs = [0u64; 4];
+    for d in digits {
+        let mut carry = u128::from(char::from(*d).to_digit(10).ok_or(())?);
+        for limb in limbs.iter_mut() {
+            let t = u128::from(*limb) * 10 + carry;
+            *limb = t as u64;
+            carry = t >> 64;
+        }
+        if carry != 0 {
+            return Err(());
+        }
+    }
+    Ok(BigInt::new(limbs))
+}
+
+fn parse_hex_bigint(hex: &str) -> Result<BigInt<4>, ()> {
+    let digits = hex.as_bytes();
+    if digits.is_empty() || digits.len() > 64 || (digits.len() > 1 && digits[0] == b'0') {
+        return Err(());
+    }
+    let mut limbs = [0u64; 4];
+    for (i, d) in digits.iter().rev().enumerate() {
+        let nibble = char::from(*d).to_digit(16).ok_or(())? as u64;
+        limbs[i / 16] |= nibble << ((i % 16) * 4);
+    }
+    Ok(BigInt::new(limbs))
+}
+
+/// Build a proof from the `pi_a`, `pi_b` and `pi_c` arrays of snarkjs's
+/// `proof.json`. The projective `z` coordinates are assumed to be 1, and
+/// every point must lie in its prime-order subgroup.
+pub fn parse_proof(
+    pi_a: &[impl AsRef<str>; 3],
+    pi_b: &[[impl AsRef<str>; 2]; 3],
+    pi_c: &[impl AsRef<str>; 3],
+) -> Result<Proof<Bn254>, ()> {
+    // G1 A
+    let ax = parse_fq(pi_a[0].as_ref())?;
+    let ay = parse_fq(pi_a[1].as_ref())?;
+    let a = checked_point(G1Affine::new_unchecked(ax, ay))?;
+
+    // G2 B
+    // snarkjs bn128 convention: pi_b[0] and pi_b[1] are Fq2 coords.
+    let bx = parse_fq2(pi_b[0][0].as_ref(), pi_b[0][1].as_ref())?;
+    let by = parse_fq2(pi_b[1][0].as_ref(), pi_b[1][1].as_ref())?;
+    let b = checked_point(G2Affine::new_unchecked(bx, by))?;
+
+    // G1 C
+    let cx = parse_fq(pi_c[0].as_ref())?;
+    let cy = parse_fq(pi_c[1].as_ref())?;
+    let c = checked_point(G1Affine::new_unchecked(cx, cy))?;
+
+    Ok(Proof::<Bn254> { a, b, c })
+}
+
+fn checked_point<P: SWCurveConfig>(point: Affine<P>) -> Result<Affine<P>, ()> {
+    if point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve() {
+        Ok(point)
+    } else {
+        Err(())
+    }
+}
+
+/// Parse the public signals of `public.json`.
+pub fn parse_public_inputs(inputs: &[impl AsRef<str>]) -> Result<Vec<Fr>, ()> {
+    inputs.iter().map(|s| parse_fr(s.as_ref())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::String};
+    use ark_ff::BigInteger;
+    use core::str::FromStr;
+
+    #[test]
+    fn hex_field_elements_match_decimal_and_must_be_canonical() {
+        let dec = "9383485363053290200918347156157836566562967994039712273449902621266178545958";
+        let to_hex = |b: BigInt<4>| -> String {
+            let digits: String = b.to_bytes_be().iter().map(|x| format!("{x:02x}")).collect();
+            format!("0x{}", digits.trim_start_matches('0'))
+        };
+        let hex = to_hex(Fq::from_str(dec).unwrap().into_bigint());
+        assert_eq!(parse_fq(&hex).unwrap(), parse_fq(dec).unwrap());
+        assert_eq!(parse_fr("0xf").unwrap(), Fr::from(15u64));
+        assert_eq!(parse_fr("0x0").unwrap(), Fr::from(0u64));
+
+        // The scalar field modulus itself is not canonical.
+        let modulus = to_hex(Fr::MODULUS);
+        assert!(parse_fr(&modulus).is_err());
+        assert!(parse_fr("0x").is_err());
+        assert!(parse_fr("0xzz").is_err());
+        assert!(parse_fr(&format!("0x{}", "0".repeat(65))).is_err());
+
+        // Leading zeros would give the same value another spelling.
+        for s in ["0x0f", "0x00", &format!("0x0{}", &hex[2..])] {
+            assert!(parse_fr(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn proof_points_must_be_in_the_subgroup() {
+        use ark_ec::AffineRepr;
+        let dec = |f: Fq| f.into_bigint().to_string();
+        let g2 = G2Affine::generator();
+        let pi_b = |p: G2Affine| {
+            let (x, y) = p.xy().unwrap();
+            [[dec(x.c0), dec(x.c1)], [dec(y.c0), dec(y.c1)], ["1".into(), "0".into()]]
+        };
+        let g1 = ["1", "2", "1"];
+        assert!(parse_proof(&g1, &pi_b(g2), &g1).is_ok());
+
+        // (1, 3) is off the curve.
+        assert!(parse_proof(&["1", "3", "1"], &pi_b(g2), &g1).is_err());
+        assert!(parse_proof(&g1, &pi_b(g2), &["1", "3", "1"]).is_err());
+
+        // G2 has a large cofactor, so a point found from an arbitrary x is on
+        // the curve but almost surely outside the prime-order subgroup.
+        let outside = (1u64..)
+            .filter_map(|x| G2Affine::get_point_from_x_unchecked(Fq2::from(x), false))
+            .find(|p| !p.is_in_correct_subgroup_assuming_on_curve())
+            .unwrap();
+        assert!(parse_proof(&g1, &pi_b(outside), &g1).is_err());
+    }
+
+    #[test]
+    fn decimal_field_elements_must_be_canonical() {
+        assert_eq!(parse_fr("0").unwrap(), Fr::from(0u64));
+        assert_eq!(parse_fr("15").unwrap(), Fr::from(15u64));
+        let max = Fr::from(-1i64).into_bigint().to_string();
+        assert_eq!(parse_fr(&max).unwrap(), Fr::from(-1i64));
+
+        // The modulus would reduce to zero, and leading zeros or signs give
+        // the same value another spelling.
+        assert!(parse_fr(&Fr::MODULUS.to_string()).is_err());
+        assert!(parse_fr(&format!("1{}", "0".repeat(80))).is_err());
+        for s in ["", "015", "00", "+15", "-1", "1 5", "1e3"] {
+            assert!(parse_fr(s).is_err(), "{s}");
+        }
+    }
+}
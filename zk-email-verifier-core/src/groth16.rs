@@ -0,0 +1,41 @@
+//! Groth16 verification over BN254.
+//!
+//! The public-input MSM is split out so callers with a faster backend (the
+//! contract uses NEAR's `alt_bn128_g1_multiexp` host function) can compute it
+//! themselves and finish with [`verify_with_prepared_inputs`].
+
+use ark_bn254::{Bn254, Fr, G1Projective};
+use ark_ec::VariableBaseMSM;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof};
+
+/// `gamma_abc_g1[0] + sum(gamma_abc_g1[i + 1] * input[i])`, computed with
+/// arkworks' Pippenger MSM. Fails if the number of inputs doesn't match the key.
+pub fn prepare_inputs(
+    pvk: &PreparedVerifyingKey<Bn254>,
+    public_inputs: &[Fr],
+) -> Result<G1Projective, ()> {
+    let bases = &pvk.vk.gamma_abc_g1;
+    if public_inputs.len() + 1 != bases.len() {
+        return Err(());
+    }
+    let sum = G1Projective::msm(&bases[1..], public_inputs).map_err(|_| ())?;
+    Ok(sum + bases[0])
+}
+
+/// Pairing check for a proof whose public inputs were already combined with
+/// [`prepare_inputs`] (or an equivalent MSM).
+pub fn verify_with_prepared_inputs(
+    pvk: &PreparedVerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+    prepared_inputs: &G1Projective,
+) -> bool {
+    Groth16::<Bn254>::verify_proof_with_prepared_inputs(pvk, proof, prepared_inputs)
+        .unwrap_or(false)
+}
+
+/// Full Groth16 check of `proof` against `public_inputs`.
+pub fn verify_proof(pvk: &PreparedVerifyingKey<Bn254>, proof: &Proof<Bn254>, public_inputs: &[Fr]) -> bool {
+    prepare_inputs(pvk, public_inputs)
+        .map(|prepared| verify_with_prepared_inputs(pvk, proof, &prepared))
+        .unwrap_or(false)
+}
@@ -0,0 +1,405 @@
+//! Public signal layout of each circuit.
+//!
+//! A [`PublicInputLayout`] lists the fields of a circuit's public signal array
+//! in order, with the number of field elements each takes and how they are
+//! encoded. Offsets are derived from that order, so anchoring a new value
+//! means adding one entry here; decoding, the binding checks and the
+//! contract's `get_public_input_layout` all look fields up by name. Packed
+//! substrings carry their own maximum length, so a circuit can size each one
+//! independently (e.g. 64 bytes in 3 elements instead of 255 in 9).
+//!
+//! `RecoverEmailCircuit` exposes its public signals in this order:
+//! `[request_id_packed[9], account_id_packed[9], public_key_packed[9],
+//! from_address_hash[32], timestamp_packed[9], pubkey[17], signature[17]]`.
+
+use core::ops::Range;
+
+use crate::packing::{packed_field_len, MAX_PACKED_SUBSTRING_LEN};
+
+/// Identifier of `circuits/RecoverEmailCircuit.circom`.
+pub const RECOVER_EMAIL_CIRCUIT_ID: &str = "RecoverEmailCircuit";
+
+/// `RecoverEmailCircuit` with a second key in the subject, e.g. a backup
+/// key added alongside the new passkey.
+pub const RECOVER_WITH_BACKUP_KEY_CIRCUIT_ID: &str = "RecoverWithBackupKeyCircuit";
+
+/// `RecoverEmailCircuit` that also names a key to delete, e.g. a stolen
+/// device's.
+pub const RECOVER_AND_REVOKE_KEY_CIRCUIT_ID: &str = "RecoverAndRevokeKeyCircuit";
+
+/// Circuit of the `migrate-` command, which moves an account to a new email
+/// and key.
+pub const MIGRATE_ACCOUNT_CIRCUIT_ID: &str = "MigrateAccountCircuit";
+
+/// Circuit of the `lock-` command, which freezes recoveries of an account.
+pub const LOCK_ACCOUNT_CIRCUIT_ID: &str = "LockAccountCircuit";
+
+/// Circuit of the `cancel-` email, which cancels the recovery with the same
+/// request id.
+pub const CANCEL_RECOVERY_CIRCUIT_ID: &str = "CancelRecoveryCircuit";
+
+/// Circuit of the `register-` email, which binds an account to the mailbox
+/// it was sent from.
+pub const REGISTER_EMAIL_CIRCUIT_ID: &str = "RegisterEmailCircuit";
+
+/// Circuit of the `change-email-` command, sent from the current address to
+/// name the next one.
+pub const CHANGE_EMAIL_CIRCUIT_ID: &str = "ChangeEmailCircuit";
+
+/// Circuit of the `confirm-email-` email, sent from the next address to
+/// accept the change.
+pub const CONFIRM_EMAIL_CHANGE_CIRCUIT_ID: &str = "ConfirmEmailChangeCircuit";
+
+/// Circuit of the `limit-` command, which changes an account's daily
+/// spending limit.
+pub const SET_SPENDING_LIMIT_CIRCUIT_ID: &str = "SetSpendingLimitCircuit";
+
+/// Circuit of the `add-guardian-` command, which adds a guardian email to
+/// an account.
+pub const ADD_GUARDIAN_CIRCUIT_ID: &str = "AddGuardianCircuit";
+
+/// Circuit of the `remove-guardian-` command, which starts the delayed
+/// removal of a guardian.
+pub const REMOVE_GUARDIAN_CIRCUIT_ID: &str = "RemoveGuardianCircuit";
+
+/// Circuit of the `veto-` command, sent by a guardian to stop the removal of
+/// another.
+pub const VETO_GUARDIAN_REMOVAL_CIRCUIT_ID: &str = "VetoGuardianRemovalCircuit";
+
+/// Hex characters of a SHA-256 digest anchored in a subject.
+pub const HEX_HASH_LEN: usize = 64;
+
+/// Number of field elements used for `from_address_hash` (32 SHA-256 bytes).
+pub const FROM_ADDRESS_HASH_LEN: usize = 32;
+
+/// Number of 121-bit limbs for the 2048-bit DKIM RSA modulus and signature.
+pub const PUBKEY_LEN: usize = 17;
+
+/// How a public signal field is encoded into field elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldEncoding {
+    /// UTF-8 bytes packed 31 per field element (`PackByteSubArray`), at
+    /// most `max_len` of them.
+    PackedBytes { max_len: usize },
+    /// One byte per field element.
+    Bytes,
+    /// Big integer split into 121-bit limbs, least significant first.
+    Limbs,
+}
+
+/// One named field of a public signal array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicInputField {
+    pub name: &'static str,
+    pub offset: usize,
+    /// Number of field elements.
+    pub len: usize,
+    pub encoding: FieldEncoding,
+}
+
+impl PublicInputField {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+}
+
+/// What a verified email of a circuit is allowed to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitKind {
+    /// A `recover-` email, accepted wherever a recovery is verified.
+    Recovery,
+    /// A command email, only accepted by the method handling its command.
+    Command,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicInputLayout {
+    pub circuit_id: &'static str,
+    pub kind: CircuitKind,
+    /// Total number of public signals.
+    pub len: usize,
+    pub fields: &'static [PublicInputField],
+}
+
+impl PublicInputLayout {
+    pub fn field(&self, name: &str) -> Option<&'static PublicInputField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// The signals of field `name` in `inputs`, or `None` if the layout has
+    /// no such field or `inputs` is too short to hold it.
+    pub fn get<'a, T>(&self, name: &str, inputs: &'a [T]) -> Option<&'a [T]> {
+        inputs.get(self.field(name)?.range())
+    }
+}
+
+/// Lay `fields` out back to back from offset 0.
+const fn contiguous<const N: usize>(
+    fields: [(&'static str, usize, FieldEncoding); N],
+) -> [PublicInputField; N] {
+    let mut out = [PublicInputField { name: "", offset: 0, len: 0, encoding: FieldEncoding::Bytes }; N];
+    let (mut i, mut offset) = (0, 0);
+    while i < N {
+        let (name, len, encoding) = fields[i];
+        out[i] = PublicInputField { name, offset, len, encoding };
+        offset += len;
+        i += 1;
+    }
+    out
+}
+
+/// A packed substring field of at most `max_len` bytes.
+const fn packed(name: &'static str, max_len: usize) -> (&'static str, usize, FieldEncoding) {
+    (name, packed_field_len(max_len), FieldEncoding::PackedBytes { max_len })
+}
+
+/// One past the last signal of `fields`.
+const fn end(fields: &[PublicInputField]) -> usize {
+    match fields.last() {
+        Some(last) => last.offset + last.len,
+        None => 0,
+    }
+}
+
+const RECOVER_EMAIL_FIELDS: [PublicInputField; 7] = {
+    use FieldEncoding::{Bytes, Limbs};
+    contiguous([
+        packed("request_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("account_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("new_public_key", MAX_PACKED_SUBSTRING_LEN),
+        ("from_address_hash", FROM_ADDRESS_HASH_LEN, Bytes),
+        packed("timestamp", MAX_PACKED_SUBSTRING_LEN),
+        ("pubkey", PUBKEY_LEN, Limbs),
+        ("signature", PUBKEY_LEN, Limbs),
+    ])
+};
+
+pub const RECOVER_EMAIL_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: RECOVER_EMAIL_CIRCUIT_ID,
+    kind: CircuitKind::Recovery,
+    len: end(&RECOVER_EMAIL_FIELDS),
+    fields: &RECOVER_EMAIL_FIELDS,
+};
+
+/// Number of public signals of `RecoverEmailCircuit`.
+pub const EXPECTED_PUBLIC_LEN: usize = RECOVER_EMAIL_LAYOUT.len;
+
+const RECOVER_WITH_BACKUP_KEY_FIELDS: [PublicInputField; 8] = {
+    use FieldEncoding::{Bytes, Limbs};
+    contiguous([
+        packed("request_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("account_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("new_public_key", MAX_PACKED_SUBSTRING_LEN),
+        packed("new_public_key_1", MAX_PACKED_SUBSTRING_LEN),
+        ("from_address_hash", FROM_ADDRESS_HASH_LEN, Bytes),
+        packed("timestamp", MAX_PACKED_SUBSTRING_LEN),
+        ("pubkey", PUBKEY_LEN, Limbs),
+        ("signature", PUBKEY_LEN, Limbs),
+    ])
+};
+
+pub const RECOVER_WITH_BACKUP_KEY_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: RECOVER_WITH_BACKUP_KEY_CIRCUIT_ID,
+    kind: CircuitKind::Recovery,
+    len: end(&RECOVER_WITH_BACKUP_KEY_FIELDS),
+    fields: &RECOVER_WITH_BACKUP_KEY_FIELDS,
+};
+
+const RECOVER_AND_REVOKE_KEY_FIELDS: [PublicInputField; 8] = {
+    use FieldEncoding::{Bytes, Limbs};
+    contiguous([
+        packed("request_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("account_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("new_public_key", MAX_PACKED_SUBSTRING_LEN),
+        packed("delete_public_key_1", MAX_PACKED_SUBSTRING_LEN),
+        ("from_address_hash", FROM_ADDRESS_HASH_LEN, Bytes),
+        packed("timestamp", MAX_PACKED_SUBSTRING_LEN),
+        ("pubkey", PUBKEY_LEN, Limbs),
+        ("signature", PUBKEY_LEN, Limbs),
+    ])
+};
+
+pub const RECOVER_AND_REVOKE_KEY_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: RECOVER_AND_REVOKE_KEY_CIRCUIT_ID,
+    kind: CircuitKind::Recovery,
+    len: end(&RECOVER_AND_REVOKE_KEY_FIELDS),
+    fields: &RECOVER_AND_REVOKE_KEY_FIELDS,
+};
+
+const MIGRATE_ACCOUNT_FIELDS: [PublicInputField; 8] = {
+    use FieldEncoding::{Bytes, Limbs};
+    contiguous([
+        packed("request_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("account_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("new_public_key", MAX_PACKED_SUBSTRING_LEN),
+        packed("new_email_hash", HEX_HASH_LEN),
+        ("from_address_hash", FROM_ADDRESS_HASH_LEN, Bytes),
+        packed("timestamp", MAX_PACKED_SUBSTRING_LEN),
+        ("pubkey", PUBKEY_LEN, Limbs),
+        ("signature", PUBKEY_LEN, Limbs),
+    ])
+};
+
+pub const MIGRATE_ACCOUNT_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: MIGRATE_ACCOUNT_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&MIGRATE_ACCOUNT_FIELDS),
+    fields: &MIGRATE_ACCOUNT_FIELDS,
+};
+
+/// Fields of the commands that only name the account.
+const ACCOUNT_COMMAND_FIELDS: [PublicInputField; 6] = {
+    use FieldEncoding::{Bytes, Limbs};
+    contiguous([
+        packed("request_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("account_id", MAX_PACKED_SUBSTRING_LEN),
+        ("from_address_hash", FROM_ADDRESS_HASH_LEN, Bytes),
+        packed("timestamp", MAX_PACKED_SUBSTRING_LEN),
+        ("pubkey", PUBKEY_LEN, Limbs),
+        ("signature", PUBKEY_LEN, Limbs),
+    ])
+};
+
+pub const LOCK_ACCOUNT_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: LOCK_ACCOUNT_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&ACCOUNT_COMMAND_FIELDS),
+    fields: &ACCOUNT_COMMAND_FIELDS,
+};
+
+pub const CANCEL_RECOVERY_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: CANCEL_RECOVERY_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&ACCOUNT_COMMAND_FIELDS),
+    fields: &ACCOUNT_COMMAND_FIELDS,
+};
+
+pub const REGISTER_EMAIL_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: REGISTER_EMAIL_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&ACCOUNT_COMMAND_FIELDS),
+    fields: &ACCOUNT_COMMAND_FIELDS,
+};
+
+const CHANGE_EMAIL_FIELDS: [PublicInputField; 7] = {
+    use FieldEncoding::{Bytes, Limbs};
+    contiguous([
+        packed("request_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("account_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("new_email_hash", HEX_HASH_LEN),
+        ("from_address_hash", FROM_ADDRESS_HASH_LEN, Bytes),
+        packed("timestamp", MAX_PACKED_SUBSTRING_LEN),
+        ("pubkey", PUBKEY_LEN, Limbs),
+        ("signature", PUBKEY_LEN, Limbs),
+    ])
+};
+
+pub const CHANGE_EMAIL_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: CHANGE_EMAIL_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&CHANGE_EMAIL_FIELDS),
+    fields: &CHANGE_EMAIL_FIELDS,
+};
+
+pub const CONFIRM_EMAIL_CHANGE_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: CONFIRM_EMAIL_CHANGE_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&ACCOUNT_COMMAND_FIELDS),
+    fields: &ACCOUNT_COMMAND_FIELDS,
+};
+
+const SET_SPENDING_LIMIT_FIELDS: [PublicInputField; 7] = {
+    use FieldEncoding::{Bytes, Limbs};
+    contiguous([
+        packed("request_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("account_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("daily_limit", MAX_PACKED_SUBSTRING_LEN),
+        ("from_address_hash", FROM_ADDRESS_HASH_LEN, Bytes),
+        packed("timestamp", MAX_PACKED_SUBSTRING_LEN),
+        ("pubkey", PUBKEY_LEN, Limbs),
+        ("signature", PUBKEY_LEN, Limbs),
+    ])
+};
+
+pub const SET_SPENDING_LIMIT_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: SET_SPENDING_LIMIT_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&SET_SPENDING_LIMIT_FIELDS),
+    fields: &SET_SPENDING_LIMIT_FIELDS,
+};
+
+/// Fields of the guardian commands, which name a guardian by its email
+/// commitment.
+const GUARDIAN_COMMAND_FIELDS: [PublicInputField; 7] = {
+    use FieldEncoding::{Bytes, Limbs};
+    contiguous([
+        packed("request_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("account_id", MAX_PACKED_SUBSTRING_LEN),
+        packed("guardian_email_hash", HEX_HASH_LEN),
+        ("from_address_hash", FROM_ADDRESS_HASH_LEN, Bytes),
+        packed("timestamp", MAX_PACKED_SUBSTRING_LEN),
+        ("pubkey", PUBKEY_LEN, Limbs),
+        ("signature", PUBKEY_LEN, Limbs),
+    ])
+};
+
+pub const ADD_GUARDIAN_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: ADD_GUARDIAN_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&GUARDIAN_COMMAND_FIELDS),
+    fields: &GUARDIAN_COMMAND_FIELDS,
+};
+
+pub const REMOVE_GUARDIAN_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: REMOVE_GUARDIAN_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&GUARDIAN_COMMAND_FIELDS),
+    fields: &GUARDIAN_COMMAND_FIELDS,
+};
+
+pub const VETO_GUARDIAN_REMOVAL_LAYOUT: PublicInputLayout = PublicInputLayout {
+    circuit_id: VETO_GUARDIAN_REMOVAL_CIRCUIT_ID,
+    kind: CircuitKind::Command,
+    len: end(&GUARDIAN_COMMAND_FIELDS),
+    fields: &GUARDIAN_COMMAND_FIELDS,
+};
+
+const LAYOUTS: &[PublicInputLayout] = &[
+    RECOVER_EMAIL_LAYOUT,
+    RECOVER_WITH_BACKUP_KEY_LAYOUT,
+    RECOVER_AND_REVOKE_KEY_LAYOUT,
+    MIGRATE_ACCOUNT_LAYOUT,
+    LOCK_ACCOUNT_LAYOUT,
+    CANCEL_RECOVERY_LAYOUT,
+    SET_SPENDING_LIMIT_LAYOUT,
+    ADD_GUARDIAN_LAYOUT,
+    REMOVE_GUARDIAN_LAYOUT,
+    VETO_GUARDIAN_REMOVAL_LAYOUT,
+    REGISTER_EMAIL_LAYOUT,
+    CHANGE_EMAIL_LAYOUT,
+    CONFIRM_EMAIL_CHANGE_LAYOUT,
+];
+
+pub fn public_input_layout(circuit_id: &str) -> Option<&'static PublicInputLayout> {
+    LAYOUTS.iter().find(|l| l.circuit_id == circuit_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recover_email_layout_matches_the_circuit() {
+        assert_eq!(EXPECTED_PUBLIC_LEN, 102);
+        let offset = |name| RECOVER_EMAIL_LAYOUT.field(name).unwrap().offset;
+        assert_eq!(offset("account_id"), 9);
+        assert_eq!(offset("from_address_hash"), 27);
+        assert_eq!(offset("signature"), 85);
+
+        let inputs: [u32; EXPECTED_PUBLIC_LEN] = core::array::from_fn(|i| i as u32);
+        assert_eq!(RECOVER_EMAIL_LAYOUT.get("timestamp", &inputs), Some(&inputs[59..68]));
+        assert_eq!(RECOVER_EMAIL_LAYOUT.get("timestamp", &inputs[..60]), None);
+        assert_eq!(RECOVER_EMAIL_LAYOUT.get("subject", &inputs), None);
+        assert!(public_input_layout("UnknownCircuit").is_none());
+    }
+}
@@ -0,0 +1,33 @@
+//! Verification logic for `RecoverEmailCircuit` proofs, shared by the NEAR
+//! contract and off-chain services (relayers, provers) that want to run the
+//! same checks before submitting a transaction.
+//!
+//! The crate is `no_std` with `alloc` when the default `std` feature is
+//! disabled. Nothing here depends on NEAR: the contract wraps these functions
+//! with its own JSON types, storage and host-function accelerated MSM.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// Parse failures carry no detail worth a dedicated error type; callers only
+// distinguish "accepted" from "rejected", as the contract does.
+#![allow(clippy::result_unit_err)]
+
+extern crate alloc;
+
+pub mod binding;
+pub mod blob;
+pub mod decode;
+pub mod dkim;
+pub mod field;
+pub mod groth16;
+pub mod layout;
+pub mod merkle;
+pub mod packing;
+pub mod proof;
+pub mod subject;
+pub mod timestamp;
+
+pub use decode::{decode_anchored_fields, decode_with_layout, numbered_fields, AnchoredFields};
+pub use field::{parse_fq, parse_fq2, parse_fr, parse_proof, parse_public_inputs};
+pub use proof::{ProofInput, SNARKJS_CURVE, SNARKJS_PROTOCOL};
+pub use groth16::{prepare_inputs, verify_proof, verify_with_prepared_inputs};
+pub use timestamp::{parse_email_timestamp, EmailTimestamp};
@@ -0,0 +1,159 @@
+//! Incremental Merkle tree over the contract's consumed nullifiers.
+//!
+//! Leaves are appended in the order the nullifiers were consumed, into a tree
+//! of fixed depth [`TREE_DEPTH`] whose unused leaves are 32 zero bytes. A
+//! parent is `hash(left || right)`. The contract only keeps one node per
+//! level ([`IncrementalTree`]), so an append costs `TREE_DEPTH` hashes at any
+//! size. Services holding every leaf (the contract's `list_nullifiers`)
+//! build inclusion proofs with [`inclusion_proof`], and light clients check
+//! them against a published root with [`verify_inclusion`].
+//!
+//! The hash function is a parameter so the contract can use its SHA-256 host
+//! function; use SHA-256 off-chain too to reproduce the contract's roots.
+
+use alloc::vec::Vec;
+
+pub const TREE_DEPTH: usize = 32;
+
+pub type Hash = [u8; 32];
+
+/// Hash of a node's 64-byte preimage, e.g. SHA-256.
+pub type Hasher = fn(&[u8]) -> Hash;
+
+/// Append-only tree that keeps the last left child seen at each level
+/// instead of the leaves.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IncrementalTree {
+    /// `TREE_DEPTH` nodes once a leaf was appended; the one at level `i` is
+    /// only meaningful while bit `i` of `len` is set.
+    pub branch: Vec<Hash>,
+    pub len: u64,
+}
+
+impl IncrementalTree {
+    /// Append `leaf`. `Err` once the tree is full.
+    pub fn append(&mut self, leaf: Hash, hash: Hasher) -> Result<(), ()> {
+        // One short of capacity, so the root always has a pending branch.
+        if self.len >= (1 << TREE_DEPTH) - 1 {
+            return Err(());
+        }
+        self.branch.resize(TREE_DEPTH, [0; 32]);
+        self.len += 1;
+        let mut node = leaf;
+        let mut size = self.len;
+        for level in 0..TREE_DEPTH {
+            if size & 1 == 1 {
+                self.branch[level] = node;
+                break;
+            }
+            node = parent(hash, &self.branch[level], &node);
+            size >>= 1;
+        }
+        Ok(())
+    }
+
+    pub fn root(&self, hash: Hasher) -> Hash {
+        let zeros = zero_hashes(hash);
+        let mut node = [0; 32];
+        let mut size = self.len;
+        for (level, zero) in zeros.iter().enumerate() {
+            node = if size & 1 == 1 {
+                parent(hash, &self.branch[level], &node)
+            } else {
+                parent(hash, &node, zero)
+            };
+            size >>= 1;
+        }
+        node
+    }
+}
+
+/// Sibling path of `leaves[index]`, from the leaf level up. `None` if
+/// `index` is out of range.
+pub fn inclusion_proof(leaves: &[Hash], index: usize, hash: Hasher) -> Option<Vec<Hash>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut layer = leaves.to_vec();
+    let mut index = index;
+    let mut proof = Vec::with_capacity(TREE_DEPTH);
+    for zero in zero_hashes(hash) {
+        proof.push(layer.get(index ^ 1).copied().unwrap_or(zero));
+        layer = layer
+            .chunks(2)
+            .map(|pair| parent(hash, &pair[0], pair.get(1).unwrap_or(&zero)))
+            .collect();
+        index >>= 1;
+    }
+    Some(proof)
+}
+
+/// Whether `proof` places `leaf` at `index` in the tree with `root`.
+pub fn verify_inclusion(root: &Hash, leaf: &Hash, index: u64, proof: &[Hash], hash: Hasher) -> bool {
+    if proof.len() != TREE_DEPTH {
+        return false;
+    }
+    let mut node = *leaf;
+    let mut index = index;
+    for sibling in proof {
+        node = if index & 1 == 0 {
+            parent(hash, &node, sibling)
+        } else {
+            parent(hash, sibling, &node)
+        };
+        index >>= 1;
+    }
+    index == 0 && node == *root
+}
+
+fn parent(hash: Hasher, left: &Hash, right: &Hash) -> Hash {
+    let mut preimage = [0; 64];
+    preimage[..32].copy_from_slice(left);
+    preimage[32..].copy_from_slice(right);
+    hash(&preimage)
+}
+
+/// Roots of empty subtrees, by height from 0 to `TREE_DEPTH - 1`.
+fn zero_hashes(hash: Hasher) -> Vec<Hash> {
+    let mut zeros = Vec::with_capacity(TREE_DEPTH);
+    let mut node = [0; 32];
+    for _ in 0..TREE_DEPTH {
+        zeros.push(node);
+        node = parent(hash, &node, &node);
+    }
+    zeros
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// FNV-1a per output byte; order-sensitive, which is all the tree needs.
+    fn fnv(bytes: &[u8]) -> Hash {
+        core::array::from_fn(|i| {
+            let seed = 0xcbf2_9ce4_8422_2325_u64 ^ i as u64;
+            let state = bytes
+                .iter()
+                .fold(seed, |s, b| (s ^ u64::from(*b)).wrapping_mul(0x100_0000_01b3));
+            (state >> 40) as u8
+        })
+    }
+
+    #[test]
+    fn proofs_verify_against_the_incremental_root() {
+        let leaves: Vec<Hash> = (0..9u8).map(|i| fnv(&[i])).collect();
+        let mut tree = IncrementalTree::default();
+        for n in 1..=leaves.len() {
+            tree.append(leaves[n - 1], fnv).unwrap();
+            let root = tree.root(fnv);
+            for (i, leaf) in leaves[..n].iter().enumerate() {
+                let proof = inclusion_proof(&leaves[..n], i, fnv).unwrap();
+                assert!(verify_inclusion(&root, leaf, i as u64, &proof, fnv), "{i} of {n}");
+                assert!(!verify_inclusion(&root, leaf, i as u64 + 1, &proof, fnv));
+                assert!(!verify_inclusion(&root, &fnv(b"other"), i as u64, &proof, fnv));
+            }
+            assert!(inclusion_proof(&leaves[..n], n, fnv).is_none());
+        }
+        assert_ne!(tree.root(fnv), IncrementalTree::default().root(fnv));
+    }
+}
@@ -0,0 +1,249 @@
+//! The base-256 packing scheme used by `PackBytes` / `PackByteSubArray` in
+//! `@zk-email/circuits`: a byte string of at most `max_len` bytes is split
+//! into 31-byte little-endian limbs, each stored in one BN254 scalar field
+//! element, for `ceil(max_len / 31)` elements in total. `RecoverEmailCircuit`
+//! packs every substring with `max_len = 255`; slimmer circuits may use less
+//! per field (e.g. 64 bytes in 3 elements), as their layout records.
+//!
+//! Byte `i` of the string becomes bits `8 * (i % 31)..` of element `i / 31`,
+//! and unused trailing bytes are zero. That makes the encoding injective on
+//! the strings it accepts, which the binding checks rely on: a proof anchoring
+//! one value can't be matched against another. Two consequences:
+//!
+//! - a string ending in a NUL byte would pack like the same string without it,
+//!   so packing rejects it (header values never contain NUL anyway);
+//! - unpacking rejects elements with bits set above the 31st byte, which no
+//!   packed string produces.
+//!
+//! The decimal-string helpers mirror what provers emit in `public.json`, so
+//! client developers can reproduce a binding comparison without reimplementing
+//! the scheme:
+//!
+//! ```
+//! use zk_email_verifier_core::packing::{pack_string, unpack_fields, PACKED_SUBSTRING_FIELD_LEN};
+//!
+//! let packed = pack_string("alice.testnet").unwrap();
+//! assert_eq!(packed.len(), PACKED_SUBSTRING_FIELD_LEN);
+//! assert_eq!(unpack_fields(&packed).unwrap(), "alice.testnet");
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use ark_bn254::Fr;
+use ark_ff::{BigInt, BigInteger, PrimeField};
+
+/// Number of bytes packed into a single field element in PackBytes / PackByteSubArray.
+/// Must match MAX_BYTES_IN_FIELD() for BN254 in @zk-email/circuits.
+pub const PACKED_BYTES_PER_FIELD: usize = 31;
+
+/// Maximum number of bytes we pack for a subject or from-email substring.
+/// Must match the `max_*_len` constants used in `RecoverEmailCircuit.circom` (255).
+pub const MAX_PACKED_SUBSTRING_LEN: usize = 255;
+
+/// Number of field elements used per packed substring (account_id, new_public_key, timestamp).
+/// 255 bytes / 31 bytes per field = 9.
+pub const PACKED_SUBSTRING_FIELD_LEN: usize = packed_field_len(MAX_PACKED_SUBSTRING_LEN);
+
+/// Number of field elements a substring of at most `max_len` bytes packs into.
+pub const fn packed_field_len(max_len: usize) -> usize {
+    max_len.div_ceil(PACKED_BYTES_PER_FIELD)
+}
+
+/// Decode one byte per field element, as used for `from_address_hash`.
+pub fn unpack_field_elems_to_bytes(fields: &[Fr]) -> Result<Vec<u8>, ()> {
+    let mut out = Vec::with_capacity(fields.len());
+    for fr in fields {
+        let bigint = fr.into_bigint();
+        let bytes = bigint.to_bytes_le();
+        if bytes.iter().skip(1).any(|b| *b != 0) {
+            return Err(());
+        }
+        out.push(*bytes.first().unwrap_or(&0u8));
+    }
+    Ok(out)
+}
+
+/// Pack `s` into a fixed array of field elements, as `RecoverEmailCircuit`
+/// does for every substring.
+///
+/// Fails if `s` is longer than `MAX_PACKED_SUBSTRING_LEN` bytes or ends in a
+/// NUL byte.
+pub fn pack_str_to_field_chunks(s: &str) -> Result<[Fr; PACKED_SUBSTRING_FIELD_LEN], ()> {
+    let chunks = pack_str_to_chunks(s, MAX_PACKED_SUBSTRING_LEN)?;
+    Ok(chunks.try_into().expect("packed_field_len(MAX_PACKED_SUBSTRING_LEN) chunks"))
+}
+
+/// Pack `s` into `packed_field_len(max_len)` field elements. Bytes are
+/// shifted straight into the little-endian limbs of each element; 31 bytes
+/// always fit below the modulus, so no field arithmetic is needed.
+///
+/// Fails if `s` is longer than `max_len` bytes or ends in a NUL byte.
+pub fn pack_str_to_chunks(s: &str, max_len: usize) -> Result<Vec<Fr>, ()> {
+    let bytes = s.as_bytes();
+    if bytes.len() > max_len || bytes.last() == Some(&0) {
+        return Err(());
+    }
+
+    let mut chunks = alloc::vec![Fr::from(0u64); packed_field_len(max_len)];
+    for (chunk, window) in chunks.iter_mut().zip(bytes.chunks(PACKED_BYTES_PER_FIELD)) {
+        let mut limbs = [0u64; 4];
+        for (j, b) in window.iter().enumerate() {
+            limbs[j / 8] |= u64::from(*b) << ((j % 8) * 8);
+        }
+        *chunk = Fr::from_bigint(BigInt::new(limbs)).ok_or(())?;
+    }
+
+    Ok(chunks)
+}
+
+/// Inverse of [`pack_str_to_field_chunks`], dropping trailing zero padding.
+///
+/// Fails if an element has bits set above its 31st byte or the bytes are not
+/// valid UTF-8.
+pub fn unpack_field_chunks_to_str(chunks: &[Fr]) -> Result<String, ()> {
+    let mut bytes = Vec::with_capacity(chunks.len() * PACKED_BYTES_PER_FIELD);
+
+    for fr in chunks {
+        let limbs = fr.into_bigint().0;
+        // 31 bytes fill bits 0..248; anything above is in the top limb's last byte.
+        if limbs[3] >> 56 != 0 {
+            return Err(());
+        }
+        bytes.extend((0..PACKED_BYTES_PER_FIELD).map(|j| (limbs[j / 8] >> ((j % 8) * 8)) as u8));
+    }
+
+    // Trim trailing zero padding introduced during packing.
+    let len = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    bytes.truncate(len);
+
+    String::from_utf8(bytes).map_err(|_| ())
+}
+
+/// Pack `s` into `PACKED_SUBSTRING_FIELD_LEN` field elements rendered as
+/// decimal strings, exactly as they appear in `public.json`.
+///
+/// Returns `None` if `s` is longer than `MAX_PACKED_SUBSTRING_LEN` bytes or
+/// ends in a NUL byte.
+pub fn pack_string(s: &str) -> Option<Vec<String>> {
+    pack_string_with_max_len(s, MAX_PACKED_SUBSTRING_LEN)
+}
+
+/// [`pack_string`] for a field of at most `max_len` bytes.
+pub fn pack_string_with_max_len(s: &str, max_len: usize) -> Option<Vec<String>> {
+    let chunks = pack_str_to_chunks(s, max_len).ok()?;
+    Some(chunks.iter().map(|fr| fr.into_bigint().to_string()).collect())
+}
+
+/// Inverse of [`pack_string`]: decode decimal field elements back into the
+/// packed UTF-8 string, dropping trailing zero padding.
+///
+/// Returns `None` if a chunk is not a canonical field element, has bits set
+/// above its 31st byte, or the bytes are not valid UTF-8.
+pub fn unpack_fields(chunks: &[String]) -> Option<String> {
+    let fields = chunks
+        .iter()
+        .map(|c| Fr::from_str(c).ok())
+        .collect::<Option<Vec<Fr>>>()?;
+    unpack_field_chunks_to_str(&fields).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_string_matches_public_json_encoding() {
+        let packed = pack_string("ab").unwrap();
+        assert_eq!(packed.len(), PACKED_SUBSTRING_FIELD_LEN);
+        assert_eq!(packed[0], (u32::from(b'a') + 256 * u32::from(b'b')).to_string());
+        assert!(packed[1..].iter().all(|c| c == "0"));
+
+        let account = "kerp30.w3a-v1.testnet";
+        assert_eq!(unpack_fields(&pack_string(account).unwrap()).unwrap(), account);
+        assert!(pack_string(&"x".repeat(MAX_PACKED_SUBSTRING_LEN + 1)).is_none());
+    }
+
+    #[test]
+    fn limb_packing_matches_field_arithmetic() {
+        let s = "subject:recover-ABC123 kerp30.w3a-v1.testnet ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm";
+        let chunks = pack_str_to_field_chunks(s).unwrap();
+        for (i, window) in s.as_bytes().chunks(PACKED_BYTES_PER_FIELD).enumerate() {
+            let expected = window
+                .iter()
+                .rev()
+                .fold(Fr::from(0u64), |acc, b| acc * Fr::from(256u64) + Fr::from(u64::from(*b)));
+            assert_eq!(chunks[i], expected);
+        }
+        assert_eq!(unpack_field_chunks_to_str(&chunks).unwrap(), s);
+    }
+
+    #[test]
+    fn smaller_fields_pack_into_fewer_elements() {
+        assert_eq!(packed_field_len(64), 3);
+        assert_eq!(packed_field_len(62), 2);
+
+        let account = "alice.testnet";
+        let slim = pack_str_to_chunks(account, 64).unwrap();
+        assert_eq!(slim.len(), 3);
+        assert_eq!(slim[..], pack_str_to_field_chunks(account).unwrap()[..3]);
+        assert_eq!(unpack_field_chunks_to_str(&slim).unwrap(), account);
+        assert!(pack_string_with_max_len(&"x".repeat(65), 64).is_none());
+    }
+
+    #[test]
+    fn trailing_nul_and_oversized_elements_are_rejected() {
+        assert!(pack_string("a\0").is_none());
+        assert!(pack_string("a\0b").is_some());
+
+        let mut chunks = pack_str_to_field_chunks("a").unwrap();
+        chunks[0] += Fr::from(BigInt::new([0, 0, 0, 1 << 56]));
+        assert!(unpack_field_chunks_to_str(&chunks).is_err());
+    }
+
+    mod roundtrip {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Any string of at most 255 bytes, NUL included.
+        fn packable() -> impl Strategy<Value = String> {
+            any::<String>().prop_map(|s| {
+                let mut end = s.len().min(MAX_PACKED_SUBSTRING_LEN);
+                while !s.is_char_boundary(end) {
+                    end -= 1;
+                }
+                s[..end].to_string()
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn unpack_inverts_pack(s in packable()) {
+                match pack_string(&s) {
+                    Some(packed) => prop_assert_eq!(unpack_fields(&packed).unwrap(), s),
+                    None => prop_assert!(s.ends_with('\0')),
+                }
+            }
+
+            #[test]
+            fn pack_is_injective(a in packable(), b in packable()) {
+                if let (Ok(pa), Ok(pb)) = (pack_str_to_field_chunks(&a), pack_str_to_field_chunks(&b)) {
+                    prop_assert_eq!(pa == pb, a == b, "{:?} vs {:?}", a, b);
+                }
+            }
+
+            #[test]
+            fn unpack_then_pack_is_identity(bytes in proptest::collection::vec(any::<u8>(), 0..=MAX_PACKED_SUBSTRING_LEN)) {
+                let chunks: Vec<Fr> = bytes
+                    .chunks(PACKED_BYTES_PER_FIELD)
+                    .map(Fr::from_le_bytes_mod_order)
+                    .collect();
+                if let Ok(s) = unpack_field_chunks_to_str(&chunks) {
+                    let repacked = pack_str_to_field_chunks(&s).unwrap();
+                    prop_assert_eq!(&repacked[..chunks.len()], &chunks[..], "{:?}", s);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! The snarkjs `proof.json` shape, shared by the contract's JSON API and
+//! off-chain clients.
+
+use alloc::string::String;
+
+use ark_bn254::Bn254;
+use ark_groth16::Proof;
+
+/// Input format for a Groth16 proof, roughly mirroring snarkjs's `proof.json`.
+/// Coordinates, like public inputs, may be decimal or `0x`-prefixed hex.
+/// `protocol` and `curve` are optional, but a proof declaring anything other
+/// than a Groth16 proof over BN254 is refused rather than misread.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProofInput {
+    /// pi_a: [Ax, Ay, Az]; we use Ax, Ay and assume Az = 1.
+    pub pi_a: [String; 3],
+    /// pi_b: [[Bx1, Bx0], [By1, By0], [Bz1, Bz0]]; we use the first two pairs.
+    pub pi_b: [[String; 2]; 3],
+    /// pi_c: [Cx, Cy, Cz]; we use Cx, Cy and assume Cz = 1.
+    pub pi_c: [String; 3],
+    /// snarkjs `protocol`; must be `groth16` if present.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub protocol: Option<String>,
+    /// snarkjs `curve`; must be `bn128` (BN254) if present.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub curve: Option<String>,
+}
+
+/// snarkjs's name for the Groth16 protocol.
+pub const SNARKJS_PROTOCOL: &str = "groth16";
+/// snarkjs's name for BN254.
+pub const SNARKJS_CURVE: &str = "bn128";
+
+impl ProofInput {
+    /// Whether the declared `protocol` and `curve`, where given, are the
+    /// ones this crate verifies.
+    pub fn is_groth16_bn254(&self) -> bool {
+        self.protocol.as_deref().is_none_or(|p| p == SNARKJS_PROTOCOL)
+            && self.curve.as_deref().is_none_or(|c| c == SNARKJS_CURVE)
+    }
+
+    /// Parse the coordinates into an arkworks proof. Fails for a proof that
+    /// isn't [`is_groth16_bn254`](Self::is_groth16_bn254).
+    pub fn parse(&self) -> Result<Proof<Bn254>, ()> {
+        if !self.is_groth16_bn254() {
+            return Err(());
+        }
+        crate::parse_proof(&self.pi_a, &self.pi_b, &self.pi_c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn only_groth16_over_bn254_is_accepted() {
+        let g1 = || ["1".to_string(), "2".to_string(), "1".to_string()];
+        let mut proof = ProofInput {
+            pi_a: g1(),
+            pi_b: core::array::from_fn(|_| ["0".to_string(), "0".to_string()]),
+            pi_c: g1(),
+            protocol: None,
+            curve: None,
+        };
+        assert!(proof.is_groth16_bn254());
+
+        proof.protocol = Some("groth16".to_string());
+        proof.curve = Some("bn128".to_string());
+        assert!(proof.is_groth16_bn254());
+
+        proof.protocol = Some("plonk".to_string());
+        assert!(!proof.is_groth16_bn254());
+        assert!(proof.parse().is_err());
+
+        proof.protocol = None;
+        proof.curve = Some("bls12381".to_string());
+        assert!(!proof.is_groth16_bn254());
+    }
+}
@@ -0,0 +1,248 @@
+//! Subject line grammar of each circuit.
+//!
+//! A template is literal text with `{field}` placeholders, e.g.
+//! `recover-{request_id} {account_id} ed25519:{new_public_key}`. Frontends
+//! render it to tell the user exactly what to send; relayers parse incoming
+//! subjects with it before proving. Placeholder values are non-empty and
+//! contain no whitespace, since the circuits delimit them with single spaces.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::layout::{
+    ADD_GUARDIAN_CIRCUIT_ID, CANCEL_RECOVERY_CIRCUIT_ID, CHANGE_EMAIL_CIRCUIT_ID,
+    CONFIRM_EMAIL_CHANGE_CIRCUIT_ID, LOCK_ACCOUNT_CIRCUIT_ID, MIGRATE_ACCOUNT_CIRCUIT_ID,
+    RECOVER_AND_REVOKE_KEY_CIRCUIT_ID, RECOVER_EMAIL_CIRCUIT_ID, RECOVER_WITH_BACKUP_KEY_CIRCUIT_ID,
+    REGISTER_EMAIL_CIRCUIT_ID, REMOVE_GUARDIAN_CIRCUIT_ID, SET_SPENDING_LIMIT_CIRCUIT_ID,
+    VETO_GUARDIAN_REMOVAL_CIRCUIT_ID,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubjectTemplate {
+    pub circuit_id: &'static str,
+    pub template: &'static str,
+}
+
+/// `RecoverEmailCircuit` checks `subject:recover-` byte for byte, then the
+/// request id, account id and key separated by single spaces.
+pub const RECOVER_EMAIL_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: RECOVER_EMAIL_CIRCUIT_ID,
+    template: "recover-{request_id} {account_id} ed25519:{new_public_key}",
+};
+
+/// Both keys are added by the recovery, or neither is.
+pub const RECOVER_WITH_BACKUP_KEY_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: RECOVER_WITH_BACKUP_KEY_CIRCUIT_ID,
+    template: "recover-{request_id} {account_id} ed25519:{new_public_key} ed25519:{new_public_key_1}",
+};
+
+/// The key to delete keeps its curve prefix, so it can be of any curve.
+pub const RECOVER_AND_REVOKE_KEY_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: RECOVER_AND_REVOKE_KEY_CIRCUIT_ID,
+    template: "recover-{request_id} {account_id} ed25519:{new_public_key} revoke {delete_public_key_1}",
+};
+
+/// `MigrateAccountCircuit` additionally anchors the hex SHA-256 commitment of
+/// the email the account moves to (see `from_address_hash`).
+pub const MIGRATE_ACCOUNT_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: MIGRATE_ACCOUNT_CIRCUIT_ID,
+    template: "migrate-{request_id} {account_id} ed25519:{new_public_key} {new_email_hash}",
+};
+
+/// `LockAccountCircuit` anchors only the request and the account.
+pub const LOCK_ACCOUNT_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: LOCK_ACCOUNT_CIRCUIT_ID,
+    template: "lock-{request_id} {account_id}",
+};
+
+/// `CancelRecoveryCircuit` repeats the request id of the `recover-` email it
+/// cancels.
+pub const CANCEL_RECOVERY_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: CANCEL_RECOVERY_CIRCUIT_ID,
+    template: "cancel-{request_id} {account_id}",
+};
+
+/// `SetSpendingLimitCircuit` anchors the new limit in yoctoNEAR, as a
+/// decimal integer.
+pub const SET_SPENDING_LIMIT_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: SET_SPENDING_LIMIT_CIRCUIT_ID,
+    template: "limit-{request_id} {account_id} {daily_limit}",
+};
+
+/// `AddGuardianCircuit` anchors the guardian's email commitment as hex.
+pub const ADD_GUARDIAN_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: ADD_GUARDIAN_CIRCUIT_ID,
+    template: "add-guardian-{request_id} {account_id} {guardian_email_hash}",
+};
+
+pub const REMOVE_GUARDIAN_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: REMOVE_GUARDIAN_CIRCUIT_ID,
+    template: "remove-guardian-{request_id} {account_id} {guardian_email_hash}",
+};
+
+/// Sent from another guardian's mailbox; names the guardian whose removal it
+/// stops.
+pub const VETO_GUARDIAN_REMOVAL_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: VETO_GUARDIAN_REMOVAL_CIRCUIT_ID,
+    template: "veto-{request_id} {account_id} {guardian_email_hash}",
+};
+
+pub const REGISTER_EMAIL_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: REGISTER_EMAIL_CIRCUIT_ID,
+    template: "register-{request_id} {account_id}",
+};
+
+pub const CHANGE_EMAIL_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: CHANGE_EMAIL_CIRCUIT_ID,
+    template: "change-email-{request_id} {account_id} {new_email_hash}",
+};
+
+/// Carries the request id of the `change-email-` email it confirms.
+pub const CONFIRM_EMAIL_CHANGE_SUBJECT: SubjectTemplate = SubjectTemplate {
+    circuit_id: CONFIRM_EMAIL_CHANGE_CIRCUIT_ID,
+    template: "confirm-email-{request_id} {account_id}",
+};
+
+const TEMPLATES: &[SubjectTemplate] = &[
+    RECOVER_EMAIL_SUBJECT,
+    RECOVER_WITH_BACKUP_KEY_SUBJECT,
+    RECOVER_AND_REVOKE_KEY_SUBJECT,
+    MIGRATE_ACCOUNT_SUBJECT,
+    LOCK_ACCOUNT_SUBJECT,
+    CANCEL_RECOVERY_SUBJECT,
+    SET_SPENDING_LIMIT_SUBJECT,
+    ADD_GUARDIAN_SUBJECT,
+    REMOVE_GUARDIAN_SUBJECT,
+    VETO_GUARDIAN_REMOVAL_SUBJECT,
+    REGISTER_EMAIL_SUBJECT,
+    CHANGE_EMAIL_SUBJECT,
+    CONFIRM_EMAIL_CHANGE_SUBJECT,
+];
+
+pub fn subject_template(circuit_id: &str) -> Option<&'static SubjectTemplate> {
+    TEMPLATES.iter().find(|t| t.circuit_id == circuit_id)
+}
+
+enum Part {
+    Literal(&'static str),
+    Field(&'static str),
+}
+
+impl SubjectTemplate {
+    fn parts(&self) -> Vec<Part> {
+        let mut parts = Vec::new();
+        let mut rest = self.template;
+        while let Some(open) = rest.find('{') {
+            let close = open + rest[open..].find('}').expect("unterminated placeholder");
+            if open > 0 {
+                parts.push(Part::Literal(&rest[..open]));
+            }
+            parts.push(Part::Field(&rest[open + 1..close]));
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest));
+        }
+        parts
+    }
+
+    /// Placeholder names in order.
+    pub fn fields(&self) -> Vec<&'static str> {
+        self.parts()
+            .into_iter()
+            .filter_map(|p| match p {
+                Part::Field(name) => Some(name),
+                Part::Literal(_) => None,
+            })
+            .collect()
+    }
+
+    /// Fill in every placeholder. `None` if a value is missing, empty or
+    /// contains whitespace.
+    pub fn render(&self, values: &[(&str, &str)]) -> Option<String> {
+        let mut out = String::new();
+        for part in self.parts() {
+            match part {
+                Part::Literal(text) => out.push_str(text),
+                Part::Field(name) => {
+                    let value = values.iter().find(|(n, _)| *n == name)?.1;
+                    if !valid_value(value) {
+                        return None;
+                    }
+                    out.push_str(value);
+                }
+            }
+        }
+        Some(out)
+    }
+
+    /// Match `subject` against the template, returning each placeholder's
+    /// value in order.
+    pub fn parse<'a>(&self, subject: &'a str) -> Option<Vec<(&'static str, &'a str)>> {
+        let parts = self.parts();
+        let mut rest = subject;
+        let mut values = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            match part {
+                Part::Literal(text) => rest = rest.strip_prefix(text)?,
+                Part::Field(name) => {
+                    // A field runs up to the next literal, or to the end.
+                    let end = match parts.get(i + 1) {
+                        Some(Part::Literal(next)) => rest.find(next)?,
+                        _ => rest.len(),
+                    };
+                    let value = &rest[..end];
+                    if !valid_value(value) {
+                        return None;
+                    }
+                    values.push((*name, value));
+                    rest = &rest[end..];
+                }
+            }
+        }
+        rest.is_empty().then_some(values)
+    }
+}
+
+fn valid_value(value: &str) -> bool {
+    !value.is_empty() && !value.contains(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn recover_email_template_renders_and_parses() {
+        let template = subject_template(RECOVER_EMAIL_CIRCUIT_ID).unwrap();
+        assert_eq!(template.fields(), vec!["request_id", "account_id", "new_public_key"]);
+
+        let values = [("request_id", "123abc"), ("account_id", "alice.near"), ("new_public_key", "86mq")];
+        let subject = template.render(&values).unwrap();
+        assert_eq!(subject, "recover-123abc alice.near ed25519:86mq");
+        assert_eq!(template.parse(&subject).unwrap(), values.to_vec());
+
+        assert!(template.render(&values[..2]).is_none());
+        assert!(template.render(&[("request_id", "a b"), values[1], values[2]]).is_none());
+        for bad in [
+            "recover- alice.near ed25519:86mq",
+            "recover-1 alice.near  ed25519:86mq",
+            "recover-1 alice.near ed25519:",
+            "Recover-1 alice.near ed25519:86mq",
+            "recover-1 alice.near 86mq",
+        ] {
+            assert!(template.parse(bad).is_none(), "{bad}");
+        }
+        assert!(subject_template("UnknownCircuit").is_none());
+    }
+
+    #[test]
+    fn template_fields_are_anchored_by_their_circuit() {
+        for template in TEMPLATES {
+            let layout = crate::layout::public_input_layout(template.circuit_id).unwrap();
+            for name in template.fields() {
+                assert!(layout.field(name).is_some(), "{} lacks {name}", template.circuit_id);
+            }
+        }
+    }
+}
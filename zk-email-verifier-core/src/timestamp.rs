@@ -0,0 +1,192 @@
+//! Parsing of the RFC 5322 `Date:` header value anchored by the circuit.
+
+use alloc::{format, string::String, vec::Vec};
+
+/// A parsed `Date:` header value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmailTimestamp {
+    /// Milliseconds since the Unix epoch.
+    pub unix_ms: u64,
+    /// Local time with the original UTC offset preserved.
+    pub rfc3339: String,
+}
+
+pub fn parse_email_timestamp(s: &str) -> Option<EmailTimestamp> {
+    // Expect formats like: "Sun, 30 Nov 2025 17:37:38 +0900"
+    let trimmed = s.trim();
+    let after_comma = match trimmed.split_once(',') {
+        Some((_, rest)) => rest.trim_start(),
+        None => trimmed,
+    };
+
+    let parts: Vec<&str> = after_comma.split_whitespace().collect();
+    if parts.len() < 5 {
+        return None;
+    }
+
+    let day: u32 = parts[0].parse().ok()?;
+    let month_str = parts[1];
+    let year: i32 = parts[2].parse().ok()?;
+
+    let month = match month_str {
+        "Jan" => 1,
+        "Feb" => 2,
+        "Mar" => 3,
+        "Apr" => 4,
+        "May" => 5,
+        "Jun" => 6,
+        "Jul" => 7,
+        "Aug" => 8,
+        "Sep" => 9,
+        "Oct" => 10,
+        "Nov" => 11,
+        "Dec" => 12,
+        _ => return None,
+    };
+
+    let time_parts: Vec<&str> = parts[3].split(':').collect();
+    if time_parts.len() != 3 {
+        return None;
+    }
+    let hour = two_digits(time_parts[0])?;
+    let minute = two_digits(time_parts[1])?;
+    // RFC 5322 and RFC 3339 both allow a leap second.
+    let second = two_digits(time_parts[2])?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Slice by bytes only after checking the offset is ASCII, so that a
+    // multi-byte character can't split a char boundary and panic.
+    let offset_str = parts[4];
+    if offset_str.len() != 5 || !offset_str.is_ascii() {
+        return None;
+    }
+    let (sign_char, rest) = offset_str.split_at(1);
+    let sign = match sign_char {
+        "+" => 1i64,
+        "-" => -1i64,
+        _ => return None,
+    };
+    let offset_hours = two_digits(&rest[0..2])?;
+    let offset_minutes = two_digits(&rest[2..4])?;
+    if offset_hours > 23 || offset_minutes > 59 {
+        return None;
+    }
+    let offset_secs: i64 = sign * (i64::from(offset_hours) * 3600 + i64::from(offset_minutes) * 60);
+
+    // Compute days since Unix epoch (1970-01-01). RFC 3339 years have four
+    // digits, which also bounds the year loop below.
+    if !(1970..=9999).contains(&year) {
+        return None;
+    }
+
+    fn is_leap_year(y: i32) -> bool {
+        (y % 4 == 0 && y % 100 != 0) || (y % 400 == 0)
+    }
+
+    const DAYS_IN_MONTH: [u32; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+    fn days_in_month(year: i32, month: u32) -> u32 {
+        if month == 2 && is_leap_year(year) {
+            29
+        } else {
+            DAYS_IN_MONTH[(month - 1) as usize]
+        }
+    }
+
+    if month == 0 || month > 12 {
+        return None;
+    }
+    if day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut days: i64 = 0;
+    let mut y = 1970;
+    while y < year {
+        days += if is_leap_year(y) { 366 } else { 365 };
+        y += 1;
+    }
+
+    let mut m = 1;
+    while m < month {
+        days += days_in_month(year, m) as i64;
+        m += 1;
+    }
+
+    days += (day - 1) as i64;
+
+    let seconds_local: i64 =
+        days * 86_400 + (hour as i64) * 3_600 + (minute as i64) * 60 + (second as i64);
+
+    // Offset is "local = UTC + offset", so UTC = local - offset.
+    let seconds_utc = seconds_local - offset_secs;
+    if seconds_utc < 0 {
+        return None;
+    }
+
+    // Only formatted once every component is known to be in range.
+    let rfc3339 = format!(
+        "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}{sign_char}{offset_hours:02}:{offset_minutes:02}"
+    );
+    Some(EmailTimestamp {
+        unix_ms: seconds_utc as u64 * 1000,
+        rfc3339,
+    })
+}
+
+/// A two-digit decimal component such as `07`; `parse` alone would also take
+/// `+7` or `7`.
+fn two_digits(s: &str) -> Option<u32> {
+    if s.len() != 2 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_timestamp_keeps_original_offset() {
+        let ts = parse_email_timestamp("Tue, 9 Dec 2025 17:13:23 +0900").unwrap();
+        assert_eq!(ts.unix_ms, 1_765_268_003_000);
+        assert_eq!(ts.rfc3339, "2025-12-09T17:13:23+09:00");
+
+        let ts = parse_email_timestamp("Sun, 30 Nov 2025 07:05:00 -0330").unwrap();
+        assert_eq!(ts.rfc3339, "2025-11-30T07:05:00-03:30");
+    }
+
+    #[test]
+    fn malformed_offsets_and_years_are_rejected() {
+        for s in [
+            "1 Jan 2025 00:00:00 \u{e9}000",
+            "1 Jan 2025 00:00:00 +0\u{e9}0",
+            "1 Jan 2025 00:00:00 +09000",
+            "1 Jan 10000 00:00:00 +0000",
+            "1 Jan 2147483647 00:00:00 +0000",
+        ] {
+            assert!(parse_email_timestamp(s).is_none(), "{s}");
+        }
+    }
+
+    #[test]
+    fn out_of_range_times_are_rejected() {
+        for s in [
+            "1 Jan 2025 24:00:00 +0000",
+            "1 Jan 2025 00:60:00 +0000",
+            "1 Jan 2025 00:00:61 +0000",
+            "1 Jan 2025 99:99:99 +0000",
+            "1 Jan 2025 +1:00:00 +0000",
+            "1 Jan 2025 00:00:00 +2400",
+            "1 Jan 2025 00:00:00 +0060",
+            "1 Jan 2025 00:00:00 +-100",
+        ] {
+            assert!(parse_email_timestamp(s).is_none(), "{s}");
+        }
+        let ts = parse_email_timestamp("31 Dec 2016 23:59:60 +0000").unwrap();
+        assert_eq!(ts.rfc3339, "2016-12-31T23:59:60+00:00");
+    }
+}
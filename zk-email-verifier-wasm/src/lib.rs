@@ -0,0 +1,226 @@
+//! Browser bindings for `zk_email_verifier_core`, built with
+//! `wasm-pack build --target web zk-email-verifier-wasm`.
+//!
+//! Wallet frontends can reject a malformed proof or a binding that won't
+//! match before asking the user to sign a transaction:
+//!
+//! ```js
+//! import init, { Verifier, decodePublicSignals, checkBinding } from "zk-email-verifier-wasm";
+//! await init();
+//! const fields = decodePublicSignals(publicSignals); // throws on malformed input
+//! const binding = checkBinding(publicSignals, "account_id", "alice.testnet", {});
+//! const verified = new Verifier(verificationKey).verify(proof, publicSignals);
+//! ```
+//!
+//! All inputs are the parsed snarkjs JSON (`proof.json`, `public.json`,
+//! `verification_key.json`). As with `zkev`, the NEAR-specific checks
+//! (account id / public key syntax, `from_address_hash`) are left to the
+//! contract.
+
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use zk_email_verifier_core::binding::{check_packed_binding, BindingOptions, BindingOutcome};
+use zk_email_verifier_core::layout::{FieldEncoding, EXPECTED_PUBLIC_LEN, RECOVER_EMAIL_LAYOUT};
+use zk_email_verifier_core::{
+    decode_anchored_fields, parse_email_timestamp, parse_public_inputs, verify_proof, ProofInput,
+};
+
+/// A prepared verifying key. Preparing it is the expensive part, so build one
+/// and reuse it.
+#[wasm_bindgen]
+pub struct Verifier {
+    pvk: PreparedVerifyingKey<Bn254>,
+}
+
+#[wasm_bindgen]
+impl Verifier {
+    #[wasm_bindgen(constructor)]
+    pub fn new(verification_key: JsValue) -> Result<Verifier, JsError> {
+        let vkey: vk_generator::VerificationKey = from_js(verification_key, "verification key")?;
+        let vk = vk_generator::build_verifying_key(&vkey).map_err(|e| JsError::new(&e))?;
+        Ok(Verifier {
+            pvk: prepare_verifying_key(&vk),
+        })
+    }
+
+    /// Run the Groth16 check the contract runs. Throws if the proof or the
+    /// public signals don't parse; returns `false` if they parse but don't
+    /// verify.
+    pub fn verify(&self, proof: JsValue, public_signals: JsValue) -> Result<bool, JsError> {
+        let proof: ProofInput = from_js(proof, "proof")?;
+        let proof = proof.parse().map_err(|()| JsError::new("proof is not a valid BN254 proof"))?;
+        let signals: Vec<String> = from_js(public_signals, "public signals")?;
+        let inputs = public_inputs(&signals).map_err(|e| JsError::new(&e))?;
+        Ok(verify_proof(&self.pvk, &proof, &inputs))
+    }
+}
+
+/// Anchored values decoded from the public signals, as returned by
+/// `decodePublicSignals`. Undecodable fields are `null`.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct DecodedFields {
+    request_id: Option<String>,
+    account_id: Option<String>,
+    new_public_key: Option<String>,
+    /// Hex.
+    from_address_hash: Option<String>,
+    timestamp: Option<String>,
+    /// `timestamp` in milliseconds since the epoch, if it parses.
+    timestamp_ms: Option<u64>,
+}
+
+/// Validate the public signals and decode their anchored fields. Throws if
+/// there aren't exactly as many signals as the circuit outputs or one of them
+/// isn't a field element.
+#[wasm_bindgen(js_name = decodePublicSignals)]
+pub fn decode_public_signals(public_signals: JsValue) -> Result<JsValue, JsError> {
+    let signals: Vec<String> = from_js(public_signals, "public signals")?;
+    let fields = decode_fields(&signals).map_err(|e| JsError::new(&e))?;
+    to_js(&fields)
+}
+
+/// Outcome of `checkBinding`: `matched`, or where/why it didn't.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct BindingReport {
+    matched: bool,
+    /// Index within the field of the first differing chunk.
+    mismatch_index: Option<usize>,
+    /// `ValueTooLong` or `UndecodableInput`.
+    error: Option<&'static str>,
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct JsBindingOptions {
+    case_insensitive_account_id: bool,
+    trim_whitespace: bool,
+}
+
+/// Check `value` against one packed field (`request_id`, `account_id`,
+/// `new_public_key` or `timestamp`) the way `verify_with_binding` does.
+/// `options` takes the same flags as the contract's `BindingOptions`.
+#[wasm_bindgen(js_name = checkBinding)]
+pub fn check_binding(
+    public_signals: JsValue,
+    field: &str,
+    value: &str,
+    options: JsValue,
+) -> Result<JsValue, JsError> {
+    let signals: Vec<String> = from_js(public_signals, "public signals")?;
+    let options: JsBindingOptions = if options.is_undefined() || options.is_null() {
+        JsBindingOptions::default()
+    } else {
+        from_js(options, "binding options")?
+    };
+    let options = BindingOptions {
+        case_insensitive_account_id: options.case_insensitive_account_id,
+        trim_whitespace: options.trim_whitespace,
+    };
+    to_js(&binding_report(&signals, field, value, &options).map_err(|e| JsError::new(&e))?)
+}
+
+fn from_js<T: serde::de::DeserializeOwned>(value: JsValue, what: &str) -> Result<T, JsError> {
+    serde_wasm_bindgen::from_value(value).map_err(|e| JsError::new(&format!("invalid {what}: {e}")))
+}
+
+fn to_js(value: &impl Serialize) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn public_inputs(signals: &[String]) -> Result<Vec<Fr>, String> {
+    if signals.len() != EXPECTED_PUBLIC_LEN {
+        return Err(format!(
+            "expected {EXPECTED_PUBLIC_LEN} public signals, got {}",
+            signals.len()
+        ));
+    }
+    parse_public_inputs(signals).map_err(|()| "public signal is not a BN254 field element".to_string())
+}
+
+fn decode_fields(signals: &[String]) -> Result<DecodedFields, String> {
+    let inputs = public_inputs(signals)?;
+    let fields = decode_anchored_fields(&inputs).expect("length checked above");
+    let timestamp_ms = fields
+        .timestamp
+        .as_deref()
+        .and_then(parse_email_timestamp)
+        .map(|ts| ts.unix_ms);
+    Ok(DecodedFields {
+        request_id: fields.request_id,
+        account_id: fields.account_id,
+        new_public_key: fields.new_public_key,
+        from_address_hash: fields
+            .from_address_hash
+            .map(|hash| hash.iter().map(|b| format!("{b:02x}")).collect()),
+        timestamp: fields.timestamp,
+        timestamp_ms,
+    })
+}
+
+fn binding_report(
+    signals: &[String],
+    field: &str,
+    value: &str,
+    options: &BindingOptions,
+) -> Result<BindingReport, String> {
+    let range = RECOVER_EMAIL_LAYOUT
+        .field(field)
+        .filter(|f| matches!(f.encoding, FieldEncoding::PackedBytes { .. }))
+        .ok_or_else(|| format!("unknown binding field {field}"))?
+        .range();
+    // The contract binds only the base58 data of the key.
+    let value = match field {
+        "new_public_key" => value.split_once(':').map_or(value, |(_, data)| data),
+        _ => value,
+    };
+    let inputs = public_inputs(signals)?;
+    let outcome = check_packed_binding(field, &inputs[range], value, options);
+    Ok(BindingReport {
+        matched: outcome.is_match(),
+        mismatch_index: match outcome {
+            BindingOutcome::Mismatch(i) => Some(i),
+            _ => None,
+        },
+        error: outcome.error(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zk_email_verifier_core::packing::pack_string;
+
+    fn signals() -> Vec<String> {
+        let mut signals = vec!["0".to_string(); EXPECTED_PUBLIC_LEN];
+        let account = pack_string("alice.testnet").unwrap();
+        let range = RECOVER_EMAIL_LAYOUT.field("account_id").unwrap().range();
+        signals[range].clone_from_slice(&account);
+        signals
+    }
+
+    #[test]
+    fn decodes_and_checks_bindings() {
+        let signals = signals();
+        assert_eq!(decode_fields(&signals).unwrap().account_id.as_deref(), Some("alice.testnet"));
+
+        let options = BindingOptions::default();
+        assert!(binding_report(&signals, "account_id", "alice.testnet", &options).unwrap().matched);
+        let report = binding_report(&signals, "account_id", "Alice.testnet", &options).unwrap();
+        assert_eq!(report.mismatch_index, Some(0));
+        let relaxed = BindingOptions {
+            case_insensitive_account_id: true,
+            ..Default::default()
+        };
+        assert!(binding_report(&signals, "account_id", "Alice.testnet", &relaxed).unwrap().matched);
+        assert!(binding_report(&signals, "from", "x", &options).is_err());
+
+        assert!(decode_fields(&signals[1..]).is_err());
+        let mut bad = signals.clone();
+        bad[0] = "not a number".to_string();
+        assert!(decode_fields(&bad).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! `zkev dkim`: fetch a DKIM key and print it the way the circuit sees it.
+
+use std::fmt::Write as _;
+
+use base64::Engine;
+use hickory_resolver::Resolver;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use zk_email_verifier_core::dkim::{limbs_preimage, modulus_to_limbs};
+
+pub const USAGE: &str = "\
+Usage: zkev dkim <selector> <domain>
+       zkev dkim --txt <record>
+
+Prints the key as JSON: the 17 pubkey limbs as they appear in public.json and
+the SHA-256 of those limbs.";
+
+/// Key material ready for a DKIM registry.
+#[derive(Serialize, Debug)]
+pub struct DkimKey {
+    /// `<selector>._domainkey.<domain>`, if the key was looked up.
+    pub name: Option<String>,
+    pub bits: usize,
+    /// Decimal limbs, least significant first.
+    pub pubkey: Vec<String>,
+    /// Hex SHA-256 of the limbs as 32-byte little-endian values.
+    pub pubkey_hash: String,
+}
+
+pub fn run(args: &[String]) -> Result<DkimKey, String> {
+    match args {
+        [flag, record] if flag == "--txt" => key_from_txt(record, None),
+        [selector, domain] if !selector.starts_with("--") => {
+            let name = format!("{selector}._domainkey.{domain}");
+            key_from_txt(&lookup_txt(&name)?, Some(name))
+        }
+        _ => Err(format!("expected <selector> <domain> or --txt <record>\n\n{USAGE}")),
+    }
+}
+
+fn lookup_txt(name: &str) -> Result<String, String> {
+    let resolver = Resolver::from_system_conf()
+        .map_err(|e| format!("failed to read the system resolver configuration: {e}"))?;
+    let response = resolver
+        .txt_lookup(format!("{name}."))
+        .map_err(|e| format!("TXT lookup for {name} failed: {e}"))?;
+    // Long records are split into several strings; a key is the first record
+    // with a `p=` tag.
+    response
+        .iter()
+        .map(|txt| txt.iter().map(|s| String::from_utf8_lossy(s)).collect::<String>())
+        .find(|record| tag(record, "p").is_some())
+        .ok_or_else(|| format!("no DKIM key record at {name}"))
+}
+
+/// Value of tag `name` in a `tag=value; ...` DKIM record.
+fn tag<'a>(record: &'a str, name: &str) -> Option<&'a str> {
+    record.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key.trim() == name).then_some(value.trim())
+    })
+}
+
+fn key_from_txt(record: &str, name: Option<String>) -> Result<DkimKey, String> {
+    if let Some(k) = tag(record, "k").filter(|k| *k != "rsa") {
+        return Err(format!("unsupported key type {k}"));
+    }
+    let p: String = tag(record, "p")
+        .ok_or("record has no p= tag")?
+        .split_whitespace()
+        .collect();
+    if p.is_empty() {
+        return Err("key has been revoked (empty p=)".to_string());
+    }
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(&p)
+        .map_err(|e| format!("p= is not base64: {e}"))?;
+    let key = RsaPublicKey::from_public_key_der(&der)
+        .or_else(|_| RsaPublicKey::from_pkcs1_der(&der))
+        .map_err(|e| format!("p= is not an RSA public key: {e}"))?;
+
+    let modulus = key.n().to_bytes_be();
+    let limbs = modulus_to_limbs(&modulus)
+        .ok_or_else(|| format!("{}-bit key is too large for the circuit", key.n().bits()))?;
+    let pubkey_hash = Sha256::digest(limbs_preimage(&limbs))
+        .iter()
+        .fold(String::new(), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        });
+    Ok(DkimKey {
+        name,
+        bits: key.n().bits(),
+        pubkey: limbs.iter().map(|l| l.to_string()).collect(),
+        pubkey_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 1024-bit test key in SubjectPublicKeyInfo form, as published in DNS.
+    const RECORD: &str = "v=DKIM1; k=rsa; p=MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQCwCZR+UGmAtNERA++fCpryyeDkGuS9cO4tmn6QEkY+vILrfhsfsZYoLIKPpmQKjrYy97aUbXVhMA0R1W8wfy9srSQsSybhnxJ5M+CEJgpr7ixDKr3WAvvTRcTmjXBZhUz1o9algSmEin51haeqn+O82IKWdvWdycEg05jiuLanIwIDAQAB";
+
+    #[test]
+    fn parses_dkim_record_into_limbs() {
+        let key = key_from_txt(RECORD, None).unwrap();
+        assert_eq!(key.bits, 1024);
+        assert_eq!(key.pubkey.len(), 17);
+        assert_eq!(key.pubkey[9..], vec!["0".to_string(); 8]);
+        assert_eq!(key.pubkey_hash.len(), 64);
+
+        assert!(key_from_txt("v=DKIM1; k=rsa; p=", None).is_err());
+        assert!(key_from_txt("v=DKIM1; k=ed25519; p=AAAA", None).is_err());
+        assert_eq!(tag(" k = rsa ; p=abc", "k"), Some("rsa"));
+    }
+}
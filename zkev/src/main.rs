@@ -0,0 +1,223 @@
+//! `zkev`: check a `RecoverEmailCircuit` proof locally.
+//!
+//! Runs the same steps as the contract's `dry_run_verify` (proof parsing, the
+//! Groth16 pairing, decoding of the anchored fields and the packed bindings)
+//! through `zk_email_verifier_core`, so a failure reproduced here fails
+//! on-chain for the same reason, without a sandbox deployment.
+//!
+//! Not covered: NEAR account id / public key syntax validation and the
+//! `from_address_hash` binding, which need the contract's NEAR types.
+//!
+//! `zkev dkim` prints a domain's DKIM key as the circuit's `pubkey` limbs.
+
+mod dkim;
+
+use std::{env, fs, process};
+
+use ark_bn254::Fr;
+use ark_groth16::prepare_verifying_key;
+use zk_email_verifier_core::binding::{check_packed_binding, BindingOptions, BindingOutcome};
+use zk_email_verifier_core::layout::{EXPECTED_PUBLIC_LEN, RECOVER_EMAIL_LAYOUT};
+use zk_email_verifier_core::{
+    decode_anchored_fields, parse_email_timestamp, parse_public_inputs, verify_proof, ProofInput,
+};
+
+const USAGE: &str = "\
+Usage: zkev verify --proof <proof.json> --public <public.json> --vk <verification_key.json>
+                   [--account-id <id>] [--new-public-key <key>] [--timestamp <date>]
+                   [--request-id <id>] [--case-insensitive-account-id] [--trim-whitespace]
+
+Exits with 0 if the proof verifies and every given binding matches, 1 otherwise.
+
+Run `zkev dkim` for the DKIM key subcommand.";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct VerifyArgs {
+    proof: String,
+    public: String,
+    vk: String,
+    account_id: Option<String>,
+    new_public_key: Option<String>,
+    timestamp: Option<String>,
+    request_id: Option<String>,
+    options: BindingOptions,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("dkim") {
+        match dkim::run(&args[1..]) {
+            Ok(key) => println!("{}", serde_json::to_string_pretty(&key).expect("serializable")),
+            Err(e) => {
+                eprintln!("error: {e}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    let verify_args = match args.split_first() {
+        Some((cmd, rest)) if cmd == "verify" => parse_verify_args(rest),
+        _ => Err(String::new()),
+    };
+    let verify_args = verify_args.unwrap_or_else(|e| {
+        if !e.is_empty() {
+            eprintln!("error: {e}\n");
+        }
+        eprintln!("{USAGE}");
+        process::exit(2);
+    });
+
+    match run(&verify_args) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("error: {e}");
+            process::exit(2);
+        }
+    }
+}
+
+fn parse_verify_args(args: &[String]) -> Result<VerifyArgs, String> {
+    let mut out = VerifyArgs::default();
+    let mut it = args.iter();
+    while let Some(flag) = it.next() {
+        let mut value = || it.next().cloned().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--proof" => out.proof = value()?,
+            "--public" => out.public = value()?,
+            "--vk" => out.vk = value()?,
+            "--account-id" => out.account_id = Some(value()?),
+            "--new-public-key" => out.new_public_key = Some(value()?),
+            "--timestamp" => out.timestamp = Some(value()?),
+            "--request-id" => out.request_id = Some(value()?),
+            "--case-insensitive-account-id" => out.options.case_insensitive_account_id = true,
+            "--trim-whitespace" => out.options.trim_whitespace = true,
+            other => return Err(format!("unknown argument {other}")),
+        }
+    }
+    for (name, path) in [("--proof", &out.proof), ("--public", &out.public), ("--vk", &out.vk)] {
+        if path.is_empty() {
+            return Err(format!("{name} is required"));
+        }
+    }
+    Ok(out)
+}
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("failed to read {path}: {e}"))
+}
+
+/// Print a report and return whether the contract would accept the proof.
+fn run(args: &VerifyArgs) -> Result<bool, String> {
+    let vkey = vk_generator::parse_verification_key(&read(&args.vk)?)?;
+    let pvk = prepare_verifying_key(&vk_generator::build_verifying_key(&vkey)?);
+
+    let proof_json: ProofInput = serde_json::from_str(&read(&args.proof)?)
+        .map_err(|e| format!("failed to parse {}: {e}", args.proof))?;
+    let public: Vec<String> = serde_json::from_str(&read(&args.public)?)
+        .map_err(|e| format!("failed to parse {}: {e}", args.public))?;
+
+    let proof = proof_json.parse().ok();
+    let inputs = parse_public_inputs(&public).ok();
+    let len_ok = public.len() == EXPECTED_PUBLIC_LEN;
+
+    println!("proof:             {}", if proof.is_some() { "parsed" } else { "INVALID" });
+    println!(
+        "public inputs:     {} (expected {EXPECTED_PUBLIC_LEN}){}",
+        public.len(),
+        if inputs.is_none() { ", INVALID field element" } else { "" }
+    );
+    if vkey.ic.len() != public.len() + 1 {
+        println!("verifying key:     expects {} public inputs", vkey.ic.len() - 1);
+    }
+
+    let pairing_ok = match (&proof, &inputs) {
+        (Some(proof), Some(inputs)) => verify_proof(&pvk, proof, inputs),
+        _ => false,
+    };
+    println!("pairing:           {}", if pairing_ok { "ok" } else { "FAILED" });
+
+    let Some(inputs) = inputs.filter(|_| len_ok) else {
+        println!("verified:          false");
+        return Ok(false);
+    };
+
+    print_anchored_fields(&inputs);
+
+    let mut bindings_ok = true;
+    let bindings = [
+        ("account_id", args.account_id.as_deref()),
+        ("new_public_key", args.new_public_key.as_deref().map(bare_public_key)),
+        ("timestamp", args.timestamp.as_deref()),
+        ("request_id", args.request_id.as_deref()),
+    ];
+    for (field, value) in bindings {
+        let Some(value) = value else { continue };
+        let actual = RECOVER_EMAIL_LAYOUT.get(field, &inputs).unwrap_or_default();
+        let outcome = check_packed_binding(field, actual, value, &args.options);
+        bindings_ok &= outcome.is_match();
+        let status = match outcome {
+            BindingOutcome::Matched => "matched".to_string(),
+            BindingOutcome::Mismatch(i) => format!("MISMATCH at chunk {i}"),
+            other => other.error().unwrap_or_default().to_string(),
+        };
+        println!("binding {field}: {status}");
+    }
+
+    let verified = pairing_ok && bindings_ok;
+    println!("verified:          {verified}");
+    Ok(verified)
+}
+
+fn print_anchored_fields(inputs: &[Fr]) {
+    let Some(fields) = decode_anchored_fields(inputs) else {
+        return;
+    };
+    let show = |s: &Option<String>| s.as_ref().map_or("<undecodable>".to_string(), |s| format!("{s:?}"));
+    println!("request_id:        {}", show(&fields.request_id));
+    println!("account_id:        {}", show(&fields.account_id));
+    println!("new_public_key:    {}", show(&fields.new_public_key));
+    match &fields.from_address_hash {
+        Some(hash) => println!(
+            "from_address_hash: {}",
+            hash.iter().map(|b| format!("{b:02x}")).collect::<String>()
+        ),
+        None => println!("from_address_hash: <undecodable>"),
+    }
+    let parsed = fields.timestamp.as_deref().and_then(parse_email_timestamp);
+    match parsed {
+        Some(ts) => println!("timestamp:         {} ({})", show(&fields.timestamp), ts.rfc3339),
+        None => println!("timestamp:         {} (unparseable)", show(&fields.timestamp)),
+    }
+}
+
+/// The contract compares only the base58 data of a bound key; the subject
+/// template already fixes the `ed25519:` prefix.
+fn bare_public_key(key: &str) -> &str {
+    key.split_once(':').map_or(key, |(_, data)| data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_verify_flags() {
+        let parsed = parse_verify_args(&args(
+            "--proof p.json --public pub.json --vk vk.json --account-id alice.near --trim-whitespace",
+        ))
+        .unwrap();
+        assert_eq!(parsed.proof, "p.json");
+        assert_eq!(parsed.account_id.as_deref(), Some("alice.near"));
+        assert!(parsed.options.trim_whitespace);
+        assert!(!parsed.options.case_insensitive_account_id);
+
+        assert!(parse_verify_args(&args("--proof p.json --public pub.json")).is_err());
+        assert!(parse_verify_args(&args("--proof")).is_err());
+        assert!(parse_verify_args(&args("--bogus x")).is_err());
+    }
+}